@@ -0,0 +1,80 @@
+# Lapce shell integration for bash.
+#
+# Marks prompts and commands with OSC 133 sequences, and reports the command
+# line and working directory with OSC 633, so that the terminal can tell the
+# commands apart. Loaded with `bash --init-file`, so it reads ~/.bashrc first.
+
+if [ -r ~/.bashrc ]; then
+    . ~/.bashrc
+fi
+
+if [ -n "$__lapce_shell_integration" ]; then
+    return
+fi
+__lapce_shell_integration=1
+
+__lapce_escape() {
+    local s=${1//\\/\\\\}
+    s=${s//;/\\x3b}
+    s=${s//$'\n'/\\x0a}
+    printf '%s' "$s"
+}
+
+__lapce_ready=""
+__lapce_in_command=""
+
+__lapce_precmd() {
+    local ret=$?
+    __lapce_ready=""
+    if [ -n "$__lapce_in_command" ]; then
+        printf '\e]133;D;%s\a' "$ret"
+        __lapce_in_command=""
+    fi
+    printf '\e]633;P;Cwd=%s\a' "$(__lapce_escape "$PWD")"
+    return $ret
+}
+
+__lapce_prompt() {
+    case "$PS1" in
+        *'133;A'*) ;;
+        *) PS1='\[\e]133;A\a\]'"$PS1"'\[\e]133;B\a\]' ;;
+    esac
+    __lapce_ready=1
+}
+
+__lapce_preexec() {
+    case "$BASH_COMMAND" in
+        __lapce_*) return ;;
+    esac
+    if [ -z "$__lapce_ready" ] || [ -n "$COMP_LINE" ]; then
+        return
+    fi
+    __lapce_ready=""
+    __lapce_in_command=1
+    local command
+    command=$(HISTTIMEFORMAT='' builtin history 1 2>/dev/null | sed 's/^ *[0-9]*\*\{0,1\} *//')
+    if [ -z "$command" ]; then
+        command=$BASH_COMMAND
+    fi
+    printf '\e]633;E;%s\a\e]133;C\a' "$(__lapce_escape "$command")"
+}
+
+# the DEBUG trap set by ~/.bashrc, which still runs after ours
+__lapce_user_debug_trap=$(trap -p DEBUG)
+if [ -n "$__lapce_user_debug_trap" ]; then
+    __lapce_user_debug_trap=${__lapce_user_debug_trap#'trap -- '}
+    __lapce_user_debug_trap=${__lapce_user_debug_trap%' DEBUG'}
+    eval "__lapce_user_debug_trap=$__lapce_user_debug_trap"
+fi
+
+__lapce_debug() {
+    local ret=$?
+    __lapce_preexec
+    if [ -n "$__lapce_user_debug_trap" ]; then
+        (exit $ret)
+        eval "$__lapce_user_debug_trap"
+    fi
+}
+
+PROMPT_COMMAND="__lapce_precmd;${PROMPT_COMMAND:+$PROMPT_COMMAND;}__lapce_prompt"
+trap '__lapce_debug' DEBUG
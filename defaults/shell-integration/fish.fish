@@ -0,0 +1,26 @@
+# Lapce shell integration for fish.
+#
+# Marks prompts and commands with OSC 133 sequences, and reports the command
+# line and working directory with OSC 633. Loaded with `fish --init-command`,
+# after the user's own configuration.
+
+function __lapce_escape
+    string join '\x0a' -- (string replace -a -- '\\' '\\\\' $argv | string replace -a -- ';' '\x3b')
+end
+
+function __lapce_preexec --on-event fish_preexec
+    printf '\e]633;E;%s\a\e]133;C\a' (__lapce_escape $argv[1])
+end
+
+function __lapce_postexec --on-event fish_postexec
+    printf '\e]133;D;%s\a' $status
+end
+
+functions -c fish_prompt __lapce_fish_prompt
+
+function fish_prompt
+    printf '\e]633;P;Cwd=%s\a' (__lapce_escape $PWD)
+    printf '\e]133;A\a'
+    __lapce_fish_prompt
+    printf '\e]133;B\a'
+end
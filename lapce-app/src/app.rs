@@ -86,6 +86,7 @@
     },
     editor_tab::{EditorTabChild, EditorTabData},
     focus_text::focus_text,
+    hex_editor::hex_editor_view,
     id::{EditorTabId, SplitId},
     keymap::keymap_view,
     keypress::keymap::KeyMap,
@@ -94,6 +95,7 @@
         SplitContent, SplitData, SplitDirection, SplitMoveDirection, TabCloseKind,
     },
     markdown::MarkdownContent,
+    markdown_preview::markdown_preview_view,
     palette::{
         PaletteStatus,
         item::{PaletteItem, PaletteItemContent},
@@ -133,6 +135,10 @@ struct Cli {
     #[clap(long, action)]
     plugin_path: Vec<PathBuf>,
 
+    /// Run the process that keeps terminal sessions alive
+    #[clap(long, action, hide = true)]
+    terminal_server: bool,
+
     /// Paths to file(s) and/or folder(s) to open.
     /// When path is a file (that exists or not),
     /// it accepts `path:line:column` syntax
@@ -339,6 +345,8 @@ fn create_windows(
                 #[cfg(not(windows))]
                 let workspace_type = LapceWorkspaceType::Local;
 
+                let folders =
+                    db.recent_workspace_folders(&workspace_type, &dir.path);
                 let info = WindowInfo {
                     size,
                     pos,
@@ -348,6 +356,7 @@ fn create_windows(
                         workspaces: vec![LapceWorkspace {
                             kind: workspace_type,
                             path: Some(dir.path.to_owned()),
+                            folders,
                             last_open: 0,
                         }],
                     },
@@ -686,6 +695,8 @@ fn editor_tab_header(
     let plugin = window_tab_data.plugin.clone();
     let editors = window_tab_data.main_split.editors;
     let diff_editors = window_tab_data.main_split.diff_editors;
+    let hex_editors = window_tab_data.main_split.hex_editors;
+    let markdown_previews = window_tab_data.main_split.markdown_previews;
     let focus = window_tab_data.common.focus;
     let config = window_tab_data.common.config;
     let internal_command = window_tab_data.common.internal_command;
@@ -728,7 +739,14 @@ fn editor_tab_header(
         let main_split = main_split.clone();
         let plugin = plugin.clone();
         let child_view = {
-            let info = child.view_info(editors, diff_editors, plugin, config);
+            let info = child.view_info(
+                editors,
+                diff_editors,
+                hex_editors,
+                markdown_previews,
+                plugin,
+                config,
+            );
             let hovered = create_rw_signal(false);
 
             use crate::config::ui::TabCloseButton;
@@ -1227,6 +1245,8 @@ fn editor_tab_content(
     let workspace = common.workspace.clone();
     let editors = main_split.editors;
     let diff_editors = main_split.diff_editors;
+    let hex_editors = main_split.hex_editors;
+    let markdown_previews = main_split.markdown_previews;
     let config = common.config;
     let focus = common.focus;
     let items = move || {
@@ -1404,6 +1424,26 @@ fn editor_tab_content(
             EditorTabChild::Volt(_, id) => {
                 plugin_info_view(plugin.clone(), id).into_any()
             }
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                if let Some(hex_editor) = hex_editors.with_untracked(|hex_editors| {
+                    hex_editors.get(&hex_editor_id).cloned()
+                }) {
+                    hex_editor_view(hex_editor).into_any()
+                } else {
+                    text("empty hex editor").into_any()
+                }
+            }
+            EditorTabChild::MarkdownPreview(markdown_preview_id) => {
+                if let Some(markdown_preview) =
+                    markdown_previews.with_untracked(|markdown_previews| {
+                        markdown_previews.get(&markdown_preview_id).cloned()
+                    })
+                {
+                    markdown_preview_view(markdown_preview).into_any()
+                } else {
+                    text("empty markdown preview").into_any()
+                }
+            }
         };
         child.style(|s| s.size_full())
     };
@@ -2558,9 +2598,15 @@ fn palette_item(
         | PaletteItemContent::SshHost { .. }
         | PaletteItemContent::Language { .. }
         | PaletteItemContent::LineEnding { .. }
+        | PaletteItemContent::ReopenWithEncoding { .. }
+        | PaletteItemContent::SaveWithEncoding { .. }
+        | PaletteItemContent::Snippet { .. }
+        | PaletteItemContent::ClipboardHistory { .. }
+        | PaletteItemContent::Macro { .. }
         | PaletteItemContent::ColorTheme { .. }
         | PaletteItemContent::SCMReference { .. }
         | PaletteItemContent::TerminalProfile { .. }
+        | PaletteItemContent::VoltPromptItem { .. }
         | PaletteItemContent::IconTheme { .. } => {
             let text = item.filter_text;
             let indices = item.indices;
@@ -2608,7 +2654,7 @@ fn palette_input(window_tab_data: Rc<WindowTabData>) -> impl View {
     let input = TextInputBuilder::new()
         .is_focused(is_focused)
         .build_editor(editor)
-        .placeholder(move || window_tab_data.palette.placeholder_text().to_owned())
+        .placeholder(move || window_tab_data.palette.placeholder_text())
         .style(|s| s.width_full());
 
     container(container(input).style(move |s| {
@@ -2673,18 +2719,16 @@ fn palette_content(
                 move |(i, item)| {
                     let workspace = workspace.clone();
                     let keymap = {
-                        let cmd_kind = match &item.content {
+                        let cmd_name = match &item.content {
                             PaletteItemContent::PaletteHelp { cmd } => {
-                                Some(CommandKind::Workbench(cmd.clone()))
+                                Some(cmd.to_string())
                             }
-                            PaletteItemContent::Command {
-                                cmd: LapceCommand { kind, .. },
-                            } => Some(kind.clone()),
+                            PaletteItemContent::Command { cmd } => Some(cmd.name()),
                             _ => None,
                         };
 
-                        cmd_kind
-                            .and_then(|kind| keymaps.get(kind.str()))
+                        cmd_name
+                            .and_then(|name| keymaps.get(&name))
                             .and_then(|maps| maps.first())
                     };
                     container(palette_item(
@@ -3711,6 +3755,12 @@ fn window(window_data: WindowData) -> impl View {
 pub fn launch() {
     let cli = Cli::parse();
 
+    if cli.terminal_server {
+        #[cfg(unix)]
+        lapce_proxy::terminal_server::mainloop();
+        return;
+    }
+
     if !cli.wait {
         logging::panic_hook();
     }
@@ -3856,6 +3906,11 @@ pub fn launch() {
             tracing::error!("{:?}", err);
         }
     }
+    if let Some(path) = Directory::snippets_directory() {
+        if let Err(err) = watcher.watch(&path, notify::RecursiveMode::Recursive) {
+            tracing::error!("{:?}", err);
+        }
+    }
 
     let windows = scope.create_rw_signal(im::HashMap::new());
     let config = LapceConfig::load(&LapceWorkspace::default(), &[], &plugin_paths);
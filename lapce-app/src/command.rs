@@ -29,6 +29,7 @@
     editor_tab::EditorTabChild,
     id::EditorTabId,
     main_split::{SplitDirection, SplitMoveDirection, TabCloseKind},
+    plugin::RegisteredVoltCommand,
     workspace::LapceWorkspace,
 };
 
@@ -38,6 +39,33 @@ pub struct LapceCommand {
     pub data: Option<Value>,
 }
 
+impl LapceCommand {
+    /// The name the command is bound by in the keymaps, which for volt
+    /// commands is their id prefixed with the id of the volt.
+    pub fn name(&self) -> String {
+        match self.volt_command() {
+            Some(volt_command) => volt_command.id(),
+            None => self.kind.str().to_string(),
+        }
+    }
+
+    pub fn desc(&self) -> Option<String> {
+        match self.volt_command() {
+            Some(volt_command) => Some(volt_command.command.title),
+            None => self.kind.desc().map(|desc| desc.to_string()),
+        }
+    }
+
+    pub fn volt_command(&self) -> Option<RegisteredVoltCommand> {
+        if self.kind
+            != CommandKind::Workbench(LapceWorkbenchCommand::ExecuteVoltCommand)
+        {
+            return None;
+        }
+        serde_json::from_value(self.data.clone()?).ok()
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum CommandKind {
     Workbench(LapceWorkbenchCommand),
@@ -179,6 +207,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Close Folder")]
     CloseFolder,
 
+    #[strum(serialize = "add_folder_to_workspace")]
+    #[strum(message = "Add Folder to Workspace")]
+    AddFolderToWorkspace,
+
     #[strum(serialize = "open_file")]
     #[strum(message = "Open File")]
     OpenFile,
@@ -221,6 +253,14 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Reveal Active File in File Explorer")]
     RevealActiveFileInFileExplorer,
 
+    #[strum(serialize = "open_in_hex_editor")]
+    #[strum(message = "Open Active File in Hex Editor")]
+    OpenInHexEditor,
+
+    #[strum(serialize = "open_markdown_preview_to_the_side")]
+    #[strum(message = "Open Preview to the Side")]
+    OpenMarkdownPreviewToTheSide,
+
     #[strum(serialize = "open_ui_inspector")]
     #[strum(message = "Open Internal UI Inspector")]
     OpenUIInspector,
@@ -281,6 +321,18 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Open Plugins Directory")]
     OpenPluginsDirectory,
 
+    #[strum(serialize = "install_plugin_from_file")]
+    #[strum(message = "Install Plugin From File")]
+    InstallVoltFromFile,
+
+    #[strum(serialize = "install_plugin_from_directory")]
+    #[strum(message = "Install Plugin From Directory")]
+    InstallVoltFromDirectory,
+
+    #[strum(serialize = "update_all_plugins")]
+    #[strum(message = "Update All Plugins")]
+    UpdateAllVolts,
+
     #[strum(serialize = "open_grammars_directory")]
     #[strum(message = "Open Grammars Directory")]
     OpenGrammarsDirectory,
@@ -325,6 +377,30 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Previous Terminal Tab")]
     PreviousTerminalTab,
 
+    #[strum(serialize = "previous_terminal_prompt")]
+    #[strum(message = "Go To Previous Terminal Prompt")]
+    PreviousTerminalPrompt,
+
+    #[strum(serialize = "next_terminal_prompt")]
+    #[strum(message = "Go To Next Terminal Prompt")]
+    NextTerminalPrompt,
+
+    #[strum(serialize = "copy_terminal_command_output")]
+    #[strum(message = "Copy Terminal Command Output")]
+    CopyTerminalCommandOutput,
+
+    #[strum(serialize = "find_in_terminal")]
+    #[strum(message = "Find in Terminal")]
+    FindInTerminal,
+
+    #[strum(serialize = "save_terminal_scrollback")]
+    #[strum(message = "Save Terminal Scrollback to File")]
+    SaveTerminalScrollback,
+
+    #[strum(serialize = "open_terminal_scrollback")]
+    #[strum(message = "Open Terminal Scrollback in Editor")]
+    OpenTerminalScrollback,
+
     #[strum(serialize = "next_window_tab")]
     #[strum(message = "Go To Next Window Tab")]
     NextWindowTab,
@@ -488,6 +564,23 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_search_visual")]
     ToggleSearchVisual,
 
+    #[strum(message = "Show Language Servers")]
+    #[strum(serialize = "show_language_servers")]
+    ShowLanguageServers,
+
+    #[strum(message = "Restart All Language Servers")]
+    #[strum(serialize = "restart_language_servers")]
+    RestartLanguageServers,
+
+    #[strum(message = "Show Local History")]
+    #[strum(serialize = "show_local_history")]
+    ShowLocalHistory,
+
+    /// Runs a command registered by a volt, with the
+    /// [`RegisteredVoltCommand`] as the data
+    #[strum(serialize = "execute_volt_command")]
+    ExecuteVoltCommand,
+
     #[strum(serialize = "focus_editor")]
     FocusEditor,
 
@@ -532,6 +625,64 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Change current file line ending")]
     ChangeFileLineEnding,
 
+    #[strum(serialize = "reopen_with_encoding")]
+    #[strum(message = "Reopen with Encoding")]
+    ReopenWithEncoding,
+
+    #[strum(serialize = "save_with_encoding")]
+    #[strum(message = "Save with Encoding")]
+    SaveWithEncoding,
+
+    #[strum(serialize = "insert_snippet")]
+    #[strum(message = "Insert Snippet")]
+    InsertSnippet,
+
+    #[strum(serialize = "start_macro_recording")]
+    #[strum(message = "Start Macro Recording")]
+    StartMacroRecording,
+
+    #[strum(serialize = "stop_macro_recording")]
+    #[strum(message = "Stop Macro Recording")]
+    StopMacroRecording,
+
+    #[strum(serialize = "replay_macro")]
+    #[strum(message = "Replay Last Macro")]
+    ReplayMacro,
+
+    #[strum(serialize = "replay_macro_n_times")]
+    #[strum(message = "Replay Last Macro N Times")]
+    ReplayMacroNTimes,
+
+    #[strum(serialize = "replay_macro_on_selected_lines")]
+    #[strum(message = "Replay Last Macro on Each Selected Line")]
+    ReplayMacroOnSelectedLines,
+
+    #[strum(serialize = "save_macro")]
+    #[strum(message = "Save Last Macro")]
+    SaveMacro,
+
+    #[strum(serialize = "run_macro")]
+    #[strum(message = "Run Saved Macro")]
+    RunMacro,
+
+    /// Vim's `q`, which records into the register typed next, or stops the
+    /// recording
+    #[strum(serialize = "record_macro_register")]
+    RecordMacroRegister,
+
+    /// Vim's `@`, which replays the register typed next
+    #[strum(serialize = "replay_macro_register")]
+    ReplayMacroRegister,
+
+    /// Vim's `"`, which chooses the register that the next command yanks to
+    /// or pastes from
+    #[strum(serialize = "select_register")]
+    SelectRegister,
+
+    #[strum(serialize = "paste_from_history")]
+    #[strum(message = "Paste from Clipboard History")]
+    PasteFromHistory,
+
     #[strum(serialize = "next_editor_tab")]
     #[strum(message = "Next Editor Tab")]
     NextEditorTab,
@@ -544,6 +695,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Toggle Inlay Hints")]
     ToggleInlayHints,
 
+    #[strum(serialize = "toggle_minimap")]
+    #[strum(message = "Toggle Minimap")]
+    ToggleMinimap,
+
     #[strum(serialize = "restart_to_update")]
     RestartToUpdate,
 
@@ -619,6 +774,13 @@ pub enum InternalCommand {
         path: PathBuf,
     },
     ReloadFileExplorer,
+    /// Add a root folder to a multi-root workspace
+    AddWorkspaceFolder {
+        path: PathBuf,
+    },
+    RemoveWorkspaceFolder {
+        path: PathBuf,
+    },
     /// Test whether a file/directory can be created at that path
     TestPathCreation {
         new_path: PathBuf,
@@ -779,6 +941,14 @@ pub enum InternalCommand {
         left_path: PathBuf,
         right_path: PathBuf,
     },
+    OpenHexEditor {
+        path: PathBuf,
+    },
+    /// Open a file linked from the preview of the Markdown document at `source`
+    OpenMarkdownLink {
+        source: PathBuf,
+        path: PathBuf,
+    },
     ExecuteProcess {
         program: String,
         arguments: Vec<String>,
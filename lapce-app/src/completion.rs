@@ -6,7 +6,8 @@
     views::editor::{id::EditorId, text::Document},
 };
 use lapce_core::{
-    buffer::rope_text::RopeText, movement::Movement, rope_text_pos::RopeTextPosition,
+    buffer::rope_text::RopeText, language::LapceLanguage, movement::Movement,
+    rope_text_pos::RopeTextPosition,
 };
 use lapce_rpc::{plugin::PluginId, proxy::ProxyRpcHandler};
 use lsp_types::{
@@ -17,6 +18,10 @@
 
 use crate::{config::LapceConfig, editor::EditorData, snippet::Snippet};
 
+/// The plugin id of the completion items of user snippets, which doesn't
+/// belong to any plugin
+pub const SNIPPET_PLUGIN_ID: PluginId = PluginId(0);
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum CompletionStatus {
     Inactive,
@@ -52,6 +57,12 @@ pub struct CompletionData {
     pub input: String,
     /// `(Input, CompletionItems)`
     pub input_items: im::HashMap<String, im::Vector<ScoredCompletionItem>>,
+    /// The items of the user snippets, offered alongside the items from the
+    /// language servers
+    pub snippet_items: im::Vector<ScoredCompletionItem>,
+    /// The items of the user snippets by language, built the first time
+    /// they are offered in it and dropped when the snippets reload
+    pub snippet_cache: im::HashMap<LapceLanguage, im::Vector<ScoredCompletionItem>>,
     /// The filtered items that are being displayed to the user
     pub filtered_items: im::Vector<ScoredCompletionItem>,
     /// The size of the completion element.  
@@ -78,6 +89,8 @@ pub fn new(cx: Scope, config: ReadSignal<Arc<LapceConfig>>) -> Self {
             active,
             input: "".to_string(),
             input_items: im::HashMap::new(),
+            snippet_items: im::Vector::new(),
+            snippet_cache: im::HashMap::new(),
             filtered_items: im::Vector::new(),
             layout_rect: Rect::ZERO,
             matcher: cx
@@ -145,6 +158,7 @@ pub fn cancel(&mut self) {
         self.active.set(0);
         self.input.clear();
         self.input_items.clear();
+        self.snippet_items.clear();
         self.filtered_items.clear();
     }
 
@@ -162,13 +176,16 @@ pub fn update_input(&mut self, input: String) {
     }
 
     fn all_items(&self) -> im::Vector<ScoredCompletionItem> {
-        self.input_items
+        let mut items = self
+            .input_items
             .get(&self.input)
             .cloned()
             .filter(|items| !items.is_empty())
             .unwrap_or_else(move || {
                 self.input_items.get("").cloned().unwrap_or_default()
-            })
+            });
+        items.append(self.snippet_items.clone());
+        items
     }
 
     pub fn filter_items(&mut self) {
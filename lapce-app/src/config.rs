@@ -1,6 +1,7 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     path::{Path, PathBuf},
+    str::FromStr,
     sync::Arc,
 };
 
@@ -28,7 +29,10 @@
     terminal::TerminalConfig,
     ui::UIConfig,
 };
-use crate::workspace::{LapceWorkspace, LapceWorkspaceType};
+use crate::{
+    keypress::macros::MacroStep,
+    workspace::{LapceWorkspace, LapceWorkspaceType},
+};
 
 pub mod color;
 pub mod color_theme;
@@ -106,6 +110,9 @@ pub struct LapceConfig {
     pub color_theme: ColorThemeConfig,
     #[serde(default)]
     pub icon_theme: IconThemeConfig,
+    /// The keyboard macros saved by name
+    #[serde(default)]
+    pub macros: BTreeMap<String, Vec<MacroStep>>,
     #[serde(flatten)]
     pub plugins: HashMap<String, HashMap<String, serde_json::Value>>,
     #[serde(skip)]
@@ -231,9 +238,34 @@ fn merge_config(
             LapceWorkspaceType::RemoteWSL(_) => {}
         }
 
+        // Only the user settings can change where plugins are downloaded from,
+        // not the settings of a workspace or a theme
+        if let Some(registry) = Self::user_plugin_registry() {
+            config = config::Config::builder()
+                .add_source(config.clone())
+                .set_override("core.plugin-registry", registry)
+                .and_then(|builder| builder.build())
+                .unwrap_or_else(|_| config.clone());
+        }
+
         config
     }
 
+    /// The plugin registry from the default and the user settings.
+    fn user_plugin_registry() -> Option<String> {
+        let mut builder =
+            config::Config::builder().add_source(DEFAULT_CONFIG.clone());
+        if let Some(path) = Self::settings_file() {
+            builder = builder
+                .add_source(config::File::from(path.as_path()).required(false));
+        }
+        builder
+            .build()
+            .ok()?
+            .get_string("core.plugin-registry")
+            .ok()
+    }
+
     fn update_id(&mut self) {
         self.id = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -363,6 +395,16 @@ pub fn color(&self, name: &str) -> Color {
         }
     }
 
+    /// Resolve a color given either as the name of a theme color or as a hex
+    /// color, e.g. by a volt.
+    pub fn color_or_hex(&self, color: &str) -> Option<Color> {
+        self.color
+            .ui
+            .get(color)
+            .copied()
+            .or_else(|| Color::from_str(color).ok())
+    }
+
     /// Retrieve a color value whose key starts with "style."
     pub fn style_color(&self, name: &str) -> Option<Color> {
         self.color.syntax.get(name).copied()
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use structdesc::FieldNames;
 
@@ -22,4 +24,12 @@ pub struct CoreConfig {
         desc = "Enable auto-reload for the plugin when its configuration changes."
     )]
     pub auto_reload_plugin: bool,
+    #[field_names(
+        desc = "The URL of the plugin registry, e.g. an internal mirror of the public one. Only read from the user settings"
+    )]
+    pub plugin_registry: String,
+    #[field_names(
+        desc = "Plugins pinned to a version, by author.name, which are never updated past it. Pin a plugin from its options in the plugin panel"
+    )]
+    pub pinned_plugins: HashMap<String, String>,
 }
@@ -80,6 +80,10 @@ pub struct EditorConfig {
     pub show_tab: bool,
     #[field_names(desc = "If navigation breadcrumbs are shown for the file")]
     pub show_bread_crumbs: bool,
+    #[field_names(
+        desc = "If a minimap of the file is shown beside the editor, drawn with the code glance font size"
+    )]
+    pub show_minimap: bool,
     #[field_names(desc = "If the editor can scroll beyond the last line")]
     pub scroll_beyond_last_line: bool,
     #[field_names(
@@ -222,6 +226,26 @@ pub struct EditorConfig {
         desc = "Whether the document should be formatted when an autosave is triggered (required Format on Save)"
     )]
     pub format_on_autosave: bool,
+    #[field_names(
+        desc = "Whether unsaved changes are backed up and restored when the workspace reopens, so they survive quitting or a crash"
+    )]
+    pub hot_exit: bool,
+    #[field_names(
+        desc = "How many snapshots of each file the local history keeps. Set to 0 to disable the local history."
+    )]
+    pub local_history_max_entries: usize,
+    #[field_names(
+        desc = "How many days the local history keeps snapshots for. Set to 0 to keep them regardless of age."
+    )]
+    pub local_history_max_days: u64,
+    #[field_names(
+        desc = "Files larger than this many megabytes open in large file mode, which reads them from disk a chunk at a time and turns off syntax highlighting, inlay hints, language servers and backups of unsaved changes. Set to 0 to disable."
+    )]
+    pub large_file_size: u64,
+    #[field_names(
+        desc = "How many copied texts the clipboard history keeps for pasting from history."
+    )]
+    pub clipboard_history_size: usize,
     #[field_names(
         desc = "If enabled the cursor treats leading soft tabs as if they are hard tabs."
     )]
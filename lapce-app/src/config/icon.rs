@@ -115,6 +115,10 @@ impl LapceIcons {
 
     pub const IMPLEMENTATION: &'static str = "implementation";
 
+    pub const LANGUAGE_SERVER: &'static str = "language_server";
+
+    pub const LOCAL_HISTORY: &'static str = "local_history";
+
     pub const SYMBOL_KIND_ARRAY: &'static str = "symbol_kind.array";
     pub const SYMBOL_KIND_BOOLEAN: &'static str = "symbol_kind.boolean";
     pub const SYMBOL_KIND_CLASS: &'static str = "symbol_kind.class";
@@ -100,6 +100,8 @@ pub fn core_config_view(&self) -> CoreConfigView {
             custom_titlebar: self.config.core.custom_titlebar,
             file_explorer_double_click: self.config.core.file_explorer_double_click,
             auto_reload_plugin: self.config.core.auto_reload_plugin,
+            plugin_registry: self.config.core.plugin_registry.clone(),
+            pinned_plugins: self.config.core.pinned_plugins.clone(),
         }
     }
 }
@@ -129,6 +131,14 @@ fn auto_reload_plugin(&self) -> bool {
         self.config.core.auto_reload_plugin
     }
 
+    fn plugin_registry(&self) -> &str {
+        &self.config.core.plugin_registry
+    }
+
+    fn pinned_plugins(&self) -> &HashMap<String, String> {
+        &self.config.core.pinned_plugins
+    }
+
     fn core_config(&self) -> &CoreConfig {
         &self.config.core
     }
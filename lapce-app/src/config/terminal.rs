@@ -19,6 +19,18 @@ pub struct TerminalConfig {
         desc = "Set the terminal line height, If 0, it uses editor line height"
     )]
     pub line_height: f64,
+    #[field_names(
+        desc = "Load the shell integration into bash, zsh and fish, which marks each command with its exit code and allows jumping between prompts"
+    )]
+    pub shell_integration: bool,
+    #[field_names(
+        desc = "Show the errors and warnings printed in run and debug terminals in the Problems panel"
+    )]
+    pub run_problems: bool,
+    #[field_names(
+        desc = "Keep the shells of terminals running in a background process when the window is closed or reloaded, and reattach to them when the workspace is opened again. Not available on Windows"
+    )]
+    pub persistent_sessions: bool,
 
     #[field_names(skip)]
     pub profiles: HashMap<String, TerminalProfile>,
@@ -96,6 +108,7 @@ pub fn get_default_profile(
             arguments: profile.arguments,
             workdir,
             environment: profile.environment,
+            ..Default::default()
         })
     }
 }
@@ -35,6 +35,12 @@ pub trait CoreConfigProvider {
     /// Get auto-reload plugin setting
     fn auto_reload_plugin(&self) -> bool;
     
+    /// Get the plugin registry URL
+    fn plugin_registry(&self) -> &str;
+    
+    /// Get the versions plugins are pinned to
+    fn pinned_plugins(&self) -> &HashMap<String, String>;
+    
     /// Get the full core configuration
     fn core_config(&self) -> &CoreConfig;
 }
@@ -266,4 +272,6 @@ pub struct CoreConfigView {
     pub custom_titlebar: bool,
     pub file_explorer_double_click: bool,
     pub auto_reload_plugin: bool,
+    pub plugin_registry: String,
+    pub pinned_plugins: HashMap<String, String>,
 }
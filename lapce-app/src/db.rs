@@ -13,11 +13,12 @@
 
 use crate::{
     app::{AppData, AppInfo},
-    doc::DocInfo,
+    doc::{DocBackup, DocInfo},
     panel::{data::PanelOrder, kind::PanelKind},
+    terminal::panel::TerminalSessionsInfo,
     window::{WindowData, WindowInfo},
     window_tab::WindowTabData,
-    workspace::{LapceWorkspace, WorkspaceInfo},
+    workspace::{LapceWorkspace, LapceWorkspaceType, WorkspaceInfo},
 };
 
 const APP: &str = "app";
@@ -27,6 +28,7 @@
 const PANEL_ORDERS: &str = "panel_orders";
 const DISABLED_VOLTS: &str = "disabled_volts";
 const RECENT_WORKSPACES: &str = "recent_workspaces";
+const TERMINAL_SESSIONS: &str = "terminal_sessions";
 
 pub enum SaveEvent {
     App(AppInfo),
@@ -36,6 +38,8 @@ pub enum SaveEvent {
     DisabledVolts(Vec<VoltID>),
     WorkspaceDisabledVolts(Arc<LapceWorkspace>, Vec<VoltID>),
     PanelOrder(PanelOrder),
+    TerminalSessions(Arc<LapceWorkspace>, TerminalSessionsInfo),
+    Backups(LapceWorkspace, Vec<DocBackup>),
 }
 
 #[derive(Clone)]
@@ -110,6 +114,20 @@ pub fn new() -> Result<Self> {
                                 tracing::error!("{:?}", err);
                             }
                         }
+                        SaveEvent::TerminalSessions(workspace, sessions) => {
+                            if let Err(err) = local_db
+                                .insert_terminal_sessions(&workspace, &sessions)
+                            {
+                                tracing::error!("{:?}", err);
+                            }
+                        }
+                        SaveEvent::Backups(workspace, backups) => {
+                            if let Err(err) =
+                                local_db.insert_backups(&workspace, &backups)
+                            {
+                                tracing::error!("{:?}", err);
+                            }
+                        }
                     }
                 }
             })
@@ -182,6 +200,20 @@ pub fn recent_workspaces(&self) -> Result<Vec<LapceWorkspace>> {
         Ok(workspaces)
     }
 
+    /// The additional root folders last used with the folder at `path`.
+    pub fn recent_workspace_folders(
+        &self,
+        kind: &LapceWorkspaceType,
+        path: &Path,
+    ) -> Vec<PathBuf> {
+        self.recent_workspaces()
+            .unwrap_or_default()
+            .into_iter()
+            .find(|w| w.path.as_deref() == Some(path) && &w.kind == kind)
+            .map(|w| w.folders)
+            .unwrap_or_default()
+    }
+
     pub fn update_recent_workspace(&self, workspace: &LapceWorkspace) -> Result<()> {
         if workspace.path.is_none() {
             return Ok(());
@@ -197,6 +229,7 @@ fn insert_recent_workspace(&self, workspace: LapceWorkspace) -> Result<()> {
         let mut exits = false;
         for w in workspaces.iter_mut() {
             if w.path == workspace.path && w.kind == workspace.kind {
+                w.folders.clone_from(&workspace.folders);
                 w.last_open = std::time::SystemTime::now()
                     .duration_since(std::time::UNIX_EPOCH)
                     .unwrap()
@@ -221,12 +254,19 @@ fn insert_recent_workspace(&self, workspace: LapceWorkspace) -> Result<()> {
     }
 
     pub fn save_window_tab(&self, data: Rc<WindowTabData>) -> Result<()> {
-        let workspace = (*data.workspace).clone();
+        let workspace = data.current_workspace();
         let workspace_info = data.workspace_info();
 
+        if workspace.path.is_some()
+            && data.common.config.get_untracked().editor.hot_exit
+        {
+            self.save_tx.send(SaveEvent::Backups(
+                workspace.clone(),
+                data.main_split.backups(),
+            ))?;
+        }
         self.save_tx
             .send(SaveEvent::Workspace(workspace, workspace_info))?;
-        // self.insert_unsaved_buffer(main_split)?;
 
         Ok(())
     }
@@ -359,12 +399,81 @@ pub fn insert_window_tab(&self, data: Rc<WindowTabData>) -> Result<()> {
         let workspace = (*data.workspace).clone();
         let workspace_info = data.workspace_info();
 
+        if workspace.path.is_some()
+            && data.common.config.get_untracked().editor.hot_exit
+        {
+            self.insert_backups(&workspace, &data.main_split.backups())?;
+        }
         self.insert_workspace(&workspace, &workspace_info)?;
-        // self.insert_unsaved_buffer(main_split)?;
 
         Ok(())
     }
 
+    pub fn get_terminal_sessions(
+        &self,
+        workspace: &LapceWorkspace,
+    ) -> Result<TerminalSessionsInfo> {
+        let folder = self.workspace_folder.join(workspace_folder_name(workspace));
+        let sessions = std::fs::read_to_string(folder.join(TERMINAL_SESSIONS))?;
+        let sessions: TerminalSessionsInfo = serde_json::from_str(&sessions)?;
+        Ok(sessions)
+    }
+
+    pub fn save_terminal_sessions(
+        &self,
+        workspace: Arc<LapceWorkspace>,
+        sessions: TerminalSessionsInfo,
+    ) {
+        if let Err(err) = self
+            .save_tx
+            .send(SaveEvent::TerminalSessions(workspace, sessions))
+        {
+            tracing::error!("{:?}", err);
+        }
+    }
+
+    fn insert_terminal_sessions(
+        &self,
+        workspace: &LapceWorkspace,
+        sessions: &TerminalSessionsInfo,
+    ) -> Result<()> {
+        let folder = self.workspace_folder.join(workspace_folder_name(workspace));
+        if let Err(err) = std::fs::create_dir_all(&folder) {
+            tracing::error!("{:?}", err);
+        }
+        let sessions = serde_json::to_string_pretty(sessions)?;
+        std::fs::write(folder.join(TERMINAL_SESSIONS), sessions)?;
+        Ok(())
+    }
+
+    pub fn get_backups(&self, workspace: &LapceWorkspace) -> Result<Vec<DocBackup>> {
+        let backups = std::fs::read_to_string(backups_path(workspace)?)?;
+        let backups: Vec<DocBackup> = serde_json::from_str(&backups)?;
+        Ok(backups)
+    }
+
+    pub fn save_backups(&self, workspace: LapceWorkspace, backups: Vec<DocBackup>) {
+        if let Err(err) = self.save_tx.send(SaveEvent::Backups(workspace, backups)) {
+            tracing::error!("{:?}", err);
+        }
+    }
+
+    fn insert_backups(
+        &self,
+        workspace: &LapceWorkspace,
+        backups: &[DocBackup],
+    ) -> Result<()> {
+        let path = backups_path(workspace)?;
+        if backups.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+        let backups = serde_json::to_string(backups)?;
+        write_atomically(&path, backups.as_bytes())
+    }
+
     pub fn get_panel_orders(&self) -> Result<PanelOrder> {
         let panel_orders = std::fs::read_to_string(self.folder.join(PANEL_ORDERS))?;
         let mut panel_orders: PanelOrder = serde_json::from_str(&panel_orders)?;
@@ -445,8 +554,85 @@ fn workspace_folder_name(workspace: &LapceWorkspace) -> String {
         .finish()
 }
 
+/// The backups of the unsaved documents of a workspace are kept in the data
+/// directory, rather than with the settings.
+fn backups_path(workspace: &LapceWorkspace) -> Result<PathBuf> {
+    let folder = Directory::backups_directory()
+        .ok_or_else(|| anyhow!("can't get backups directory"))?;
+    Ok(folder.join(workspace_folder_name(workspace)))
+}
+
 fn doc_path_name(path: &Path) -> String {
     let mut hasher = Sha256::new();
     hasher.update(path.to_string_lossy().as_bytes());
     format!("{:x}", hasher.finalize())
 }
+
+/// Write the file through a temporary file next to it, so that a crash while
+/// writing doesn't leave it cut off.
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_rpc::buffer::BufferId;
+
+    use super::*;
+    use crate::doc::DocContent;
+
+    #[test]
+    fn test_write_atomically() {
+        let dir = std::env::temp_dir().join(format!(
+            "lapce-test-write-atomically-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("backups");
+
+        write_atomically(&path, b"first").unwrap();
+        write_atomically(&path, b"second").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert!(!path.with_extension("tmp").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_backups_roundtrip() {
+        let backups = vec![
+            DocBackup {
+                content: DocContent::File {
+                    path: PathBuf::from("/tmp/main.rs"),
+                    read_only: false,
+                },
+                text: "fn main() {}".to_string(),
+                disk_hash: Some("abc".to_string()),
+            },
+            DocBackup {
+                content: DocContent::Scratch {
+                    id: BufferId::next(),
+                    name: "Untitled-3".to_string(),
+                },
+                text: "notes".to_string(),
+                disk_hash: None,
+            },
+        ];
+        let json = serde_json::to_string(&backups).unwrap();
+        let restored: Vec<DocBackup> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(
+            restored[0].content.path(),
+            Some(&PathBuf::from("/tmp/main.rs"))
+        );
+        assert_eq!(restored[0].disk_hash.as_deref(), Some("abc"));
+        let DocContent::Scratch { name, .. } = &restored[1].content else {
+            panic!("the scratch backup should stay a scratch document");
+        };
+        assert_eq!(name, "Untitled-3");
+        assert_eq!(restored[1].text, "notes");
+    }
+}
@@ -56,8 +56,8 @@
     word::{CharClassification, WordCursor, get_char_property},
 };
 use lapce_rpc::{
-    buffer::BufferId,
-    plugin::PluginId,
+    buffer::{BufferId, FileEncoding},
+    plugin::{PluginId, VoltDecoration, VoltID},
     proxy::ProxyResponse,
     style::{LineStyle, LineStyles, Style},
 };
@@ -73,11 +73,12 @@
 use smallvec::SmallVec;
 
 use crate::{
-    command::{CommandKind, LapceCommand},
+    alert::AlertButton,
+    command::{CommandKind, InternalCommand, LapceCommand},
     config::{LapceConfig, color::LapceColor},
     editor::{EditorData, compute_screen_lines, gutter::FoldingRanges},
     find::{Find, FindProgress, FindResult},
-    history::DocumentHistory,
+    history::{DocumentHistory, LocalHistorySource},
     keypress::KeyPressFocus,
     main_split::Editors,
     panel::{
@@ -155,6 +156,16 @@ pub struct DocInfo {
     pub cursor_offset: usize,
 }
 
+/// An unsaved document, backed up so that its changes survive quitting or a
+/// crash
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DocBackup {
+    pub content: DocContent,
+    pub text: String,
+    /// The hash of the file on disk the changes were made to
+    pub disk_hash: Option<String>,
+}
+
 /// (Offset -> (Plugin the code actions are from, Code Actions))
 pub type CodeActions =
     im::HashMap<usize, (PluginId, im::Vector<CodeActionOrCommand>)>;
@@ -170,10 +181,23 @@ pub struct Doc {
     /// Whether the buffer's content has been loaded/initialized into the buffer.
     pub loaded: RwSignal<bool>,
     pub buffer: RwSignal<Buffer>,
+    /// The encoding of the file on disk
+    pub encoding: RwSignal<FileEncoding>,
+    /// Whether the file is opened in large file mode, which leaves out syntax
+    /// highlighting, inlay hints and language server features
+    pub large_file: RwSignal<bool>,
+    /// The hash of the file on disk, as last loaded or saved
+    pub disk_hash: RwSignal<Option<String>>,
+    /// The content of the file on disk, as last loaded or saved, which goes
+    /// to the local history before a save replaces it
+    pub disk_content: RwSignal<Option<Rope>>,
     pub syntax: RwSignal<Syntax>,
     semantic_styles: RwSignal<Option<Spans<Style>>>,
     /// Inlay hints for the document
     pub inlay_hints: RwSignal<Option<Spans<InlayHint>>>,
+    /// Decorations set by volts, keyed by the volt and the key it set them under
+    pub volt_decorations:
+        RwSignal<im::HashMap<(VoltID, String), Spans<VoltDecoration>>>,
     /// Current completion lens text, if any.
     /// This will be displayed even on views that are not focused.
     pub completion_lens: RwSignal<Option<String>>,
@@ -238,6 +262,7 @@ pub fn new(
             ))),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
+            volt_decorations: cx.create_rw_signal(im::HashMap::new()),
             diagnostics,
             completion_lens: cx.create_rw_signal(None),
             completion_pos: cx.create_rw_signal((0, 0)),
@@ -249,6 +274,10 @@ pub fn new(
                 read_only: false,
             }),
             loaded: cx.create_rw_signal(false),
+            encoding: cx.create_rw_signal(FileEncoding::default()),
+            large_file: cx.create_rw_signal(false),
+            disk_hash: cx.create_rw_signal(None),
+            disk_content: cx.create_rw_signal(None),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
@@ -288,6 +317,7 @@ pub fn new_content(
             ))),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
+            volt_decorations: cx.create_rw_signal(im::HashMap::new()),
             diagnostics: DiagnosticData {
                 expanded: cx.create_rw_signal(true),
                 diagnostics: cx.create_rw_signal(im::Vector::new()),
@@ -303,6 +333,10 @@ pub fn new_content(
             head_changes: cx.create_rw_signal(im::Vector::new()),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
             loaded: cx.create_rw_signal(true),
+            encoding: cx.create_rw_signal(FileEncoding::default()),
+            large_file: cx.create_rw_signal(false),
+            disk_hash: cx.create_rw_signal(None),
+            disk_content: cx.create_rw_signal(None),
             find_result: FindResult::new(cx),
             code_actions: cx.create_rw_signal(im::HashMap::new()),
             preedit: PreeditData::new(cx),
@@ -339,6 +373,7 @@ pub fn new_history(
             ))),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
+            volt_decorations: cx.create_rw_signal(im::HashMap::new()),
             diagnostics: DiagnosticData {
                 expanded: cx.create_rw_signal(true),
                 diagnostics: cx.create_rw_signal(im::Vector::new()),
@@ -352,6 +387,10 @@ pub fn new_history(
             content: cx.create_rw_signal(content),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
             loaded: cx.create_rw_signal(true),
+            encoding: cx.create_rw_signal(FileEncoding::default()),
+            large_file: cx.create_rw_signal(false),
+            disk_hash: cx.create_rw_signal(None),
+            disk_content: cx.create_rw_signal(None),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
             code_actions: cx.create_rw_signal(im::HashMap::new()),
@@ -451,12 +490,102 @@ pub fn init_content(&self, content: Rope) {
             });
             self.loaded.set(true);
             self.on_update(None);
-            self.init_parser();
+            if !self.large_file.get_untracked() {
+                self.init_parser();
+                self.retrieve_head();
+            }
             self.init_diagnostics();
-            self.retrieve_head();
         });
     }
 
+    /// Receive the rest of a file opened in large file mode from `offset`, a
+    /// chunk at a time as the proxy reads it from disk, keeping the document
+    /// read only until it is done.
+    pub fn load_large_file(
+        &self,
+        offset: usize,
+        read_only: bool,
+        unsaved: Option<String>,
+    ) {
+        self.load_chunks(offset, Rope::default(), read_only, unsaved);
+    }
+
+    /// Request the chunk at `offset`, adding the chunks received to `pending`
+    /// until they are worth adding to the document.
+    fn load_chunks(
+        &self,
+        offset: usize,
+        mut pending: Rope,
+        read_only: bool,
+        unsaved: Option<String>,
+    ) {
+        let DocContent::File { path, .. } = self.content.get_untracked() else {
+            return;
+        };
+        let doc = self.clone();
+        let send = create_ext_action(self.scope, move |result| match result {
+            Ok(ProxyResponse::LoadBufferChunkResponse { content, done }) => {
+                let offset = offset + content.len();
+                let len = pending.len();
+                pending.edit(len..len, content);
+                // The document is only built again once it would at least
+                // double, so that loading stays linear in the size of the file.
+                let doc_len = doc.buffer.with_untracked(|buffer| buffer.len());
+                if done || pending.len() >= doc_len {
+                    doc.append_chunks(std::mem::take(&mut pending), done);
+                }
+                if !done {
+                    doc.load_chunks(offset, pending, read_only, unsaved);
+                    return;
+                }
+                doc.content.update(|content| {
+                    if let DocContent::File {
+                        read_only: doc_read_only,
+                        ..
+                    } = content
+                    {
+                        *doc_read_only = read_only;
+                    }
+                });
+                if let Some(unsaved) = unsaved {
+                    doc.reload(Rope::from(unsaved), false);
+                }
+            }
+            Err(err) => {
+                tracing::error!("{:?}", err);
+            }
+            Ok(_) => {}
+        });
+        self.common
+            .proxy
+            .load_buffer_chunk(path, offset, move |result| {
+                send(result);
+            });
+    }
+
+    /// Append chunks of a file that is loading in large file mode. The buffer
+    /// is initialized again, rather than edited, so that loading doesn't add
+    /// to the undo history or mark the document as changed. The indentation
+    /// is detected once the whole file is there, as the document can't be
+    /// edited before.
+    fn append_chunks(&self, chunks: Rope, done: bool) {
+        let mut content = self.buffer.with_untracked(|buffer| buffer.text().clone());
+        let len = content.len();
+        content.edit(len..len, chunks);
+        self.syntax.with_untracked(|syntax| {
+            self.buffer.update(|buffer| {
+                *buffer = Buffer::new("");
+                buffer.init_content(content);
+                if done {
+                    buffer.detect_indent(|| {
+                        IndentStyle::from_str(syntax.language.indent_unit())
+                    });
+                }
+            });
+        });
+        self.on_update(None);
+    }
+
     fn init_parser(&self) {
         let code = self.buffer.get_untracked().to_string();
         self.syntax.with_untracked(|syntax| {
@@ -488,12 +617,38 @@ pub fn reload(&self, content: Rope, set_pristine: bool) {
         self.apply_deltas(&[delta]);
     }
 
-    pub fn handle_file_changed(&self, content: Rope) {
+    pub fn handle_file_changed(&self, content: Rope, disk_hash: Option<String>) {
         if self.is_pristine() {
+            self.record_local_history(LocalHistorySource::ExternalChange);
+            self.disk_hash.set(disk_hash);
+            self.disk_content.set(Some(content.clone()));
             self.reload(content, true);
         }
     }
 
+    /// Record a snapshot of the content the file had on disk in the local
+    /// history.
+    pub fn record_local_history(&self, source: LocalHistorySource) {
+        let DocContent::File { path, .. } = self.content.get_untracked() else {
+            return;
+        };
+        if !self.loaded() || self.large_file.get_untracked() {
+            return;
+        }
+        let Some(content) = self.disk_content.get_untracked() else {
+            return;
+        };
+        let config = self.common.config.get_untracked();
+        self.common.local_history.record(
+            self.scope,
+            path,
+            content.to_string(),
+            source,
+            config.editor.local_history_max_entries,
+            config.editor.local_history_max_days,
+        );
+    }
+
     pub fn do_insert(
         &self,
         cursor: &mut Cursor,
@@ -601,6 +756,7 @@ pub fn apply_deltas(&self, deltas: &[(Rope, RopeDelta, InvalLines)]) {
             for (i, (_, delta, inval)) in deltas.iter().enumerate() {
                 self.update_styles(delta);
                 self.update_inlay_hints(delta);
+                self.update_volt_decorations(delta);
                 self.update_diagnostics(delta);
                 self.update_completion_lens(delta);
                 self.update_find_result(delta);
@@ -645,15 +801,20 @@ pub fn line_ending(&self) -> LineEnding {
 
     fn on_update(&self, edits: Option<SmallVec<[SyntaxEdit; 3]>>) {
         batch(|| {
+            self.check_auto_save();
+            self.find_result.reset();
+            self.clear_code_actions();
+            self.clear_style_cache();
+            // Large files leave out everything that goes through the whole
+            // text.
+            if self.large_file.get_untracked() {
+                return;
+            }
             self.trigger_syntax_change(edits);
             self.trigger_head_change();
-            self.check_auto_save();
             self.get_inlay_hints();
-            self.find_result.reset();
             self.get_semantic_styles();
             self.do_bracket_colorization();
-            self.clear_code_actions();
-            self.clear_style_cache();
             self.get_code_lens();
             self.get_document_symbol();
             self.get_folding_range();
@@ -775,6 +936,62 @@ fn update_inlay_hints(&self, delta: &RopeDelta) {
         });
     }
 
+    fn update_volt_decorations(&self, delta: &RopeDelta) {
+        if self.volt_decorations.with_untracked(|d| d.is_empty()) {
+            return;
+        }
+        self.volt_decorations.update(|decorations| {
+            for spans in decorations.values_mut() {
+                spans.apply_shape(delta);
+            }
+        });
+    }
+
+    /// Replace the decorations a volt set under `key`, an empty list removes them.
+    pub fn set_volt_decorations(
+        &self,
+        volt_id: VoltID,
+        key: String,
+        mut decorations: Vec<VoltDecoration>,
+    ) {
+        if decorations.is_empty() {
+            self.volt_decorations.update(|d| {
+                d.remove(&(volt_id, key));
+            });
+        } else {
+            decorations.sort_by(|a, b| a.range.start.cmp(&b.range.start));
+            let spans = self.buffer.with_untracked(|buffer| {
+                let len = buffer.len();
+                let mut spans = SpansBuilder::new(len);
+                for decoration in decorations {
+                    let start = buffer.offset_of_position(&decoration.range.start);
+                    let end = buffer.offset_of_position(&decoration.range.end);
+                    spans.add_span(
+                        Interval::new(start.min(len), end.min(len)),
+                        decoration,
+                    );
+                }
+                spans.build()
+            });
+            self.volt_decorations.update(|d| {
+                d.insert((volt_id, key), spans);
+            });
+        }
+        self.clear_text_cache();
+    }
+
+    /// Remove every decoration set by a volt, e.g. once it was removed.
+    pub fn clear_volt_decorations(&self, volt_id: &VoltID) {
+        if self
+            .volt_decorations
+            .with_untracked(|d| d.keys().any(|(id, _)| id == volt_id))
+        {
+            self.volt_decorations
+                .update(|d| d.retain(|(id, _), _| id != volt_id));
+            self.clear_text_cache();
+        }
+    }
+
     pub fn trigger_syntax_change(&self, edits: Option<SmallVec<[SyntaxEdit; 3]>>) {
         let (rev, text) =
             self.buffer.with_untracked(|b| (b.rev(), b.text().clone()));
@@ -858,8 +1075,12 @@ pub fn line_style(&self, line: usize) -> Arc<Vec<LineStyle>> {
     }
 
     /// Request semantic styles for the buffer from the LSP through the proxy.
+    /// Before the document has any, the visible lines are requested first so
+    /// large files are highlighted quickly, and the whole document after they
+    /// arrived. Only one request is made at a time.
     pub fn get_semantic_styles(&self) {
-        if !self.loaded() {
+        // Large files aren't opened in the language servers
+        if !self.loaded() || self.large_file.get_untracked() {
             return;
         }
 
@@ -870,50 +1091,132 @@ pub fn get_semantic_styles(&self) {
                 return;
             };
 
+        let lines = if self.semantic_styles.with_untracked(|s| s.is_none()) {
+            self.visible_lines().filter(|(start_line, end_line)| {
+                let last_line = self.buffer.with_untracked(|b| b.last_line());
+                *start_line > 0 || *end_line < last_line
+            })
+        } else {
+            None
+        };
+        self.request_semantic_styles(
+            path,
+            lines.map(|(start_line, end_line)| (start_line, end_line + 1)),
+        );
+    }
+
+    /// The first and last line shown by the editors of this document.
+    fn visible_lines(&self) -> Option<(usize, usize)> {
+        self.editors.with_editors_untracked(|editors| {
+            editors
+                .values()
+                .filter(|editor| editor.doc().buffer_id == self.buffer_id)
+                .filter_map(|editor| {
+                    editor.screen_lines().with_untracked(|screen_lines| {
+                        let start = screen_lines.lines.first()?.line;
+                        let end = screen_lines.lines.last()?.line;
+                        Some((start, end))
+                    })
+                })
+                .reduce(|(start_a, end_a), (start_b, end_b)| {
+                    (start_a.min(start_b), end_a.max(end_b))
+                })
+        })
+    }
+
+    /// Request the semantic styles of the whole document, or only of the lines
+    /// `lines.0..lines.1` which are then merged into the current styles and
+    /// followed by a request for the whole document.
+    fn request_semantic_styles(&self, path: PathBuf, lines: Option<(usize, usize)>) {
         let (atomic_rev, rev, len) = self
             .buffer
             .with_untracked(|b| (b.atomic_rev(), b.rev(), b.len()));
+        let (range, offsets) = match lines {
+            Some((start_line, end_line)) => self.buffer.with_untracked(|b| {
+                let range = lsp_types::Range {
+                    start: lsp_types::Position::new(start_line as u32, 0),
+                    end: lsp_types::Position::new(end_line as u32, 0),
+                };
+                let offsets =
+                    (b.offset_of_line(start_line), b.offset_of_line(end_line));
+                (Some(range), Some(offsets))
+            }),
+            None => (None, None),
+        };
+        // The styles outside of the requested range are kept
+        let current_styles = if offsets.is_some() {
+            self.semantic_styles.get_untracked()
+        } else {
+            None
+        };
 
         let doc = self.clone();
+        let full_path = path.clone();
         let send = create_ext_action(self.scope, move |styles| {
+            let current = doc.buffer.with_untracked(|b| b.rev()) == rev;
             if let Some(styles) = styles {
-                if doc.buffer.with_untracked(|b| b.rev()) == rev {
+                if current {
                     doc.semantic_styles.set(Some(styles));
                     doc.clear_style_cache();
                 }
             }
+            // A newer edit requests the styles again by itself
+            if lines.is_some() && current {
+                doc.request_semantic_styles(full_path, None);
+            }
         });
 
-        self.common.proxy.get_semantic_tokens(path, move |result| {
-            if let Ok(ProxyResponse::GetSemanticTokens { styles }) = result {
-                if styles.styles.is_empty() {
-                    send(None);
-                    return;
-                }
-                if atomic_rev.load(atomic::Ordering::Acquire) != rev {
-                    send(None);
-                    return;
-                }
-                std::thread::spawn(move || {
-                    let mut styles_span = SpansBuilder::new(len);
-                    for style in styles.styles {
-                        if atomic_rev.load(atomic::Ordering::Acquire) != rev {
-                            send(None);
-                            return;
-                        }
-                        styles_span.add_span(
-                            Interval::new(style.start, style.end),
-                            style.style,
-                        );
+        self.common
+            .proxy
+            .get_semantic_tokens(path, range, move |result| {
+                if let Ok(ProxyResponse::GetSemanticTokens { styles }) = result {
+                    if styles.styles.is_empty() && offsets.is_none() {
+                        send(None);
+                        return;
                     }
+                    if atomic_rev.load(atomic::Ordering::Acquire) != rev {
+                        send(None);
+                        return;
+                    }
+                    std::thread::spawn(move || {
+                        let mut styles_span = SpansBuilder::new(len);
+                        let (start_offset, mut end_offset) =
+                            offsets.unwrap_or((0, len));
+                        let current_styles = current_styles.as_ref();
+                        let current_styles = || {
+                            current_styles
+                                .into_iter()
+                                .flat_map(|styles| styles.iter())
+                        };
+                        for (iv, style) in current_styles()
+                            .filter(|(iv, _)| iv.end() <= start_offset)
+                        {
+                            styles_span.add_span(iv, style.clone());
+                        }
+                        for style in styles.styles {
+                            if atomic_rev.load(atomic::Ordering::Acquire) != rev {
+                                send(None);
+                                return;
+                            }
+                            styles_span.add_span(
+                                Interval::new(style.start, style.end),
+                                style.style,
+                            );
+                            end_offset = end_offset.max(style.end);
+                        }
+                        for (iv, style) in current_styles()
+                            .filter(|(iv, _)| iv.start() >= end_offset)
+                        {
+                            styles_span.add_span(iv, style.clone());
+                        }
 
-                    let styles = styles_span.build();
-                    send(Some(styles));
-                });
-            } else {
-                send(None);
-            }
-        });
+                        let styles = styles_span.build();
+                        send(Some(styles));
+                    });
+                } else {
+                    send(None);
+                }
+            });
     }
 
     pub fn get_code_lens(&self) {
@@ -1402,25 +1705,140 @@ pub fn save(&self, after_action: impl FnOnce() + 'static) {
         let content = self.content.get_untracked();
         if let DocContent::File { path, .. } = content {
             let rev = self.rev();
+            let doc = self.clone();
+            let saved_path = path.clone();
             let buffer = self.buffer;
-            let send = create_ext_action(self.scope, move |result| {
-                if let Ok(ProxyResponse::SaveResponse {}) = result {
+            let disk_hash = self.disk_hash;
+            let disk_content = self.disk_content;
+            let saved_content =
+                buffer.with_untracked(|buffer| buffer.text().clone());
+            let internal_command = self.common.internal_command;
+            let send = create_ext_action(self.scope, move |result| match result {
+                Ok(ProxyResponse::SaveResponse {
+                    disk_hash: saved_hash,
+                }) => {
+                    disk_hash.set(saved_hash);
+                    disk_content.set(Some(saved_content));
                     let current_rev = buffer.with_untracked(|buffer| buffer.rev());
                     if current_rev == rev {
                         buffer.update(|buffer| {
                             buffer.set_pristine();
                         });
+                        if saved_path.parent().is_some_and(|dir| {
+                            doc.common.snippets_dirs().iter().any(|d| d == dir)
+                        }) {
+                            doc.common.reload_snippets();
+                        }
                         after_action();
                     }
                 }
+                Err(err) => {
+                    internal_command.send(InternalCommand::ShowAlert {
+                        title: "Failed to save the file".to_string(),
+                        msg: err.message,
+                        buttons: Vec::new(),
+                    });
+                }
+                Ok(_) => {}
             });
 
-            self.common.proxy.save(rev, path, true, move |result| {
-                send(result);
-            })
+            // The content the save replaces goes to the local history, so
+            // that it can be restored.
+            self.record_local_history(LocalHistorySource::Save);
+            let encoding = self.encoding.get_untracked();
+            self.common
+                .proxy
+                .save(rev, path, true, encoding, move |result| {
+                    send(result);
+                })
         }
     }
 
+    /// The backup of the document, if it's a file or scratch document with
+    /// unsaved changes. Files in large file mode aren't backed up.
+    pub fn backup(&self) -> Option<DocBackup> {
+        if self.is_pristine() || self.large_file.get_untracked() {
+            return None;
+        }
+        let content = self.content.get_untracked();
+        if !matches!(
+            content,
+            DocContent::File { .. } | DocContent::Scratch { .. }
+        ) {
+            return None;
+        }
+        Some(DocBackup {
+            content,
+            text: self.buffer.with_untracked(|buffer| buffer.to_string()),
+            disk_hash: self.disk_hash.get_untracked(),
+        })
+    }
+
+    /// Read the file again, decoding it with `encoding`, after asking
+    /// whether to discard the unsaved changes, if any.
+    pub fn reopen_with_encoding(&self, encoding: FileEncoding) {
+        let DocContent::File { path, .. } = self.content.get_untracked() else {
+            return;
+        };
+        if self.is_pristine() {
+            self.reload_with_encoding(path, encoding);
+            return;
+        }
+
+        let internal_command = self.common.internal_command;
+        let doc = self.clone();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        internal_command.send(InternalCommand::ShowAlert {
+            title: format!("Do you want to discard the changes you made to {name}?"),
+            msg: format!(
+                "Reopening the file with {encoding} replaces your unsaved changes."
+            ),
+            buttons: vec![AlertButton {
+                text: "Discard and Reopen".to_string(),
+                action: Rc::new(move || {
+                    internal_command.send(InternalCommand::HideAlert);
+                    doc.reload_with_encoding(path.clone(), encoding.clone());
+                }),
+            }],
+        });
+    }
+
+    fn reload_with_encoding(&self, path: PathBuf, encoding: FileEncoding) {
+        let doc = self.clone();
+        let send = create_ext_action(self.scope, move |result| match result {
+            Ok(ProxyResponse::NewBufferResponse {
+                content,
+                encoding,
+                disk_hash,
+                ..
+            }) => {
+                let content = Rope::from(content);
+                doc.encoding.set(encoding);
+                doc.disk_hash.set(disk_hash);
+                doc.disk_content.set(Some(content.clone()));
+                doc.reload(content, true);
+            }
+            Err(err) => {
+                doc.common
+                    .internal_command
+                    .send(InternalCommand::ShowAlert {
+                        title: "Failed to reopen the file".to_string(),
+                        msg: err.message,
+                        buttons: Vec::new(),
+                    });
+            }
+            Ok(_) => {}
+        });
+        self.common
+            .proxy
+            .reopen_with_encoding(path, encoding, move |result| {
+                send(result);
+            });
+    }
+
     pub fn set_inline_completion(
         &self,
         inline_completion: String,
@@ -1784,6 +2202,41 @@ fn phantom_text(
 
         text.append(&mut diag_text);
 
+        // Text volts asked to show after the end of a decorated range
+        self.volt_decorations.with_untracked(|decorations| {
+            self.buffer.with_untracked(|buffer| {
+                for spans in decorations.values() {
+                    for (iv, decoration) in
+                        spans.iter_chunks(start_offset..end_offset)
+                    {
+                        let Some(after_text) = decoration.after_text.as_ref() else {
+                            continue;
+                        };
+                        if buffer.line_of_offset(iv.end()) != line {
+                            continue;
+                        }
+                        let fg = decoration
+                            .after_text_color
+                            .as_deref()
+                            .and_then(|c| config.color_or_hex(c))
+                            .unwrap_or_else(|| {
+                                config.color(LapceColor::INLAY_HINT_FOREGROUND)
+                            });
+                        text.push(PhantomText {
+                            kind: PhantomTextKind::InlayHint,
+                            col: end_offset - start_offset,
+                            affinity: Some(CursorAffinity::Backward),
+                            text: format!("    {after_text}"),
+                            fg: Some(fg),
+                            font_size: Some(config.editor.inlay_hint_font_size()),
+                            bg: None,
+                            under_line: None,
+                        });
+                    }
+                }
+            });
+        });
+
         let (completion_line, completion_col) = self.completion_pos.get_untracked();
         let completion_text = config
             .editor
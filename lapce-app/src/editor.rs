@@ -24,7 +24,7 @@
         command::CommandExecuted,
         id::EditorId,
         movement,
-        text::Document,
+        text::{Document, SystemClipboard},
         view::{
             DiffSection, DiffSectionKind, LineInfo, ScreenLines, ScreenLinesBase,
         },
@@ -44,16 +44,22 @@
     },
     cursor::{Cursor, CursorMode},
     editor::EditType,
-    mode::{Mode, MotionMode},
+    mode::{Mode, MotionMode, VisualMode},
+    register::{Clipboard, Register},
     rope_text_pos::RopeTextPosition,
     selection::{InsertDrift, SelRegion, Selection},
 };
-use lapce_rpc::{buffer::BufferId, plugin::PluginId, proxy::ProxyResponse};
+use lapce_rpc::{
+    buffer::BufferId,
+    plugin::{PluginId, VoltCommandContext},
+    proxy::ProxyResponse,
+};
 use lapce_xi_rope::{Rope, RopeDelta, Transformer};
 use lsp_types::{
-    CodeActionResponse, CompletionItem, CompletionTextEdit, GotoDefinitionResponse,
-    HoverContents, InlayHint, InlayHintLabel, InlineCompletionTriggerKind, Location,
-    MarkedString, MarkupKind, Range, TextEdit,
+    CodeActionResponse, CompletionItem, CompletionItemKind, CompletionTextEdit,
+    GotoDefinitionResponse, HoverContents, InlayHint, InlayHintLabel,
+    InlineCompletionTriggerKind, Location, MarkedString, MarkupKind, Range,
+    TextEdit, Url,
 };
 use nucleo::Utf32Str;
 use serde::{Deserialize, Serialize};
@@ -62,10 +68,11 @@
 use self::{
     diff::DiffInfo,
     location::{EditorLocation, EditorPosition},
+    registers::{EditorRegisters, Yank},
 };
 use crate::{
     command::{CommandKind, InternalCommand, LapceCommand, LapceWorkbenchCommand},
-    completion::CompletionStatus,
+    completion::{CompletionStatus, SNIPPET_PLUGIN_ID, ScoredCompletionItem},
     config::LapceConfig,
     db::LapceDb,
     doc::{Doc, DocContent},
@@ -83,7 +90,7 @@
         implementation_view::{init_implementation_root, map_to_location},
         kind::PanelKind,
     },
-    snippet::Snippet,
+    snippet::{Snippet, SnippetVariables, UserSnippet, resolve_body},
     tracing::*,
     window_tab::{CommonData, Focus, WindowTabData},
 };
@@ -91,6 +98,8 @@
 pub mod diff;
 pub mod gutter;
 pub mod location;
+pub mod minimap;
+pub mod registers;
 pub mod view;
 
 #[derive(Clone, Debug)]
@@ -429,7 +438,14 @@ fn run_edit_command(&self, cmd: &EditCommand) -> CommandExecuted {
             .with_untracked(|config| config.editor.smart_tab);
         let doc_before_edit = text.text().clone();
         let mut cursor = self.editor.cursor.get_untracked();
-        let mut register = self.common.register.get_untracked();
+
+        let copied = matches!(
+            cmd,
+            EditCommand::ClipboardCopy
+                | EditCommand::ClipboardCut
+                | EditCommand::Yank
+        )
+        .then(|| cursor_yank(&cursor, &text));
 
         let yank_data =
             if let lapce_core::cursor::CursorMode::Visual { .. } = &cursor.mode {
@@ -438,17 +454,26 @@ fn run_edit_command(&self, cmd: &EditCommand) -> CommandExecuted {
                 None
             };
 
-        let deltas =
-            batch(|| doc.do_edit(&mut cursor, cmd, modal, &mut register, smart_tab));
+        let (deltas, written) = self.with_selected_register(false, |register| {
+            // A yank in visual mode writes to the register
+            let mut written = yank_data.is_some() && *cmd == EditCommand::Yank;
+            let deltas =
+                batch(|| doc.do_edit(&mut cursor, cmd, modal, register, smart_tab));
 
-        if !deltas.is_empty() {
-            if let Some(data) = yank_data {
-                register.add_delete(data);
+            if !deltas.is_empty() {
+                if let Some(data) = yank_data {
+                    register.add_delete(data);
+                    written = true;
+                }
             }
+            (deltas, written)
+        });
+
+        if let Some(yank) = copied.or(written) {
+            self.push_yank(yank);
         }
 
         self.editor.cursor.set(cursor);
-        self.editor.register.set(register);
 
         if show_completion(cmd, &doc_before_edit, &deltas) {
             self.update_completion(false);
@@ -489,22 +514,123 @@ fn run_motion_mode_command(
             MotionModeCommand::MotionModeYank => MotionMode::Yank { count },
         };
         let mut cursor = self.editor.cursor.get_untracked();
-        let mut register = self.common.register.get_untracked();
-
-        movement::do_motion_mode(
-            &self.editor,
-            &*self.doc(),
-            &mut cursor,
-            motion_mode,
-            &mut register,
+        // The second `d` of `dd` deletes the line and the second `y` of `yy`
+        // yanks it
+        let writes = matches!(
+            (&cursor.motion_mode, &motion_mode),
+            (Some(MotionMode::Delete { .. }), MotionMode::Delete { .. })
+                | (Some(MotionMode::Yank { .. }), MotionMode::Yank { .. })
         );
 
+        let ((), written) = self.with_selected_register(true, |register| {
+            movement::do_motion_mode(
+                &self.editor,
+                &*self.doc(),
+                &mut cursor,
+                motion_mode,
+                register,
+            );
+            ((), writes)
+        });
+        if let Some(yank) = written {
+            self.push_yank(yank);
+        }
+
         self.editor.cursor.set(cursor);
-        self.common.register.set(register);
 
         CommandExecuted::Yes
     }
 
+    /// Run an edit with the vim register selected with `"` standing in for the
+    /// unnamed register. `f` returns whether the edit wrote to the register,
+    /// in which case what it wrote is stored in the selected one and returned,
+    /// unless that's the black hole register. The selection is kept for a
+    /// pending motion if `keep_selected` is set and nothing was written.
+    fn with_selected_register<T>(
+        &self,
+        keep_selected: bool,
+        f: impl FnOnce(&mut Register) -> (T, bool),
+    ) -> (T, Option<Yank>) {
+        let registers = self.registers();
+        let mut register = self.common.register.get_untracked();
+        let Some(selected) = registers.selected.get_untracked() else {
+            let (result, written) = f(&mut register);
+            let yank = written.then(|| Yank::from_register(&register.unnamed));
+            self.common.register.set(register);
+            return (result, yank);
+        };
+
+        let unnamed = register.unnamed.clone();
+        register.unnamed = registers
+            .read(selected)
+            .unwrap_or_else(|| Yank::new(String::new(), VisualMode::Normal))
+            .to_register();
+
+        let (result, written) = f(&mut register);
+
+        let yank = written.then(|| Yank::from_register(&register.unnamed));
+        if let Some(yank) = &yank {
+            registers.write(selected, yank);
+        }
+        // The black hole register leaves the unnamed one untouched
+        if yank.is_none() || selected == '_' {
+            register.unnamed = unnamed;
+        }
+        if yank.is_some() || !keep_selected {
+            registers.selected.set(None);
+        }
+        self.common.register.set(register);
+        (result, yank.filter(|_| selected != '_'))
+    }
+
+    fn registers(&self) -> EditorRegisters {
+        self.common
+            .keypress
+            .with_untracked(|keypress| keypress.registers)
+    }
+
+    /// Add a copied text to the clipboard history.
+    fn push_yank(&self, yank: Yank) {
+        let size = self
+            .common
+            .config
+            .with_untracked(|config| config.editor.clipboard_history_size);
+        self.registers().push_yank(yank, size);
+    }
+
+    /// Paste a text from the clipboard history, putting each piece of a
+    /// multi-cursor copy at its own cursor when the number of cursors matches.
+    pub fn paste_yank(&self, yank: &Yank) {
+        let selection = self.doc().buffer.with_untracked(|buffer| {
+            self.cursor().get_untracked().edit_selection(buffer)
+        });
+        if yank.pieces.len() > 1 && selection.regions().len() == yank.pieces.len() {
+            let edits = selection
+                .regions()
+                .iter()
+                .zip(&yank.pieces)
+                .map(|(region, piece)| {
+                    (
+                        Selection::region(region.min(), region.max()),
+                        piece.as_str(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            self.do_edit(&selection, &edits);
+            return;
+        }
+
+        let unnamed = self.common.register.try_update(|register| {
+            std::mem::replace(&mut register.unnamed, yank.to_register())
+        });
+        self.run_edit_command(&EditCommand::Paste);
+        if let Some(unnamed) = unnamed {
+            self.common.register.update(|register| {
+                register.unnamed = unnamed;
+            });
+        }
+    }
+
     fn run_multi_selection_command(
         &self,
         cmd: &MultiSelectionCommand,
@@ -681,7 +807,29 @@ fn run_move_command(
         self.editor.last_movement.set(movement.clone());
 
         let mut cursor = self.cursor().get_untracked();
-        self.common.register.update(|register| {
+        // Only a move that finishes a delete or a yank writes to a register
+        let writes = matches!(
+            cursor.motion_mode,
+            Some(MotionMode::Delete { .. } | MotionMode::Yank { .. })
+        );
+        if writes {
+            let ((), written) = self.with_selected_register(false, |register| {
+                movement::move_cursor(
+                    &self.editor,
+                    &*self.doc(),
+                    &mut cursor,
+                    movement,
+                    count.unwrap_or(1),
+                    mods.shift(),
+                    register,
+                );
+                ((), true)
+            });
+            if let Some(yank) = written {
+                self.push_yank(yank);
+            }
+        } else {
+            let motion_pending = cursor.motion_mode.is_some();
             movement::move_cursor(
                 &self.editor,
                 &*self.doc(),
@@ -689,9 +837,14 @@ fn run_move_command(
                 movement,
                 count.unwrap_or(1),
                 mods.shift(),
-                register,
-            )
-        });
+                &mut Register::default(),
+            );
+            // The register is selected for the motion a move finishes
+            let selected = self.registers().selected;
+            if !motion_pending && selected.with_untracked(Option::is_some) {
+                selected.set(None);
+            }
+        }
 
         self.editor.cursor.set(cursor);
 
@@ -1720,7 +1873,11 @@ pub fn select_completion(&self) {
         self.cancel_completion();
         let doc = self.doc();
         if let Some(item) = item {
-            if item.item.data.is_some() {
+            if item.plugin_id == SNIPPET_PLUGIN_ID {
+                if let Err(err) = self.apply_user_snippet_item(&item.item) {
+                    tracing::error!("{:?}", err);
+                }
+            } else if item.item.data.is_some() {
                 let editor = self.clone();
                 let rev = doc.buffer.with_untracked(|buffer| buffer.rev());
                 let path = doc.content.with_untracked(|c| c.path().cloned());
@@ -1859,12 +2016,14 @@ fn update_completion(&self, display_if_empty_input: bool) {
         }
 
         let doc = self.doc();
+        let snippet_items = self.user_snippet_items();
         self.common.completion.update(|completion| {
             completion.path.clone_from(&path);
             completion.offset = start_offset;
             completion.input.clone_from(&input);
             completion.status = CompletionStatus::Started;
             completion.input_items.clear();
+            completion.snippet_items = snippet_items;
             completion.request_id += 1;
             let start_pos = doc
                 .buffer
@@ -2039,6 +2198,139 @@ pub fn completion_apply_snippet(
         Ok(())
     }
 
+    /// The values of the snippet variables at the cursor.
+    fn snippet_variables(&self, clipboard: bool) -> SnippetVariables {
+        let doc = self.doc();
+        let cursor = self.cursor().get_untracked();
+        let mut variables = doc.buffer.with_untracked(|buffer| {
+            let region = cursor
+                .edit_selection(buffer)
+                .last_inserted()
+                .copied()
+                .unwrap_or_else(|| SelRegion::caret(cursor.offset()));
+            let line_index = buffer.line_of_offset(region.start);
+            let (word_start, word_end) = buffer.select_word(region.start);
+            SnippetVariables {
+                line_index,
+                current_line: buffer
+                    .line_content(line_index)
+                    .trim_end_matches(['\r', '\n'])
+                    .to_string(),
+                current_word: buffer.slice_to_cow(word_start..word_end).to_string(),
+                selected_text: buffer
+                    .slice_to_cow(region.min()..region.max())
+                    .to_string(),
+                ..Default::default()
+            }
+        });
+        variables.path = doc.content.with_untracked(|c| c.path().cloned());
+        variables.workspace = self.common.workspace.path.clone();
+        if clipboard {
+            let mut clipboard = SystemClipboard::new();
+            variables.clipboard = clipboard.get_string();
+        }
+        variables
+    }
+
+    /// The indentation of the line the cursor is on, which the lines of a
+    /// multi-line snippet are indented by.
+    fn snippet_indent(&self, variables: &SnippetVariables) -> String {
+        variables
+            .current_line
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect()
+    }
+
+    /// The completion items of the user snippets for the document's language,
+    /// which hold the snippet bodies as they are until one is accepted.
+    fn user_snippet_items(&self) -> im::Vector<ScoredCompletionItem> {
+        let language = self.doc().syntax().with_untracked(|syntax| syntax.language);
+        let completion = self.common.completion;
+        if let Some(items) = completion.with_untracked(|completion| {
+            completion.snippet_cache.get(&language).cloned()
+        }) {
+            return items;
+        }
+        let items: im::Vector<ScoredCompletionItem> =
+            self.common.snippets.with_untracked(|snippets| {
+                snippets
+                    .iter()
+                    .filter(|snippet| snippet.applies_to(language))
+                    .flat_map(|snippet| {
+                        snippet.prefixes.iter().map(move |prefix| {
+                            ScoredCompletionItem {
+                                item: CompletionItem {
+                                    label: prefix.clone(),
+                                    kind: Some(CompletionItemKind::SNIPPET),
+                                    detail: Some(
+                                        snippet
+                                            .description
+                                            .clone()
+                                            .unwrap_or_else(|| snippet.name.clone()),
+                                    ),
+                                    insert_text: Some(snippet.body.clone()),
+                                    insert_text_format: Some(
+                                        lsp_types::InsertTextFormat::SNIPPET,
+                                    ),
+                                    ..Default::default()
+                                },
+                                plugin_id: SNIPPET_PLUGIN_ID,
+                                score: 0,
+                                label_score: 0,
+                                indices: Vec::new(),
+                            }
+                        })
+                    })
+                    .collect()
+            });
+        completion.update(|completion| {
+            completion.snippet_cache.insert(language, items.clone());
+        });
+        items
+    }
+
+    /// Replace the word at the cursor with the snippet of a user snippet
+    /// completion item, resolving its variables now that it's accepted.
+    fn apply_user_snippet_item(&self, item: &CompletionItem) -> anyhow::Result<()> {
+        let body = item.insert_text.as_deref().unwrap_or(item.label.as_str());
+        let variables = self.snippet_variables(body.contains("CLIPBOARD"));
+        let body = resolve_body(body, &self.snippet_indent(&variables), &variables);
+        let offset = self.cursor().with_untracked(|c| c.offset());
+        let (start_offset, end_offset) =
+            self.doc().buffer.with_untracked(|buffer| {
+                (
+                    buffer.prev_code_boundary(offset),
+                    buffer.next_code_boundary(offset),
+                )
+            });
+        let selection = Selection::region(start_offset, end_offset);
+        self.completion_apply_snippet(&body, &selection, Vec::new(), start_offset)
+    }
+
+    /// Replace the selection with a user snippet.
+    pub fn insert_snippet(&self, snippet: &UserSnippet) {
+        let variables = self.snippet_variables(snippet.body.contains("CLIPBOARD"));
+        let body = snippet.resolve(&self.snippet_indent(&variables), &variables);
+        let region = self.doc().buffer.with_untracked(|buffer| {
+            let cursor = self.cursor().get_untracked();
+            cursor
+                .edit_selection(buffer)
+                .last_inserted()
+                .copied()
+                .unwrap_or_else(|| SelRegion::caret(cursor.offset()))
+        });
+        let selection = Selection::region(region.min(), region.max());
+        if let Err(err) = self.completion_apply_snippet(
+            &body,
+            &selection,
+            Vec::new(),
+            region.min(),
+        ) {
+            tracing::error!("{:?}", err);
+        }
+    }
+
     fn add_snippet_placeholders(
         &self,
         new_placeholders: Vec<(usize, (usize, usize))>,
@@ -2597,6 +2889,28 @@ fn rename(&self) {
             });
     }
 
+    /// The file and selection of the editor, which volts get along with the
+    /// commands they registered.
+    pub fn volt_command_context(&self) -> VoltCommandContext {
+        let doc = self.doc();
+        let uri = doc
+            .content
+            .with_untracked(|c| c.path().cloned())
+            .and_then(|path| Url::from_file_path(path).ok());
+        let selection = doc.buffer.with_untracked(|buffer| {
+            let selection = self.cursor().get_untracked().edit_selection(buffer);
+            selection.last_inserted().map(|region| Range {
+                start: buffer.offset_to_position(region.min()),
+                end: buffer.offset_to_position(region.max()),
+            })
+        });
+        VoltCommandContext {
+            uri,
+            language_id: None,
+            selection,
+        }
+    }
+
     #[instrument]
     pub fn word_at_cursor(&self) -> String {
         let doc = self.doc();
@@ -3367,6 +3681,10 @@ fn expect_char(&self) -> bool {
         }
     }
 
+    fn records_macros(&self) -> bool {
+        true
+    }
+
     fn receive_char(&self, c: &str) {
         if self.common.find.visual.get_untracked() && self.find_focus.get_untracked()
         {
@@ -3461,6 +3779,25 @@ pub fn track(&self) {
     }
 }
 
+/// The text the cursor copies, with a piece for each of multiple selections.
+fn cursor_yank(cursor: &Cursor, text: &RopeTextVal) -> Yank {
+    if let CursorMode::Insert(selection) = &cursor.mode {
+        if selection.regions().len() > 1 && !selection.is_caret() {
+            return Yank {
+                pieces: selection
+                    .regions()
+                    .iter()
+                    .map(|region| {
+                        text.slice_to_cow(region.min()..region.max()).to_string()
+                    })
+                    .collect(),
+                mode: VisualMode::Normal,
+            };
+        }
+    }
+    Yank::from_register(&cursor.yank(text))
+}
+
 /// Checks if completion should be triggered if the received command
 /// is one that inserts whitespace or deletes whitespace
 fn show_completion(
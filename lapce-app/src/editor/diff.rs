@@ -22,6 +22,7 @@
 use crate::{
     config::{color::LapceColor, icon::LapceIcons},
     doc::{Doc, DocContent},
+    history::LOCAL_HISTORY_VERSION,
     id::{DiffEditorId, EditorTabId},
     main_split::{Editors, MainSplitData},
     wave::wave_box,
@@ -70,7 +71,19 @@ pub fn to_data(
                     );
                     let doc = Rc::new(doc);
 
-                    {
+                    if history.version.starts_with(LOCAL_HISTORY_VERSION) {
+                        let doc = doc.clone();
+                        common.local_history.read(
+                            cx,
+                            history.path.clone(),
+                            &history.version,
+                            move |text| {
+                                doc.init_content(Rope::from(
+                                    text.unwrap_or_default(),
+                                ));
+                            },
+                        );
+                    } else {
                         let doc = doc.clone();
                         let send = create_ext_action(cx, move |result| {
                             if let Ok(ProxyResponse::BufferHeadResponse {
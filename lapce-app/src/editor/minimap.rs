@@ -0,0 +1,457 @@
+use floem::{
+    Renderer, View, ViewId,
+    context::{EventCx, PaintCx},
+    event::{Event, EventPropagation},
+    kurbo::Vec2,
+    peniko::{
+        Color,
+        kurbo::{Point, Rect, Size},
+    },
+    prelude::SignalTrack,
+    reactive::{SignalGet, SignalUpdate, SignalWith, create_effect},
+};
+use lapce_core::buffer::rope_text::RopeText;
+use lsp_types::DiagnosticSeverity;
+
+use super::{EditorData, view::changes_color_iter};
+use crate::config::{LapceConfig, color::LapceColor};
+
+/// The number of columns of each line that are drawn in the minimap.
+const MINIMAP_COLUMNS: usize = 120;
+/// The width of the markers for diagnostics, search matches and changes.
+const MARKER_WIDTH: f64 = 3.0;
+
+pub fn minimap_width(config: &LapceConfig) -> f64 {
+    MINIMAP_COLUMNS as f64 * char_width(config) + MARKER_WIDTH * 2.0
+}
+
+fn line_height(config: &LapceConfig) -> f64 {
+    config.editor.code_glance_font_size.max(1) as f64
+}
+
+fn char_width(config: &LapceConfig) -> f64 {
+    line_height(config) * 0.6
+}
+
+/// Where the lines of the document are drawn in the minimap.
+struct MinimapLayout {
+    line_height: f64,
+    /// The first line drawn at the top of the minimap
+    top_line: usize,
+    /// The first line visible in the editor
+    first_visible: usize,
+    /// The number of lines visible in the editor
+    visible_lines: usize,
+    /// The scale from the first visible line to the top of the viewport
+    /// indicator
+    indicator_scale: f64,
+}
+
+impl MinimapLayout {
+    fn new(editor: &EditorData, height: f64, config: &LapceConfig) -> Self {
+        let (first_visible, last_visible) = editor
+            .screen_lines()
+            .with_untracked(|screen_lines| {
+                screen_lines
+                    .rvline_range()
+                    .map(|(min, max)| (min.line, max.line))
+            })
+            .unwrap_or((0, 0));
+        Self::from_lines(
+            editor.editor.last_line() + 1,
+            first_visible,
+            last_visible,
+            height,
+            line_height(config),
+        )
+    }
+
+    fn from_lines(
+        total_lines: usize,
+        first_visible: usize,
+        last_visible: usize,
+        height: f64,
+        line_height: f64,
+    ) -> Self {
+        let visible_lines = last_visible.saturating_sub(first_visible) + 1;
+        let rows = (height / line_height).floor() as usize;
+
+        // When the document doesn't fit, the minimap scrolls along with the
+        // editor so that both reach the end of the document together.
+        let scroll_ratio = if total_lines > rows && total_lines > visible_lines {
+            (total_lines - rows) as f64 / (total_lines - visible_lines) as f64
+        } else {
+            0.0
+        };
+        let top_line = (first_visible as f64 * scroll_ratio).round() as usize;
+
+        Self {
+            line_height,
+            top_line,
+            first_visible,
+            visible_lines,
+            indicator_scale: (1.0 - scroll_ratio).max(0.0) * line_height,
+        }
+    }
+
+    fn y_of_line(&self, line: usize) -> f64 {
+        (line as f64 - self.top_line as f64) * self.line_height
+    }
+
+    fn indicator(&self, width: f64) -> Rect {
+        Rect::from_origin_size(
+            Point::new(0.0, self.y_of_line(self.first_visible)),
+            Size::new(width, self.visible_lines as f64 * self.line_height),
+        )
+    }
+
+    /// The first visible line that puts the top of the indicator at `y`.
+    fn first_line_at(&self, y: f64) -> usize {
+        if self.indicator_scale <= 0.0 {
+            return 0;
+        }
+        (y / self.indicator_scale).max(0.0).round() as usize
+    }
+}
+
+/// The runs of non-whitespace characters of the same color in a line, as
+/// `(start column, end column, color)`, where `color_at` gives the color of
+/// the character at a byte index. Tabs are expanded to `tab_width`, and
+/// nothing past `MINIMAP_COLUMNS` is included.
+fn line_runs(
+    content: &str,
+    tab_width: usize,
+    color_at: impl Fn(usize) -> Color,
+) -> Vec<(usize, usize, Color)> {
+    let mut runs = Vec::new();
+    let mut col = 0;
+    let mut run: Option<(usize, Color)> = None;
+    for (index, c) in content.char_indices() {
+        if col >= MINIMAP_COLUMNS {
+            break;
+        }
+        let color = if c.is_whitespace() {
+            None
+        } else {
+            Some(color_at(index))
+        };
+        if let Some((start, run_color)) = run {
+            if color != Some(run_color) {
+                runs.push((start, col, run_color));
+                run = None;
+            }
+        }
+        if let (None, Some(color)) = (run, color) {
+            run = Some((col, color));
+        }
+        col += if c == '\t' {
+            tab_width - col % tab_width
+        } else {
+            1
+        };
+    }
+    if let Some((start, color)) = run {
+        runs.push((start, col.min(MINIMAP_COLUMNS), color));
+    }
+    runs
+}
+
+/// Paint the characters from column `start` to `end` as a block.
+fn paint_run(
+    cx: &mut PaintCx,
+    start: usize,
+    end: usize,
+    y: f64,
+    height: f64,
+    char_width: f64,
+    color: Color,
+) {
+    let rect = Rect::from_origin_size(
+        Point::new(MARKER_WIDTH + start as f64 * char_width, y),
+        Size::new((end - start) as f64 * char_width, height),
+    );
+    cx.fill(&rect, color.multiply_alpha(0.6), 0.0);
+}
+
+pub struct MinimapView {
+    id: ViewId,
+    editor: EditorData,
+    size: Size,
+    /// The distance from the top of the viewport indicator to the pointer
+    /// while it is dragged
+    drag_offset: Option<f64>,
+}
+
+pub fn minimap_view(editor: EditorData) -> MinimapView {
+    let id = ViewId::new();
+
+    let doc = editor.doc_signal();
+    let viewport = editor.viewport();
+    let config = editor.common.config;
+    create_effect(move |_| {
+        viewport.track();
+        config.track();
+        let doc = doc.get();
+        doc.buffer.with(|buffer| buffer.rev());
+        doc.head_changes().track();
+        doc.diagnostics.diagnostics_span.track();
+        doc.find_result.occurrences.track();
+        id.request_paint();
+    });
+
+    MinimapView {
+        id,
+        editor,
+        size: Size::ZERO,
+        drag_offset: None,
+    }
+}
+
+impl MinimapView {
+    /// Scroll the editor so that the top of the viewport indicator is at `y`.
+    fn scroll_to(&self, layout: &MinimapLayout, y: f64) {
+        let last_line = self.editor.editor.last_line();
+        let line = layout.first_line_at(y).min(last_line);
+        let config = self.editor.common.config.get_untracked();
+        let vline = self.editor.editor.vline_of_line(line);
+        let y = (self.editor.visual_line(vline.get()) * config.editor.line_height())
+            as f64;
+        let x = self.editor.viewport().get_untracked().x0;
+        self.editor.scroll_to().set(Some(Vec2::new(x, y)));
+    }
+
+    fn paint_text(
+        &self,
+        cx: &mut PaintCx,
+        layout: &MinimapLayout,
+        config: &LapceConfig,
+    ) {
+        let doc = self.editor.doc();
+        let char_width = char_width(config);
+        let text_height = (layout.line_height * 0.8).max(1.0);
+        let default_color = config.color(LapceColor::EDITOR_FOREGROUND);
+        let tab_width = config.editor.tab_width.max(1);
+        let rows = (self.size.height / layout.line_height).ceil() as usize;
+        let last_line = self.editor.editor.last_line();
+
+        for line in layout.top_line..=(layout.top_line + rows).min(last_line) {
+            let content = doc.buffer.with_untracked(|buffer| {
+                buffer
+                    .line_content(line)
+                    .trim_end_matches(['\r', '\n'])
+                    .to_string()
+            });
+            let styles = doc.line_style(line);
+            let y = layout.y_of_line(line);
+
+            // Draw each run of non-whitespace characters of the same color as
+            // a single block.
+            let runs = line_runs(&content, tab_width, |index| {
+                styles
+                    .iter()
+                    .find(|style| style.start <= index && index < style.end)
+                    .and_then(|style| style.style.fg_color.as_ref())
+                    .and_then(|color| config.style_color(color))
+                    .unwrap_or(default_color)
+            });
+            for (start, end, color) in runs {
+                paint_run(cx, start, end, y, text_height, char_width, color);
+            }
+        }
+    }
+
+    /// Paint markers for the git changes on the left, and the diagnostics and
+    /// search matches on the right.
+    fn paint_markers(
+        &self,
+        cx: &mut PaintCx,
+        layout: &MinimapLayout,
+        config: &LapceConfig,
+    ) {
+        let doc = self.editor.doc();
+        let marker_height = layout.line_height.max(2.0);
+
+        let changes = doc.head_changes().get_untracked();
+        let mut line = 0;
+        for (len, color, _) in changes_color_iter(&changes, config) {
+            if let Some(color) = color {
+                let rect = Rect::from_origin_size(
+                    Point::new(0.0, layout.y_of_line(line)),
+                    Size::new(
+                        MARKER_WIDTH,
+                        (len as f64 * layout.line_height).max(marker_height),
+                    ),
+                );
+                cx.fill(&rect, color, 0.0);
+            }
+            line += len;
+        }
+
+        let right = self.size.width - MARKER_WIDTH;
+        let color = config.color(LapceColor::EDITOR_FOCUS);
+        doc.find_result.occurrences.with_untracked(|occurrences| {
+            doc.buffer.with_untracked(|buffer| {
+                for region in occurrences.regions() {
+                    let line = buffer.line_of_offset(region.min());
+                    let rect = Rect::from_origin_size(
+                        Point::new(right, layout.y_of_line(line)),
+                        Size::new(MARKER_WIDTH, marker_height),
+                    );
+                    cx.fill(&rect, color, 0.0);
+                }
+            });
+        });
+
+        // The spans follow the edits made since the diagnostics came in.
+        doc.diagnostics
+            .diagnostics_span
+            .with_untracked(|diagnostics| {
+                doc.buffer.with_untracked(|buffer| {
+                    for (interval, diagnostic) in diagnostics.iter() {
+                        let color = match diagnostic.severity {
+                            Some(DiagnosticSeverity::ERROR) => {
+                                config.color(LapceColor::LAPCE_ERROR)
+                            }
+                            Some(DiagnosticSeverity::WARNING) => {
+                                config.color(LapceColor::LAPCE_WARN)
+                            }
+                            _ => continue,
+                        };
+                        let line = buffer.line_of_offset(interval.start());
+                        let rect = Rect::from_origin_size(
+                            Point::new(right, layout.y_of_line(line)),
+                            Size::new(MARKER_WIDTH, marker_height),
+                        );
+                        cx.fill(&rect, color, 0.0);
+                    }
+                });
+            });
+    }
+}
+
+impl View for MinimapView {
+    fn id(&self) -> ViewId {
+        self.id
+    }
+
+    fn compute_layout(
+        &mut self,
+        _cx: &mut floem::context::ComputeLayoutCx,
+    ) -> Option<Rect> {
+        if let Some(layout) = self.id.get_layout() {
+            self.size =
+                Size::new(layout.size.width as f64, layout.size.height as f64);
+        }
+        None
+    }
+
+    fn event_before_children(
+        &mut self,
+        cx: &mut EventCx,
+        event: &Event,
+    ) -> EventPropagation {
+        let config = self.editor.common.config.get_untracked();
+        let layout = MinimapLayout::new(&self.editor, self.size.height, &config);
+        match event {
+            Event::PointerDown(pointer) if pointer.button.is_primary() => {
+                let indicator = layout.indicator(self.size.width);
+                // Dragging from outside the indicator first centers it on the
+                // pointer.
+                let offset = if indicator.contains(pointer.pos) {
+                    pointer.pos.y - indicator.y0
+                } else {
+                    indicator.height() / 2.0
+                };
+                self.drag_offset = Some(offset);
+                self.scroll_to(&layout, pointer.pos.y - offset);
+                cx.update_active(self.id);
+                EventPropagation::Stop
+            }
+            Event::PointerMove(pointer) => {
+                if let Some(offset) = self.drag_offset {
+                    if cx.is_active(self.id) {
+                        self.scroll_to(&layout, pointer.pos.y - offset);
+                        return EventPropagation::Stop;
+                    }
+                }
+                EventPropagation::Continue
+            }
+            Event::PointerUp(_) => {
+                self.drag_offset = None;
+                EventPropagation::Continue
+            }
+            _ => EventPropagation::Continue,
+        }
+    }
+
+    fn paint(&mut self, cx: &mut PaintCx) {
+        let config = self.editor.common.config.get_untracked();
+        let layout = MinimapLayout::new(&self.editor, self.size.height, &config);
+
+        cx.save();
+        cx.clip(&self.size.to_rect());
+        cx.fill(
+            &layout.indicator(self.size.width),
+            config
+                .color(LapceColor::LAPCE_SCROLL_BAR)
+                .multiply_alpha(0.5),
+            0.0,
+        );
+        self.paint_text(cx, &layout, &config);
+        self.paint_markers(cx, &layout, &config);
+        cx.restore();
+    }
+
+    fn debug_name(&self) -> std::borrow::Cow<'static, str> {
+        "Editor Minimap".into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_fits() {
+        // A short document is drawn from its first line and doesn't scroll.
+        let layout = MinimapLayout::from_lines(20, 5, 14, 100.0, 2.0);
+        assert_eq!(layout.top_line, 0);
+        assert_eq!(layout.y_of_line(5), 10.0);
+        assert_eq!(layout.indicator(50.0).height(), 20.0);
+        assert_eq!(layout.first_line_at(10.0), 5);
+    }
+
+    #[test]
+    fn test_layout_scrolls() {
+        // 1000 lines with 10 visible, in a minimap of 100 rows
+        let top = MinimapLayout::from_lines(1000, 0, 9, 200.0, 2.0);
+        assert_eq!(top.top_line, 0);
+        let bottom = MinimapLayout::from_lines(1000, 990, 999, 200.0, 2.0);
+        assert_eq!(bottom.top_line, 900);
+        // Both reach the end together, with the indicator at the bottom.
+        assert_eq!(bottom.indicator(50.0).y1, 200.0);
+        // Dragging the indicator to where it is keeps the editor there.
+        let middle = MinimapLayout::from_lines(1000, 495, 504, 200.0, 2.0);
+        let y = middle.indicator(50.0).y0;
+        assert_eq!(middle.first_line_at(y), 495);
+    }
+
+    #[test]
+    fn test_line_runs() {
+        let red = Color::from_rgb8(255, 0, 0);
+        let blue = Color::from_rgb8(0, 0, 255);
+        // `let` is red and the rest blue
+        let color_at = |index: usize| if index < 5 { red } else { blue };
+        assert_eq!(
+            line_runs("\tlet x=1;", 4, color_at),
+            vec![(4, 7, red), (8, 12, blue)]
+        );
+
+        let long = "a".repeat(MINIMAP_COLUMNS + 10);
+        assert_eq!(
+            line_runs(&long, 4, |_| red),
+            vec![(0, MINIMAP_COLUMNS, red)]
+        );
+        assert!(line_runs("  \t ", 4, |_| red).is_empty());
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::{HashMap, VecDeque};
+
+use floem::{
+    reactive::{RwSignal, Scope, SignalUpdate, SignalWith},
+    views::editor::text::SystemClipboard,
+};
+use lapce_core::{
+    mode::VisualMode,
+    register::{Clipboard, RegisterData},
+};
+
+/// A copied text, where a copy from multiple cursors keeps the text of each
+/// cursor as a separate piece.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Yank {
+    pub pieces: Vec<String>,
+    pub mode: VisualMode,
+}
+
+impl Yank {
+    pub fn new(content: String, mode: VisualMode) -> Self {
+        Self {
+            pieces: vec![content],
+            mode,
+        }
+    }
+
+    pub fn from_register(data: &RegisterData) -> Self {
+        Self::new(data.content.clone(), data.mode)
+    }
+
+    pub fn to_register(&self) -> RegisterData {
+        RegisterData {
+            content: self.content(),
+            mode: self.mode,
+        }
+    }
+
+    /// The text of all the pieces, one per line like a multi-cursor copy.
+    pub fn content(&self) -> String {
+        self.pieces.join("\n")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pieces.iter().all(String::is_empty)
+    }
+
+    /// Append to the text, like yanking into an uppercase vim register.
+    pub fn append(&mut self, other: &Yank) {
+        let mut content = self.content();
+        let linewise = other.mode == VisualMode::Linewise;
+        if linewise && !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&other.content());
+        self.pieces = vec![content];
+        if linewise {
+            self.mode = VisualMode::Linewise;
+        }
+    }
+}
+
+/// The most recently copied texts, newest first.
+#[derive(Clone, Debug, Default)]
+pub struct ClipboardRing {
+    entries: VecDeque<Yank>,
+}
+
+impl ClipboardRing {
+    /// Add a copied text, moving it to the front if it is already in the ring
+    /// and keeping at most `size` entries.
+    pub fn push(&mut self, yank: Yank, size: usize) {
+        if yank.is_empty() {
+            return;
+        }
+        self.entries.retain(|entry| entry != &yank);
+        self.entries.push_front(yank);
+        self.entries.truncate(size);
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Yank> {
+        self.entries.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Yank> {
+        self.entries.iter()
+    }
+}
+
+/// The clipboard history and the vim registers of the editors.
+#[derive(Clone, Copy, Debug)]
+pub struct EditorRegisters {
+    pub ring: RwSignal<ClipboardRing>,
+    /// The registers from `a` to `z`
+    pub named: RwSignal<HashMap<char, Yank>>,
+    /// The register chosen with `"` for the next command
+    pub selected: RwSignal<Option<char>>,
+    /// Whether `"` was typed, so the next character names the register
+    pub selecting: RwSignal<bool>,
+}
+
+impl EditorRegisters {
+    pub fn new(cx: Scope) -> Self {
+        Self {
+            ring: cx.create_rw_signal(ClipboardRing::default()),
+            named: cx.create_rw_signal(HashMap::new()),
+            selected: cx.create_rw_signal(None),
+            selecting: cx.create_rw_signal(false),
+        }
+    }
+
+    pub fn push_yank(&self, yank: Yank, size: usize) {
+        self.ring.update(|ring| ring.push(yank, size));
+    }
+
+    /// The text in a register, where `_` is always empty, `+` and `*` are the
+    /// system clipboard and `0` to `9` are the clipboard history.
+    pub fn read(&self, register: char) -> Option<Yank> {
+        match register {
+            '_' => Some(Yank::new(String::new(), VisualMode::Normal)),
+            '+' | '*' => SystemClipboard::new()
+                .get_string()
+                .map(|content| Yank::new(content, VisualMode::Normal)),
+            '0'..='9' => {
+                let index = register.to_digit(10)? as usize;
+                self.ring.with_untracked(|ring| ring.get(index).cloned())
+            }
+            _ => self.named.with_untracked(|named| {
+                named.get(&register.to_ascii_lowercase()).cloned()
+            }),
+        }
+    }
+
+    /// Store a text in a register, where an uppercase register appends to the
+    /// lowercase one.
+    pub fn write(&self, register: char, yank: &Yank) {
+        match register {
+            '+' | '*' => {
+                SystemClipboard::new().put_string(yank.content());
+            }
+            'a'..='z' => {
+                self.named.update(|named| {
+                    named.insert(register, yank.clone());
+                });
+            }
+            'A'..='Z' => {
+                self.named.update(|named| {
+                    named
+                        .entry(register.to_ascii_lowercase())
+                        .and_modify(|existing| existing.append(yank))
+                        .or_insert_with(|| yank.clone());
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clipboard_ring() {
+        let yank = |s: &str| Yank::new(s.to_string(), VisualMode::Normal);
+        let mut ring = ClipboardRing::default();
+        ring.push(yank("a"), 3);
+        ring.push(yank(""), 3);
+        ring.push(yank("b"), 3);
+        ring.push(yank("c"), 3);
+        ring.push(yank("a"), 3);
+        ring.push(yank("d"), 3);
+        let contents: Vec<_> = ring.iter().map(Yank::content).collect();
+        assert_eq!(contents, ["d", "a", "c"]);
+
+        ring.push(
+            Yank {
+                pieces: vec!["x".to_string(), "y".to_string()],
+                mode: VisualMode::Normal,
+            },
+            3,
+        );
+        assert_eq!(ring.get(0).unwrap().pieces.len(), 2);
+        assert_eq!(ring.get(0).unwrap().content(), "x\ny");
+    }
+
+    #[test]
+    fn test_yank_append() {
+        let mut yank = Yank::new("foo".to_string(), VisualMode::Normal);
+        yank.append(&Yank::new("bar".to_string(), VisualMode::Normal));
+        assert_eq!(yank.content(), "foobar");
+        yank.append(&Yank::new("baz\n".to_string(), VisualMode::Linewise));
+        assert_eq!(yank.content(), "foobar\nbaz\n");
+        assert_eq!(yank.mode, VisualMode::Linewise);
+    }
+}
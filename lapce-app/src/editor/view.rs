@@ -53,7 +53,11 @@
 use lapce_xi_rope::find::CaseMatching;
 use lsp_types::CodeLens;
 
-use super::{DocSignal, EditorData, gutter::editor_gutter_view};
+use super::{
+    DocSignal, EditorData,
+    gutter::editor_gutter_view,
+    minimap::{minimap_view, minimap_width},
+};
 use crate::{
     app::clickable_icon,
     command::InternalCommand,
@@ -549,61 +553,56 @@ fn paint_find_region(
         screen_lines: &ScreenLines,
         line_height: f64,
     ) {
-        let start = region.min();
-        let end = region.max();
-
-        // TODO(minor): the proper affinity here should probably be tracked by selregion
-        let (start_rvline, start_col) =
-            ed.rvline_col_of_offset(start, CursorAffinity::Forward);
-        let (end_rvline, end_col) =
-            ed.rvline_col_of_offset(end, CursorAffinity::Backward);
-
-        for line_info in screen_lines.iter_line_info() {
-            let rvline_info = line_info.vline_info;
-            let rvline = rvline_info.rvline;
-            let line = rvline.line;
-
-            if rvline < start_rvline {
-                continue;
-            }
-
-            if rvline > end_rvline {
-                break;
-            }
+        for rect in
+            region_rects(ed, region.min(), region.max(), screen_lines, line_height)
+        {
+            cx.stroke(&rect, color, &Stroke::new(1.0));
+        }
+    }
 
-            let left_col = if rvline == start_rvline { start_col } else { 0 };
-            let (right_col, _vline_end) = if rvline == end_rvline {
-                let max_col = ed.last_col(rvline_info, true);
-                (end_col.min(max_col), false)
-            } else {
-                (ed.last_col(rvline_info, true), true)
-            };
+    /// Paint the backgrounds of the ranges volts decorated.
+    fn paint_volt_decorations(&self, cx: &mut PaintCx, screen_lines: &ScreenLines) {
+        let e_data = &self.editor;
+        let ed = &e_data.editor;
+        let doc = e_data.doc();
+        if doc.volt_decorations.with_untracked(|d| d.is_empty()) {
+            return;
+        }
+        if screen_lines.lines.is_empty() {
+            return;
+        }
 
-            // TODO(minor): sel region should have the affinity of the start/end
-            let x0 = ed
-                .line_point_of_line_col(
-                    line,
-                    left_col,
-                    CursorAffinity::Forward,
-                    true,
-                )
-                .x;
-            let x1 = ed
-                .line_point_of_line_col(
-                    line,
-                    right_col,
-                    CursorAffinity::Backward,
-                    true,
-                )
-                .x;
+        let min_vline = *screen_lines.lines.first().unwrap();
+        let max_vline = *screen_lines.lines.last().unwrap();
+        let min_line = screen_lines.info(min_vline).unwrap().vline_info.rvline.line;
+        let max_line = screen_lines.info(max_vline).unwrap().vline_info.rvline.line;
+        let start = ed.offset_of_line(min_line);
+        let end = ed.offset_of_line(max_line + 1);
 
-            if !rvline_info.is_empty() && start != end && left_col != right_col {
-                let rect = Size::new(x1 - x0, line_height)
-                    .to_rect()
-                    .with_origin(Point::new(x0, line_info.vline_y));
-                cx.stroke(&rect, color, &Stroke::new(1.0));
+        let config = e_data.common.config.get_untracked();
+        let line_height = config.editor.line_height() as f64;
+        doc.volt_decorations.with_untracked(|decorations| {
+            for spans in decorations.values() {
+                for (iv, decoration) in spans.iter_chunks(start..end) {
+                    let Some(color) = decoration
+                        .background
+                        .as_deref()
+                        .and_then(|c| config.color_or_hex(c))
+                    else {
+                        continue;
+                    };
+                    for rect in region_rects(
+                        ed,
+                        iv.start(),
+                        iv.end(),
+                        screen_lines,
+                        line_height,
+                    ) {
+                        cx.fill(&rect, color, 0.0);
+                    }
+                }
             }
-        }
+        });
     }
 
     fn paint_sticky_headers(
@@ -1145,6 +1144,8 @@ fn paint(&mut self, cx: &mut PaintCx) {
         let screen_lines = ed.screen_lines.get_untracked();
         self.paint_find(cx, &screen_lines);
         let screen_lines = ed.screen_lines.get_untracked();
+        self.paint_volt_decorations(cx, &screen_lines);
+        let screen_lines = ed.screen_lines.get_untracked();
         self.paint_bracket_highlights_scope_lines(cx, viewport, &screen_lines);
         let screen_lines = ed.screen_lines.get_untracked();
         FloemEditorView::paint_text(
@@ -1161,6 +1162,61 @@ fn paint(&mut self, cx: &mut PaintCx) {
     }
 }
 
+/// The rects covering `start..end` on each of the screen lines it spans.
+fn region_rects(
+    ed: &Editor,
+    start: usize,
+    end: usize,
+    screen_lines: &ScreenLines,
+    line_height: f64,
+) -> Vec<Rect> {
+    // TODO(minor): the proper affinity here should probably be tracked by selregion
+    let (start_rvline, start_col) =
+        ed.rvline_col_of_offset(start, CursorAffinity::Forward);
+    let (end_rvline, end_col) =
+        ed.rvline_col_of_offset(end, CursorAffinity::Backward);
+
+    let mut rects = Vec::new();
+    for line_info in screen_lines.iter_line_info() {
+        let rvline_info = line_info.vline_info;
+        let rvline = rvline_info.rvline;
+        let line = rvline.line;
+
+        if rvline < start_rvline {
+            continue;
+        }
+
+        if rvline > end_rvline {
+            break;
+        }
+
+        let left_col = if rvline == start_rvline { start_col } else { 0 };
+        let (right_col, _vline_end) = if rvline == end_rvline {
+            let max_col = ed.last_col(rvline_info, true);
+            (end_col.min(max_col), false)
+        } else {
+            (ed.last_col(rvline_info, true), true)
+        };
+
+        // TODO(minor): sel region should have the affinity of the start/end
+        let x0 = ed
+            .line_point_of_line_col(line, left_col, CursorAffinity::Forward, true)
+            .x;
+        let x1 = ed
+            .line_point_of_line_col(line, right_col, CursorAffinity::Backward, true)
+            .x;
+
+        if !rvline_info.is_empty() && start != end && left_col != right_col {
+            rects.push(
+                Size::new(x1 - x0, line_height)
+                    .to_rect()
+                    .with_origin(Point::new(x0, line_info.vline_y)),
+            );
+        }
+    }
+    rects
+}
+
 fn get_sticky_header_info(
     editor_data: &EditorData,
     viewport: RwSignal<Rect>,
@@ -1316,6 +1372,21 @@ pub fn editor_container_view(
                 viewport,
             ),
             editor_content(editor, debug_breakline, is_active),
+            minimap_view(editor.get_untracked()).style(move |s| {
+                let config = config.get();
+                s.width(minimap_width(&config) as f32)
+                    .height_full()
+                    .flex_shrink(0.0)
+                    .border_left(1.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+                    .apply_if(
+                        !config.editor.show_minimap
+                            || !editor_view.get().is_normal()
+                            || doc.get().content.with(|content| content.is_local())
+                            || doc.get().large_file.get(),
+                        |s| s.hide(),
+                    )
+            }),
             empty().style(move |s| {
                 let config = config.get();
                 s.absolute()
@@ -1732,6 +1803,68 @@ fn editor_gutter_code_lens(
     .debug_name("CodeLens Stack")
 }
 
+/// The gutter icons of the decorations volts set on the document.
+fn editor_gutter_volt_icons(
+    window_tab_data: Rc<WindowTabData>,
+    doc: DocSignal,
+    screen_lines: RwSignal<ScreenLines>,
+    viewport: RwSignal<Rect>,
+    icon_padding: f32,
+) -> impl View {
+    let config = window_tab_data.common.config;
+
+    dyn_stack(
+        move || {
+            let doc = doc.get();
+            let decorations = doc.volt_decorations.get();
+            doc.buffer.with_untracked(|buffer| {
+                let mut icons = BTreeMap::new();
+                for spans in decorations.values() {
+                    for (iv, decoration) in spans.iter() {
+                        if let Some(icon) = decoration.gutter_icon.as_ref() {
+                            icons
+                                .entry(buffer.line_of_offset(iv.start()))
+                                .or_insert_with(|| icon.clone());
+                        }
+                    }
+                }
+                icons
+            })
+        },
+        move |(line, icon)| {
+            (*line, icon.clone(), doc.with_untracked(|doc| doc.rev()))
+        },
+        move |(line, icon)| {
+            container(svg(move || icon.clone()).style(move |s| {
+                let size = config.get().ui.icon_size() as f32;
+                s.size(size, size)
+            }))
+            .style(move |s| {
+                let line_info = screen_lines.with(|s| s.info_for_line(line));
+                let line_y = line_info.map(|l| l.y).unwrap_or(-100.0);
+                let rect = viewport.get();
+                let config = config.get();
+                let width = config.ui.icon_size() as f32 + icon_padding * 2.0;
+                s.absolute()
+                    .width(width)
+                    .height(config.editor.line_height() as f32)
+                    .justify_center()
+                    .items_center()
+                    .margin_top(line_y as f32 - rect.y0 as f32)
+            })
+        },
+    )
+    .style(move |s| {
+        let config = config.get();
+        let width = config.ui.icon_size() as f32 + icon_padding * 2.0;
+        s.absolute()
+            .width(width)
+            .height_full()
+            .margin_left(width - 8.0)
+    })
+    .debug_name("Volt Gutter Icons")
+}
+
 fn editor_gutter_folding_range(
     window_tab_data: Rc<WindowTabData>,
     doc: DocSignal,
@@ -1915,6 +2048,13 @@ fn editor_gutter(
                     viewport,
                     icon_padding,
                 ),
+                editor_gutter_volt_icons(
+                    window_tab_data.clone(),
+                    doc,
+                    screen_lines,
+                    viewport,
+                    icon_padding,
+                ),
                 editor_gutter_view(e_data.get_untracked(), gutter_padding_right)
                     .on_resize(move |rect| {
                         gutter_rect.set(rect);
@@ -2521,7 +2661,7 @@ fn find_view(
 }
 
 /// Iterator over (len, color, modified) for each change in the diff
-fn changes_color_iter<'a>(
+pub fn changes_color_iter<'a>(
     changes: &'a im::Vector<DiffLines>,
     config: &'a LapceConfig,
 ) -> impl Iterator<Item = (usize, Option<Color>, bool)> + 'a {
@@ -26,11 +26,13 @@
         diff::{DiffEditorData, DiffEditorInfo},
         location::EditorLocation,
     },
+    hex_editor::HexEditorData,
     id::{
-        DiffEditorId, EditorTabId, KeymapId, SettingsId, SplitId,
-        ThemeColorSettingsId, VoltViewId,
+        DiffEditorId, EditorTabId, HexEditorId, KeymapId, MarkdownPreviewId,
+        SettingsId, SplitId, ThemeColorSettingsId, VoltViewId,
     },
     main_split::{Editors, MainSplitData},
+    markdown_preview::MarkdownPreviewData,
     plugin::PluginData,
     window_tab::WindowTabData,
 };
@@ -43,6 +45,8 @@ pub enum EditorTabChildInfo {
     ThemeColorSettings,
     Keymap,
     Volt(VoltID),
+    HexEditor(PathBuf),
+    MarkdownPreview(PathBuf),
 }
 
 impl EditorTabChildInfo {
@@ -70,6 +74,25 @@ pub fn to_data(
             EditorTabChildInfo::Volt(id) => {
                 EditorTabChild::Volt(VoltViewId::next(), id.to_owned())
             }
+            EditorTabChildInfo::HexEditor(path) => {
+                EditorTabChild::HexEditor(data.new_hex_editor(path.to_owned()))
+            }
+            EditorTabChildInfo::MarkdownPreview(path) => {
+                let (doc, _) = data.get_doc(path.to_owned(), None);
+                let markdown_preview = MarkdownPreviewData::new(
+                    data.scope,
+                    MarkdownPreviewId::next(),
+                    path.to_owned(),
+                    doc,
+                    data.active_editor,
+                    data.common.clone(),
+                );
+                let id = markdown_preview.id;
+                data.markdown_previews.update(|markdown_previews| {
+                    markdown_previews.insert(id, markdown_preview);
+                });
+                EditorTabChild::MarkdownPreview(id)
+            }
         }
     }
 }
@@ -131,6 +154,7 @@ pub enum EditorTabChildSource {
     ThemeColorSettings,
     Keymap,
     Volt(VoltID),
+    HexEditor(PathBuf),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -141,6 +165,8 @@ pub enum EditorTabChild {
     ThemeColorSettings(ThemeColorSettingsId),
     Keymap(KeymapId),
     Volt(VoltViewId, VoltID),
+    HexEditor(HexEditorId),
+    MarkdownPreview(MarkdownPreviewId),
 }
 
 #[derive(PartialEq)]
@@ -162,6 +188,8 @@ pub fn id(&self) -> u64 {
             EditorTabChild::ThemeColorSettings(id) => id.to_raw(),
             EditorTabChild::Keymap(id) => id.to_raw(),
             EditorTabChild::Volt(id, _) => id.to_raw(),
+            EditorTabChild::HexEditor(id) => id.to_raw(),
+            EditorTabChild::MarkdownPreview(id) => id.to_raw(),
         }
     }
 
@@ -195,6 +223,26 @@ pub fn child_info(&self, data: &WindowTabData) -> EditorTabChildInfo {
             }
             EditorTabChild::Keymap(_) => EditorTabChildInfo::Keymap,
             EditorTabChild::Volt(_, id) => EditorTabChildInfo::Volt(id.to_owned()),
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                let hex_editor = data
+                    .main_split
+                    .hex_editors
+                    .with_untracked(|hex_editors| {
+                        hex_editors.get(hex_editor_id).cloned()
+                    })
+                    .unwrap();
+                EditorTabChildInfo::HexEditor(hex_editor.path)
+            }
+            EditorTabChild::MarkdownPreview(markdown_preview_id) => {
+                let markdown_preview = data
+                    .main_split
+                    .markdown_previews
+                    .with_untracked(|markdown_previews| {
+                        markdown_previews.get(markdown_preview_id).cloned()
+                    })
+                    .unwrap();
+                EditorTabChildInfo::MarkdownPreview(markdown_preview.path)
+            }
         }
     }
 
@@ -202,6 +250,10 @@ pub fn view_info(
         &self,
         editors: Editors,
         diff_editors: RwSignal<im::HashMap<DiffEditorId, DiffEditorData>>,
+        hex_editors: RwSignal<im::HashMap<HexEditorId, HexEditorData>>,
+        markdown_previews: RwSignal<
+            im::HashMap<MarkdownPreviewId, MarkdownPreviewData>,
+        >,
         plugin: PluginData,
         config: ReadSignal<Arc<LapceConfig>>,
     ) -> Memo<EditorTabChildViewInfo> {
@@ -398,6 +450,52 @@ pub fn view_info(
                     is_pristine: true,
                 }
             }),
+            EditorTabChild::HexEditor(hex_editor_id) => create_memo(move |_| {
+                let config = config.get();
+                let hex_editor = hex_editors
+                    .with(|hex_editors| hex_editors.get(&hex_editor_id).cloned());
+                let (path, is_pristine) = hex_editor
+                    .map(|hex_editor| {
+                        (hex_editor.path.clone(), hex_editor.is_pristine())
+                    })
+                    .unwrap_or_default();
+                let (icon, color) = config.file_svg(&path);
+                EditorTabChildViewInfo {
+                    icon,
+                    color,
+                    name: format!(
+                        "{} (Hex)",
+                        path.file_name().unwrap_or_default().to_string_lossy()
+                    ),
+                    path: Some(path),
+                    confirmed: None,
+                    is_pristine,
+                }
+            }),
+            EditorTabChild::MarkdownPreview(markdown_preview_id) => {
+                create_memo(move |_| {
+                    let config = config.get();
+                    let path = markdown_previews
+                        .with(|markdown_previews| {
+                            markdown_previews.get(&markdown_preview_id).map(
+                                |markdown_preview| markdown_preview.path.clone(),
+                            )
+                        })
+                        .unwrap_or_default();
+                    let (icon, color) = config.file_svg(&path);
+                    EditorTabChildViewInfo {
+                        icon,
+                        color,
+                        name: format!(
+                            "Preview {}",
+                            path.file_name().unwrap_or_default().to_string_lossy()
+                        ),
+                        path: Some(path),
+                        confirmed: None,
+                        is_pristine: true,
+                    }
+                })
+            }
         }
     }
 }
@@ -25,7 +25,7 @@
 use lapce_rpc::{
     file::{
         Duplicating, FileNodeItem, FileNodeViewKind, Naming, NamingState, NewNode,
-        Renaming,
+        Renaming, workspace_root_of,
     },
     proxy::ProxyResponse,
 };
@@ -50,7 +50,8 @@ enum RenamedPath {
 
 #[derive(Clone, Debug)]
 pub struct FileExplorerData {
-    pub root: RwSignal<FileNodeItem>,
+    /// The tree of every root folder of the workspace, the primary folder first
+    pub roots: RwSignal<Vec<FileNodeItem>>,
     pub naming: RwSignal<Naming>,
     pub naming_editor_data: EditorData,
     pub common: Rc<CommonData>,
@@ -120,18 +121,17 @@ fn receive_char(&self, c: &str) {
 impl FileExplorerData {
     pub fn new(cx: Scope, editors: Editors, common: Rc<CommonData>) -> Self {
         let path = common.workspace.path.clone().unwrap_or_default();
-        let root = cx.create_rw_signal(FileNodeItem {
-            path: path.clone(),
-            is_dir: true,
-            read: false,
-            open: false,
-            children: HashMap::new(),
-            children_open_count: 0,
-        });
+        let folders = common.workspace_folders.get_untracked();
+        let roots = cx.create_rw_signal(
+            std::iter::once(path.clone())
+                .chain(folders.iter().cloned())
+                .map(new_root)
+                .collect(),
+        );
         let naming = cx.create_rw_signal(Naming::None);
         let naming_editor_data = editors.make_local(cx, common.clone());
         let data = Self {
-            root,
+            roots,
             naming,
             naming_editor_data,
             common,
@@ -142,21 +142,68 @@ pub fn new(cx: Scope, editors: Editors, common: Rc<CommonData>) -> Self {
         if data.common.workspace.path.is_some() {
             // only fill in the child files if there is open folder
             data.toggle_expand(&path);
+            for folder in folders.iter() {
+                data.toggle_expand(folder);
+            }
         }
         data
     }
 
-    /// Reload the file explorer data via reading the root directory.  
+    /// Reload the file explorer data via reading the root directories.  
     /// Note that this will not update immediately.
     pub fn reload(&self) {
-        let path = self.root.with_untracked(|root| root.path.clone());
-        self.read_dir(&path);
+        let paths: Vec<PathBuf> = self
+            .roots
+            .with_untracked(|roots| roots.iter().map(|r| r.path.clone()).collect());
+        for path in paths {
+            self.read_dir(&path);
+        }
+    }
+
+    /// Show an additional root folder in the explorer.
+    pub fn add_root(&self, path: PathBuf) {
+        let exists = self
+            .roots
+            .with_untracked(|roots| roots.iter().any(|root| root.path == path));
+        if exists {
+            return;
+        }
+        self.roots
+            .update(|roots| roots.push(new_root(path.clone())));
+        self.toggle_expand(&path);
+    }
+
+    /// Stop showing an additional root folder in the explorer.  
+    /// The primary folder of the workspace is never removed.
+    pub fn remove_root(&self, path: &Path) {
+        self.roots.update(|roots| {
+            if let Some(index) = roots.iter().skip(1).position(|r| r.path == path) {
+                roots.remove(index + 1);
+            }
+        });
+    }
+
+    /// Whether `path` is one of the root folders.
+    pub fn is_root(&self, path: &Path) -> bool {
+        self.roots
+            .with_untracked(|roots| roots.iter().any(|root| root.path == path))
+    }
+
+    /// Run `f` on the tree of the root folder that contains `path`.
+    fn update_root<R>(
+        &self,
+        path: &Path,
+        f: impl FnOnce(&mut FileNodeItem) -> R,
+    ) -> Option<R> {
+        self.roots
+            .try_update(|roots| root_of_mut(roots, path).map(f))
+            .flatten()
     }
 
     /// Toggle whether the directory is expanded or not.  
     /// Does nothing if the path does not exist or is not a directory.
     pub fn toggle_expand(&self, path: &Path) {
-        let Some(Some(read)) = self.root.try_update(|root| {
+        let Some(Some(read)) = self.update_root(path, |root| {
             let read = if let Some(node) = root.get_file_node_mut(path) {
                 if !node.is_dir {
                     return None;
@@ -189,7 +236,7 @@ pub fn read_dir(&self, path: &Path) {
     /// `done : FnOnce(was_read: bool)` is called when the operation is completed, whether success,
     /// failure, or ignored.
     pub fn read_dir_cb(&self, path: &Path, done: impl FnOnce(bool) + 'static) {
-        let root = self.root;
+        let roots = self.roots;
         let data = self.clone();
         let config = self.common.config;
         let send = {
@@ -200,7 +247,10 @@ pub fn read_dir_cb(&self, path: &Path, done: impl FnOnce(bool) + 'static) {
                     return;
                 };
 
-                root.update(|root| {
+                roots.update(|roots| {
+                    let Some(root) = root_of_mut(roots, &path) else {
+                        return;
+                    };
                     // Get the node for this path, which should already exist if we're calling
                     // read_dir on it.
                     if let Some(node) = root.get_file_node_mut(&path) {
@@ -256,8 +306,11 @@ pub fn read_dir_cb(&self, path: &Path, done: impl FnOnce(bool) + 'static) {
     /// Returns `true` if `path` exists in the file explorer tree and is a directory, `false`
     /// otherwise.
     fn is_dir(&self, path: &Path) -> bool {
-        self.root.with_untracked(|root| {
-            root.get_file_node(path).is_some_and(|node| node.is_dir)
+        self.roots.with_untracked(|roots| {
+            roots
+                .iter()
+                .filter_map(|root| root.get_file_node(path))
+                .any(|node| node.is_dir)
         })
     }
 
@@ -406,8 +459,7 @@ pub fn click(&self, path: &Path, config: ReadSignal<Arc<LapceConfig>>) {
 
     pub fn reveal_in_file_tree(&self, path: PathBuf) {
         let done = self
-            .root
-            .try_update(|root| {
+            .update_root(&path, |root| {
                 // the directories in which the file are located are all readed and opened
                 if root.get_file_node(&path).is_some() {
                     for current_path in path.ancestors() {
@@ -448,8 +500,7 @@ pub fn reveal_in_file_tree(&self, path: PathBuf) {
             })
             .unwrap_or(false);
         if done {
-            let (found, line) =
-                self.root.with_untracked(|x| x.find_file_at_line(&path));
+            let (found, line) = self.find_file_at_line(&path);
             if found {
                 self.scroll_to_line.set(Some(line));
                 self.select.set(Some(FileNodeViewKind::Path(path)));
@@ -457,6 +508,22 @@ pub fn reveal_in_file_tree(&self, path: PathBuf) {
         }
     }
 
+    /// The row of `path` in the explorer, counting the rows of the root folders
+    /// listed before the one that contains it.
+    fn find_file_at_line(&self, path: &Path) -> (bool, f64) {
+        self.roots.with_untracked(|roots| {
+            let Some(index) = root_index(roots, path) else {
+                return (false, 0.0);
+            };
+            let offset: usize = roots[..index]
+                .iter()
+                .map(|root| root.children_open_count + 1)
+                .sum();
+            let (found, line) = roots[index].find_file_at_line(path);
+            (found, line + offset as f64)
+        })
+    }
+
     pub fn double_click(
         &self,
         path: &Path,
@@ -486,10 +553,17 @@ pub fn secondary_click(&self, path: &Path) {
         // TODO: should we just pass is_dir into secondary click?
         let is_dir = self.is_dir(path);
 
-        let Some(workspace_path) = self.common.workspace.path.as_ref() else {
+        if self.common.workspace.path.is_none() {
             // There is no context menu if we are not in a workspace
             return;
+        }
+        let roots: Vec<PathBuf> = self
+            .roots
+            .with_untracked(|roots| roots.iter().map(|r| r.path.clone()).collect());
+        let Some(workspace_path) = workspace_root_of(&roots, path) else {
+            return;
         };
+        let workspace_path = workspace_path.to_path_buf();
 
         let is_workspace = path == workspace_path;
 
@@ -498,7 +572,7 @@ pub fn secondary_click(&self, path: &Path) {
         } else {
             path_a.parent().map(ToOwned::to_owned)
         };
-        let base_path_a = base_path_a.as_ref().unwrap_or(workspace_path);
+        let base_path_a = base_path_a.as_ref().unwrap_or(&workspace_path);
 
         let mut menu = Menu::new("");
 
@@ -620,13 +694,8 @@ pub fn secondary_click(&self, path: &Path) {
         }));
 
         let path = path_a.clone();
-        let workspace = common.workspace.clone();
         menu = menu.entry(MenuItem::new("Copy Relative Path").action(move || {
-            let relative_path = if let Some(workspace_path) = &workspace.path {
-                path.strip_prefix(workspace_path).unwrap_or(&path)
-            } else {
-                path.as_ref()
-            };
+            let relative_path = path.strip_prefix(&workspace_path).unwrap_or(&path);
 
             let mut clipboard = SystemClipboard::new();
             clipboard.put_string(relative_path.to_string_lossy());
@@ -656,6 +725,16 @@ pub fn secondary_click(&self, path: &Path) {
             ))
         }
 
+        if !is_dir {
+            let path = path_a.clone();
+            let internal_command = common.internal_command;
+            menu =
+                menu.entry(MenuItem::new("Open in Hex Editor").action(move || {
+                    internal_command
+                        .send(InternalCommand::OpenHexEditor { path: path.clone() });
+                }));
+        }
+
         menu = menu.separator();
 
         let internal_command = common.internal_command;
@@ -663,6 +742,17 @@ pub fn secondary_click(&self, path: &Path) {
             internal_command.send(InternalCommand::ReloadFileExplorer);
         }));
 
+        if is_workspace && common.workspace.path.as_deref() != Some(path) {
+            let path = path_a.clone();
+            menu = menu.entry(MenuItem::new("Remove Folder from Workspace").action(
+                move || {
+                    internal_command.send(InternalCommand::RemoveWorkspaceFolder {
+                        path: path.clone(),
+                    });
+                },
+            ));
+        }
+
         show_context_menu(menu, None);
     }
 
@@ -679,3 +769,29 @@ pub fn middle_click(&self, path: &Path) -> EventPropagation {
         }
     }
 }
+
+fn new_root(path: PathBuf) -> FileNodeItem {
+    FileNodeItem {
+        path,
+        is_dir: true,
+        read: false,
+        open: false,
+        children: HashMap::new(),
+        children_open_count: 0,
+    }
+}
+
+/// The index of the innermost root folder that contains `path`.
+fn root_index(roots: &[FileNodeItem], path: &Path) -> Option<usize> {
+    let paths: Vec<PathBuf> = roots.iter().map(|root| root.path.clone()).collect();
+    let root = workspace_root_of(&paths, path)?;
+    roots.iter().position(|item| item.path == root)
+}
+
+fn root_of_mut<'a>(
+    roots: &'a mut [FileNodeItem],
+    path: &Path,
+) -> Option<&'a mut FileNodeItem> {
+    let index = root_index(roots, path)?;
+    roots.get_mut(index)
+}
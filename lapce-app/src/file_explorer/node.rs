@@ -2,22 +2,22 @@
 use lapce_rpc::file::{FileNodeItem, FileNodeViewData, Naming};
 
 pub struct FileNodeVirtualList {
-    file_node_item: FileNodeItem,
+    roots: Vec<FileNodeItem>,
     naming: Naming,
 }
 
 impl FileNodeVirtualList {
-    pub fn new(file_node_item: FileNodeItem, naming: Naming) -> Self {
-        Self {
-            file_node_item,
-            naming,
-        }
+    pub fn new(roots: Vec<FileNodeItem>, naming: Naming) -> Self {
+        Self { roots, naming }
     }
 }
 
 impl VirtualVector<FileNodeViewData> for FileNodeVirtualList {
     fn total_len(&self) -> usize {
-        self.file_node_item.children_open_count + 1
+        self.roots
+            .iter()
+            .map(|root| root.children_open_count + 1)
+            .sum()
     }
 
     fn slice(
@@ -25,13 +25,20 @@ fn slice(
         range: std::ops::Range<usize>,
     ) -> impl Iterator<Item = FileNodeViewData> {
         let naming = &self.naming;
-        let root = &self.file_node_item;
 
         let min = range.start;
         let max = range.end;
         let mut view_items = Vec::new();
 
-        root.append_view_slice(&mut view_items, naming, min, max, 0, 1);
+        // Each root folder is listed right after the rows of the previous one
+        let mut current = 0;
+        for root in self.roots.iter() {
+            if current > max {
+                break;
+            }
+            root.append_view_slice(&mut view_items, naming, min, max, current, 1);
+            current += root.children_open_count + 1;
+        }
 
         view_items.into_iter()
     }
@@ -308,7 +308,7 @@ fn file_explorer_view(
     data: FileExplorerData,
     source_control: SourceControlData,
 ) -> impl View {
-    let root = data.root;
+    let roots = data.roots;
     let ui_line_height = data.common.ui_line_height;
     let config = data.common.config;
     let naming = data.naming;
@@ -319,7 +319,7 @@ fn file_explorer_view(
 
     scroll(
         virtual_stack(
-            move || FileNodeVirtualList::new(root.get(), data.naming.get()),
+            move || FileNodeVirtualList::new(roots.get(), data.naming.get()),
             move |node| (node.kind.clone(), node.is_dir, node.open, node.level),
             move |node| {
                 let level = node.level;
@@ -495,6 +495,8 @@ fn file_explorer_view(
 
 fn open_editors_view(window_tab_data: Rc<WindowTabData>) -> impl View {
     let diff_editors = window_tab_data.main_split.diff_editors;
+    let hex_editors = window_tab_data.main_split.hex_editors;
+    let markdown_previews = window_tab_data.main_split.markdown_previews;
     let editors = window_tab_data.main_split.editors;
     let editor_tabs = window_tab_data.main_split.editor_tabs;
     let config = window_tab_data.common.config;
@@ -509,7 +511,14 @@ fn open_editors_view(window_tab_data: Rc<WindowTabData>) -> impl View {
         let editor_tab_id =
             editor_tab.with_untracked(|editor_tab| editor_tab.editor_tab_id);
         let child_for_close = child.clone();
-        let info = child.view_info(editors, diff_editors, plugin, config);
+        let info = child.view_info(
+            editors,
+            diff_editors,
+            hex_editors,
+            markdown_previews,
+            plugin,
+            config,
+        );
         let hovered = create_rw_signal(false);
 
         stack((
@@ -0,0 +1,1009 @@
+use std::{cell::RefCell, ops::Range, path::PathBuf, rc::Rc};
+
+use floem::{
+    View,
+    event::EventListener,
+    keyboard::Modifiers,
+    peniko::kurbo::{Point, Rect, Size},
+    prelude::SignalTrack,
+    reactive::{RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
+    style::CursorStyle,
+    views::{
+        Decorators, VirtualVector, container, dyn_stack, label, scroll, stack, text,
+        virtual_stack,
+    },
+};
+use lapce_core::{
+    command::{EditCommand, FocusCommand, ScrollCommand},
+    mode::Mode,
+    movement::{LinePosition, Movement},
+};
+use lapce_rpc::proxy::ProxyResponse;
+use lapce_xi_rope::Rope;
+
+use crate::{
+    alert::AlertButton,
+    command::{
+        CommandExecuted, CommandKind, InternalCommand, LapceCommand,
+        LapceWorkbenchCommand,
+    },
+    config::color::LapceColor,
+    editor::EditorData,
+    id::HexEditorId,
+    keypress::{KeyPressFocus, condition::Condition},
+    main_split::Editors,
+    text_input::TextInputBuilder,
+    window_tab::{CommonData, Focus},
+};
+
+/// The number of bytes shown on each row.
+pub const BYTES_PER_ROW: usize = 16;
+/// Counting stops here, so a search for a common byte stays fast.
+const MAX_MATCHES: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ByteEdit {
+    offset: usize,
+    old: u8,
+    new: u8,
+}
+
+/// The undo and redo stacks of the edits to the bytes of a file.
+#[derive(Clone, Debug)]
+pub struct HexHistory {
+    undo: Vec<ByteEdit>,
+    redo: Vec<ByteEdit>,
+    /// The length of the undo stack when the file was last saved, if that
+    /// state can still be reached
+    saved: Option<usize>,
+    /// Incremented by every change, to tell whether a save is still current
+    rev: u64,
+}
+
+impl Default for HexHistory {
+    fn default() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            saved: Some(0),
+            rev: 0,
+        }
+    }
+}
+
+impl HexHistory {
+    /// Overwrite the byte at `offset`. With `merge` the edit is folded into
+    /// the last one when that changed the same byte, so that both digits of a
+    /// byte typed in hex are undone together.
+    pub fn edit(&mut self, bytes: &mut [u8], offset: usize, new: u8, merge: bool) {
+        let Some(byte) = bytes.get_mut(offset) else {
+            return;
+        };
+        let old = std::mem::replace(byte, new);
+        self.rev += 1;
+        if self.saved.is_some_and(|saved| saved > self.undo.len()) {
+            self.saved = None;
+        }
+        self.redo.clear();
+        if merge {
+            if let Some(last) = self.undo.last_mut() {
+                if last.offset == offset {
+                    last.new = new;
+                    if self.saved == Some(self.undo.len()) {
+                        self.saved = None;
+                    }
+                    return;
+                }
+            }
+        }
+        self.undo.push(ByteEdit { offset, old, new });
+    }
+
+    /// Undo the last edit, returning the offset of the byte it changed.
+    pub fn undo(&mut self, bytes: &mut [u8]) -> Option<usize> {
+        let edit = self.undo.pop()?;
+        bytes[edit.offset] = edit.old;
+        self.redo.push(edit);
+        self.rev += 1;
+        Some(edit.offset)
+    }
+
+    /// Redo the last undone edit, returning the offset of the byte it changed.
+    pub fn redo(&mut self, bytes: &mut [u8]) -> Option<usize> {
+        let edit = self.redo.pop()?;
+        bytes[edit.offset] = edit.new;
+        self.undo.push(edit);
+        self.rev += 1;
+        Some(edit.offset)
+    }
+
+    pub fn rev(&self) -> u64 {
+        self.rev
+    }
+
+    pub fn is_pristine(&self) -> bool {
+        self.saved == Some(self.undo.len())
+    }
+
+    pub fn set_pristine(&mut self) {
+        self.saved = Some(self.undo.len());
+    }
+}
+
+/// Parse a byte pattern to search for, either hex digits such as
+/// `DE AD be ef`, or text in double quotes.
+pub fn parse_byte_pattern(pattern: &str) -> Option<Vec<u8>> {
+    let pattern = pattern.trim();
+    if pattern.len() > 2 && pattern.starts_with('"') && pattern.ends_with('"') {
+        return Some(pattern[1..pattern.len() - 1].as_bytes().to_vec());
+    }
+    let digits = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<Vec<u8>>>()?;
+    if digits.is_empty() || digits.len() % 2 != 0 {
+        return None;
+    }
+    Some(
+        digits
+            .chunks(2)
+            .map(|pair| (pair[0] << 4) | pair[1])
+            .collect(),
+    )
+}
+
+/// Parse an offset to go to, which is hexadecimal with a `0x` prefix and
+/// decimal otherwise.
+pub fn parse_offset(offset: &str) -> Option<usize> {
+    let offset = offset.trim();
+    match offset
+        .strip_prefix("0x")
+        .or_else(|| offset.strip_prefix("0X"))
+    {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => offset.parse().ok(),
+    }
+}
+
+/// The offsets of the occurrences of `pattern` in `bytes`.
+pub fn find_bytes(bytes: &[u8], pattern: &[u8]) -> Vec<usize> {
+    let escaped: String = pattern.iter().map(|b| format!("\\x{b:02x}")).collect();
+    let Ok(regex) = regex::bytes::Regex::new(&format!("(?s-u){escaped}")) else {
+        return Vec::new();
+    };
+    regex
+        .find_iter(bytes)
+        .take(MAX_MATCHES)
+        .map(|m| m.start())
+        .collect()
+}
+
+/// The text shown for a byte in the ASCII column.
+fn ascii_char(byte: u8) -> char {
+    if byte.is_ascii_graphic() || byte == b' ' {
+        byte as char
+    } else {
+        '.'
+    }
+}
+
+/// Which of the inputs above the bytes has the keyboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HexInput {
+    None,
+    Offset,
+    Search,
+}
+
+/// The search pattern of a hex editor and where it matches.
+#[derive(Clone, Debug, Default)]
+pub struct HexMatches {
+    pub offsets: Vec<usize>,
+    pub len: usize,
+    /// Whether the pattern couldn't be parsed
+    pub invalid: bool,
+}
+
+impl HexMatches {
+    fn contains(&self, offset: usize) -> bool {
+        let index = self.offsets.partition_point(|start| *start <= offset);
+        index > 0 && offset < self.offsets[index - 1] + self.len
+    }
+}
+
+/// The bytes of a file open in hex editors, shared by all the hex editors of
+/// the file, so that an edit made in one shows in the others.
+#[derive(Clone, Copy)]
+pub struct HexFile {
+    /// Disposed of once the last hex editor of the file is closed
+    pub scope: Scope,
+    pub bytes: RwSignal<Vec<u8>>,
+    pub loaded: RwSignal<bool>,
+    /// The error from reading the file, if it couldn't be read
+    pub error: RwSignal<Option<String>>,
+    pub history: RwSignal<HexHistory>,
+    /// The hash of the bytes on disk the edits are made to
+    pub disk_hash: RwSignal<Option<String>>,
+    /// Whether the file changed on disk while it had unsaved edits
+    pub changed_on_disk: RwSignal<bool>,
+}
+
+impl HexFile {
+    fn new(cx: Scope) -> Self {
+        let cx = cx.create_child();
+        Self {
+            scope: cx,
+            bytes: cx.create_rw_signal(Vec::new()),
+            loaded: cx.create_rw_signal(false),
+            error: cx.create_rw_signal(None),
+            history: cx.create_rw_signal(HexHistory::default()),
+            disk_hash: cx.create_rw_signal(None),
+            changed_on_disk: cx.create_rw_signal(false),
+        }
+    }
+
+    /// Replace the bytes with those read from disk, dropping the edits.
+    fn set_disk_bytes(&self, content: Vec<u8>, disk_hash: String) {
+        self.bytes.set(content);
+        self.history.set(HexHistory::default());
+        self.disk_hash.set(Some(disk_hash));
+        self.changed_on_disk.set(false);
+        self.error.set(None);
+        self.loaded.set(true);
+    }
+
+    /// Reload the bytes after the file changed on disk, unless they have
+    /// unsaved edits, which are kept and saving asks first.
+    pub fn handle_file_changed(&self, content: Vec<u8>, disk_hash: String) {
+        if self
+            .disk_hash
+            .with_untracked(|hash| hash.as_ref() == Some(&disk_hash))
+        {
+            return;
+        }
+        if self.history.with_untracked(HexHistory::is_pristine) {
+            self.set_disk_bytes(content, disk_hash);
+        } else {
+            self.changed_on_disk.set(true);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct HexEditorData {
+    pub id: HexEditorId,
+    pub scope: Scope,
+    pub path: PathBuf,
+    pub file: HexFile,
+    pub cursor: RwSignal<usize>,
+    /// Whether the next hex digit typed goes into the low half of the byte
+    pub low_nibble: RwSignal<bool>,
+    /// Whether typing goes into the ASCII column rather than the hex one
+    pub ascii: RwSignal<bool>,
+    pub input: RwSignal<HexInput>,
+    pub offset_editor: EditorData,
+    pub search_editor: EditorData,
+    pub matches: RwSignal<HexMatches>,
+    pub viewport: RwSignal<Rect>,
+    pub common: Rc<CommonData>,
+}
+
+impl std::fmt::Debug for HexEditorData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HexEditorData")
+            .field("id", &self.id)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl KeyPressFocus for HexEditorData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        self.input.get_untracked() == HexInput::Search
+            && condition == Condition::SearchFocus
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: Modifiers,
+    ) -> CommandExecuted {
+        match (self.input.get_untracked(), &command.kind) {
+            (_, CommandKind::Focus(FocusCommand::Search)) => {
+                self.focus_input(HexInput::Search);
+            }
+            (_, CommandKind::Workbench(LapceWorkbenchCommand::PaletteLine)) => {
+                self.focus_input(HexInput::Offset);
+            }
+            (_, CommandKind::Focus(FocusCommand::Save)) => {
+                self.save(|| {});
+            }
+            (HexInput::None, _) => return self.run_byte_command(command, count),
+            (_, CommandKind::Focus(FocusCommand::ModalClose)) => {
+                self.input.set(HexInput::None);
+            }
+            (HexInput::Offset, CommandKind::Edit(EditCommand::InsertNewLine)) => {
+                self.go_to_input_offset();
+            }
+            (
+                HexInput::Search,
+                CommandKind::Focus(FocusCommand::SearchForward)
+                | CommandKind::Edit(EditCommand::InsertNewLine),
+            ) => {
+                self.next_match(true);
+            }
+            (HexInput::Search, CommandKind::Focus(FocusCommand::SearchBackward)) => {
+                self.next_match(false);
+            }
+            (
+                input,
+                CommandKind::Edit(_)
+                | CommandKind::Move(_)
+                | CommandKind::MultiSelection(_),
+            ) => {
+                return self.input_editor(input).run_command(command, count, mods);
+            }
+            _ => return CommandExecuted::No,
+        }
+        CommandExecuted::Yes
+    }
+
+    fn receive_char(&self, c: &str) {
+        match self.input.get_untracked() {
+            HexInput::None => self.type_bytes(c),
+            input => self.input_editor(input).receive_char(c),
+        }
+    }
+}
+
+impl HexEditorData {
+    /// Create a hex editor of the file at `path`, sharing the bytes of
+    /// `file` when the file is already open in another hex editor.
+    pub fn new(
+        cx: Scope,
+        id: HexEditorId,
+        path: PathBuf,
+        file: Option<HexFile>,
+        editors: Editors,
+        common: Rc<CommonData>,
+    ) -> Self {
+        let (file, load) = match file {
+            Some(file) => (file, false),
+            None => (HexFile::new(cx), true),
+        };
+        let hex_editor =
+            Self::with_file(cx.create_child(), id, path, file, editors, common);
+        if load {
+            hex_editor.load();
+        }
+        hex_editor
+    }
+
+    fn with_file(
+        cx: Scope,
+        id: HexEditorId,
+        path: PathBuf,
+        file: HexFile,
+        editors: Editors,
+        common: Rc<CommonData>,
+    ) -> Self {
+        let hex_editor = Self {
+            id,
+            scope: cx,
+            path,
+            file,
+            cursor: cx.create_rw_signal(0),
+            low_nibble: cx.create_rw_signal(false),
+            ascii: cx.create_rw_signal(false),
+            input: cx.create_rw_signal(HexInput::None),
+            offset_editor: editors.make_local(cx, common.clone()),
+            search_editor: editors.make_local(cx, common.clone()),
+            matches: cx.create_rw_signal(HexMatches::default()),
+            viewport: cx.create_rw_signal(Rect::ZERO),
+            common,
+        };
+
+        {
+            let hex_editor = hex_editor.clone();
+            let buffer = hex_editor.search_editor.doc().buffer;
+            cx.create_effect(move |_| {
+                let pattern = buffer.with(|buffer| buffer.to_string());
+                hex_editor.file.loaded.track();
+                hex_editor.file.disk_hash.track();
+                hex_editor.search(&pattern);
+            });
+        }
+
+        hex_editor
+    }
+
+    /// Copy the hex editor into a new tab, with its own cursor but the same
+    /// bytes and history.
+    pub fn copy(&self, cx: Scope, id: HexEditorId, editors: Editors) -> Self {
+        let hex_editor = Self::with_file(
+            cx.create_child(),
+            id,
+            self.path.clone(),
+            self.file,
+            editors,
+            self.common.clone(),
+        );
+        hex_editor.cursor.set(self.cursor.get_untracked());
+        hex_editor
+    }
+
+    /// Read the bytes of the file, dropping any edits.
+    fn load(&self) {
+        let file = self.file;
+        let send = file.scope.create_ext_action(move |result| match result {
+            Ok(ProxyResponse::ReadFileBytesResponse { content, disk_hash }) => {
+                file.set_disk_bytes(content, disk_hash);
+            }
+            Err(err) => {
+                file.error.set(Some(err.message));
+            }
+            Ok(_) => {}
+        });
+        self.common
+            .proxy
+            .read_file_bytes(self.path.clone(), move |result| {
+                send(result);
+            });
+    }
+
+    pub fn is_pristine(&self) -> bool {
+        self.file.history.with(HexHistory::is_pristine)
+    }
+
+    /// Save the bytes, asking first whether to overwrite the file when it
+    /// changed on disk since.
+    pub fn save(&self, after_action: impl FnOnce() + 'static) {
+        if !self.file.loaded.get_untracked() {
+            return;
+        }
+        if !self.file.changed_on_disk.get_untracked() {
+            self.write(false, after_action);
+            return;
+        }
+
+        let internal_command = self.common.internal_command;
+        let name = self
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        let after_action = Rc::new(RefCell::new(Some(after_action)));
+        let overwrite_hex_editor = self.clone();
+        let reload_hex_editor = self.clone();
+        internal_command.send(InternalCommand::ShowAlert {
+            title: format!("{name} changed on disk since it was opened"),
+            msg: "Saving replaces what changed on disk with your changes."
+                .to_string(),
+            buttons: vec![
+                AlertButton {
+                    text: "Overwrite".to_string(),
+                    action: Rc::new(move || {
+                        internal_command.send(InternalCommand::HideAlert);
+                        if let Some(after_action) = after_action.borrow_mut().take()
+                        {
+                            overwrite_hex_editor.write(true, after_action);
+                        }
+                    }),
+                },
+                AlertButton {
+                    text: "Discard Changes and Reload".to_string(),
+                    action: Rc::new(move || {
+                        internal_command.send(InternalCommand::HideAlert);
+                        reload_hex_editor.load();
+                    }),
+                },
+            ],
+        });
+    }
+
+    /// Write the bytes to disk. Unless `overwrite`, the proxy refuses to
+    /// when the file no longer has the bytes the edits were made to.
+    fn write(&self, overwrite: bool, after_action: impl FnOnce() + 'static) {
+        let file = self.file;
+        let rev = file.history.with_untracked(HexHistory::rev);
+        let internal_command = self.common.internal_command;
+        let send = file.scope.create_ext_action(move |result| match result {
+            Ok(ProxyResponse::SaveResponse { disk_hash }) => {
+                if file.history.with_untracked(HexHistory::rev) == rev {
+                    file.history.update(HexHistory::set_pristine);
+                }
+                file.disk_hash.set(disk_hash);
+                file.changed_on_disk.set(false);
+                after_action();
+            }
+            Err(err) => {
+                internal_command.send(InternalCommand::ShowAlert {
+                    title: "Failed to save the file".to_string(),
+                    msg: err.message,
+                    buttons: Vec::new(),
+                });
+            }
+            Ok(_) => {}
+        });
+        let disk_hash = if overwrite {
+            None
+        } else {
+            file.disk_hash.get_untracked()
+        };
+        self.common.proxy.save_file_bytes(
+            self.path.clone(),
+            file.bytes.get_untracked(),
+            disk_hash,
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    fn len(&self) -> usize {
+        self.file.bytes.with_untracked(Vec::len)
+    }
+
+    fn set_cursor(&self, offset: usize) {
+        self.cursor.set(offset.min(self.len().saturating_sub(1)));
+        self.low_nibble.set(false);
+    }
+
+    fn rows_per_page(&self) -> usize {
+        let row_height = self
+            .common
+            .config
+            .with_untracked(|config| config.editor.line_height())
+            .max(1);
+        (self.viewport.get_untracked().height() as usize / row_height).max(1)
+    }
+
+    fn run_byte_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+    ) -> CommandExecuted {
+        let cursor = self.cursor.get_untracked();
+        let count = count.unwrap_or(1);
+        let row_start = cursor - cursor % BYTES_PER_ROW;
+        let offset = match &command.kind {
+            CommandKind::Move(cmd) => match cmd.to_movement(None) {
+                Movement::Left => cursor.saturating_sub(count),
+                Movement::Right => cursor + count,
+                Movement::Up => cursor.saturating_sub(count * BYTES_PER_ROW),
+                Movement::Down => cursor + count * BYTES_PER_ROW,
+                Movement::StartOfLine | Movement::FirstNonBlank => row_start,
+                Movement::EndOfLine => row_start + BYTES_PER_ROW - 1,
+                Movement::Line(LinePosition::First) => 0,
+                Movement::Line(LinePosition::Last) => self.len(),
+                _ => return CommandExecuted::No,
+            },
+            CommandKind::Scroll(ScrollCommand::PageUp) => {
+                cursor.saturating_sub(count * self.rows_per_page() * BYTES_PER_ROW)
+            }
+            CommandKind::Scroll(ScrollCommand::PageDown) => {
+                cursor + count * self.rows_per_page() * BYTES_PER_ROW
+            }
+            CommandKind::Edit(EditCommand::Undo) => {
+                match self
+                    .file
+                    .bytes
+                    .try_update(|bytes| {
+                        self.file.history.try_update(|history| history.undo(bytes))
+                    })
+                    .flatten()
+                    .flatten()
+                {
+                    Some(offset) => offset,
+                    None => return CommandExecuted::Yes,
+                }
+            }
+            CommandKind::Edit(EditCommand::Redo) => {
+                match self
+                    .file
+                    .bytes
+                    .try_update(|bytes| {
+                        self.file.history.try_update(|history| history.redo(bytes))
+                    })
+                    .flatten()
+                    .flatten()
+                {
+                    Some(offset) => offset,
+                    None => return CommandExecuted::Yes,
+                }
+            }
+            CommandKind::Focus(FocusCommand::SearchForward) => {
+                self.next_match(true);
+                return CommandExecuted::Yes;
+            }
+            CommandKind::Focus(FocusCommand::SearchBackward) => {
+                self.next_match(false);
+                return CommandExecuted::Yes;
+            }
+            _ => return CommandExecuted::No,
+        };
+        self.set_cursor(offset);
+        CommandExecuted::Yes
+    }
+
+    /// Overwrite the bytes at the cursor with typed hex digits, or with the
+    /// typed characters in the ASCII column.
+    fn type_bytes(&self, c: &str) {
+        if !self.file.loaded.get_untracked() {
+            return;
+        }
+        let ascii = self.ascii.get_untracked();
+        for c in c.chars() {
+            let offset = self.cursor.get_untracked();
+            if offset >= self.len() {
+                return;
+            }
+            if ascii {
+                if !c.is_ascii() {
+                    continue;
+                }
+                self.edit(offset, c as u8, false);
+                self.set_cursor(offset + 1);
+                continue;
+            }
+            let Some(digit) = c.to_digit(16).map(|digit| digit as u8) else {
+                continue;
+            };
+            let byte = self.file.bytes.with_untracked(|bytes| bytes[offset]);
+            if self.low_nibble.get_untracked() {
+                self.edit(offset, (byte & 0xf0) | digit, true);
+                self.set_cursor(offset + 1);
+            } else {
+                self.edit(offset, (digit << 4) | (byte & 0x0f), false);
+                self.low_nibble.set(true);
+            }
+        }
+    }
+
+    fn edit(&self, offset: usize, byte: u8, merge: bool) {
+        self.file.bytes.update(|bytes| {
+            self.file
+                .history
+                .update(|history| history.edit(bytes, offset, byte, merge));
+        });
+    }
+
+    fn input_editor(&self, input: HexInput) -> &EditorData {
+        match input {
+            HexInput::Offset => &self.offset_editor,
+            HexInput::None | HexInput::Search => &self.search_editor,
+        }
+    }
+
+    fn focus_input(&self, input: HexInput) {
+        self.common.focus.set(Focus::Workbench);
+        self.input.set(input);
+    }
+
+    fn go_to_input_offset(&self) {
+        let text = self
+            .offset_editor
+            .doc()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string());
+        if let Some(offset) = parse_offset(&text) {
+            self.set_cursor(offset);
+            self.offset_editor.doc().reload(Rope::from(""), true);
+            self.input.set(HexInput::None);
+        }
+    }
+
+    fn search(&self, pattern: &str) {
+        let matches = if pattern.trim().is_empty() {
+            HexMatches::default()
+        } else if let Some(pattern) = parse_byte_pattern(pattern) {
+            HexMatches {
+                offsets: self
+                    .file
+                    .bytes
+                    .with_untracked(|bytes| find_bytes(bytes, &pattern)),
+                len: pattern.len(),
+                invalid: false,
+            }
+        } else {
+            HexMatches {
+                invalid: true,
+                ..Default::default()
+            }
+        };
+        self.matches.set(matches);
+    }
+
+    /// Move the cursor to the next match after it, or the previous one
+    /// before it, wrapping around the file.
+    fn next_match(&self, forward: bool) {
+        let cursor = self.cursor.get_untracked();
+        let offset = self.matches.with_untracked(|matches| {
+            let offsets = &matches.offsets;
+            if forward {
+                offsets
+                    .iter()
+                    .find(|offset| **offset > cursor)
+                    .or_else(|| offsets.first())
+                    .copied()
+            } else {
+                offsets
+                    .iter()
+                    .rev()
+                    .find(|offset| **offset < cursor)
+                    .or_else(|| offsets.last())
+                    .copied()
+            }
+        });
+        if let Some(offset) = offset {
+            self.set_cursor(offset);
+        }
+    }
+
+    /// The position of the focused match and the number of matches.
+    fn match_label(&self) -> String {
+        let cursor = self.cursor.get();
+        self.matches.with(|matches| {
+            if matches.invalid {
+                return "Invalid pattern".to_string();
+            }
+            let count = matches.offsets.len();
+            match matches.offsets.binary_search(&cursor) {
+                Ok(index) => format!("{} of {count}", index + 1),
+                Err(_) if count == 0 => "No results".to_string(),
+                Err(_) => format!("{count} results"),
+            }
+        })
+    }
+}
+
+struct HexRows(usize);
+
+impl VirtualVector<usize> for HexRows {
+    fn total_len(&self) -> usize {
+        self.0
+    }
+
+    fn slice(&mut self, range: Range<usize>) -> impl Iterator<Item = usize> {
+        range
+    }
+}
+
+fn byte_cell(hex_editor: HexEditorData, offset: usize, ascii: bool) -> impl View {
+    let config = hex_editor.common.config;
+    let bytes = hex_editor.file.bytes;
+    let cursor = hex_editor.cursor;
+    let matches = hex_editor.matches;
+    let ascii_focus = hex_editor.ascii;
+    label(move || {
+        bytes.with(|bytes| match bytes.get(offset) {
+            Some(byte) if ascii => ascii_char(*byte).to_string(),
+            Some(byte) => format!("{byte:02X}"),
+            None => String::new(),
+        })
+    })
+    .on_click_stop(move |_| {
+        hex_editor.set_cursor(offset);
+        hex_editor.ascii.set(ascii);
+        hex_editor.focus_input(HexInput::None);
+    })
+    .style(move |s| {
+        let config = config.get();
+        let is_cursor = cursor.get() == offset;
+        let is_match = matches.with(|matches| matches.contains(offset));
+        let char_width = config.editor.font_size() as f64 * 0.6;
+        s.width(char_width * if ascii { 1.0 } else { 2.0 })
+            .margin_right(if ascii { 0.0 } else { char_width })
+            .apply_if(is_match, |s| {
+                s.background(
+                    config.color(LapceColor::EDITOR_FOCUS).multiply_alpha(0.3),
+                )
+            })
+            .apply_if(is_cursor, |s| {
+                s.background(config.color(LapceColor::EDITOR_SELECTION))
+            })
+            .apply_if(is_cursor && ascii_focus.get() == ascii, |s| {
+                s.border_bottom(2.0)
+                    .border_color(config.color(LapceColor::EDITOR_CARET))
+            })
+    })
+}
+
+fn row_view(hex_editor: HexEditorData, row: usize) -> impl View {
+    let config = hex_editor.common.config;
+    let start = row * BYTES_PER_ROW;
+    let ascii_editor = hex_editor.clone();
+    stack((
+        text(format!("{start:08X}")).style(move |s| {
+            let config = config.get();
+            s.margin_right(config.editor.font_size() as f32 * 1.5)
+                .color(config.color(LapceColor::EDITOR_DIM))
+        }),
+        dyn_stack(
+            move || start..start + BYTES_PER_ROW,
+            |offset| *offset,
+            move |offset| byte_cell(hex_editor.clone(), offset, false),
+        ),
+        dyn_stack(
+            move || start..start + BYTES_PER_ROW,
+            |offset| *offset,
+            move |offset| byte_cell(ascii_editor.clone(), offset, true),
+        )
+        .style(move |s| s.margin_left(config.get().editor.font_size() as f32)),
+    ))
+    .style(move |s| {
+        let config = config.get();
+        s.items_center()
+            .height(config.editor.line_height() as f32)
+            .padding_horiz(10.0)
+    })
+}
+
+fn input_view(
+    hex_editor: &HexEditorData,
+    input: HexInput,
+    placeholder: &'static str,
+) -> impl View {
+    let config = hex_editor.common.config;
+    let focused = hex_editor.input;
+    let local_hex_editor = hex_editor.clone();
+    TextInputBuilder::new()
+        .is_focused(move || focused.get() == input)
+        .key_focus(hex_editor.clone())
+        .build_editor(hex_editor.input_editor(input).clone())
+        .placeholder(move || placeholder.to_string())
+        .on_event_cont(EventListener::PointerDown, move |_| {
+            local_hex_editor.focus_input(input);
+        })
+        .style(move |s| {
+            s.width(200.0)
+                .margin_right(10.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+        })
+}
+
+pub fn hex_editor_view(hex_editor: HexEditorData) -> impl View {
+    let config = hex_editor.common.config;
+    let bytes = hex_editor.file.bytes;
+    let cursor = hex_editor.cursor;
+    let error = hex_editor.file.error;
+    let viewport = hex_editor.viewport;
+    let focus = hex_editor.common.focus;
+    let header_hex_editor = hex_editor.clone();
+    let rows_hex_editor = hex_editor.clone();
+
+    stack((
+        stack((
+            input_view(&hex_editor, HexInput::Offset, "Go to Offset"),
+            input_view(&hex_editor, HexInput::Search, "Search Bytes or \"Text\""),
+            label(move || header_hex_editor.match_label()),
+            label(move || {
+                if let Some(error) = error.get() {
+                    return error;
+                }
+                let cursor = cursor.get();
+                let len = bytes.with(Vec::len);
+                format!("Offset {cursor:#X} ({cursor}) of {len} bytes")
+            })
+            .style(|s| s.margin_left(20.0)),
+        ))
+        .style(move |s| {
+            s.items_center()
+                .padding(10.0)
+                .border_bottom(1.0)
+                .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+        }),
+        container(
+            scroll(
+                virtual_stack(
+                    move || {
+                        HexRows(
+                            bytes.with(|bytes| bytes.len().div_ceil(BYTES_PER_ROW)),
+                        )
+                    },
+                    |row| *row,
+                    move |row| row_view(rows_hex_editor.clone(), row),
+                )
+                .item_size_fixed(move || config.get().editor.line_height() as f64)
+                .style(|s| s.flex_col().min_width_full()),
+            )
+            .on_scroll(move |rect| {
+                viewport.set(rect);
+            })
+            .ensure_visible(move || {
+                let line_height = config.get().editor.line_height() as f64;
+                let row = cursor.get() / BYTES_PER_ROW;
+                Rect::from_origin_size(
+                    Point::new(0.0, row as f64 * line_height),
+                    Size::new(1.0, line_height),
+                )
+            })
+            .style(|s| s.absolute().size_full()),
+        )
+        .style(|s| s.width_full().flex_basis(0.0).flex_grow(1.0)),
+    ))
+    .on_event_cont(EventListener::PointerDown, move |_| {
+        focus.set(Focus::Workbench);
+    })
+    .style(move |s| {
+        let config = config.get();
+        s.size_full()
+            .flex_col()
+            .cursor(CursorStyle::Text)
+            .font_family(config.editor.font_family.clone())
+            .font_size(config.editor.font_size() as f32)
+            .color(config.color(LapceColor::EDITOR_FOREGROUND))
+            .background(config.color(LapceColor::EDITOR_BACKGROUND))
+    })
+    .debug_name("Hex Editor")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_history() {
+        let mut bytes = vec![0x00, 0x11, 0x22];
+        let mut history = HexHistory::default();
+        history.edit(&mut bytes, 1, 0xa1, false);
+        history.edit(&mut bytes, 1, 0xab, true);
+        history.edit(&mut bytes, 2, 0xcd, true);
+        assert_eq!(bytes, [0x00, 0xab, 0xcd]);
+        assert!(!history.is_pristine());
+
+        assert_eq!(history.undo(&mut bytes), Some(2));
+        assert_eq!(history.undo(&mut bytes), Some(1));
+        assert_eq!(history.undo(&mut bytes), None);
+        assert_eq!(bytes, [0x00, 0x11, 0x22]);
+        assert!(history.is_pristine());
+
+        assert_eq!(history.redo(&mut bytes), Some(1));
+        history.set_pristine();
+        history.edit(&mut bytes, 0, 0xff, false);
+        assert_eq!(history.undo(&mut bytes), Some(0));
+        assert!(history.is_pristine());
+    }
+
+    #[test]
+    fn test_file_changed() {
+        let file = HexFile::new(Scope::new());
+        file.set_disk_bytes(vec![0x00, 0x11], "a".to_string());
+        file.handle_file_changed(vec![0x22, 0x33], "b".to_string());
+        assert_eq!(file.bytes.get_untracked(), [0x22, 0x33]);
+        assert!(!file.changed_on_disk.get_untracked());
+
+        // Unsaved edits aren't dropped.
+        file.bytes.update(|bytes| {
+            file.history
+                .update(|history| history.edit(bytes, 0, 0xff, false));
+        });
+        file.handle_file_changed(vec![0x44, 0x55], "c".to_string());
+        assert_eq!(file.bytes.get_untracked(), [0xff, 0x33]);
+        assert!(file.changed_on_disk.get_untracked());
+    }
+
+    #[test]
+    fn test_parse_byte_pattern() {
+        assert_eq!(
+            parse_byte_pattern("DE AD be ef"),
+            Some(vec![0xde, 0xad, 0xbe, 0xef])
+        );
+        assert_eq!(parse_byte_pattern("\"ELF\""), Some(b"ELF".to_vec()));
+        assert_eq!(parse_byte_pattern("ABC"), None);
+        assert_eq!(parse_byte_pattern("zz"), None);
+        assert_eq!(parse_offset("0x1F"), Some(31));
+        assert_eq!(parse_offset("31"), Some(31));
+        assert_eq!(find_bytes(b"a.b.a.b", b".b"), vec![1, 5]);
+        assert_eq!(find_bytes(&[0xff, 0x00, 0xff], &[0xff]), vec![0, 2]);
+    }
+}
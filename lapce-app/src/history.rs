@@ -1,6 +1,15 @@
-use std::path::PathBuf;
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use lapce_core::buffer::Buffer;
+use floem::{
+    ext_event::create_ext_action,
+    reactive::{RwSignal, Scope, SignalUpdate},
+};
+use lapce_core::{buffer::Buffer, directory::Directory};
+use sha2::{Digest, Sha256};
 
 #[derive(Clone)]
 pub struct DocumentHistory {
@@ -24,3 +33,350 @@ pub fn new(_path: PathBuf, _version: String, content: &str) -> Self {
         }
     }
 }
+
+/// The prefix of the `DocHistory` version of a local history snapshot, which
+/// is followed by the snapshot's timestamp.
+pub const LOCAL_HISTORY_VERSION: &str = "local:";
+
+/// Why a local history snapshot was taken
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LocalHistorySource {
+    /// The file was saved, and this is the content the save replaced.
+    Save,
+    /// The file was changed by another program, and this is the content from
+    /// before the change.
+    ExternalChange,
+}
+
+impl LocalHistorySource {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LocalHistorySource::Save => "save",
+            LocalHistorySource::ExternalChange => "external",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "save" => Some(LocalHistorySource::Save),
+            "external" => Some(LocalHistorySource::ExternalChange),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LocalHistorySource::Save => "Before Save",
+            LocalHistorySource::ExternalChange => "Before Change on Disk",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LocalHistoryEntry {
+    /// Milliseconds since the unix epoch
+    pub timestamp: u64,
+    pub source: LocalHistorySource,
+    /// The file holding the snapshot
+    pub file: PathBuf,
+}
+
+impl LocalHistoryEntry {
+    /// Parse a snapshot file name, which is `<timestamp>-<source>`
+    fn from_file(file: PathBuf) -> Option<Self> {
+        let name = file.file_name()?.to_str()?;
+        let (timestamp, source) = name.split_once('-')?;
+        Some(Self {
+            timestamp: timestamp.parse().ok()?,
+            source: LocalHistorySource::parse(source)?,
+            file,
+        })
+    }
+
+    pub fn read(&self) -> io::Result<String> {
+        std::fs::read_to_string(&self.file)
+    }
+
+    pub fn version(&self) -> String {
+        format!("{LOCAL_HISTORY_VERSION}{}", self.timestamp)
+    }
+
+    /// The local time the snapshot was taken at
+    pub fn time(&self) -> String {
+        chrono::DateTime::from_timestamp_millis(self.timestamp as i64)
+            .map(|time| {
+                time.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// The snapshots of files in a folder, kept independently of source control.
+/// Each file has its own folder, named by the hash of its path.
+#[derive(Clone, Debug)]
+pub struct LocalHistoryStore {
+    dir: PathBuf,
+}
+
+impl LocalHistoryStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn file_dir(&self, path: &Path) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(path.to_string_lossy().as_bytes());
+        self.dir.join(format!("{:x}", hasher.finalize()))
+    }
+
+    /// The snapshots of the file, newest first
+    pub fn entries(&self, path: &Path) -> Vec<LocalHistoryEntry> {
+        let Ok(dir) = std::fs::read_dir(self.file_dir(path)) else {
+            return Vec::new();
+        };
+        let mut entries: Vec<LocalHistoryEntry> = dir
+            .flatten()
+            .filter_map(|entry| LocalHistoryEntry::from_file(entry.path()))
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.timestamp));
+        entries
+    }
+
+    pub fn entry(&self, path: &Path, timestamp: u64) -> Option<LocalHistoryEntry> {
+        self.entries(path)
+            .into_iter()
+            .find(|entry| entry.timestamp == timestamp)
+    }
+
+    /// Record a snapshot of the file, unless it's the same as the latest one,
+    /// and then drop the snapshots beyond `max_entries` or older than
+    /// `max_days`. A `max_days` of zero keeps snapshots regardless of age.
+    pub fn record(
+        &self,
+        path: &Path,
+        text: &str,
+        source: LocalHistorySource,
+        timestamp: u64,
+        max_entries: usize,
+        max_days: u64,
+    ) -> io::Result<()> {
+        let dir = self.file_dir(path);
+        let entries = self.entries(path);
+        let unchanged = entries
+            .first()
+            .and_then(|latest| latest.read().ok())
+            .map(|latest| latest == text)
+            .unwrap_or(false);
+        if !unchanged {
+            std::fs::create_dir_all(&dir)?;
+            std::fs::write(
+                dir.join(format!("{timestamp}-{}", source.as_str())),
+                text,
+            )?;
+        }
+        self.prune(path, timestamp, max_entries, max_days)
+    }
+
+    fn prune(
+        &self,
+        path: &Path,
+        now: u64,
+        max_entries: usize,
+        max_days: u64,
+    ) -> io::Result<()> {
+        let max_age =
+            Duration::from_secs(max_days * 24 * 60 * 60).as_millis() as u64;
+        for (i, entry) in self.entries(path).into_iter().enumerate() {
+            let expired =
+                max_days > 0 && now.saturating_sub(entry.timestamp) > max_age;
+            if i >= max_entries || expired {
+                std::fs::remove_file(&entry.file)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The local history of the files opened in a window tab
+#[derive(Clone)]
+pub struct LocalHistory {
+    store: Option<LocalHistoryStore>,
+    /// Bumped after a snapshot is recorded, so that timelines refresh
+    pub rev: RwSignal<u64>,
+}
+
+impl LocalHistory {
+    pub fn new(cx: Scope) -> Self {
+        Self {
+            store: Directory::local_history_directory().map(LocalHistoryStore::new),
+            rev: cx.create_rw_signal(0),
+        }
+    }
+
+    /// Read the snapshots of the file in the background, and send them
+    /// newest first.
+    pub fn entries(
+        &self,
+        scope: Scope,
+        path: PathBuf,
+        send: impl FnOnce(Vec<LocalHistoryEntry>) + 'static,
+    ) {
+        let Some(store) = self.store.clone() else {
+            send(Vec::new());
+            return;
+        };
+        let send = create_ext_action(scope, send);
+        let result = std::thread::Builder::new()
+            .name("LocalHistoryEntries".to_owned())
+            .spawn(move || {
+                send(store.entries(&path));
+            });
+        if let Err(err) = result {
+            tracing::error!("{:?}", err);
+        }
+    }
+
+    /// Read the snapshot with the given `DocHistory` version in the
+    /// background, and send its text.
+    pub fn read(
+        &self,
+        scope: Scope,
+        path: PathBuf,
+        version: &str,
+        send: impl FnOnce(Option<String>) + 'static,
+    ) {
+        let (Some(store), Some(timestamp)) = (
+            self.store.clone(),
+            version
+                .strip_prefix(LOCAL_HISTORY_VERSION)
+                .and_then(|timestamp| timestamp.parse().ok()),
+        ) else {
+            send(None);
+            return;
+        };
+        let send = create_ext_action(scope, send);
+        let result = std::thread::Builder::new()
+            .name("LocalHistoryRead".to_owned())
+            .spawn(move || {
+                send(
+                    store
+                        .entry(&path, timestamp)
+                        .and_then(|entry| entry.read().ok()),
+                );
+            });
+        if let Err(err) = result {
+            tracing::error!("{:?}", err);
+        }
+    }
+
+    /// Record a snapshot in the background. A `max_entries` of zero disables
+    /// the local history.
+    pub fn record(
+        &self,
+        scope: Scope,
+        path: PathBuf,
+        text: String,
+        source: LocalHistorySource,
+        max_entries: usize,
+        max_days: u64,
+    ) {
+        if max_entries == 0 {
+            return;
+        }
+        let Some(store) = self.store.clone() else {
+            return;
+        };
+        let rev = self.rev;
+        let send = create_ext_action(scope, move |_: ()| {
+            rev.update(|rev| *rev += 1);
+        });
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let result = std::thread::Builder::new()
+            .name("LocalHistory".to_owned())
+            .spawn(move || {
+                if let Err(err) = store.record(
+                    &path,
+                    &text,
+                    source,
+                    timestamp,
+                    max_entries,
+                    max_days,
+                ) {
+                    tracing::error!("{:?}", err);
+                }
+                send(());
+            });
+        if let Err(err) = result {
+            tracing::error!("{:?}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60 * 1000;
+
+    #[test]
+    fn test_record_skips_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = LocalHistoryStore::new(dir.path().to_path_buf());
+        let path = Path::new("/project/main.rs");
+
+        store
+            .record(path, "a", LocalHistorySource::Save, 1, 10, 0)
+            .unwrap();
+        store
+            .record(path, "a", LocalHistorySource::Save, 2, 10, 0)
+            .unwrap();
+        store
+            .record(path, "b", LocalHistorySource::ExternalChange, 3, 10, 0)
+            .unwrap();
+
+        let entries = store.entries(path);
+        assert_eq!(
+            entries
+                .iter()
+                .map(|e| (e.timestamp, e.source))
+                .collect::<Vec<_>>(),
+            vec![
+                (3, LocalHistorySource::ExternalChange),
+                (1, LocalHistorySource::Save)
+            ]
+        );
+        assert_eq!(entries[0].read().unwrap(), "b");
+        assert!(store.entries(Path::new("/project/other.rs")).is_empty());
+
+        // The file's folder holds only the snapshots.
+        let files = std::fs::read_dir(store.file_dir(path)).unwrap().count();
+        assert_eq!(files, 2);
+    }
+
+    #[test]
+    fn test_record_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = LocalHistoryStore::new(dir.path().to_path_buf());
+        let path = Path::new("/project/main.rs");
+
+        for i in 0..5 {
+            store
+                .record(path, &i.to_string(), LocalHistorySource::Save, i, 3, 0)
+                .unwrap();
+        }
+        let timestamps = |store: &LocalHistoryStore| -> Vec<u64> {
+            store.entries(path).iter().map(|e| e.timestamp).collect()
+        };
+        assert_eq!(timestamps(&store), vec![4, 3, 2]);
+
+        store
+            .record(path, "new", LocalHistorySource::Save, 2 + 2 * DAY, 3, 1)
+            .unwrap();
+        assert_eq!(timestamps(&store), vec![2 + 2 * DAY]);
+    }
+}
@@ -8,4 +8,6 @@
 pub type ThemeColorSettingsId = Id;
 pub type VoltViewId = Id;
 pub type DiffEditorId = Id;
+pub type HexEditorId = Id;
+pub type MarkdownPreviewId = Id;
 pub type TerminalTabId = Id;
@@ -60,9 +60,8 @@ pub fn keymap_view(editors: Editors, common: Rc<CommonData>) -> impl View {
                 let cmd = keypress.commands.get(&keymap.command).cloned()?;
 
                 let cmd_name_contains_pattern =
-                    cmd.kind.str().replace('_', " ").contains(&pattern);
+                    cmd.name().replace('_', " ").contains(&pattern);
                 let cmd_desc_contains_pattern = cmd
-                    .kind
                     .desc()
                     .map(|desc| desc.to_lowercase().contains(&pattern))
                     .unwrap_or(false);
@@ -88,9 +87,8 @@ pub fn keymap_view(editors: Editors, common: Rc<CommonData>) -> impl View {
             })
             .collect::<im::Vector<(LapceCommand, Option<KeyMap>)>>();
         items.extend(keypress.commands_without_keymap.iter().filter_map(|cmd| {
-            let match_pattern = cmd.kind.str().replace('_', " ").contains(&pattern)
+            let match_pattern = cmd.name().replace('_', " ").contains(&pattern)
                 || cmd
-                    .kind
                     .desc()
                     .map(|desc| desc.to_lowercase().contains(&pattern))
                     .unwrap_or(false);
@@ -111,20 +109,15 @@ pub fn keymap_view(editors: Editors, common: Rc<CommonData>) -> impl View {
             let local_cmd = cmd.clone();
             stack((
                 container(
-                    text(
-                        cmd.kind
-                            .desc()
-                            .map(|desc| desc.to_string())
-                            .unwrap_or_else(|| cmd.kind.str().replace('_', " ")),
-                    )
-                    .style(|s| {
-                        s.text_ellipsis()
-                            .absolute()
-                            .items_center()
-                            .min_width(0.0)
-                            .padding_horiz(10.0)
-                            .size_pct(100.0, 100.0)
-                    }),
+                    text(cmd.desc().unwrap_or_else(|| cmd.name().replace('_', " ")))
+                        .style(|s| {
+                            s.text_ellipsis()
+                                .absolute()
+                                .items_center()
+                                .min_width(0.0)
+                                .padding_horiz(10.0)
+                                .size_pct(100.0, 100.0)
+                        }),
                 )
                 .style(move |s| {
                     s.height_pct(100.0)
@@ -253,7 +246,7 @@ pub fn keymap_view(editors: Editors, common: Rc<CommonData>) -> impl View {
                     keymap
                 } else {
                     KeyMap {
-                        command: local_cmd.kind.str().to_string(),
+                        command: local_cmd.name(),
                         key: Vec::new(),
                         modes: Modes::empty(),
                         when: None,
@@ -350,7 +343,7 @@ pub fn keymap_view(editors: Editors, common: Rc<CommonData>) -> impl View {
                     |(i, (cmd, keymap)): &(
                         usize,
                         (LapceCommand, Option<KeyMap>),
-                    )| { (*i, cmd.kind.str(), keymap.clone()) },
+                    )| { (*i, cmd.name(), keymap.clone()) },
                     view_fn,
                 )
                 .item_size_fixed(ui_line_height)
@@ -383,10 +376,8 @@ fn keyboard_picker_view(
                 picker_cmd.with(|cmd| {
                     cmd.as_ref()
                         .map(|cmd| {
-                            cmd.kind
-                                .desc()
-                                .map(|desc| desc.to_string())
-                                .unwrap_or_else(|| cmd.kind.str().replace('_', " "))
+                            cmd.desc()
+                                .unwrap_or_else(|| cmd.name().replace('_', " "))
                         })
                         .unwrap_or_default()
                 })
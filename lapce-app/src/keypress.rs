@@ -2,6 +2,7 @@
 mod key;
 pub mod keymap;
 mod loader;
+pub mod macros;
 mod press;
 
 use std::{path::PathBuf, rc::Rc, str::FromStr, time::SystemTime};
@@ -15,20 +16,27 @@
 use indexmap::IndexMap;
 use itertools::Itertools;
 use lapce_core::mode::{Mode, Modes};
+use lapce_rpc::plugin::VoltID;
 
 pub use self::press::KeyPress;
 use self::{
     key::KeyInput,
     keymap::{KeyMap, KeyMapPress},
     loader::KeyMapLoader,
+    macros::{MacroData, MacroStep, PendingRegister},
 };
 use crate::{
-    command::{CommandExecuted, CommandKind, LapceCommand, lapce_internal_commands},
+    command::{
+        CommandExecuted, CommandKind, LapceCommand, LapceWorkbenchCommand,
+        lapce_internal_commands,
+    },
     config::LapceConfig,
+    editor::registers::EditorRegisters,
     keypress::{
         condition::{CheckCondition, Condition},
         keymap::KeymapMatch,
     },
+    plugin::RegisteredVoltCommand,
     tracing::*,
 };
 
@@ -59,6 +67,12 @@ fn focus_only(&self) -> bool {
         false
     }
 
+    /// Whether the commands and text this focus receives are recorded into
+    /// keyboard macros.
+    fn records_macros(&self) -> bool {
+        false
+    }
+
     fn receive_char(&self, c: &str);
 }
 impl KeyPressFocus for () {
@@ -115,6 +129,10 @@ fn focus_only(&self) -> bool {
         (**self).focus_only()
     }
 
+    fn records_macros(&self) -> bool {
+        (**self).records_macros()
+    }
+
     fn receive_char(&self, c: &str) {
         (**self).receive_char(c)
     }
@@ -153,12 +171,16 @@ pub struct KeyPressData {
     pub command_keymaps: Rc<IndexMap<String, Vec<KeyMap>>>,
     pub commands_with_keymap: Rc<Vec<KeyMap>>,
     pub commands_without_keymap: Rc<Vec<LapceCommand>>,
+    /// The commands registered by volts, by their id
+    pub volt_commands: Rc<IndexMap<String, RegisteredVoltCommand>>,
+    pub macros: MacroData,
+    pub registers: EditorRegisters,
 }
 
 impl KeyPressData {
     pub fn new(cx: Scope, config: &LapceConfig) -> Self {
-        let (keymaps, command_keymaps) =
-            Self::get_keymaps(config).unwrap_or((IndexMap::new(), IndexMap::new()));
+        let (keymaps, command_keymaps) = Self::get_keymaps(config, &IndexMap::new())
+            .unwrap_or((IndexMap::new(), IndexMap::new()));
         let mut keypress = Self {
             count: cx.create_rw_signal(None),
             pending_keypress: cx.create_rw_signal((Vec::new(), None)),
@@ -167,19 +189,88 @@ pub fn new(cx: Scope, config: &LapceConfig) -> Self {
             commands: Rc::new(lapce_internal_commands()),
             commands_with_keymap: Rc::new(Vec::new()),
             commands_without_keymap: Rc::new(Vec::new()),
+            volt_commands: Rc::new(IndexMap::new()),
+            macros: MacroData::new(cx),
+            registers: EditorRegisters::new(cx),
         };
         keypress.load_commands();
         keypress
     }
 
     pub fn update_keymaps(&mut self, config: &LapceConfig) {
-        if let Ok((new_keymaps, new_command_keymaps)) = Self::get_keymaps(config) {
+        if let Ok((new_keymaps, new_command_keymaps)) =
+            Self::get_keymaps(config, &self.volt_commands)
+        {
             self.keymaps = Rc::new(new_keymaps);
             self.command_keymaps = Rc::new(new_command_keymaps);
             self.load_commands();
         }
     }
 
+    /// Adds the command of a volt, replacing the one the volt registered with
+    /// the same id, and binds its default key.
+    pub fn register_volt_command(
+        &mut self,
+        command: RegisteredVoltCommand,
+        config: &LapceConfig,
+    ) {
+        let id = command.id();
+        let data = match serde_json::to_value(&command) {
+            Ok(data) => data,
+            Err(err) => {
+                trace!(TraceLevel::ERROR, "Failed to register {id}: {err}");
+                return;
+            }
+        };
+        Rc::make_mut(&mut self.commands).insert(
+            id.clone(),
+            LapceCommand {
+                kind: CommandKind::Workbench(
+                    LapceWorkbenchCommand::ExecuteVoltCommand,
+                ),
+                data: Some(data),
+            },
+        );
+        Rc::make_mut(&mut self.volt_commands).insert(id, command);
+        self.update_keymaps(config);
+    }
+
+    pub fn remove_volt_commands(&mut self, volt_id: &VoltID, config: &LapceConfig) {
+        let ids: Vec<String> = self
+            .volt_commands
+            .iter()
+            .filter(|(_, command)| &command.volt_id == volt_id)
+            .map(|(id, _)| id.clone())
+            .collect();
+        if ids.is_empty() {
+            return;
+        }
+
+        let commands = Rc::make_mut(&mut self.commands);
+        let volt_commands = Rc::make_mut(&mut self.volt_commands);
+        for id in ids {
+            let registered = volt_commands.shift_remove(&id);
+            if commands.get(&id).and_then(LapceCommand::volt_command) == registered {
+                commands.shift_remove(&id);
+            }
+        }
+        self.update_keymaps(config);
+    }
+
+    /// The command a volt registered with the id `command`, which can't be a
+    /// built-in command or one of another volt.
+    pub fn volt_command(
+        &self,
+        volt_id: &VoltID,
+        command: &str,
+    ) -> Option<LapceCommand> {
+        let id = format!("{volt_id}.{command}");
+        self.volt_commands
+            .get(&id)
+            .filter(|registered| &registered.volt_id == volt_id)?;
+        self.commands.get(&id).cloned()
+    }
+
     fn load_commands(&mut self) {
         let mut commands_with_keymap = Vec::new();
         let mut commands_without_keymap = Vec::new();
@@ -191,10 +282,10 @@ fn load_commands(&mut self) {
             }
         }
 
-        for (_, cmd) in self.commands.iter() {
+        for (name, cmd) in self.commands.iter() {
             if self
                 .command_keymaps
-                .get(cmd.kind.str())
+                .get(name)
                 .map(|x| x.is_empty())
                 .unwrap_or(true)
             {
@@ -211,7 +302,7 @@ fn handle_count<T: KeyPressFocus + ?Sized>(
         focus: &T,
         keypress: &KeyPress,
     ) -> bool {
-        if focus.expect_char() {
+        if self.expect_char(focus) {
             return false;
         }
         let mode = focus.get_mode();
@@ -247,10 +338,173 @@ fn run_command<T: KeyPressFocus + ?Sized>(
         mods: Modifiers,
         focus: &T,
     ) -> CommandExecuted {
-        if let Some(cmd) = self.commands.get(command) {
-            focus.run_command(cmd, count, mods)
-        } else {
-            CommandExecuted::No
+        let Some(cmd) = self.commands.get(command) else {
+            return CommandExecuted::No;
+        };
+
+        if focus.records_macros() {
+            // The vim register commands need the count and the focus, so they
+            // are handled here rather than by the workbench.
+            match &cmd.kind {
+                CommandKind::Workbench(
+                    LapceWorkbenchCommand::RecordMacroRegister,
+                ) => {
+                    if self.macros.is_recording() {
+                        self.macros.stop_recording();
+                    } else {
+                        self.macros
+                            .pending_register
+                            .set(Some(PendingRegister::Record));
+                    }
+                    return CommandExecuted::Yes;
+                }
+                CommandKind::Workbench(
+                    LapceWorkbenchCommand::ReplayMacroRegister,
+                ) => {
+                    self.macros
+                        .pending_register
+                        .set(Some(PendingRegister::Replay { count }));
+                    return CommandExecuted::Yes;
+                }
+                CommandKind::Workbench(LapceWorkbenchCommand::SelectRegister) => {
+                    self.registers.selecting.set(true);
+                    return CommandExecuted::Yes;
+                }
+                _ => {}
+            }
+        }
+        if self
+            .macros
+            .pending_register
+            .with_untracked(|pending| pending.is_some())
+        {
+            self.macros.pending_register.set(None);
+        }
+        if self.registers.selecting.get_untracked() {
+            self.registers.selecting.set(false);
+        }
+
+        self.run_macro_command(cmd, command, count, mods, focus)
+    }
+
+    /// Run a command, recording it if a macro is being recorded.
+    fn run_macro_command<T: KeyPressFocus + ?Sized>(
+        &self,
+        cmd: &LapceCommand,
+        command: &str,
+        count: Option<usize>,
+        mods: Modifiers,
+        focus: &T,
+    ) -> CommandExecuted {
+        let executed = focus.run_command(cmd, count, mods);
+        if executed == CommandExecuted::Yes && focus.records_macros() {
+            self.macros.record(
+                MacroStep::Command {
+                    command: command.to_string(),
+                    count,
+                    shift: mods.shift(),
+                },
+                false,
+            );
+        }
+        executed
+    }
+
+    /// Whether the focus expects a character, such as the name of the register
+    /// after `q` or `@`.
+    fn expect_char<T: KeyPressFocus + ?Sized>(&self, focus: &T) -> bool {
+        focus.expect_char()
+            || (focus.records_macros()
+                && (self.registers.selecting.get_untracked()
+                    || self
+                        .macros
+                        .pending_register
+                        .with_untracked(|pending| pending.is_some())))
+    }
+
+    /// Pass the text to the focus, or to the register command waiting for it,
+    /// recording it if a macro is being recorded.
+    fn receive_char<T: KeyPressFocus + ?Sized>(&self, focus: &T, c: &str) {
+        if focus.records_macros() {
+            if self.registers.selecting.get_untracked() {
+                self.registers.selecting.set(false);
+                if let Some(register) = c.chars().next() {
+                    self.registers.selected.set(Some(register));
+                }
+                return;
+            }
+            if let Some(pending) = self
+                .macros
+                .pending_register
+                .try_update(Option::take)
+                .flatten()
+            {
+                let Some(register) = c.chars().next() else {
+                    return;
+                };
+                match pending {
+                    PendingRegister::Record => {
+                        self.macros.start_recording(Some(register));
+                    }
+                    PendingRegister::Replay { count } => {
+                        if let Some((register, steps)) =
+                            self.macros.register(register)
+                        {
+                            self.macros.last_register.set(Some(register));
+                            self.replay_macro(&steps, count.unwrap_or(1), focus);
+                        }
+                    }
+                }
+                return;
+            }
+        }
+
+        focus.receive_char(c);
+        if focus.records_macros() {
+            self.macros.record(
+                MacroStep::Text {
+                    text: c.to_string(),
+                },
+                focus.get_mode() == Mode::Insert,
+            );
+        }
+    }
+
+    /// Replay the steps of a macro `times` times on the focus.
+    pub fn replay_macro<T: KeyPressFocus + ?Sized>(
+        &self,
+        steps: &[MacroStep],
+        times: usize,
+        focus: &T,
+    ) {
+        for _ in 0..times {
+            for step in steps {
+                match step {
+                    MacroStep::Command {
+                        command,
+                        count,
+                        shift,
+                    } => {
+                        let Some(cmd) = self.commands.get(command) else {
+                            continue;
+                        };
+                        // Workbench commands, including the macro commands
+                        // themselves, aren't replayed.
+                        if let CommandKind::Workbench(_) = cmd.kind {
+                            continue;
+                        }
+                        let mods = if *shift {
+                            Modifiers::SHIFT
+                        } else {
+                            Modifiers::empty()
+                        };
+                        self.run_macro_command(cmd, command, *count, mods, focus);
+                    }
+                    MacroStep::Text { text } => {
+                        self.receive_char(focus, text);
+                    }
+                }
+            }
         }
     }
 
@@ -400,8 +654,9 @@ pub fn handle_keymatch<T: KeyPressFocus + ?Sized>(
                     {
                         if let Some(cmd) = self.commands.get(&command) {
                             if let CommandKind::Move(_) = cmd.kind {
-                                let handled = focus.run_command(cmd, None, mods)
-                                    == CommandExecuted::Yes;
+                                let handled = self.run_macro_command(
+                                    cmd, &command, None, mods, focus,
+                                ) == CommandExecuted::Yes;
                                 return KeyPressHandle {
                                     handled,
                                     keymatch,
@@ -429,7 +684,7 @@ pub fn handle_keymatch<T: KeyPressFocus + ?Sized>(
         if mods.is_empty() {
             if let KeyInput::Keyboard { logical, .. } = &keypress.key {
                 if let Key::Character(c) = logical {
-                    focus.receive_char(c);
+                    self.receive_char(focus, c);
                     self.count.set(None);
                     return KeyPressHandle {
                         handled: true,
@@ -437,7 +692,7 @@ pub fn handle_keymatch<T: KeyPressFocus + ?Sized>(
                         keypress,
                     };
                 } else if let Key::Named(NamedKey::Space) = logical {
-                    focus.receive_char(" ");
+                    self.receive_char(focus, " ");
                     self.count.set(None);
                     return KeyPressHandle {
                         handled: true,
@@ -484,7 +739,7 @@ fn match_keymap<T: KeyPressFocus + ?Sized>(
                 keymaps
                     .iter()
                     .filter(|keymap| {
-                        if check.expect_char()
+                        if self.expect_char(check)
                             && keypresses.len() == 1
                             && keypresses[0].is_char()
                         {
@@ -565,6 +820,7 @@ fn check_one_condition<T: KeyPressFocus + ?Sized>(
     #[allow(clippy::type_complexity)]
     fn get_keymaps(
         config: &LapceConfig,
+        volt_commands: &IndexMap<String, RegisteredVoltCommand>,
     ) -> Result<(
         IndexMap<Vec<KeyMapPress>, Vec<KeyMap>>,
         IndexMap<String, Vec<KeyMap>>,
@@ -587,6 +843,18 @@ fn get_keymaps(
             trace!(TraceLevel::ERROR, "Failed to load OS defaults: {err}");
         }
 
+        // The default keys of volt commands, which the user keymaps can override
+        for (id, volt_command) in volt_commands {
+            if let Some(key) = volt_command.command.key.as_ref() {
+                loader.add_keymap(KeyMap {
+                    key: KeyMapPress::parse(key),
+                    modes: Modes::empty(),
+                    when: volt_command.command.when.clone(),
+                    command: id.clone(),
+                });
+            }
+        }
+
         if let Some(path) = Self::file() {
             if let Ok(content) = std::fs::read_to_string(&path) {
                 if let Err(err) = loader.load_from_str(&content, is_modal) {
@@ -74,16 +74,36 @@ pub enum Condition {
 }
 
 #[cfg(test)]
-mod test {
+pub(super) mod test {
+    use std::cell::RefCell;
+
     use floem::keyboard::Modifiers;
     use lapce_core::mode::Mode;
 
     use super::Condition;
     use crate::keypress::{KeyPressData, KeyPressFocus, condition::CheckCondition};
 
-    #[derive(Clone, Copy, Debug)]
-    struct MockFocus {
-        accepted_conditions: &'static [Condition],
+    #[derive(Clone, Debug)]
+    pub(crate) struct MockFocus {
+        pub(crate) accepted_conditions: &'static [Condition],
+        pub(crate) mode: Mode,
+        /// The names of the commands run and the text received, in order
+        pub(crate) received: RefCell<Vec<String>>,
+    }
+
+    impl MockFocus {
+        pub(crate) fn new(accepted_conditions: &'static [Condition]) -> Self {
+            Self {
+                accepted_conditions,
+                mode: Mode::Normal,
+                received: RefCell::new(Vec::new()),
+            }
+        }
+
+        /// Take what the focus received so far
+        pub(crate) fn take(&self) -> Vec<String> {
+            self.received.take()
+        }
     }
 
     impl KeyPressFocus for MockFocus {
@@ -92,20 +112,29 @@ fn check_condition(&self, condition: Condition) -> bool {
         }
 
         fn get_mode(&self) -> Mode {
-            unimplemented!()
+            self.mode
         }
 
         fn run_command(
             &self,
-            _command: &crate::command::LapceCommand,
-            _count: Option<usize>,
+            command: &crate::command::LapceCommand,
+            count: Option<usize>,
             _mods: Modifiers,
         ) -> crate::command::CommandExecuted {
-            unimplemented!()
+            let name = command.kind.str();
+            self.received.borrow_mut().push(match count {
+                Some(count) => format!("{count}{name}"),
+                None => name.to_string(),
+            });
+            crate::command::CommandExecuted::Yes
+        }
+
+        fn records_macros(&self) -> bool {
+            true
         }
 
-        fn receive_char(&self, _c: &str) {
-            unimplemented!()
+        fn receive_char(&self, c: &str) {
+            self.received.borrow_mut().push(c.to_string());
         }
     }
 
@@ -131,9 +160,7 @@ fn test_parse() {
 
     #[test]
     fn test_check_condition() {
-        let focus = MockFocus {
-            accepted_conditions: &[Condition::EditorFocus, Condition::ListFocus],
-        };
+        let focus = MockFocus::new(&[Condition::EditorFocus, Condition::ListFocus]);
 
         let test_cases = [
             ("editor_focus", true),
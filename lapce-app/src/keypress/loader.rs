@@ -42,38 +42,46 @@ pub fn load_from_str<'a>(
                 }
             };
 
-            let (command, bind) = match keymap.command.strip_prefix('-') {
-                Some(cmd) => (cmd.to_string(), false),
-                None => (keymap.command.clone(), true),
-            };
+            self.add_keymap(keymap);
+        }
 
-            let current_keymaps = self.command_keymaps.entry(command).or_default();
-            if bind {
-                current_keymaps.push(keymap.clone());
-                for i in 1..keymap.key.len() + 1 {
-                    let key = keymap.key[..i].to_vec();
-                    self.keymaps.entry(key).or_default().push(keymap.clone());
-                }
-            } else {
-                let is_keymap = |k: &KeyMap| -> bool {
-                    k.when == keymap.when
-                        && k.modes == keymap.modes
-                        && k.key == keymap.key
-                };
-                if let Some(index) = current_keymaps.iter().position(is_keymap) {
-                    current_keymaps.remove(index);
-                }
-                for i in 1..keymap.key.len() + 1 {
-                    if let Some(keymaps) = self.keymaps.get_mut(&keymap.key[..i]) {
-                        if let Some(index) = keymaps.iter().position(is_keymap) {
-                            keymaps.remove(index);
-                        }
+        Ok(self)
+    }
+
+    /// Binds the key of the keymap, or unbinds it if the command starts with
+    /// `-`.
+    pub fn add_keymap(&mut self, keymap: KeyMap) -> &mut Self {
+        let (command, bind) = match keymap.command.strip_prefix('-') {
+            Some(cmd) => (cmd.to_string(), false),
+            None => (keymap.command.clone(), true),
+        };
+
+        let current_keymaps = self.command_keymaps.entry(command).or_default();
+        if bind {
+            current_keymaps.push(keymap.clone());
+            for i in 1..keymap.key.len() + 1 {
+                let key = keymap.key[..i].to_vec();
+                self.keymaps.entry(key).or_default().push(keymap.clone());
+            }
+        } else {
+            let is_keymap = |k: &KeyMap| -> bool {
+                k.when == keymap.when
+                    && k.modes == keymap.modes
+                    && k.key == keymap.key
+            };
+            if let Some(index) = current_keymaps.iter().position(is_keymap) {
+                current_keymaps.remove(index);
+            }
+            for i in 1..keymap.key.len() + 1 {
+                if let Some(keymaps) = self.keymaps.get_mut(&keymap.key[..i]) {
+                    if let Some(index) = keymaps.iter().position(is_keymap) {
+                        keymaps.remove(index);
                     }
                 }
             }
         }
 
-        Ok(self)
+        self
     }
 
     #[allow(clippy::type_complexity)]
@@ -250,4 +258,34 @@ fn test_keymap() {
             KeyMapKey::Logical(Key::Character("+".into()))
         );
     }
+
+    #[test]
+    fn test_add_keymap() {
+        let mut loader = KeyMapLoader::new();
+        loader.add_keymap(KeyMap {
+            key: KeyMapPress::parse("ctrl+alt+d"),
+            modes: Modes::empty(),
+            when: None,
+            command: "team.deploy".to_string(),
+        });
+        loader
+            .load_from_str(
+                r#"
+[[keymaps]]
+key = "ctrl+alt+d"
+command = "-team.deploy"
+
+[[keymaps]]
+key = "ctrl+alt+p"
+command = "team.deploy"
+            "#,
+                false,
+            )
+            .unwrap();
+
+        let (keymaps, command_keymaps) = loader.finalize();
+        assert!(keymaps[&KeyMapPress::parse("ctrl+alt+d")].is_empty());
+        assert_eq!(keymaps[&KeyMapPress::parse("ctrl+alt+p")].len(), 1);
+        assert_eq!(command_keymaps["team.deploy"].len(), 1);
+    }
 }
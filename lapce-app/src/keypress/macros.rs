@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+
+use floem::reactive::{RwSignal, Scope, SignalGet, SignalUpdate, SignalWith};
+use serde::{Deserialize, Serialize};
+
+/// One step of a keyboard macro, as it is saved in the settings.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MacroStep {
+    Command {
+        command: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        count: Option<usize>,
+        /// Whether shift was held, which extends the selection of moves
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        shift: bool,
+    },
+    Text {
+        text: String,
+    },
+}
+
+impl MacroStep {
+    /// Convert the step to the inline table it is saved as.
+    fn to_toml(&self) -> toml_edit::Value {
+        let mut table = toml_edit::InlineTable::new();
+        match self {
+            MacroStep::Command {
+                command,
+                count,
+                shift,
+            } => {
+                table.insert("command", command.as_str().into());
+                if let Some(count) = count {
+                    table.insert("count", (*count as i64).into());
+                }
+                if *shift {
+                    table.insert("shift", true.into());
+                }
+            }
+            MacroStep::Text { text } => {
+                table.insert("text", text.as_str().into());
+            }
+        }
+        toml_edit::Value::InlineTable(table)
+    }
+}
+
+/// Convert the steps of a macro to the array it is saved as in the settings.
+pub fn macro_to_toml(steps: &[MacroStep]) -> toml_edit::Value {
+    toml_edit::Value::Array(steps.iter().map(MacroStep::to_toml).collect())
+}
+
+/// Add a step to a macro, appending text to the previous text step when
+/// `merge_text` is set so that typing is replayed as a single insertion.
+pub fn push_step(steps: &mut Vec<MacroStep>, step: MacroStep, merge_text: bool) {
+    if let (Some(MacroStep::Text { text }), MacroStep::Text { text: new_text }) =
+        (steps.last_mut(), &step)
+    {
+        if merge_text {
+            text.push_str(new_text);
+            return;
+        }
+    }
+    steps.push(step);
+}
+
+#[derive(Clone, Debug)]
+pub struct MacroRecording {
+    /// The vim register the macro is stored in when the recording stops
+    pub register: Option<char>,
+    pub steps: Vec<MacroStep>,
+}
+
+/// The register command that is waiting for the name of its register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PendingRegister {
+    Record,
+    Replay { count: Option<usize> },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MacroData {
+    pub recording: RwSignal<Option<MacroRecording>>,
+    /// The most recently recorded macro
+    pub last: RwSignal<Option<Vec<MacroStep>>>,
+    pub registers: RwSignal<HashMap<char, Vec<MacroStep>>>,
+    /// The register that was replayed last, for `@@`
+    pub last_register: RwSignal<Option<char>>,
+    pub pending_register: RwSignal<Option<PendingRegister>>,
+}
+
+impl MacroData {
+    pub fn new(cx: Scope) -> Self {
+        Self {
+            recording: cx.create_rw_signal(None),
+            last: cx.create_rw_signal(None),
+            registers: cx.create_rw_signal(HashMap::new()),
+            last_register: cx.create_rw_signal(None),
+            pending_register: cx.create_rw_signal(None),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.with_untracked(Option::is_some)
+    }
+
+    pub fn start_recording(&self, register: Option<char>) {
+        self.recording.set(Some(MacroRecording {
+            register,
+            steps: Vec::new(),
+        }));
+    }
+
+    /// Stop the recording, keeping it as the last macro and in its register.
+    pub fn stop_recording(&self) {
+        let Some(recording) = self.recording.try_update(Option::take).flatten()
+        else {
+            return;
+        };
+        if let Some(register) = recording.register {
+            // Like vim, an uppercase register appends to the lowercase one.
+            if register.is_uppercase() {
+                let register = register.to_ascii_lowercase();
+                self.registers.update(|registers| {
+                    registers
+                        .entry(register)
+                        .or_default()
+                        .extend(recording.steps.iter().cloned())
+                });
+            } else {
+                self.registers.update(|registers| {
+                    registers.insert(register, recording.steps.clone());
+                });
+            }
+        }
+        self.last.set(Some(recording.steps));
+    }
+
+    /// Record a step if a recording is in progress.
+    pub fn record(&self, step: MacroStep, merge_text: bool) {
+        if !self.is_recording() {
+            return;
+        }
+        self.recording.update(|recording| {
+            if let Some(recording) = recording {
+                push_step(&mut recording.steps, step, merge_text);
+            }
+        });
+    }
+
+    /// The steps stored in a register, where `@` is the register that was
+    /// replayed last.
+    pub fn register(&self, register: char) -> Option<(char, Vec<MacroStep>)> {
+        let register = if register == '@' {
+            self.last_register.get_untracked()?
+        } else {
+            register.to_ascii_lowercase()
+        };
+        let steps = self
+            .registers
+            .with_untracked(|registers| registers.get(&register).cloned())?;
+        Some((register, steps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use floem::keyboard::Modifiers;
+    use indexmap::IndexMap;
+    use lapce_core::mode::Mode;
+
+    use super::*;
+    use crate::{
+        command::lapce_internal_commands,
+        editor::registers::EditorRegisters,
+        keypress::{KeyPressData, condition::test::MockFocus},
+    };
+
+    fn keypress_data(cx: Scope) -> KeyPressData {
+        KeyPressData {
+            count: cx.create_rw_signal(None),
+            pending_keypress: cx.create_rw_signal((Vec::new(), None)),
+            commands: Rc::new(lapce_internal_commands()),
+            keymaps: Rc::new(IndexMap::new()),
+            command_keymaps: Rc::new(IndexMap::new()),
+            commands_with_keymap: Rc::new(Vec::new()),
+            commands_without_keymap: Rc::new(Vec::new()),
+            volt_commands: Rc::new(IndexMap::new()),
+            macros: MacroData::new(cx),
+            registers: EditorRegisters::new(cx),
+        }
+    }
+
+    fn run(keypress: &KeyPressData, focus: &MockFocus, command: &str) {
+        keypress.run_command(command, None, Modifiers::empty(), focus);
+    }
+
+    fn text(text: &str) -> MacroStep {
+        MacroStep::Text {
+            text: text.to_string(),
+        }
+    }
+
+    fn down() -> MacroStep {
+        MacroStep::Command {
+            command: "down".to_string(),
+            count: None,
+            shift: false,
+        }
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let cx = Scope::new();
+        let keypress = keypress_data(cx);
+        let mut focus = MockFocus::new(&[]);
+        focus.mode = Mode::Insert;
+
+        // `qa`, then a move and some typing, then `q`
+        run(&keypress, &focus, "record_macro_register");
+        keypress.receive_char(&focus, "a");
+        assert!(keypress.macros.is_recording());
+        run(&keypress, &focus, "down");
+        keypress.receive_char(&focus, "x");
+        keypress.receive_char(&focus, "y");
+        run(&keypress, &focus, "record_macro_register");
+        assert!(!keypress.macros.is_recording());
+
+        // The register name isn't passed on or recorded, and the typing is
+        // one step.
+        assert_eq!(focus.take(), vec!["down", "x", "y"]);
+        let steps = vec![down(), text("xy")];
+        assert_eq!(keypress.macros.register('a'), Some(('a', steps.clone())));
+        assert_eq!(keypress.macros.last.get_untracked(), Some(steps));
+
+        // `2@a` replays it twice
+        keypress.run_command(
+            "replay_macro_register",
+            Some(2),
+            Modifiers::empty(),
+            &focus,
+        );
+        keypress.receive_char(&focus, "a");
+        assert_eq!(focus.take(), vec!["down", "xy", "down", "xy"]);
+
+        // `@@` replays the register replayed last
+        run(&keypress, &focus, "replay_macro_register");
+        keypress.receive_char(&focus, "@");
+        assert_eq!(focus.take(), vec!["down", "xy"]);
+
+        // An empty register replays nothing
+        run(&keypress, &focus, "replay_macro_register");
+        keypress.receive_char(&focus, "z");
+        assert!(focus.take().is_empty());
+    }
+
+    #[test]
+    fn test_replay_skips_workbench_commands() {
+        let cx = Scope::new();
+        let keypress = keypress_data(cx);
+        let focus = MockFocus::new(&[]);
+
+        keypress.macros.start_recording(None);
+        run(&keypress, &focus, "palette");
+        run(&keypress, &focus, "down");
+        keypress.macros.stop_recording();
+        assert_eq!(focus.take(), vec!["palette", "down"]);
+
+        let steps = keypress.macros.last.get_untracked().unwrap();
+        assert_eq!(steps.len(), 2);
+        keypress.replay_macro(&steps, 1, &focus);
+        assert_eq!(focus.take(), vec!["down"]);
+    }
+
+    #[test]
+    fn test_uppercase_register_appends() {
+        let cx = Scope::new();
+        let keypress = keypress_data(cx);
+        let focus = MockFocus::new(&[]);
+
+        for (register, typed) in [("b", "1"), ("B", "2")] {
+            run(&keypress, &focus, "record_macro_register");
+            keypress.receive_char(&focus, register);
+            keypress.receive_char(&focus, typed);
+            run(&keypress, &focus, "record_macro_register");
+        }
+        assert_eq!(
+            keypress.macros.register('b'),
+            Some(('b', vec![text("1"), text("2")]))
+        );
+        assert_eq!(keypress.macros.register('B'), keypress.macros.register('b'));
+
+        // Another command cancels a register command waiting for its name.
+        run(&keypress, &focus, "replay_macro_register");
+        run(&keypress, &focus, "down");
+        keypress.receive_char(&focus, "b");
+        assert_eq!(focus.take(), vec!["1", "2", "down", "b"]);
+    }
+
+    #[test]
+    fn test_select_register() {
+        let cx = Scope::new();
+        let keypress = keypress_data(cx);
+        let focus = MockFocus::new(&[]);
+
+        // `"a` selects the register without passing the name on
+        run(&keypress, &focus, "select_register");
+        assert!(keypress.expect_char(&focus));
+        keypress.receive_char(&focus, "a");
+        assert_eq!(keypress.registers.selected.get_untracked(), Some('a'));
+        assert!(!keypress.registers.selecting.get_untracked());
+        assert!(keypress.macros.pending_register.get_untracked().is_none());
+        assert!(focus.take().is_empty());
+
+        // Another command cancels `"` waiting for the name
+        run(&keypress, &focus, "select_register");
+        run(&keypress, &focus, "down");
+        keypress.receive_char(&focus, "b");
+        assert_eq!(keypress.registers.selected.get_untracked(), Some('a'));
+        assert_eq!(focus.take(), vec!["down", "b"]);
+    }
+
+    #[test]
+    fn test_push_step() {
+        let mut steps = Vec::new();
+        let text = |text: &str| MacroStep::Text {
+            text: text.to_string(),
+        };
+        push_step(&mut steps, text("a"), true);
+        push_step(&mut steps, text("b"), true);
+        push_step(
+            &mut steps,
+            MacroStep::Command {
+                command: "down".to_string(),
+                count: Some(2),
+                shift: false,
+            },
+            true,
+        );
+        push_step(&mut steps, text("c"), false);
+        push_step(&mut steps, text("d"), false);
+        assert_eq!(steps.len(), 4);
+        assert_eq!(steps[0], text("ab"));
+
+        let saved = macro_to_toml(&steps).to_string();
+        assert_eq!(
+            saved,
+            r#"[{ text = "ab" }, { command = "down", count = 2 }, { text = "c" }, { text = "d" }]"#
+        );
+    }
+}
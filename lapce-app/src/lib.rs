@@ -15,6 +15,7 @@
 pub mod find;
 pub mod focus_text;
 pub mod global_search;
+pub mod hex_editor;
 pub mod history;
 pub mod hover;
 pub mod id;
@@ -25,6 +26,7 @@
 pub mod lsp;
 pub mod main_split;
 pub mod markdown;
+pub mod markdown_preview;
 pub mod palette;
 pub mod panel;
 pub mod plugin;
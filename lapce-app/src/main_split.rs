@@ -1,11 +1,13 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
     rc::Rc,
+    sync::Arc,
+    time::Duration,
 };
 
 use floem::{
-    action::save_as,
+    action::{exec_after, save_as},
     ext_event::create_ext_action,
     file::{FileDialogOptions, FileInfo},
     keyboard::Modifiers,
@@ -16,18 +18,20 @@
 use itertools::Itertools;
 use lapce_core::{
     buffer::rope_text::RopeText, command::FocusCommand, cursor::Cursor,
-    rope_text_pos::RopeTextPosition, selection::Selection, syntax::Syntax,
+    language::LapceLanguage, rope_text_pos::RopeTextPosition, selection::Selection,
+    syntax::Syntax,
 };
 use lapce_rpc::{
     buffer::BufferId,
     core::FileChanged,
-    plugin::{PluginId, VoltID},
+    plugin::{PluginId, VoltDecoration, VoltID},
     proxy::ProxyResponse,
 };
 use lapce_xi_rope::{Rope, spans::SpansBuilder};
 use lsp_types::{
-    CodeAction, CodeActionOrCommand, DiagnosticSeverity, DocumentChangeOperation,
-    DocumentChanges, OneOf, Position, TextEdit, Url, WorkspaceEdit,
+    CodeAction, CodeActionOrCommand, Diagnostic, DiagnosticSeverity,
+    DocumentChangeOperation, DocumentChanges, OneOf, Position, TextEdit, Url,
+    WorkspaceEdit,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -37,7 +41,10 @@
     alert::AlertButton,
     code_lens::CodeLensData,
     command::InternalCommand,
-    doc::{DiagnosticData, Doc, DocContent, DocHistory, EditorDiagnostic},
+    db::LapceDb,
+    doc::{
+        DiagnosticData, Doc, DocBackup, DocContent, DocHistory, EditorDiagnostic,
+    },
     editor::{
         EditorData,
         diff::DiffEditorData,
@@ -46,15 +53,23 @@
     editor_tab::{
         EditorTabChild, EditorTabChildSource, EditorTabData, EditorTabInfo,
     },
+    hex_editor::{HexEditorData, HexFile},
+    history::LocalHistoryEntry,
     id::{
-        DiffEditorId, EditorTabId, KeymapId, SettingsId, SplitId,
-        ThemeColorSettingsId, VoltViewId,
+        DiffEditorId, EditorTabId, HexEditorId, KeymapId, MarkdownPreviewId,
+        SettingsId, SplitId, ThemeColorSettingsId, VoltViewId,
     },
     keypress::{EventRef, KeyPressData, KeyPressHandle},
+    markdown_preview::MarkdownPreviewData,
     panel::implementation_view::ReferencesRoot,
+    terminal::link::TERMINAL_PROBLEM_SOURCE,
     window_tab::{CommonData, Focus, WindowTabData},
+    workspace::LapceWorkspace,
 };
 
+/// How often the documents with unsaved changes are backed up
+const BACKUP_INTERVAL: Duration = Duration::from_secs(5);
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SplitDirection {
     Vertical,
@@ -396,9 +411,17 @@ pub struct MainSplitData {
     pub editor_tabs: RwSignal<im::HashMap<EditorTabId, RwSignal<EditorTabData>>>,
     pub editors: Editors,
     pub diff_editors: RwSignal<im::HashMap<DiffEditorId, DiffEditorData>>,
+    pub hex_editors: RwSignal<im::HashMap<HexEditorId, HexEditorData>>,
+    pub markdown_previews:
+        RwSignal<im::HashMap<MarkdownPreviewId, MarkdownPreviewData>>,
     pub docs: RwSignal<im::HashMap<PathBuf, Rc<Doc>>>,
     pub scratch_docs: RwSignal<im::HashMap<String, Rc<Doc>>>,
     pub diagnostics: RwSignal<im::HashMap<PathBuf, DiagnosticData>>,
+    /// The decorations volts set on files that aren't open, by the volt and
+    /// the key they were set under, applied once the file is opened
+    pub pending_volt_decorations: RwSignal<
+        im::HashMap<PathBuf, im::HashMap<(VoltID, String), Vec<VoltDecoration>>>,
+    >,
     pub references: RwSignal<ReferencesRoot>,
     pub implementations: RwSignal<crate::panel::implementation_view::ReferencesRoot>,
     pub active_editor: Memo<Option<EditorData>>,
@@ -429,6 +452,8 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
         let editors = Editors::new(cx);
         let diff_editors: RwSignal<im::HashMap<DiffEditorId, DiffEditorData>> =
             cx.create_rw_signal(im::HashMap::new());
+        let hex_editors = cx.create_rw_signal(im::HashMap::new());
+        let markdown_previews = cx.create_rw_signal(im::HashMap::new());
         let docs: RwSignal<im::HashMap<PathBuf, Rc<Doc>>> =
             cx.create_rw_signal(im::HashMap::new());
         let scratch_docs = cx.create_rw_signal(im::HashMap::new());
@@ -485,12 +510,15 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
             editor_tabs,
             editors,
             diff_editors,
+            hex_editors,
+            markdown_previews,
             docs,
             scratch_docs,
             active_editor,
             find_editor,
             replace_editor,
             diagnostics,
+            pending_volt_decorations: cx.create_rw_signal(im::HashMap::new()),
             locations,
             current_location,
             width: cx.create_rw_signal(0.0),
@@ -538,6 +566,13 @@ pub fn key_down<'a>(
             EditorTabChild::ThemeColorSettings(_) => None,
             EditorTabChild::Keymap(_) => None,
             EditorTabChild::Volt(_, _) => None,
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                let hex_editor = self.hex_editors.with_untracked(|hex_editors| {
+                    hex_editors.get(&hex_editor_id).cloned()
+                })?;
+                Some(keypress.key_down(event, &hex_editor))
+            }
+            EditorTabChild::MarkdownPreview(_) => None,
         }
     }
 
@@ -640,38 +675,127 @@ pub fn get_doc(
             {
                 let doc = doc.clone();
                 let local_doc = doc.clone();
+                let pending_volt_decorations = self.pending_volt_decorations;
+                let local_path = path.clone();
+                let main_split = self.clone();
                 let send = create_ext_action(cx, move |result| {
                     if let Ok(ProxyResponse::NewBufferResponse {
                         content,
                         read_only,
+                        encoding,
+                        large_file,
+                        disk_hash,
+                        binary,
                     }) = result
                     {
-                        local_doc.init_content(Rope::from(content));
-                        if read_only {
+                        if binary {
+                            main_split.open_binary_file(&local_path);
+                            return;
+                        }
+                        local_doc.encoding.set(encoding);
+                        local_doc.disk_hash.set(disk_hash);
+                        local_doc.large_file.set(large_file);
+                        let offset = content.len();
+                        let content = Rope::from(content);
+                        if !large_file {
+                            local_doc.disk_content.set(Some(content.clone()));
+                        }
+                        local_doc.init_content(content);
+                        // Large files stay read only while the rest of them
+                        // loads.
+                        if read_only || large_file {
                             local_doc.content.update(|content| {
                                 if let DocContent::File { read_only, .. } = content {
                                     *read_only = true;
                                 }
                             });
+                        }
+                        let unsaved = unsaved.filter(|_| !read_only);
+                        if large_file {
+                            local_doc.load_large_file(offset, read_only, unsaved);
                         } else if let Some(unsaved) = unsaved {
                             local_doc.reload(Rope::from(unsaved), false);
                         }
+                        let decorations = pending_volt_decorations
+                            .try_update(|pending| pending.remove(&local_path));
+                        for ((volt_id, key), decorations) in
+                            decorations.flatten().unwrap_or_default()
+                        {
+                            local_doc.set_volt_decorations(
+                                volt_id,
+                                key,
+                                decorations,
+                            );
+                        }
                     }
                 });
 
-                self.common
-                    .proxy
-                    .new_buffer(doc.buffer_id, path, move |result| {
+                let large_file_size = self.common.config.with_untracked(|config| {
+                    let size = config.editor.large_file_size;
+                    (size > 0).then(|| size * 1024 * 1024)
+                });
+                self.common.proxy.new_buffer(
+                    doc.buffer_id,
+                    path,
+                    large_file_size,
+                    move |result| {
                         send(result);
-                    });
+                    },
+                );
             }
-            doc.get_code_lens();
-            doc.get_folding_range();
-            doc.get_document_symbol();
             (doc, true)
         }
     }
 
+    /// Replace the decorations a volt set on a file under `key`, keeping them
+    /// until the file is opened if it isn't yet.
+    pub fn set_volt_decorations(
+        &self,
+        volt_id: VoltID,
+        path: &Path,
+        key: String,
+        decorations: Vec<VoltDecoration>,
+    ) {
+        let doc = self.docs.with_untracked(|docs| docs.get(path).cloned());
+        if let Some(doc) = doc {
+            doc.set_volt_decorations(volt_id, key, decorations);
+            return;
+        }
+        self.pending_volt_decorations.update(|pending| {
+            let file = pending.entry(path.to_path_buf()).or_default();
+            if decorations.is_empty() {
+                file.remove(&(volt_id, key));
+            } else {
+                file.insert((volt_id, key), decorations);
+            }
+            if file.is_empty() {
+                pending.remove(path);
+            }
+        });
+    }
+
+    /// Remove the decorations a volt set on every file, e.g. once it was
+    /// removed.
+    pub fn clear_volt_decorations(&self, volt_id: &VoltID) {
+        self.docs.with_untracked(|docs| {
+            for doc in docs.values() {
+                doc.clear_volt_decorations(volt_id);
+            }
+        });
+        if self.pending_volt_decorations.with_untracked(|pending| {
+            pending
+                .values()
+                .any(|file| file.keys().any(|(id, _)| id == volt_id))
+        }) {
+            self.pending_volt_decorations.update(|pending| {
+                for file in pending.values_mut() {
+                    file.retain(|(id, _), _| id != volt_id);
+                }
+                pending.retain(|_, file| !file.is_empty());
+            });
+        }
+    }
+
     pub fn go_to_location(
         &self,
         location: EditorLocation,
@@ -728,6 +852,70 @@ pub fn open_file_changes(&self, path: PathBuf) {
         );
     }
 
+    /// Open a diff of a local history snapshot against the file's current
+    /// content.
+    pub fn open_local_history_diff(&self, path: PathBuf, entry: LocalHistoryEntry) {
+        let text = match entry.read() {
+            Ok(text) => text,
+            Err(err) => {
+                tracing::error!("{:?}", err);
+                return;
+            }
+        };
+        let (right, _) = self.get_doc(path.clone(), None);
+        let left = Doc::new_history(
+            self.scope,
+            DocContent::History(DocHistory {
+                path,
+                version: entry.version(),
+            }),
+            self.editors,
+            self.common.clone(),
+        );
+        left.init_content(Rope::from(text));
+
+        self.get_editor_tab_child(
+            EditorTabChildSource::DiffEditor {
+                left: Rc::new(left),
+                right,
+            },
+            false,
+            false,
+        );
+    }
+
+    /// Replace the file's content with a local history snapshot, which is
+    /// left unsaved.
+    pub fn restore_local_history(&self, path: PathBuf, entry: LocalHistoryEntry) {
+        let text = match entry.read() {
+            Ok(text) => text,
+            Err(err) => {
+                tracing::error!("{:?}", err);
+                return;
+            }
+        };
+        self.go_to_location(EditorLocation {
+            path: path.clone(),
+            position: None,
+            scroll_offset: None,
+            ignore_unconfirmed: false,
+            same_editor_tab: false,
+        });
+        let (doc, _) = self.get_doc(path, None);
+        let scope = self.scope.create_child();
+        scope.create_effect(move |restored: Option<bool>| {
+            if restored == Some(true) {
+                return true;
+            }
+            if !doc.loaded.get() {
+                return false;
+            }
+            doc.reload(Rope::from(text.as_str()), false);
+            exec_after(Duration::ZERO, move |_| scope.dispose());
+            true
+        });
+    }
+
     pub fn open_diff_files(&self, left_path: PathBuf, right_path: PathBuf) {
         let [left, right] =
             [left_path, right_path].map(|path| self.get_doc(path, None).0);
@@ -781,6 +969,7 @@ fn get_editor_tab_child(
 
         let editors = self.editors;
         let diff_editors = self.diff_editors.get_untracked();
+        let hex_editors = self.hex_editors.get_untracked();
 
         let active_editor_tab = if let Some(editor_tab) = active_editor_tab {
             editor_tab
@@ -804,6 +993,13 @@ fn get_editor_tab_child(
             *editor_tab
         };
 
+        let is_same_hex_editor = |hex_editor_id: &HexEditorId, path: &PathBuf| {
+            hex_editors
+                .get(hex_editor_id)
+                .map(|hex_editor| &hex_editor.path == path)
+                .unwrap_or(false)
+        };
+
         let is_same_diff_editor =
             |diff_editor_id: &DiffEditorId, left: &Rc<Doc>, right: &Rc<Doc>| {
                 diff_editors
@@ -871,6 +1067,22 @@ fn get_editor_tab_child(
                         EditorTabChild::ThemeColorSettings(_) => true,
                         EditorTabChild::Keymap(_) => true,
                         EditorTabChild::Volt(_, _) => true,
+                        EditorTabChild::HexEditor(hex_editor_id) => {
+                            let same_path =
+                                if let EditorTabChildSource::HexEditor(path) =
+                                    &source
+                                {
+                                    is_same_hex_editor(hex_editor_id, path)
+                                } else {
+                                    false
+                                };
+                            same_path
+                                || hex_editors
+                                    .get(hex_editor_id)
+                                    .map(|hex_editor| hex_editor.is_pristine())
+                                    .unwrap_or(false)
+                        }
+                        EditorTabChild::MarkdownPreview(_) => true,
                     };
 
                     if can_be_selected {
@@ -1036,6 +1248,34 @@ fn get_editor_tab_child(
                         })
                     }
                 }
+                EditorTabChildSource::HexEditor(path) => {
+                    if let Some(index) =
+                        active_editor_tab.with_untracked(|editor_tab| {
+                            editor_tab.children.iter().position(|(_, _, child)| {
+                                if let EditorTabChild::HexEditor(hex_editor_id) =
+                                    child
+                                {
+                                    is_same_hex_editor(hex_editor_id, path)
+                                } else {
+                                    false
+                                }
+                            })
+                        })
+                    {
+                        Some(index)
+                    } else if ignore_unconfirmed {
+                        None
+                    } else {
+                        active_editor_tab.with_untracked(|editor_tab| {
+                            editor_tab
+                                .get_unconfirmed_editor_tab_child(
+                                    editors,
+                                    &diff_editors,
+                                )
+                                .map(|(i, _)| i)
+                        })
+                    }
+                }
             }
         };
 
@@ -1109,6 +1349,9 @@ fn get_editor_tab_child(
                     });
                     EditorTabChild::DiffEditor(diff_editor_id)
                 }
+                EditorTabChildSource::HexEditor(path) => {
+                    EditorTabChild::HexEditor(self.new_hex_editor(path.to_owned()))
+                }
             };
 
         if let Some(selected) = selected {
@@ -1129,6 +1372,8 @@ fn get_editor_tab_child(
                         EditorTabChild::ThemeColorSettings(_) => {}
                         EditorTabChild::Keymap(_) => {}
                         EditorTabChild::Volt(_, _) => {}
+                        EditorTabChild::HexEditor(_) => {}
+                        EditorTabChild::MarkdownPreview(_) => {}
                     }
                     (editor_tab_id, current_child.clone())
                 });
@@ -1169,6 +1414,10 @@ fn get_editor_tab_child(
                 (EditorTabChild::Settings(_), EditorTabChildSource::Settings) => {
                     true
                 }
+                (
+                    EditorTabChild::HexEditor(hex_editor_id),
+                    EditorTabChildSource::HexEditor(path),
+                ) => is_same_hex_editor(hex_editor_id, path),
                 _ => false,
             };
             if is_same {
@@ -1195,6 +1444,12 @@ fn get_editor_tab_child(
                 EditorTabChild::ThemeColorSettings(_) => {}
                 EditorTabChild::Keymap(_) => {}
                 EditorTabChild::Volt(_, _) => {}
+                EditorTabChild::HexEditor(hex_editor_id) => {
+                    self.remove_hex_editor(*hex_editor_id);
+                }
+                EditorTabChild::MarkdownPreview(markdown_preview_id) => {
+                    self.remove_markdown_preview(*markdown_preview_id);
+                }
             }
 
             // Now loading the new child
@@ -1270,6 +1525,18 @@ fn get_editor_tab_child(
                                         false
                                     }
                                 }),
+                            EditorTabChildSource::HexEditor(path) => editor_tab
+                                .children
+                                .iter()
+                                .position(|(_, _, child)| {
+                                    if let EditorTabChild::HexEditor(hex_editor_id) =
+                                        child
+                                    {
+                                        is_same_hex_editor(hex_editor_id, path)
+                                    } else {
+                                        false
+                                    }
+                                }),
                             EditorTabChildSource::NewFileEditor => None,
                         })
                     {
@@ -1344,6 +1611,255 @@ pub fn remove_editor(&self, editor_id: EditorId) {
         }
     }
 
+    pub fn remove_hex_editor(&self, hex_editor_id: HexEditorId) {
+        let hex_editor = self
+            .hex_editors
+            .try_update(|hex_editors| hex_editors.remove(&hex_editor_id))
+            .unwrap();
+        if let Some(hex_editor) = hex_editor {
+            self.editors.remove(hex_editor.offset_editor.id());
+            self.editors.remove(hex_editor.search_editor.id());
+            hex_editor.scope.dispose();
+            if self.hex_editor_count(&hex_editor.path) == 0 {
+                hex_editor.file.scope.dispose();
+            }
+        }
+    }
+
+    /// Create a hex editor of the file at `path`, which shares the bytes of
+    /// the hex editors the file is already open in.
+    pub fn new_hex_editor(&self, path: PathBuf) -> HexEditorId {
+        let file = self.hex_file(&path);
+        let hex_editor = HexEditorData::new(
+            self.scope,
+            HexEditorId::next(),
+            path,
+            file,
+            self.editors,
+            self.common.clone(),
+        );
+        let hex_editor_id = hex_editor.id;
+        self.hex_editors.update(|hex_editors| {
+            hex_editors.insert(hex_editor_id, hex_editor);
+        });
+        hex_editor_id
+    }
+
+    /// The bytes of the file at `path`, if it's open in a hex editor.
+    fn hex_file(&self, path: &Path) -> Option<HexFile> {
+        self.hex_editors.with_untracked(|hex_editors| {
+            hex_editors
+                .values()
+                .find(|hex_editor| hex_editor.path == path)
+                .map(|hex_editor| hex_editor.file)
+        })
+    }
+
+    /// The number of hex editors the file at `path` is open in.
+    fn hex_editor_count(&self, path: &Path) -> usize {
+        self.hex_editors.with_untracked(|hex_editors| {
+            hex_editors
+                .values()
+                .filter(|hex_editor| hex_editor.path == path)
+                .count()
+        })
+    }
+
+    /// Show a file that turned out not to be text in hex editors, in place of
+    /// the editors it was opened in.
+    fn open_binary_file(&self, path: &Path) {
+        for editor_tab in self.editor_tabs.get_untracked().values() {
+            while let Some((index, editor)) =
+                editor_tab.with_untracked(|editor_tab| {
+                    editor_tab.get_editor(self.editors, path)
+                })
+            {
+                let child = EditorTabChild::HexEditor(
+                    self.new_hex_editor(path.to_path_buf()),
+                );
+                editor_tab.update(|editor_tab| {
+                    editor_tab.children[index] = (
+                        editor_tab.scope.create_rw_signal(0),
+                        editor_tab.scope.create_rw_signal(Rect::ZERO),
+                        child,
+                    );
+                });
+                self.remove_editor(editor.id());
+            }
+        }
+        self.docs.update(|docs| {
+            docs.remove(path);
+        });
+    }
+
+    fn hex_editor_close_warning(
+        &self,
+        editor_tab_id: EditorTabId,
+        child: EditorTabChild,
+        hex_editor: HexEditorData,
+    ) {
+        let internal_command = self.common.internal_command;
+        let name = hex_editor
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        let save_action = {
+            let main_split = self.clone();
+            let child = child.clone();
+            Rc::new(move || {
+                let main_split = main_split.clone();
+                let child = child.clone();
+                internal_command.send(InternalCommand::HideAlert);
+                hex_editor.save(move || {
+                    main_split.editor_tab_child_close(editor_tab_id, child, false);
+                });
+            })
+        };
+        let main_split = self.clone();
+        internal_command.send(InternalCommand::ShowAlert {
+            title: format!("Do you want to save the changes you made to {name}?"),
+            msg: "Your changes will be lost if you don't save them.".to_string(),
+            buttons: vec![
+                AlertButton {
+                    text: "Save".to_string(),
+                    action: save_action,
+                },
+                AlertButton {
+                    text: "Don't Save".to_string(),
+                    action: Rc::new(move || {
+                        internal_command.send(InternalCommand::HideAlert);
+                        main_split.editor_tab_child_close(
+                            editor_tab_id,
+                            child.clone(),
+                            true,
+                        );
+                    }),
+                },
+            ],
+        });
+    }
+
+    pub fn open_hex_editor(&self, path: PathBuf) {
+        self.get_editor_tab_child(
+            EditorTabChildSource::HexEditor(path),
+            false,
+            false,
+        );
+    }
+
+    pub fn remove_markdown_preview(&self, markdown_preview_id: MarkdownPreviewId) {
+        let markdown_preview = self
+            .markdown_previews
+            .try_update(|markdown_previews| {
+                markdown_previews.remove(&markdown_preview_id)
+            })
+            .unwrap();
+        if let Some(markdown_preview) = markdown_preview {
+            markdown_preview.scope.dispose();
+        }
+    }
+
+    /// Show a live preview of the Markdown document of the editor in a split to
+    /// the right of the editor, or focus the preview that is already open.
+    pub fn open_markdown_preview(&self, editor: &EditorData) -> Option<()> {
+        let doc = editor.doc();
+        let path = doc
+            .content
+            .with_untracked(|content| content.path().cloned())?;
+        if LapceLanguage::from_path(&path) != LapceLanguage::Markdown {
+            return None;
+        }
+        let editor_tab_id = editor.editor_tab_id.get_untracked()?;
+        let editor_tabs = self.editor_tabs.get_untracked();
+
+        let markdown_previews = self.markdown_previews.get_untracked();
+        let existing = editor_tabs.values().find_map(|editor_tab| {
+            let index = editor_tab.with_untracked(|editor_tab| {
+                editor_tab.children.iter().position(|(_, _, child)| {
+                    if let EditorTabChild::MarkdownPreview(id) = child {
+                        markdown_previews
+                            .get(id)
+                            .is_some_and(|preview| preview.path == path)
+                    } else {
+                        false
+                    }
+                })
+            })?;
+            Some((*editor_tab, index))
+        });
+        if let Some((editor_tab, index)) = existing {
+            editor_tab.update(|editor_tab| {
+                editor_tab.active = index;
+            });
+            return Some(());
+        }
+
+        let markdown_preview = MarkdownPreviewData::new(
+            self.scope,
+            MarkdownPreviewId::next(),
+            path,
+            doc,
+            self.active_editor,
+            self.common.clone(),
+        );
+        let markdown_preview_id = markdown_preview.id;
+        self.markdown_previews.update(|markdown_previews| {
+            markdown_previews.insert(markdown_preview_id, markdown_preview);
+        });
+
+        let editor_tab = editor_tabs.get(&editor_tab_id).copied()?;
+        let index = editor_tab
+            .try_update(|editor_tab| {
+                editor_tab.children.push((
+                    editor_tab.scope.create_rw_signal(0),
+                    editor_tab.scope.create_rw_signal(Rect::ZERO),
+                    EditorTabChild::MarkdownPreview(markdown_preview_id),
+                ));
+                editor_tab.children.len() - 1
+            })
+            .unwrap();
+        self.move_editor_tab_child_to_new_split(
+            editor_tab_id,
+            index,
+            editor_tab_id,
+            SplitMoveDirection::Right,
+        );
+        // Keep typing in the editor while the preview follows along
+        self.active_editor_tab.set(Some(editor_tab_id));
+        Some(())
+    }
+
+    /// Open a file linked from the preview of the Markdown document at
+    /// `source`, next to the editor of the document rather than in the
+    /// preview's editor tab.
+    pub fn open_markdown_link(&self, source: &Path, path: PathBuf) {
+        let editor_tab_id = self.editor_tabs.with_untracked(|editor_tabs| {
+            editor_tabs
+                .iter()
+                .find(|(_, editor_tab)| {
+                    editor_tab.with_untracked(|editor_tab| {
+                        editor_tab.get_editor(self.editors, source).is_some()
+                    })
+                })
+                .map(|(editor_tab_id, _)| *editor_tab_id)
+        });
+        if let Some(editor_tab_id) = editor_tab_id {
+            self.active_editor_tab.set(Some(editor_tab_id));
+        }
+        self.jump_to_location(
+            EditorLocation {
+                path,
+                position: None,
+                scroll_offset: None,
+                ignore_unconfirmed: false,
+                same_editor_tab: false,
+            },
+            None,
+        );
+    }
+
     pub fn jump_location_backward(&self, local: bool) {
         let (locations, current_location) = if local {
             let active_editor_tab_id = self.active_editor_tab.get_untracked();
@@ -1574,6 +2090,31 @@ fn split_editor_tab(
             EditorTabChild::Volt(_, id) => {
                 EditorTabChild::Volt(VoltViewId::next(), id.to_owned())
             }
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                let new_hex_editor_id = HexEditorId::next();
+                let hex_editor = self
+                    .hex_editors
+                    .get_untracked()
+                    .get(hex_editor_id)?
+                    .copy(cx, new_hex_editor_id, self.editors);
+                self.hex_editors.update(|hex_editors| {
+                    hex_editors.insert(new_hex_editor_id, hex_editor);
+                });
+                EditorTabChild::HexEditor(new_hex_editor_id)
+            }
+            EditorTabChild::MarkdownPreview(markdown_preview_id) => {
+                let new_markdown_preview_id = MarkdownPreviewId::next();
+                let markdown_preview = self
+                    .markdown_previews
+                    .get_untracked()
+                    .get(markdown_preview_id)?
+                    .copy(cx, new_markdown_preview_id);
+                self.markdown_previews.update(|markdown_previews| {
+                    markdown_previews
+                        .insert(new_markdown_preview_id, markdown_preview);
+                });
+                EditorTabChild::MarkdownPreview(new_markdown_preview_id)
+            }
         };
 
         let editor_tab = {
@@ -1916,6 +2457,8 @@ fn editor_tab_child_close_warning(
             EditorTabChild::ThemeColorSettings(_) => None,
             EditorTabChild::Keymap(_) => None,
             EditorTabChild::Volt(_, _) => None,
+            EditorTabChild::HexEditor(_) => None,
+            EditorTabChild::MarkdownPreview(_) => None,
         }
     }
 
@@ -2010,6 +2553,19 @@ pub fn editor_tab_child_close(
         force: bool,
     ) -> Option<()> {
         if !force {
+            if let EditorTabChild::HexEditor(hex_editor_id) = &child {
+                let hex_editor = self.hex_editors.with_untracked(|hex_editors| {
+                    hex_editors.get(hex_editor_id).cloned()
+                })?;
+                // The edits stay in the file's other hex editors
+                if !hex_editor.is_pristine()
+                    && self.hex_editor_count(&hex_editor.path) == 1
+                {
+                    self.hex_editor_close_warning(editor_tab_id, child, hex_editor);
+                    return Some(());
+                }
+            }
+
             if let Some((name, doc, editor)) =
                 self.editor_tab_child_close_warning(&child)
             {
@@ -2139,6 +2695,12 @@ pub fn editor_tab_child_close(
             EditorTabChild::Editor(editor_id) => {
                 self.remove_editor(editor_id);
             }
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                self.remove_hex_editor(hex_editor_id);
+            }
+            EditorTabChild::MarkdownPreview(markdown_preview_id) => {
+                self.remove_markdown_preview(markdown_preview_id);
+            }
             EditorTabChild::DiffEditor(diff_editor_id) => {
                 let removed_diff_editor = self
                     .diff_editors
@@ -2337,6 +2899,41 @@ fn file_diagnostics_items(
             .collect()
     }
 
+    /// Replace the problems found in the output of terminals, keeping the
+    /// diagnostics of the language servers.
+    pub fn set_terminal_problems(&self, problems: Vec<(PathBuf, Diagnostic)>) {
+        let is_terminal_problem =
+            |d: &Diagnostic| d.source.as_deref() == Some(TERMINAL_PROBLEM_SOURCE);
+        let mut changed: HashSet<PathBuf> = self.diagnostics.with_untracked(|d| {
+            d.iter()
+                .filter(|(_, data)| {
+                    data.diagnostics
+                        .with_untracked(|d| d.iter().any(is_terminal_problem))
+                })
+                .map(|(path, _)| path.clone())
+                .collect()
+        });
+        for path in &changed {
+            self.get_diagnostic_data(path)
+                .diagnostics
+                .update(|d| d.retain(|d| !is_terminal_problem(d)));
+        }
+        for (path, diagnostic) in problems {
+            self.get_diagnostic_data(&path).diagnostics.update(|d| {
+                d.push_back(diagnostic);
+                d.sort_by(|a, b| a.range.start.cmp(&b.range.start));
+            });
+            changed.insert(path);
+        }
+        for path in changed {
+            if let Some(doc) =
+                self.docs.with_untracked(|docs| docs.get(&path).cloned())
+            {
+                doc.init_diagnostics();
+            }
+        }
+    }
+
     pub fn get_diagnostic_data(&self, path: &Path) -> DiagnosticData {
         if let Some(d) = self.diagnostics.with_untracked(|d| d.get(path).cloned()) {
             d
@@ -2358,13 +2955,18 @@ pub fn get_diagnostic_data(&self, path: &Path) -> DiagnosticData {
     pub fn open_file_changed(&self, path: &Path, content: &FileChanged) {
         tracing::debug!("open_file_changed {:?}", path);
         match content {
-            FileChanged::Change(content) => {
+            FileChanged::Change { content, disk_hash } => {
                 let doc = self.docs.with_untracked(|docs| docs.get(path).cloned());
                 let doc = match doc {
                     Some(doc) => doc,
                     None => return,
                 };
-                doc.handle_file_changed(Rope::from(content));
+                doc.handle_file_changed(Rope::from(content), disk_hash.clone());
+            }
+            FileChanged::Bytes { content, disk_hash } => {
+                if let Some(file) = self.hex_file(path) {
+                    file.handle_file_changed(content.clone(), disk_hash.clone());
+                }
             }
             FileChanged::Delete => {
                 if self.docs.with_untracked(|x| x.get(path).is_none()) {
@@ -2434,24 +3036,19 @@ pub fn new_file(&self) -> EditorTabChild {
     }
 
     pub fn save_as(&self, doc: Rc<Doc>, path: PathBuf, action: impl Fn() + 'static) {
-        let (buffer_id, doc_content, rev, content) = (
+        let (buffer_id, doc_content, rev, content, encoding) = (
             doc.buffer_id,
             doc.content.get_untracked(),
             doc.rev(),
             doc.buffer.with_untracked(|b| b.to_string()),
+            doc.encoding.get_untracked(),
         );
         match doc_content {
             DocContent::Scratch { .. } => {
                 let send = {
                     let path = path.clone();
-                    create_ext_action(self.scope, move |result| {
-                        if let Err(err) = result {
-                            event!(
-                                Level::WARN,
-                                "Failed to save as a file: {:?}",
-                                err
-                            );
-                        } else {
+                    create_ext_action(self.scope, move |result| match result {
+                        Ok(response) => {
                             let syntax = Syntax::init(&path);
                             doc.content.set(DocContent::File {
                                 path: path.clone(),
@@ -2460,10 +3057,25 @@ pub fn save_as(&self, doc: Rc<Doc>, path: PathBuf, action: impl Fn() + 'static)
                             doc.buffer.update(|buffer| {
                                 buffer.set_pristine();
                             });
+                            if let ProxyResponse::SaveResponse { disk_hash } =
+                                response
+                            {
+                                doc.disk_hash.set(disk_hash);
+                            }
+                            doc.disk_content.set(Some(
+                                doc.buffer.with_untracked(|b| b.text().clone()),
+                            ));
                             doc.set_syntax(syntax);
                             doc.trigger_syntax_change(None);
                             action();
                         }
+                        Err(err) => {
+                            event!(
+                                Level::WARN,
+                                "Failed to save as a file: {:?}",
+                                err
+                            );
+                        }
                     })
                 };
                 self.common.proxy.save_buffer_as(
@@ -2472,6 +3084,7 @@ pub fn save_as(&self, doc: Rc<Doc>, path: PathBuf, action: impl Fn() + 'static)
                     rev,
                     content,
                     true,
+                    encoding,
                     Box::new(move |result| {
                         send(result);
                     }),
@@ -2489,24 +3102,19 @@ pub fn save_as2(
         path: PathBuf,
         action: impl Fn() + 'static,
     ) {
-        let (buffer_id, doc_content, rev, content) = (
+        let (buffer_id, doc_content, rev, content, encoding) = (
             doc.buffer_id,
             doc.content.get_untracked(),
             doc.rev(),
             doc.buffer.with_untracked(|b| b.to_string()),
+            doc.encoding.get_untracked(),
         );
         match doc_content {
             DocContent::Scratch { .. } => {
                 let send = {
                     let path = path.clone();
-                    create_ext_action(self.scope, move |result| {
-                        if let Err(err) = result {
-                            event!(
-                                Level::WARN,
-                                "Failed to save as a file: {:?}",
-                                err
-                            );
-                        } else {
+                    create_ext_action(self.scope, move |result| match result {
+                        Ok(response) => {
                             let syntax = Syntax::init(&path);
                             doc.content.set(DocContent::File {
                                 path: path.clone(),
@@ -2515,10 +3123,25 @@ pub fn save_as2(
                             doc.buffer.update(|buffer| {
                                 buffer.set_pristine();
                             });
+                            if let ProxyResponse::SaveResponse { disk_hash } =
+                                response
+                            {
+                                doc.disk_hash.set(disk_hash);
+                            }
+                            doc.disk_content.set(Some(
+                                doc.buffer.with_untracked(|b| b.text().clone()),
+                            ));
                             doc.set_syntax(syntax);
                             doc.trigger_syntax_change(None);
                             action();
                         }
+                        Err(err) => {
+                            event!(
+                                Level::WARN,
+                                "Failed to save as a file: {:?}",
+                                err
+                            );
+                        }
                     })
                 };
                 self.common.proxy.save_buffer_as(
@@ -2527,6 +3150,7 @@ pub fn save_as2(
                     rev,
                     content,
                     true,
+                    encoding,
                     Box::new(move |result| {
                         send(result);
                     }),
@@ -2747,6 +3371,8 @@ fn editor_tab_child_set_parent(
             EditorTabChild::ThemeColorSettings(_) => {}
             EditorTabChild::Keymap(_) => {}
             EditorTabChild::Volt(_, _) => {}
+            EditorTabChild::HexEditor(_) => {}
+            EditorTabChild::MarkdownPreview(_) => {}
         }
         Some(())
     }
@@ -2966,6 +3592,196 @@ pub fn export_theme(&self) {
         }
     }
 
+    /// The backups of the documents with unsaved changes.
+    pub fn backups(&self) -> Vec<DocBackup> {
+        let docs = self.docs.get_untracked();
+        let scratch_docs = self.scratch_docs.get_untracked();
+        docs.values()
+            .chain(scratch_docs.values())
+            .filter_map(|doc| doc.backup())
+            .collect()
+    }
+
+    /// Back up the documents with unsaved changes every few seconds, while
+    /// hot exit is enabled, until the window tab is closed.
+    pub fn backup_periodically(
+        &self,
+        workspace: Arc<LapceWorkspace>,
+        db: Arc<LapceDb>,
+        saved: Option<Vec<(DocContent, u64)>>,
+    ) {
+        let main_split = self.clone();
+        exec_after(BACKUP_INTERVAL, move |_| {
+            let Some(docs) =
+                main_split.docs.try_with_untracked(|docs| docs.cloned())
+            else {
+                return;
+            };
+            let mut saved = saved;
+            if main_split.common.config.get_untracked().editor.hot_exit {
+                let scratch_docs = main_split.scratch_docs.get_untracked();
+                let revs: Vec<(DocContent, u64)> = docs
+                    .values()
+                    .chain(scratch_docs.values())
+                    .filter(|doc| !doc.is_pristine())
+                    .map(|doc| (doc.content.get_untracked(), doc.rev()))
+                    .collect();
+                if saved.as_ref() != Some(&revs) {
+                    db.save_backups((*workspace).clone(), main_split.backups());
+                    saved = Some(revs);
+                }
+            }
+            main_split.backup_periodically(workspace, db, saved);
+        });
+    }
+
+    /// Restore the unsaved changes backed up when the workspace was last
+    /// open, asking first when a file changed on disk since.
+    pub fn restore_backups(&self, backups: Vec<DocBackup>) {
+        for backup in backups {
+            match &backup.content {
+                DocContent::File { path, .. } => {
+                    if !self.docs.with_untracked(|docs| docs.contains_key(path)) {
+                        self.go_to_location(EditorLocation {
+                            path: path.clone(),
+                            position: None,
+                            scroll_offset: None,
+                            ignore_unconfirmed: false,
+                            same_editor_tab: false,
+                        });
+                    }
+                    let (doc, _) = self.get_doc(path.clone(), None);
+                    let main_split = self.clone();
+                    // The effect only waits for the document to load, so its
+                    // scope is disposed once the backup is restored.
+                    let scope = self.scope.create_child();
+                    scope.create_effect(move |restored: Option<bool>| {
+                        if restored == Some(true) {
+                            return true;
+                        }
+                        if !doc.loaded.get() {
+                            return false;
+                        }
+                        main_split.restore_backup(doc.clone(), backup.clone());
+                        exec_after(Duration::ZERO, move |_| scope.dispose());
+                        true
+                    });
+                }
+                DocContent::Scratch { name, .. } => {
+                    let doc = self
+                        .scratch_docs
+                        .with_untracked(|docs| docs.get(name).cloned())
+                        .or_else(|| self.new_scratch_doc(Some(name)));
+                    if let Some(doc) = doc {
+                        doc.reload(Rope::from(backup.text), false);
+                    }
+                }
+                DocContent::Local | DocContent::History(_) => {}
+            }
+        }
+    }
+
+    fn restore_backup(&self, doc: Rc<Doc>, backup: DocBackup) {
+        // The editor restored the same unsaved changes along with the layout
+        if doc.buffer.with_untracked(|buffer| buffer.to_string()) == backup.text {
+            return;
+        }
+
+        let disk_hash = doc.disk_hash.get_untracked();
+        // The file was deleted or can't be written to anymore, so the changes
+        // go to a new file instead of being lost.
+        let gone = backup.disk_hash.is_some() && disk_hash.is_none();
+        if gone || doc.content.with_untracked(|content| content.read_only()) {
+            if let Some(doc) = self.new_scratch_doc(None) {
+                doc.reload(Rope::from(backup.text), false);
+            }
+            return;
+        }
+
+        let changed = backup.disk_hash.is_some() && backup.disk_hash != disk_hash;
+        if !changed {
+            doc.reload(Rope::from(backup.text), false);
+            return;
+        }
+
+        let name = backup
+            .content
+            .path()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let internal_command = self.common.internal_command;
+        let main_split = self.clone();
+        let text = backup.text.clone();
+        internal_command.send(InternalCommand::ShowAlert {
+            title: format!(
+                "{name} changed on disk since its unsaved changes were backed up"
+            ),
+            msg: "Restoring the unsaved changes replaces what changed on disk \
+                  once you save them."
+                .to_string(),
+            buttons: vec![
+                AlertButton {
+                    text: "Restore Unsaved Changes".to_string(),
+                    action: Rc::new(move || {
+                        internal_command.send(InternalCommand::HideAlert);
+                        doc.reload(Rope::from(backup.text.as_str()), false);
+                    }),
+                },
+                AlertButton {
+                    text: "Open in New File".to_string(),
+                    action: Rc::new(move || {
+                        internal_command.send(InternalCommand::HideAlert);
+                        if let Some(doc) = main_split.new_scratch_doc(None) {
+                            doc.reload(Rope::from(text.as_str()), false);
+                        }
+                    }),
+                },
+                AlertButton {
+                    text: "Keep File on Disk".to_string(),
+                    action: Rc::new(move || {
+                        internal_command.send(InternalCommand::HideAlert);
+                    }),
+                },
+            ],
+        });
+    }
+
+    /// Open a new scratch file, named `name` instead of the next untitled
+    /// name if given, so that a restored scratch file keeps its name.
+    fn new_scratch_doc(&self, name: Option<&str>) -> Option<Rc<Doc>> {
+        let EditorTabChild::Editor(id) = self.new_file() else {
+            return None;
+        };
+        let doc = self.editors.editor_untracked(id)?.doc();
+        if let Some(name) = name {
+            let old_name = doc.content.with_untracked(|content| match content {
+                DocContent::Scratch { name, .. } => Some(name.clone()),
+                _ => None,
+            })?;
+            doc.content.update(|content| {
+                if let DocContent::Scratch { name: doc_name, .. } = content {
+                    *doc_name = name.to_string();
+                }
+            });
+            self.scratch_docs.update(|scratch_docs| {
+                scratch_docs.remove(&old_name);
+                scratch_docs.insert(name.to_string(), doc.clone());
+            });
+        }
+        Some(doc)
+    }
+
+    /// Open the text in a new scratch file.
+    pub fn open_scratch(&self, text: String) {
+        let child = self.new_file();
+        if let EditorTabChild::Editor(id) = child {
+            if let Some(editor) = self.editors.editor_untracked(id) {
+                editor.doc().reload(Rope::from(text), true);
+            }
+        }
+    }
+
     pub fn show_env(&self) {
         let child = self.new_file();
         if let EditorTabChild::Editor(id) = child {
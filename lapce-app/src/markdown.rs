@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use floem::text::{
     Attrs, AttrsList, FamilyOwned, LineHeightValue, Style, TextLayout, Weight,
 };
@@ -16,12 +18,56 @@ pub enum MarkdownContent {
     Separator,
 }
 
+/// A link in the text of a [`MarkdownContent::Text`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarkdownLink {
+    /// The index of the content the link is in
+    pub content: usize,
+    /// The range of the link in the text of the content
+    pub range: Range<usize>,
+    pub url: String,
+}
+
+/// The Markdown extensions that are rendered
+pub fn markdown_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_HEADING_ATTRIBUTES
+}
+
 pub fn parse_markdown(
     text: &str,
     line_height: f64,
     config: &LapceConfig,
 ) -> Vec<MarkdownContent> {
+    parse_markdown_with_links(text, line_height, config).0
+}
+
+/// Parse Markdown like [`parse_markdown`], also returning where its links are.
+pub fn parse_markdown_with_links(
+    text: &str,
+    line_height: f64,
+    config: &LapceConfig,
+) -> (Vec<MarkdownContent>, Vec<MarkdownLink>) {
+    render_markdown_events(
+        Parser::new_ext(text, markdown_options()),
+        line_height,
+        config,
+    )
+}
+
+/// Render the events of parsed Markdown, which can be those of a part of a
+/// document parsed as a whole, so that its reference links and footnotes
+/// resolve.
+pub fn render_markdown_events<'a>(
+    events: impl IntoIterator<Item = Event<'a>>,
+    line_height: f64,
+    config: &LapceConfig,
+) -> (Vec<MarkdownContent>, Vec<MarkdownLink>) {
     let mut res = Vec::new();
+    let mut links = Vec::new();
 
     let mut current_text = String::new();
     let code_font_family: Vec<FamilyOwned> =
@@ -39,19 +85,11 @@ pub fn parse_markdown(
 
     let mut tag_stack: SmallVec<[(usize, Tag); 4]> = SmallVec::new();
 
-    let parser = Parser::new_ext(
-        text,
-        Options::ENABLE_TABLES
-            | Options::ENABLE_FOOTNOTES
-            | Options::ENABLE_STRIKETHROUGH
-            | Options::ENABLE_TASKLISTS
-            | Options::ENABLE_HEADING_ATTRIBUTES,
-    );
     let mut last_text = CowStr::from("");
     // Whether we should add a newline on the next entry
     // This is used so that we don't emit newlines at the very end of the generation
     let mut add_newline = false;
-    for event in parser {
+    for event in events {
         // Add the newline since we're going to be outputting more
         if add_newline {
             current_text.push('\n');
@@ -62,6 +100,12 @@ pub fn parse_markdown(
 
         match event {
             Event::Start(tag) => {
+                if let Tag::FootnoteDefinition(label) = &tag {
+                    let label = format!("[{label}] ");
+                    current_text.push_str(&label);
+                    pos += label.len();
+                    builder_dirty = true;
+                }
                 tag_stack.push((pos, tag));
             }
             Event::End(end_tag) => {
@@ -128,6 +172,15 @@ pub fn parse_markdown(
                                 title: title.to_string(),
                             });
                         }
+                        Tag::Link { dest_url, .. } => {
+                            // The text is pushed as the next content
+                            links.push(MarkdownLink {
+                                content: res.len(),
+                                range: start_offset..pos,
+                                url: dest_url.to_string(),
+                            });
+                            builder_dirty = true;
+                        }
                         _ => {
                             // Presumably?
                             builder_dirty = true;
@@ -182,7 +235,18 @@ pub fn parse_markdown(
                 builder_dirty = true;
             }
             Event::Rule => {}
-            Event::FootnoteReference(_text) => {}
+            Event::FootnoteReference(label) => {
+                let label = format!("[{label}]");
+                attr_list.add_span(
+                    pos..pos + label.len(),
+                    default_attrs
+                        .clone()
+                        .color(config.color(LapceColor::EDITOR_LINK)),
+                );
+                current_text.push_str(&label);
+                pos += label.len();
+                builder_dirty = true;
+            }
             Event::TaskListMarker(_text) => {}
             Event::InlineHtml(_) => {} // TODO(panekj): Implement
             Event::InlineMath(_) => {} // TODO(panekj): Implement
@@ -196,7 +260,7 @@ pub fn parse_markdown(
         res.push(MarkdownContent::Text(text_layout));
     }
 
-    res
+    (res, links)
 }
 
 fn attribute_for_tag<'a>(
@@ -0,0 +1,587 @@
+use std::{
+    collections::{HashMap, HashSet, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+
+use floem::{
+    View,
+    action::{TimerToken, exec_after},
+    event::{Event, EventListener},
+    ext_event::create_ext_action,
+    peniko::kurbo::Point,
+    prelude::SignalTrack,
+    reactive::{
+        Memo, RwSignal, Scope, SignalGet, SignalUpdate, SignalWith, create_rw_signal,
+    },
+    style::CursorStyle,
+    text::TextLayout,
+    views::{
+        Decorators, container, dyn_container, dyn_stack, empty, img, label,
+        rich_text, scroll, stack_from_iter, svg,
+    },
+};
+use lapce_rpc::proxy::ProxyResponse;
+use pulldown_cmark::{Event as MdEvent, Parser, Tag};
+use url::Url;
+
+use crate::{
+    command::InternalCommand,
+    config::{LapceConfig, color::LapceColor},
+    doc::Doc,
+    editor::EditorData,
+    id::MarkdownPreviewId,
+    markdown::{
+        MarkdownContent, MarkdownLink, markdown_options, render_markdown_events,
+    },
+    plugin::VoltIcon,
+    window_tab::CommonData,
+};
+
+/// How long typing has to pause for before the preview renders the document
+/// again.
+const RENDER_DELAY: Duration = Duration::from_millis(150);
+
+/// A top level block of a Markdown document, which is rendered on its own so
+/// that the preview can scroll to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarkdownBlock {
+    /// The line of the document the block starts on
+    pub line: usize,
+    pub range: Range<usize>,
+    pub code: bool,
+    /// The range of the events of the document that are the block's
+    pub events: Range<usize>,
+}
+
+/// Split the events of a Markdown document, parsed as a whole, into its top
+/// level blocks.
+pub fn markdown_blocks(
+    text: &str,
+    events: &[(MdEvent<'_>, Range<usize>)],
+) -> Vec<MarkdownBlock> {
+    let mut blocks: Vec<MarkdownBlock> = Vec::new();
+    let mut depth = 0usize;
+    let mut line = 0;
+    let mut last_offset = 0;
+    for (index, (event, range)) in events.iter().enumerate() {
+        let start = match event {
+            MdEvent::Start(tag) => {
+                depth += 1;
+                (depth == 1).then_some(matches!(tag, Tag::CodeBlock(_)))
+            }
+            MdEvent::End(_) => {
+                depth = depth.saturating_sub(1);
+                None
+            }
+            _ if depth == 0 => Some(false),
+            _ => None,
+        };
+        if let Some(code) = start {
+            line += text[last_offset..range.start].matches('\n').count();
+            last_offset = range.start;
+            blocks.push(MarkdownBlock {
+                line,
+                range: range.clone(),
+                code,
+                events: index..index,
+            });
+        }
+        if let Some(block) = blocks.last_mut() {
+            block.events.end = index + 1;
+        }
+    }
+    blocks
+}
+
+/// A top level block of a Markdown document as rendered.
+#[derive(Clone)]
+pub struct RenderedBlock {
+    /// Identifies the block by what it renders, so that the blocks which
+    /// didn't change keep their views
+    pub key: u64,
+    pub line: usize,
+    pub code: bool,
+    pub contents: Rc<Vec<MarkdownContent>>,
+    pub links: Rc<Vec<MarkdownLink>>,
+}
+
+/// Parse a Markdown document and render its blocks, reusing the blocks of
+/// `previous` that render the same.
+fn render_blocks(
+    text: &str,
+    config: &LapceConfig,
+    previous: &[RenderedBlock],
+) -> Vec<RenderedBlock> {
+    let events: Vec<_> = Parser::new_ext(text, markdown_options())
+        .into_offset_iter()
+        .collect();
+    let mut previous: HashMap<u64, &RenderedBlock> =
+        previous.iter().map(|block| (block.key, block)).collect();
+    let mut seen = HashSet::new();
+    markdown_blocks(text, &events)
+        .into_iter()
+        .map(|block| {
+            let events = &events[block.events];
+            // The events have the links as resolved, so a block whose
+            // reference changed elsewhere in the document is rendered again.
+            // Their offsets are left out, as typing above a block moves it.
+            let mut hasher = DefaultHasher::new();
+            for (event, _) in events {
+                format!("{event:?}").hash(&mut hasher);
+            }
+            let mut key = hasher.finish();
+            // Identical blocks, like several rules, each get their own key
+            while !seen.insert(key) {
+                key = key.wrapping_add(1);
+            }
+            if let Some(rendered) = previous.remove(&key) {
+                return RenderedBlock {
+                    line: block.line,
+                    ..rendered.clone()
+                };
+            }
+            let (contents, links) = render_markdown_events(
+                events.iter().map(|(event, _)| event.clone()),
+                1.8,
+                config,
+            );
+            RenderedBlock {
+                key,
+                line: block.line,
+                code: block.code,
+                contents: Rc::new(contents),
+                links: Rc::new(links),
+            }
+        })
+        .collect()
+}
+
+/// Where a link in a Markdown document goes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MarkdownLinkTarget {
+    File(PathBuf),
+    Web(String),
+}
+
+/// Resolve a link in the Markdown document at `path`, with relative links
+/// going to files next to the document.
+pub fn markdown_link_target(path: &Path, url: &str) -> Option<MarkdownLinkTarget> {
+    let url = Url::from_file_path(path).ok()?.join(url).ok()?;
+    if url.scheme() != "file" {
+        return Some(MarkdownLinkTarget::Web(url.to_string()));
+    }
+    let target = url.to_file_path().ok()?;
+    // Links to a heading of the document itself aren't followed
+    (target != path).then_some(MarkdownLinkTarget::File(target))
+}
+
+#[derive(Clone)]
+pub struct MarkdownPreviewData {
+    pub id: MarkdownPreviewId,
+    pub scope: Scope,
+    pub path: PathBuf,
+    pub doc: Rc<Doc>,
+    pub blocks: RwSignal<Vec<RenderedBlock>>,
+    /// The heights of the rendered blocks, by their keys
+    pub block_heights: RwSignal<HashMap<u64, f64>>,
+    /// The first line shown by the last active editor of the document, which
+    /// the preview follows
+    pub first_line: Memo<usize>,
+    active_editor: Memo<Option<EditorData>>,
+    pub common: Rc<CommonData>,
+}
+
+impl std::fmt::Debug for MarkdownPreviewData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MarkdownPreviewData")
+            .field("id", &self.id)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl MarkdownPreviewData {
+    pub fn new(
+        cx: Scope,
+        id: MarkdownPreviewId,
+        path: PathBuf,
+        doc: Rc<Doc>,
+        active_editor: Memo<Option<EditorData>>,
+        common: Rc<CommonData>,
+    ) -> Self {
+        let cx = cx.create_child();
+
+        let blocks = cx.create_rw_signal(Vec::new());
+        let block_heights = cx.create_rw_signal(HashMap::new());
+        {
+            let buffer = doc.buffer;
+            let config = common.config;
+            let timer = cx.create_rw_signal(TimerToken::INVALID);
+            cx.create_effect(move |last: Option<Arc<LapceConfig>>| {
+                buffer.track();
+                let config = config.get();
+                // Blocks rendered with another theme or font aren't reused
+                let reuse = last.is_some_and(|last| Arc::ptr_eq(&last, &config));
+                let render = {
+                    let config = config.clone();
+                    move || {
+                        let text =
+                            buffer.with_untracked(|buffer| buffer.to_string());
+                        let new_blocks = blocks.with_untracked(|blocks| {
+                            render_blocks(
+                                &text,
+                                &config,
+                                if reuse { blocks } else { &[] },
+                            )
+                        });
+                        let keys: HashSet<u64> =
+                            new_blocks.iter().map(|block| block.key).collect();
+                        block_heights.update(|heights| {
+                            heights.retain(|key, _| keys.contains(key));
+                        });
+                        blocks.set(new_blocks);
+                    }
+                };
+                if reuse {
+                    let token = exec_after(RENDER_DELAY, move |token| {
+                        if timer.try_get_untracked() == Some(token) {
+                            render();
+                        }
+                    });
+                    timer.set(token);
+                } else {
+                    timer.set(TimerToken::INVALID);
+                    render();
+                }
+                config
+            });
+        }
+
+        let first_line = {
+            let path = path.clone();
+            cx.create_memo(move |last: Option<&usize>| {
+                let last = last.copied().unwrap_or(0);
+                let Some(editor) = active_editor.get() else {
+                    return last;
+                };
+                let is_doc = editor
+                    .doc_signal()
+                    .with(|doc| doc.content.with(|c| c.path() == Some(&path)));
+                if !is_doc {
+                    return last;
+                }
+                editor
+                    .screen_lines()
+                    .with(|screen_lines| {
+                        screen_lines.rvline_range().map(|(min, _)| min.line)
+                    })
+                    .unwrap_or(last)
+            })
+        };
+
+        Self {
+            id,
+            scope: cx,
+            path,
+            doc,
+            blocks,
+            block_heights,
+            first_line,
+            active_editor,
+            common,
+        }
+    }
+
+    pub fn copy(&self, cx: Scope, id: MarkdownPreviewId) -> Self {
+        Self::new(
+            cx,
+            id,
+            self.path.clone(),
+            self.doc.clone(),
+            self.active_editor,
+            self.common.clone(),
+        )
+    }
+
+    /// The offset in the preview of `line` of the document.
+    fn line_y(&self, line: usize) -> f64 {
+        self.blocks.with_untracked(|blocks| {
+            let index = blocks
+                .partition_point(|block| block.line <= line)
+                .saturating_sub(1);
+            let fraction = match (blocks.get(index), blocks.get(index + 1)) {
+                (Some(block), Some(next)) if next.line > block.line => {
+                    (line.saturating_sub(block.line) as f64
+                        / (next.line - block.line) as f64)
+                        .min(1.0)
+                }
+                _ => 0.0,
+            };
+            self.block_heights.with_untracked(|heights| {
+                let height = |block: &RenderedBlock| {
+                    heights.get(&block.key).copied().unwrap_or(0.0)
+                };
+                blocks[..index].iter().map(height).sum::<f64>()
+                    + blocks.get(index).map(height).unwrap_or(0.0) * fraction
+            })
+        })
+    }
+
+    fn open_link(&self, url: &str) {
+        match markdown_link_target(&self.path, url) {
+            Some(MarkdownLinkTarget::File(path)) => {
+                self.common.internal_command.send(
+                    InternalCommand::OpenMarkdownLink {
+                        source: self.path.clone(),
+                        path,
+                    },
+                );
+            }
+            Some(MarkdownLinkTarget::Web(uri)) => {
+                self.common
+                    .internal_command
+                    .send(InternalCommand::OpenWebUri { uri });
+            }
+            None => {}
+        }
+    }
+}
+
+fn text_view(
+    preview: MarkdownPreviewData,
+    text_layout: TextLayout,
+    links: Vec<MarkdownLink>,
+) -> impl View {
+    let width = create_rw_signal(0.0);
+    let hovered_link = create_rw_signal(false);
+    let hit_layout = text_layout.clone();
+    let link_at = move |point: Point| {
+        let mut text_layout = hit_layout.clone();
+        text_layout.set_size(width.get_untracked() as f32, f32::MAX);
+        let hit = text_layout.hit_point(point);
+        if !hit.is_inside {
+            return None;
+        }
+        links
+            .iter()
+            .find(|link| link.range.contains(&hit.index))
+            .map(|link| link.url.clone())
+    };
+    let link_at_move = link_at.clone();
+    rich_text(move || text_layout.clone())
+        .on_resize(move |rect| {
+            width.set(rect.width());
+        })
+        .on_event_cont(EventListener::PointerMove, move |event| {
+            if let Event::PointerMove(pointer_event) = event {
+                let is_link = link_at_move(pointer_event.pos).is_some();
+                if hovered_link.get_untracked() != is_link {
+                    hovered_link.set(is_link);
+                }
+            }
+        })
+        .on_event_cont(EventListener::PointerLeave, move |_| {
+            hovered_link.set(false);
+        })
+        .on_event_cont(EventListener::PointerUp, move |event| {
+            if let Event::PointerUp(pointer_event) = event {
+                if let Some(url) = link_at(pointer_event.pos) {
+                    preview.open_link(&url);
+                }
+            }
+        })
+        .style(move |s| {
+            s.width_full()
+                .apply_if(hovered_link.get(), |s| s.cursor(CursorStyle::Pointer))
+        })
+}
+
+/// An image of the document, read from the file it links to, or a link to it
+/// when it's on the web.
+fn image_view(
+    preview: MarkdownPreviewData,
+    url: String,
+    title: String,
+) -> impl View {
+    let config = preview.common.config;
+    let path = match markdown_link_target(&preview.path, &url) {
+        Some(MarkdownLinkTarget::File(path)) => path,
+        _ => {
+            let text = if title.is_empty() { url.clone() } else { title };
+            return label(move || text.clone())
+                .on_click_stop(move |_| {
+                    preview.open_link(&url);
+                })
+                .style(move |s| {
+                    s.cursor(CursorStyle::Pointer)
+                        .color(config.get().color(LapceColor::EDITOR_LINK))
+                })
+                .into_any();
+        }
+    };
+
+    let image = create_rw_signal(None);
+    let send = create_ext_action(Scope::current(), move |result| {
+        if let Ok(ProxyResponse::ReadFileBytesResponse { content, .. }) = result {
+            match VoltIcon::from_bytes(&content) {
+                Ok(icon) => image.set(Some(icon)),
+                Err(err) => tracing::error!("{:?}", err),
+            }
+        }
+    });
+    preview.common.proxy.read_file_bytes(path, move |result| {
+        send(result);
+    });
+
+    dyn_container(
+        move || image.get(),
+        move |image| match image {
+            Some(VoltIcon::Svg(svg_str)) => svg(move || svg_str.clone())
+                .style(|s| s.max_width_full())
+                .into_any(),
+            Some(VoltIcon::Img(buf)) => img(move || buf.clone())
+                .style(|s| s.max_width_full())
+                .into_any(),
+            None => empty().into_any(),
+        },
+    )
+    .into_any()
+}
+
+fn block_view(preview: MarkdownPreviewData, block: RenderedBlock) -> impl View {
+    let config = preview.common.config;
+    let block_heights = preview.block_heights;
+    let key = block.key;
+    let code = block.code;
+    let contents = block
+        .contents
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(i, content)| match content {
+            MarkdownContent::Text(text_layout) => {
+                let links = block
+                    .links
+                    .iter()
+                    .filter(|link| link.content == i)
+                    .cloned()
+                    .collect();
+                text_view(preview.clone(), text_layout, links).into_any()
+            }
+            MarkdownContent::Image { url, title } => {
+                image_view(preview.clone(), url, title).into_any()
+            }
+            MarkdownContent::Separator => empty()
+                .style(move |s| {
+                    s.width_full()
+                        .margin_vert(5.0)
+                        .height(1.0)
+                        .background(config.get().color(LapceColor::LAPCE_BORDER))
+                })
+                .into_any(),
+        })
+        .collect::<Vec<_>>();
+    container(stack_from_iter(contents).style(move |s| {
+        s.flex_col().width_full().apply_if(code, |s| {
+            s.padding(10.0)
+                .border_radius(6.0)
+                .background(config.get().color(LapceColor::EDITOR_CURRENT_LINE))
+        })
+    }))
+    .on_resize(move |rect| {
+        block_heights.update(|heights| {
+            heights.insert(key, rect.height());
+        });
+    })
+    .style(|s| s.width_full().padding_bottom(10.0))
+}
+
+pub fn markdown_preview_view(preview: MarkdownPreviewData) -> impl View {
+    let config = preview.common.config;
+    let blocks = preview.blocks;
+    let scroll_preview = preview.clone();
+
+    scroll(
+        dyn_stack(
+            move || blocks.get(),
+            |block| block.key,
+            move |block| block_view(preview.clone(), block),
+        )
+        .style(|s| s.flex_col().width_full().padding(20.0)),
+    )
+    .scroll_to(move || {
+        let line = scroll_preview.first_line.get();
+        Some(Point::new(0.0, scroll_preview.line_y(line)))
+    })
+    .style(move |s| {
+        let config = config.get();
+        s.absolute()
+            .size_full()
+            .background(config.color(LapceColor::EDITOR_BACKGROUND))
+    })
+    .debug_name("Markdown Preview")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_blocks() {
+        let text =
+            "# Title\n\nSome *text*\nmore\n\n```rust\nfn main() {}\n```\n\n---\n";
+        let events: Vec<_> = Parser::new_ext(text, markdown_options())
+            .into_offset_iter()
+            .collect();
+        let blocks = markdown_blocks(text, &events);
+        let lines: Vec<_> = blocks.iter().map(|b| (b.line, b.code)).collect();
+        assert_eq!(lines, [(0, false), (2, false), (5, true), (9, false)]);
+        assert!(text[blocks[1].range.clone()].starts_with("Some *text*\nmore"));
+        assert_eq!(blocks[0].events.start, 0);
+        assert_eq!(blocks[3].events.end, events.len());
+        for pair in blocks.windows(2) {
+            assert_eq!(pair[0].events.end, pair[1].events.start);
+        }
+    }
+
+    #[test]
+    fn test_reference_link_blocks() {
+        // The reference is defined in another block than the link.
+        let text = "See [the docs][docs].\n\n[docs]: https://example.com\n";
+        let events: Vec<_> = Parser::new_ext(text, markdown_options())
+            .into_offset_iter()
+            .collect();
+        let blocks = markdown_blocks(text, &events);
+        let link = events[blocks[0].events.clone()]
+            .iter()
+            .find_map(|(event, _)| match event {
+                MdEvent::Start(Tag::Link { dest_url, .. }) => Some(dest_url),
+                _ => None,
+            });
+        assert_eq!(
+            link.map(|url| url.to_string()).as_deref(),
+            Some("https://example.com")
+        );
+    }
+
+    #[test]
+    fn test_markdown_link_target() {
+        let path = Path::new("/docs/guide/intro.md");
+        assert_eq!(
+            markdown_link_target(path, "../api%20notes.md#usage"),
+            Some(MarkdownLinkTarget::File(PathBuf::from(
+                "/docs/api notes.md"
+            )))
+        );
+        assert_eq!(
+            markdown_link_target(path, "https://example.com/a"),
+            Some(MarkdownLinkTarget::Web("https://example.com/a".to_string()))
+        );
+        assert_eq!(markdown_link_target(path, "#usage"), None);
+    }
+}
@@ -27,7 +27,10 @@
     line_ending::LineEnding, mode::Mode, movement::Movement, selection::Selection,
     syntax::Syntax,
 };
-use lapce_rpc::proxy::ProxyResponse;
+use lapce_rpc::{
+    buffer::{ENCODINGS, FileEncoding},
+    proxy::ProxyResponse,
+};
 use lapce_xi_rope::Rope;
 use lsp_types::{DocumentSymbol, DocumentSymbolResponse};
 use nucleo::Utf32Str;
@@ -40,7 +43,8 @@
 };
 use crate::{
     command::{
-        CommandExecuted, CommandKind, InternalCommand, LapceCommand, WindowCommand,
+        CommandExecuted, CommandKind, InternalCommand, LapceCommand,
+        LapceWorkbenchCommand, WindowCommand,
     },
     db::LapceDb,
     debug::{RunDebugConfigs, RunDebugMode},
@@ -107,6 +111,17 @@ pub struct PaletteData {
     pub source_control: SourceControlData,
     pub common: Rc<CommonData>,
     left_diff_path: RwSignal<Option<PathBuf>>,
+    /// The volt prompt waiting for the user to pick an item
+    volt_prompt: RwSignal<Option<VoltPrompt>>,
+}
+
+/// A list of items a volt asked the user to pick one of.
+#[derive(Clone, Debug)]
+pub struct VoltPrompt {
+    pub id: u64,
+    pub title: String,
+    pub placeholder: String,
+    pub items: Vec<String>,
 }
 
 impl std::fmt::Debug for PaletteData {
@@ -214,6 +229,7 @@ pub fn new(
 
         let clicked_index = cx.create_rw_signal(Option::<usize>::None);
         let left_diff_path = cx.create_rw_signal(None);
+        let volt_prompt = cx.create_rw_signal(None);
 
         let palette = Self {
             run_id_counter,
@@ -238,6 +254,7 @@ pub fn new(
             source_control,
             common,
             left_diff_path,
+            volt_prompt,
         };
 
         {
@@ -330,6 +347,9 @@ pub fn new(
 
     /// Start and focus the palette for the given kind.
     pub fn run(&self, kind: PaletteKind) {
+        if kind != PaletteKind::VoltPrompt {
+            self.answer_volt_prompt(None);
+        }
         self.common.focus.set(Focus::Palette);
         self.status.set(PaletteStatus::Started);
         let symbol = kind.symbol();
@@ -342,19 +362,56 @@ pub fn run(&self, kind: PaletteKind) {
     }
 
     /// Get the placeholder text to use in the palette input field.
-    pub fn placeholder_text(&self) -> &'static str {
+    pub fn placeholder_text(&self) -> String {
         match self.kind.get() {
             PaletteKind::SshHost => {
                 "Type [user@]host or select a previously connected workspace below"
+                    .to_owned()
             }
             PaletteKind::DiffFiles => {
                 if self.left_diff_path.with(Option::is_some) {
-                    "Select right file"
+                    "Select right file".to_owned()
                 } else {
-                    "Seleft left file"
+                    "Seleft left file".to_owned()
                 }
             }
-            _ => "",
+            PaletteKind::MacroName => {
+                "Type a name to save the last macro under".to_owned()
+            }
+            PaletteKind::MacroRepeat => {
+                "Type the number of times to replay the last macro".to_owned()
+            }
+            PaletteKind::VoltPrompt => self.volt_prompt.with(|prompt| {
+                prompt
+                    .as_ref()
+                    .map(|prompt| {
+                        if prompt.placeholder.is_empty() {
+                            prompt.title.clone()
+                        } else {
+                            format!("{}: {}", prompt.title, prompt.placeholder)
+                        }
+                    })
+                    .unwrap_or_default()
+            }),
+            _ => String::new(),
+        }
+    }
+
+    /// Let the user pick one of the items of a volt prompt in the palette,
+    /// dismissing any prompt that is still open.
+    pub fn show_volt_prompt(&self, prompt: VoltPrompt) {
+        self.answer_volt_prompt(None);
+        self.volt_prompt.set(Some(prompt));
+        self.run(PaletteKind::VoltPrompt);
+    }
+
+    /// Send the picked item back to the volt that is waiting for it.
+    fn answer_volt_prompt(&self, index: Option<usize>) {
+        if self.volt_prompt.with_untracked(Option::is_none) {
+            return;
+        }
+        if let Some(prompt) = self.volt_prompt.try_update(Option::take).flatten() {
+            self.common.proxy.answer_volt_prompt(prompt.id, index);
         }
     }
 
@@ -412,10 +469,30 @@ fn run_inner(&self, kind: PaletteKind) {
             PaletteKind::LineEnding => {
                 self.get_line_endings();
             }
+            PaletteKind::ReopenWithEncoding => {
+                self.get_encodings(false);
+            }
+            PaletteKind::SaveWithEncoding => {
+                self.get_encodings(true);
+            }
+            PaletteKind::Snippet => {
+                self.get_snippets();
+            }
+            PaletteKind::ClipboardHistory => {
+                self.get_clipboard_history();
+            }
+            PaletteKind::Macro => {
+                self.get_macros();
+            }
+            // Free input, without items to pick from
+            PaletteKind::MacroName | PaletteKind::MacroRepeat => {
+                self.items.set(Vector::new());
+            }
             PaletteKind::SCMReferences => {
                 self.get_scm_references();
             }
             PaletteKind::TerminalProfile => self.get_terminal_profiles(),
+            PaletteKind::VoltPrompt => self.get_volt_prompt_items(),
         }
     }
 
@@ -564,9 +641,9 @@ fn get_commands(&self) {
                 .rev()
                 .filter_map(|(key, _)| {
                     keypress.commands.get(key).and_then(|c| {
-                        c.kind.desc().as_ref().map(|m| PaletteItem {
+                        c.desc().map(|m| PaletteItem {
                             content: PaletteItemContent::Command { cmd: c.clone() },
-                            filter_text: m.to_string(),
+                            filter_text: m,
                             score: 0,
                             indices: vec![],
                         })
@@ -575,18 +652,18 @@ fn get_commands(&self) {
                 .collect();
             // Add all the rest of the commands, ignoring palette commands (because we're in it)
             // and commands that are sorted earlier due to being executed.
-            items.extend(keypress.commands.iter().filter_map(|(_, c)| {
-                if EXCLUDED_ITEMS.contains(&c.kind.str()) {
+            items.extend(keypress.commands.iter().filter_map(|(name, c)| {
+                if EXCLUDED_ITEMS.contains(&name.as_str()) {
                     return None;
                 }
 
-                if self.executed_commands.borrow().contains_key(c.kind.str()) {
+                if self.executed_commands.borrow().contains_key(name) {
                     return None;
                 }
 
-                c.kind.desc().as_ref().map(|m| PaletteItem {
+                c.desc().map(|m| PaletteItem {
                     content: PaletteItemContent::Command { cmd: c.clone() },
-                    filter_text: m.to_string(),
+                    filter_text: m,
                     score: 0,
                     indices: vec![],
                 })
@@ -1047,6 +1124,99 @@ fn get_line_endings(&self) {
         self.items.set(items);
     }
 
+    /// The user snippets for the language of the active editor.
+    fn get_snippets(&self) {
+        let Some(editor) = self.main_split.active_editor.get_untracked() else {
+            return;
+        };
+        let language = editor
+            .doc()
+            .syntax()
+            .with_untracked(|syntax| syntax.language);
+        let items = self.common.snippets.with_untracked(|snippets| {
+            snippets
+                .iter()
+                .filter(|snippet| snippet.applies_to(language))
+                .map(|snippet| PaletteItem {
+                    filter_text: format!(
+                        "{} - {}",
+                        snippet.prefixes.join(", "),
+                        snippet.description.as_deref().unwrap_or(&snippet.name)
+                    ),
+                    content: PaletteItemContent::Snippet {
+                        snippet: snippet.clone(),
+                    },
+                    score: 0,
+                    indices: Vec::new(),
+                })
+                .collect()
+        });
+        self.items.set(items);
+    }
+
+    /// The copied texts, newest first, with the text of each cursor of a
+    /// multi-cursor copy separated by `|`.
+    fn get_clipboard_history(&self) {
+        let ring = self
+            .common
+            .keypress
+            .with_untracked(|keypress| keypress.registers.ring);
+        let items = ring.with_untracked(|ring| {
+            ring.iter()
+                .map(|yank| PaletteItem {
+                    filter_text: yank
+                        .pieces
+                        .iter()
+                        .map(|piece| piece.split_whitespace().join(" "))
+                        .join(" | ")
+                        .chars()
+                        .take(200)
+                        .collect(),
+                    content: PaletteItemContent::ClipboardHistory {
+                        yank: yank.clone(),
+                    },
+                    score: 0,
+                    indices: Vec::new(),
+                })
+                .collect()
+        });
+        self.items.set(items);
+    }
+
+    /// The macros saved in the settings.
+    fn get_macros(&self) {
+        let items = self.common.config.with_untracked(|config| {
+            config
+                .macros
+                .keys()
+                .map(|name| PaletteItem {
+                    content: PaletteItemContent::Macro { name: name.clone() },
+                    filter_text: name.clone(),
+                    score: 0,
+                    indices: Vec::new(),
+                })
+                .collect()
+        });
+        self.items.set(items);
+    }
+
+    fn get_volt_prompt_items(&self) {
+        let items = self.volt_prompt.with_untracked(|prompt| {
+            prompt
+                .iter()
+                .flat_map(|prompt| prompt.items.iter())
+                .enumerate()
+                .map(|(index, item)| PaletteItem {
+                    content: PaletteItemContent::VoltPromptItem { index },
+                    filter_text: item.to_owned(),
+                    score: 0,
+                    indices: Vec::new(),
+                })
+                .collect()
+        });
+        self.items.set(items);
+    }
+
     fn get_scm_references(&self) {
         let branches = self.source_control.branches.get_untracked();
         let tags = self.source_control.tags.get_untracked();
@@ -1099,6 +1269,7 @@ fn get_terminal_profiles(&self) {
                         arguments: profile.arguments,
                         workdir: uri,
                         environment: profile.environment,
+                        ..Default::default()
                     },
                 },
                 filter_text: name.to_owned(),
@@ -1110,6 +1281,47 @@ fn get_terminal_profiles(&self) {
         self.items.set(items);
     }
 
+    /// The encodings to reopen or save the file with, where saving can
+    /// also choose whether UTF-8 files start with a byte order mark.
+    fn get_encodings(&self, save: bool) {
+        let mut encodings: Vec<FileEncoding> = ENCODINGS
+            .iter()
+            .map(|name| FileEncoding::new(*name))
+            .collect();
+        if save {
+            encodings.insert(
+                1,
+                FileEncoding {
+                    name: "UTF-8".to_string(),
+                    bom: true,
+                },
+            );
+        }
+        let items = encodings
+            .into_iter()
+            .map(|encoding| PaletteItem {
+                filter_text: encoding.to_string(),
+                content: if save {
+                    PaletteItemContent::SaveWithEncoding { encoding }
+                } else {
+                    PaletteItemContent::ReopenWithEncoding { encoding }
+                },
+                score: 0,
+                indices: Vec::new(),
+            })
+            .collect();
+        if let Some(editor) = self.main_split.active_editor.get_untracked() {
+            let encoding = editor.doc().encoding.get_untracked();
+            let current = if save {
+                encoding.to_string()
+            } else {
+                encoding.name
+            };
+            self.preselect_matching(&items, &current);
+        }
+        self.items.set(items);
+    }
+
     fn preselect_matching(&self, items: &im::Vector<PaletteItem>, matching: &str) {
         let Some((idx, _)) = items
             .iter()
@@ -1124,6 +1336,11 @@ fn preselect_matching(&self, items: &im::Vector<PaletteItem>, matching: &str) {
     fn select(&self) {
         let index = self.index.get_untracked();
         let items = self.filtered_items.get_untracked();
+        if let Some(PaletteItemContent::VoltPromptItem { index }) =
+            items.get(index).map(|item| &item.content)
+        {
+            self.answer_volt_prompt(Some(*index));
+        }
         self.close();
         if let Some(item) = items.get(index) {
             match &item.content {
@@ -1208,6 +1425,7 @@ fn select(&self) {
                             workspace: LapceWorkspace {
                                 kind: LapceWorkspaceType::RemoteSSH(host.clone()),
                                 path: None,
+                                folders: Vec::new(),
                                 last_open: 0,
                             },
                         },
@@ -1220,6 +1438,7 @@ fn select(&self) {
                             workspace: LapceWorkspace {
                                 kind: LapceWorkspaceType::RemoteWSL(host.clone()),
                                 path: None,
+                                folders: Vec::new(),
                                 last_open: 0,
                             },
                         },
@@ -1313,6 +1532,44 @@ fn select(&self) {
                         buffer.set_line_ending(*kind);
                     });
                 }
+                PaletteItemContent::ReopenWithEncoding { encoding } => {
+                    let Some(editor) = self.main_split.active_editor.get_untracked()
+                    else {
+                        return;
+                    };
+                    editor.doc().reopen_with_encoding(encoding.clone());
+                }
+                PaletteItemContent::SaveWithEncoding { encoding } => {
+                    let Some(editor) = self.main_split.active_editor.get_untracked()
+                    else {
+                        return;
+                    };
+                    let doc = editor.doc();
+                    doc.encoding.set(encoding.clone());
+                    doc.save(|| {});
+                }
+                PaletteItemContent::Snippet { snippet } => {
+                    let Some(editor) = self.main_split.active_editor.get_untracked()
+                    else {
+                        return;
+                    };
+                    editor.insert_snippet(snippet);
+                }
+                PaletteItemContent::ClipboardHistory { yank } => {
+                    let Some(editor) = self.main_split.active_editor.get_untracked()
+                    else {
+                        return;
+                    };
+                    editor.paste_yank(yank);
+                }
+                PaletteItemContent::Macro { name } => {
+                    self.common.lapce_command.send(LapceCommand {
+                        kind: CommandKind::Workbench(
+                            LapceWorkbenchCommand::RunMacro,
+                        ),
+                        data: Some(serde_json::json!(name)),
+                    });
+                }
                 PaletteItemContent::SCMReference { name } => {
                     self.common
                         .lapce_command
@@ -1329,6 +1586,8 @@ fn select(&self) {
                     .send(InternalCommand::NewTerminal {
                         profile: Some(profile.to_owned()),
                     }),
+                // Answered before closing the palette
+                PaletteItemContent::VoltPromptItem { .. } => {}
             }
         } else if self.kind.get_untracked() == PaletteKind::SshHost {
             let input = self.input.with_untracked(|input| input.input.clone());
@@ -1338,10 +1597,32 @@ fn select(&self) {
                     workspace: LapceWorkspace {
                         kind: LapceWorkspaceType::RemoteSSH(ssh),
                         path: None,
+                        folders: Vec::new(),
                         last_open: 0,
                     },
                 },
             );
+        } else if self.kind.get_untracked() == PaletteKind::MacroName {
+            let name = self.input.with_untracked(|input| input.input.clone());
+            let name = name.trim();
+            if !name.is_empty() {
+                self.common.lapce_command.send(LapceCommand {
+                    kind: CommandKind::Workbench(LapceWorkbenchCommand::SaveMacro),
+                    data: Some(serde_json::json!(name)),
+                });
+            }
+        } else if self.kind.get_untracked() == PaletteKind::MacroRepeat {
+            let times = self
+                .input
+                .with_untracked(|input| input.input.trim().parse::<usize>());
+            if let Ok(times) = times {
+                self.common.lapce_command.send(LapceCommand {
+                    kind: CommandKind::Workbench(
+                        LapceWorkbenchCommand::ReplayMacroNTimes,
+                    ),
+                    data: Some(serde_json::json!(times)),
+                });
+            }
         }
     }
 
@@ -1394,6 +1675,11 @@ fn preview(&self) {
                 PaletteItemContent::WslHost { .. } => {}
                 PaletteItemContent::Language { .. } => {}
                 PaletteItemContent::LineEnding { .. } => {}
+                PaletteItemContent::ReopenWithEncoding { .. } => {}
+                PaletteItemContent::SaveWithEncoding { .. } => {}
+                PaletteItemContent::Snippet { .. } => {}
+                PaletteItemContent::ClipboardHistory { .. } => {}
+                PaletteItemContent::Macro { .. } => {}
                 PaletteItemContent::Reference { location, .. } => {
                     self.has_preview.set(true);
                     let (doc, new_doc) =
@@ -1461,6 +1747,7 @@ fn preview(&self) {
                     }),
                 PaletteItemContent::SCMReference { .. } => {}
                 PaletteItemContent::TerminalProfile { .. } => {}
+                PaletteItemContent::VoltPromptItem { .. } => {}
             }
         }
     }
@@ -1482,6 +1769,7 @@ fn cancel(&self) {
 
     /// Close the palette, reverting focus back to the workbench.
     fn close(&self) {
+        self.answer_volt_prompt(None);
         self.status.set(PaletteStatus::Inactive);
         if self.common.focus.get_untracked() == Focus::Palette {
             self.common.focus.set(Focus::Workbench);
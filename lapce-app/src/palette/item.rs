@@ -1,13 +1,14 @@
 use std::path::PathBuf;
 
 use lapce_core::line_ending::LineEnding;
-use lapce_rpc::dap_types::RunDebugConfig;
+use lapce_rpc::{buffer::FileEncoding, dap_types::RunDebugConfig};
 use lsp_types::{Range, SymbolKind};
 
 use crate::{
     command::{LapceCommand, LapceWorkbenchCommand},
     debug::RunDebugMode,
-    editor::location::EditorLocation,
+    editor::{location::EditorLocation, registers::Yank},
+    snippet::UserSnippet,
     workspace::{LapceWorkspace, SshHost},
 };
 
@@ -77,6 +78,21 @@ pub enum PaletteItemContent {
     LineEnding {
         kind: LineEnding,
     },
+    ReopenWithEncoding {
+        encoding: FileEncoding,
+    },
+    SaveWithEncoding {
+        encoding: FileEncoding,
+    },
+    Snippet {
+        snippet: UserSnippet,
+    },
+    ClipboardHistory {
+        yank: Yank,
+    },
+    Macro {
+        name: String,
+    },
     SCMReference {
         name: String,
     },
@@ -84,4 +100,8 @@ pub enum PaletteItemContent {
         name: String,
         profile: lapce_rpc::terminal::TerminalProfile,
     },
+    /// The index of the item in the volt prompt
+    VoltPromptItem {
+        index: usize,
+    },
 }
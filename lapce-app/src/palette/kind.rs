@@ -20,10 +20,18 @@ pub enum PaletteKind {
     IconTheme,
     Language,
     LineEnding,
+    ReopenWithEncoding,
+    SaveWithEncoding,
+    Snippet,
+    ClipboardHistory,
+    Macro,
+    MacroName,
+    MacroRepeat,
     SCMReferences,
     TerminalProfile,
     DiffFiles,
     HelpAndFile,
+    VoltPrompt,
 }
 
 impl PaletteKind {
@@ -46,9 +54,17 @@ pub fn symbol(&self) -> &'static str {
             | PaletteKind::IconTheme
             | PaletteKind::Language
             | PaletteKind::LineEnding
+            | PaletteKind::ReopenWithEncoding
+            | PaletteKind::SaveWithEncoding
+            | PaletteKind::Snippet
+            | PaletteKind::ClipboardHistory
+            | PaletteKind::Macro
+            | PaletteKind::MacroName
+            | PaletteKind::MacroRepeat
             | PaletteKind::SCMReferences
             | PaletteKind::HelpAndFile
-            | PaletteKind::DiffFiles => "",
+            | PaletteKind::DiffFiles
+            | PaletteKind::VoltPrompt => "",
             #[cfg(windows)]
             PaletteKind::WslHost => "",
         }
@@ -98,11 +114,27 @@ pub fn command(self) -> Option<LapceWorkbenchCommand> {
             PaletteKind::LineEnding => {
                 Some(LapceWorkbenchCommand::ChangeFileLineEnding)
             }
+            PaletteKind::ReopenWithEncoding => {
+                Some(LapceWorkbenchCommand::ReopenWithEncoding)
+            }
+            PaletteKind::SaveWithEncoding => {
+                Some(LapceWorkbenchCommand::SaveWithEncoding)
+            }
+            PaletteKind::Snippet => Some(LapceWorkbenchCommand::InsertSnippet),
+            PaletteKind::ClipboardHistory => {
+                Some(LapceWorkbenchCommand::PasteFromHistory)
+            }
+            PaletteKind::Macro => Some(LapceWorkbenchCommand::RunMacro),
+            PaletteKind::MacroName => Some(LapceWorkbenchCommand::SaveMacro),
+            PaletteKind::MacroRepeat => {
+                Some(LapceWorkbenchCommand::ReplayMacroNTimes)
+            }
             PaletteKind::SCMReferences => {
                 Some(LapceWorkbenchCommand::PaletteSCMReferences)
             }
             PaletteKind::TerminalProfile => None, // InternalCommand::NewTerminal
             PaletteKind::DiffFiles => Some(LapceWorkbenchCommand::DiffFiles),
+            PaletteKind::VoltPrompt => None, // PaletteData::show_volt_prompt
         }
     }
 
@@ -129,8 +161,16 @@ pub fn get_input<'a>(&self, input: &'a str) -> &'a str {
             | PaletteKind::IconTheme
             | PaletteKind::Language
             | PaletteKind::LineEnding
+            | PaletteKind::ReopenWithEncoding
+            | PaletteKind::SaveWithEncoding
+            | PaletteKind::Snippet
+            | PaletteKind::ClipboardHistory
+            | PaletteKind::Macro
+            | PaletteKind::MacroName
+            | PaletteKind::MacroRepeat
             | PaletteKind::SCMReferences | PaletteKind::HelpAndFile
-            | PaletteKind::DiffFiles => input,
+            | PaletteKind::DiffFiles
+            | PaletteKind::VoltPrompt => input,
             PaletteKind::PaletteHelp
             | PaletteKind::Command
             | PaletteKind::Workspace
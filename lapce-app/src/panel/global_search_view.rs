@@ -114,6 +114,7 @@ fn search_result(
     config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View {
     let ui_line_height = global_search_data.common.ui_line_height;
+    let workspace_folders = global_search_data.common.workspace_folders;
     container({
         scroll({
             virtual_stack(
@@ -121,14 +122,10 @@ fn search_result(
                 move |(path, _)| path.to_owned(),
                 move |(path, match_data)| {
                     let full_path = path.clone();
-                    let path = if let Some(workspace_path) = workspace.path.as_ref()
-                    {
-                        path.strip_prefix(workspace_path)
-                            .unwrap_or(&full_path)
-                            .to_path_buf()
-                    } else {
-                        path
-                    };
+                    let path = workspace.relative_path(
+                        &workspace_folders.get_untracked(),
+                        &full_path,
+                    );
                     let style_path = path.clone();
 
                     let file_name = path
@@ -19,6 +19,8 @@ pub enum PanelKind {
     DocumentSymbol,
     References,
     Implementation,
+    LanguageServer,
+    LocalHistory,
 }
 
 impl PanelKind {
@@ -35,6 +37,8 @@ pub fn svg_name(&self) -> &'static str {
             PanelKind::DocumentSymbol => LapceIcons::DOCUMENT_SYMBOL,
             PanelKind::References => LapceIcons::REFERENCES,
             PanelKind::Implementation => LapceIcons::IMPLEMENTATION,
+            PanelKind::LanguageServer => LapceIcons::LANGUAGE_SERVER,
+            PanelKind::LocalHistory => LapceIcons::LOCAL_HISTORY,
         }
     }
 
@@ -61,6 +65,8 @@ pub fn default_position(&self) -> PanelPosition {
             PanelKind::DocumentSymbol => PanelPosition::RightTop,
             PanelKind::References => PanelPosition::BottomLeft,
             PanelKind::Implementation => PanelPosition::BottomLeft,
+            PanelKind::LanguageServer => PanelPosition::BottomLeft,
+            PanelKind::LocalHistory => PanelPosition::RightTop,
         }
     }
 }
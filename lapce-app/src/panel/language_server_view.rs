@@ -0,0 +1,174 @@
+use std::{rc::Rc, sync::Arc};
+
+use floem::{
+    View,
+    reactive::{ReadSignal, SignalGet, SignalWith},
+    style::CursorStyle,
+    views::{Decorators, container, dyn_stack, label, scroll, stack},
+};
+use indexmap::IndexMap;
+use lapce_rpc::plugin::{LanguageServerInfo, PluginId};
+
+use super::position::PanelPosition;
+use crate::{
+    command::InternalCommand,
+    config::{LapceConfig, color::LapceColor},
+    window_tab::WindowTabData,
+};
+
+pub fn language_server_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let servers = window_tab_data.plugin.language_servers;
+    let proxy = window_tab_data.common.proxy.clone();
+    let internal_command = window_tab_data.common.internal_command;
+
+    let server_view = move |plugin_id: PluginId| {
+        let info = move |f: fn(&LanguageServerInfo) -> String| {
+            servers
+                .with(|servers| servers.get(&plugin_id).map(f).unwrap_or_default())
+        };
+        let running = move || {
+            servers.with(|servers| {
+                servers.get(&plugin_id).map(|s| s.running).unwrap_or(false)
+            })
+        };
+        let trace_log = move || {
+            servers.with(|servers| {
+                servers.get(&plugin_id).and_then(|s| s.trace_log.clone())
+            })
+        };
+        let restart_proxy = proxy.clone();
+        let trace_proxy = proxy.clone();
+        stack((
+            stack((
+                label(move || info(|s| s.name.clone())).style(|s| {
+                    s.font_bold()
+                        .text_ellipsis()
+                        .min_width(0.0)
+                        .selectable(false)
+                }),
+                label(move || {
+                    if running() { "Running" } else { "Stopped" }.to_string()
+                })
+                .style(move |s| {
+                    let config = config.get();
+                    s.margin_left(10.0)
+                        .selectable(false)
+                        .apply_if(!running(), |s| {
+                            s.color(config.color(LapceColor::LAPCE_ERROR))
+                        })
+                }),
+            ))
+            .style(|s| s.width_pct(100.0).justify_between().items_center()),
+            label(move || {
+                let capabilities = info(|s| s.capabilities.join(", "));
+                if capabilities.is_empty() {
+                    "No capabilities".to_string()
+                } else {
+                    capabilities
+                }
+            })
+            .style(move |s| {
+                s.width_pct(100.0)
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+            }),
+            stack((
+                button("Restart", config).on_click_stop(move |_| {
+                    restart_proxy.restart_language_server(plugin_id);
+                }),
+                button_dyn(
+                    move || {
+                        if trace_log().is_some() {
+                            "Stop Trace"
+                        } else {
+                            "Start Trace"
+                        }
+                        .to_string()
+                    },
+                    config,
+                )
+                .on_click_stop(move |_| {
+                    trace_proxy
+                        .set_language_server_trace(plugin_id, trace_log().is_none());
+                }),
+                button("Open Trace Log", config)
+                    .on_click_stop(move |_| {
+                        if let Some(path) = trace_log() {
+                            internal_command
+                                .send(InternalCommand::OpenFile { path });
+                        }
+                    })
+                    .style(move |s| s.apply_if(trace_log().is_none(), |s| s.hide())),
+            ))
+            .style(|s| s.margin_top(4.0)),
+        ))
+        .style(move |s| {
+            s.flex_col()
+                .width_pct(100.0)
+                .padding_horiz(10.0)
+                .padding_vert(6.0)
+                .border_bottom(1.0)
+                .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+        })
+    };
+
+    stack((
+        label(|| "No language server is running".to_string()).style(move |s| {
+            s.padding(10.0)
+                .selectable(false)
+                .apply_if(!servers.with(IndexMap::is_empty), |s| s.hide())
+        }),
+        container(
+            scroll(
+                dyn_stack(
+                    move || {
+                        servers.with(|servers| {
+                            servers.keys().copied().collect::<Vec<_>>()
+                        })
+                    },
+                    |plugin_id| *plugin_id,
+                    server_view,
+                )
+                .style(|s| s.flex_col().width_pct(100.0)),
+            )
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        )
+        .style(|s| s.size_pct(100.0, 100.0)),
+    ))
+    .style(|s| s.flex_col().size_pct(100.0, 100.0).line_height(1.6))
+    .debug_name("Language Server Panel")
+}
+
+pub fn button(
+    text: &'static str,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    button_dyn(move || text.to_string(), config)
+}
+
+fn button_dyn(
+    text: impl Fn() -> String + 'static,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    label(text).style(move |s| {
+        let config = config.get();
+        s.margin_right(6.0)
+            .padding_horiz(6.0)
+            .border(1.0)
+            .border_radius(6.0)
+            .border_color(config.color(LapceColor::LAPCE_BORDER))
+            .selectable(false)
+            .hover(|s| {
+                s.cursor(CursorStyle::Pointer)
+                    .background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+            })
+            .active(|s| {
+                s.background(
+                    config.color(LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND),
+                )
+            })
+    })
+}
@@ -0,0 +1,122 @@
+use std::rc::Rc;
+
+use floem::{
+    View,
+    prelude::SignalTrack,
+    reactive::{
+        SignalGet, SignalUpdate, SignalWith, create_effect, create_memo,
+        create_rw_signal,
+    },
+    views::{Decorators, container, dyn_stack, label, scroll, stack},
+};
+
+use super::{language_server_view::button, position::PanelPosition};
+use crate::{
+    config::color::LapceColor, history::LocalHistoryEntry, window_tab::WindowTabData,
+};
+
+pub fn local_history_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let scope = window_tab_data.scope;
+    let local_history = window_tab_data.common.local_history.clone();
+    let main_split = window_tab_data.main_split.clone();
+    let active_editor = main_split.active_editor;
+    let path = create_memo(move |_| {
+        active_editor.get().and_then(|editor| {
+            editor
+                .doc_signal()
+                .get()
+                .content
+                .with(|content| content.path().cloned())
+        })
+    });
+    let entries = create_rw_signal(Vec::new());
+    create_effect(move |_| {
+        local_history.rev.track();
+        let Some(current) = path.get() else {
+            entries.set(Vec::new());
+            return;
+        };
+        let requested = current.clone();
+        local_history.entries(scope, current, move |new_entries| {
+            // The active file may have changed while the folder was read
+            if path.get_untracked().as_ref() == Some(&requested) {
+                entries.set(new_entries);
+            }
+        });
+    });
+
+    let entry_view = move |entry: LocalHistoryEntry| {
+        let diff_split = main_split.clone();
+        let restore_split = main_split.clone();
+        let time = entry.time();
+        let source = entry.source.label();
+        let diff_entry = entry.clone();
+        let restore_entry = entry;
+        stack((
+            stack((
+                label(move || time.clone())
+                    .style(|s| s.text_ellipsis().min_width(0.0).selectable(false)),
+                label(move || source.to_string()).style(move |s| {
+                    s.margin_left(10.0)
+                        .selectable(false)
+                        .color(config.get().color(LapceColor::EDITOR_DIM))
+                }),
+            ))
+            .style(|s| s.items_center().min_width(0.0)),
+            stack((
+                button("Compare", config).on_click_stop(move |_| {
+                    if let Some(path) = path.get_untracked() {
+                        diff_split.open_local_history_diff(path, diff_entry.clone());
+                    }
+                }),
+                button("Restore", config).on_click_stop(move |_| {
+                    if let Some(path) = path.get_untracked() {
+                        restore_split
+                            .restore_local_history(path, restore_entry.clone());
+                    }
+                }),
+            ))
+            .style(|s| s.margin_left(10.0)),
+        ))
+        .style(move |s| {
+            let config = config.get();
+            s.width_pct(100.0)
+                .justify_between()
+                .items_center()
+                .padding_horiz(10.0)
+                .padding_vert(4.0)
+                .hover(|s| {
+                    s.background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+                })
+        })
+    };
+
+    stack((
+        label(move || {
+            if path.with(Option::is_none) {
+                "No file is open".to_string()
+            } else {
+                "No local history for this file".to_string()
+            }
+        })
+        .style(move |s| {
+            s.padding(10.0)
+                .selectable(false)
+                .apply_if(!entries.with(Vec::is_empty), |s| s.hide())
+        }),
+        container(
+            scroll(
+                dyn_stack(move || entries.get(), |entry| entry.clone(), entry_view)
+                    .style(|s| s.flex_col().width_pct(100.0)),
+            )
+            .style(|s| s.absolute().size_pct(100.0, 100.0)),
+        )
+        .style(|s| s.size_pct(100.0, 100.0)),
+    ))
+    .style(|s| s.flex_col().size_pct(100.0, 100.0).line_height(1.6))
+    .debug_name("Local History Panel")
+}
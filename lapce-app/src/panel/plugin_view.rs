@@ -101,6 +101,7 @@ fn installed_view(plugin: PluginData) -> impl View {
         let volt_id = meta.id();
         let local_volt_id = volt_id.clone();
         let icon = volt.icon;
+        let crash = volt.crash;
         stack((
             dyn_container(
                 move || icon.get(),
@@ -130,8 +131,17 @@ fn installed_view(plugin: PluginData) -> impl View {
                         .min_width(0.0)
                         .selectable(false)
                 }),
-                label(move || meta.description.clone())
-                    .style(|s| s.text_ellipsis().min_width(0.0).selectable(false)),
+                label(move || {
+                    crash.get().unwrap_or_else(|| meta.description.clone())
+                })
+                .style(move |s| {
+                    s.text_ellipsis()
+                        .min_width(0.0)
+                        .selectable(false)
+                        .apply_if(crash.with(|c| c.is_some()), |s| {
+                            s.color(config.get().color(LapceColor::LAPCE_ERROR))
+                        })
+                }),
                 stack((
                     stack((
                         label(move || meta.author.clone()).style(|s| {
@@ -142,6 +152,8 @@ fn installed_view(plugin: PluginData) -> impl View {
                                 || workspace_disabled.with(|d| d.contains(&volt_id))
                             {
                                 "Disabled".to_string()
+                            } else if crash.with(|c| c.is_some()) {
+                                "Crashed".to_string()
                             } else if volt.meta.with(|m| {
                                 volt.latest.with(|i| i.version != m.version)
                             }) {
@@ -205,6 +205,7 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
     let panel_width = create_memo(move |_| panel_rect.get().width());
     let lapce_command = source_control.common.lapce_command;
     let internal_command = source_control.common.internal_command;
+    let workspace_folders = source_control.common.workspace_folders;
 
     let view_fn = move |(path, (diff, checked)): (PathBuf, (FileDiff, bool))| {
         let diff_for_style = diff.clone();
@@ -212,13 +213,8 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
         let diff_for_menu = diff.clone();
         let path_for_click = full_path.clone();
 
-        let path = if let Some(workspace_path) = workspace.path.as_ref() {
-            path.strip_prefix(workspace_path)
-                .unwrap_or(&full_path)
-                .to_path_buf()
-        } else {
-            path
-        };
+        let path =
+            workspace.relative_path(&workspace_folders.get_untracked(), &full_path);
         let file_name = path
             .file_name()
             .and_then(|s| s.to_str())
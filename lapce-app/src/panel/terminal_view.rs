@@ -1,5 +1,6 @@
 use std::rc::Rc;
 
+use alacritty_terminal::index::Direction;
 use floem::{
     View, ViewId,
     action::show_context_menu,
@@ -25,6 +26,7 @@
     terminal::{
         panel::TerminalPanelData, tab::TerminalTabData, view::terminal_view,
     },
+    text_input::TextInputBuilder,
     window_tab::{Focus, WindowTabData},
 };
 
@@ -32,6 +34,7 @@ pub fn terminal_panel(window_tab_data: Rc<WindowTabData>) -> impl View {
     let focus = window_tab_data.common.focus;
     stack((
         terminal_tab_header(window_tab_data.clone()),
+        terminal_search(window_tab_data.clone()),
         terminal_tab_content(window_tab_data.clone()),
     ))
     .on_event_cont(EventListener::PointerDown, move |_| {
@@ -273,6 +276,7 @@ fn terminal_tab_split(
     let workspace = terminal_panel_data.workspace.clone();
     let active = terminal_tab_data.active;
     let terminal_tab_scope = terminal_tab_data.scope;
+    let search_focused = terminal_panel_data.search.focused;
     dyn_stack(
         move || {
             let terminals = terminal_tab_data.terminals.get();
@@ -303,6 +307,7 @@ fn terminal_tab_split(
                 terminal_view
                     .on_event_cont(EventListener::PointerDown, move |_| {
                         active.set(index.get_untracked());
+                        search_focused.set(false);
                     })
                     .on_secondary_click_stop(move |_| {
                         if have_task {
@@ -346,6 +351,101 @@ fn terminal_tab_split(
     .style(|s| s.size_pct(100.0, 100.0))
 }
 
+fn terminal_search(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let search = window_tab_data.terminal.search.clone();
+    let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
+    let visible = search.visible;
+    let focused = search.focused;
+    let case_sensitive = search.case_sensitive;
+    let is_regex = search.is_regex;
+    let position = search.position;
+    let is_focused =
+        move || focused.get() && focus.get() == Focus::Panel(PanelKind::Terminal);
+
+    let backward = search.clone();
+    let forward = search.clone();
+    let close = search.clone();
+    stack((
+        TextInputBuilder::new()
+            .is_focused(is_focused)
+            .build_editor(search.editor.clone())
+            .style(|s| s.width(200.0)),
+        clickable_icon(
+            || LapceIcons::SEARCH_CASE_SENSITIVE,
+            move || {
+                case_sensitive.update(|case_sensitive| {
+                    *case_sensitive = !*case_sensitive;
+                });
+            },
+            move || case_sensitive.get(),
+            || false,
+            || "Case Sensitive",
+            config,
+        )
+        .style(|s| s.padding_left(6.0)),
+        clickable_icon(
+            || LapceIcons::SEARCH_REGEX,
+            move || {
+                is_regex.update(|is_regex| {
+                    *is_regex = !*is_regex;
+                });
+            },
+            move || is_regex.get(),
+            || false,
+            || "Use Regex",
+            config,
+        )
+        .style(|s| s.padding_left(6.0)),
+        label(move || match position.get() {
+            (_, 0) => "No Results".to_string(),
+            (Some(current), total) => format!("{} of {total}", current + 1),
+            (None, total) => format!("{total} results"),
+        })
+        .style(|s| s.margin_left(10.0).min_width(80.0)),
+        clickable_icon(
+            || LapceIcons::SEARCH_BACKWARD,
+            move || backward.search(Direction::Left),
+            || false,
+            || false,
+            || "Previous Match",
+            config,
+        )
+        .style(|s| s.padding_left(6.0)),
+        clickable_icon(
+            || LapceIcons::SEARCH_FORWARD,
+            move || forward.search(Direction::Right),
+            || false,
+            || false,
+            || "Next Match",
+            config,
+        )
+        .style(|s| s.padding_left(6.0)),
+        clickable_icon(
+            || LapceIcons::CLOSE,
+            move || close.close(),
+            || false,
+            || false,
+            || "Close",
+            config,
+        )
+        .style(|s| s.padding_left(6.0)),
+    ))
+    .on_event_cont(EventListener::PointerDown, move |_| {
+        focused.set(true);
+    })
+    .style(move |s| {
+        s.width_pct(100.0)
+            .items_center()
+            .padding_horiz(10.0)
+            .padding_vert(4.0)
+            .border_bottom(1.0)
+            .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+            .apply_if(!visible.get(), |s| s.hide())
+    })
+    .debug_name("Terminal Search")
+}
+
 fn terminal_tab_content(window_tab_data: Rc<WindowTabData>) -> impl View {
     let terminal = window_tab_data.terminal.clone();
     tab(
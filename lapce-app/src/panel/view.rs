@@ -33,7 +33,8 @@
     panel::{
         call_hierarchy_view::show_hierarchy_panel, document_symbol::symbol_panel,
         implementation_view::implementation_panel,
-        references_view::references_panel,
+        language_server_view::language_server_panel,
+        local_history_view::local_history_panel, references_view::references_panel,
     },
     window_tab::{DragContent, WindowTabData},
 };
@@ -507,6 +508,13 @@ fn panel_view(
                     implementation_panel(window_tab_data.clone(), position)
                         .into_any()
                 }
+                PanelKind::LanguageServer => {
+                    language_server_panel(window_tab_data.clone(), position)
+                        .into_any()
+                }
+                PanelKind::LocalHistory => {
+                    local_history_panel(window_tab_data.clone(), position).into_any()
+                }
             };
             view.style(|s| s.size_pct(100.0, 100.0))
         },
@@ -563,6 +571,8 @@ fn panel_picker(
                 PanelKind::DocumentSymbol => "Document Symbol",
                 PanelKind::References => "References",
                 PanelKind::Implementation => "Implementation",
+                PanelKind::LanguageServer => "Language Servers",
+                PanelKind::LocalHistory => "Local History",
             };
             let icon = p.svg_name();
             let is_active = {
@@ -1,5 +1,6 @@
 use std::{
     collections::HashSet,
+    path::{Path, PathBuf},
     rc::Rc,
     sync::{Arc, atomic::AtomicU64},
 };
@@ -23,18 +24,27 @@
     },
 };
 use indexmap::IndexMap;
+use itertools::Itertools;
 use lapce_core::{command::EditCommand, directory::Directory, mode::Mode};
-use lapce_proxy::plugin::{download_volt, volt_icon, wasi::find_all_volts};
+use lapce_proxy::plugin::{
+    download_volt, download_volt_metadata, download_volt_update, kept_volt_versions,
+    registry_url, restore_volt_version, unpack_volt_from_path, volt_icon,
+    volt_metadata_from_path, wasi::find_all_volts,
+};
 use lapce_rpc::{
     core::{CoreNotification, CoreRpcHandler},
-    plugin::{VoltID, VoltInfo, VoltMetadata},
+    plugin::{
+        LanguageServerInfo, PluginId, VoltCommand, VoltID, VoltInfo, VoltMetadata,
+        VoltSource, VoltStatusBarItem,
+    },
 };
 use lsp_types::MessageType;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
 use crate::{
-    command::{CommandExecuted, CommandKind},
+    alert::AlertButton,
+    command::{CommandExecuted, CommandKind, InternalCommand},
     config::{LapceConfig, color::LapceColor},
     db::LapceDb,
     editor::EditorData,
@@ -81,6 +91,27 @@ pub struct InstalledVoltData {
     pub meta: RwSignal<VoltMetadata>,
     pub icon: RwSignal<Option<VoltIcon>>,
     pub latest: RwSignal<VoltInfo>,
+    /// Why the volt crashed the last time, until it's installed or reloaded again
+    pub crash: RwSignal<Option<String>>,
+    /// The previous versions kept on disk for rolling back, newest first
+    pub kept_versions: RwSignal<Vec<String>>,
+}
+
+/// A command registered by a running volt.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RegisteredVoltCommand {
+    pub plugin_id: PluginId,
+    pub volt_id: VoltID,
+    pub command: VoltCommand,
+}
+
+impl RegisteredVoltCommand {
+    /// The id the command is listed and bound by, prefixed with the id of the
+    /// volt so it can't replace a built-in command or one of another volt,
+    /// e.g. `author.name.deploy`.
+    pub fn id(&self) -> String {
+        format!("{}.{}", self.volt_id, self.command.command)
+    }
 }
 
 #[derive(Clone, PartialEq)]
@@ -106,6 +137,10 @@ pub struct PluginData {
     pub all: RwSignal<im::HashMap<VoltID, AvailableVoltData>>,
     pub disabled: RwSignal<HashSet<VoltID>>,
     pub workspace_disabled: RwSignal<HashSet<VoltID>>,
+    /// The language servers started by the proxy, in the order they started
+    pub language_servers: RwSignal<IndexMap<PluginId, LanguageServerInfo>>,
+    /// The status bar items volts added, in the order they were added
+    pub status_items: RwSignal<IndexMap<(VoltID, String), VoltStatusBarItem>>,
     pub common: Rc<CommonData>,
 }
 
@@ -180,6 +215,8 @@ pub fn new(
             all: cx.create_rw_signal(im::HashMap::new()),
             disabled,
             workspace_disabled,
+            language_servers: cx.create_rw_signal(IndexMap::new()),
+            status_items: cx.create_rw_signal(IndexMap::new()),
             common,
         };
 
@@ -267,6 +304,11 @@ pub fn volt_installed(&self, volt: &VoltMetadata, icon: &Option<Vec<u8>>) {
                                 .and_then(|icon| VoltIcon::from_bytes(icon).ok()),
                         ),
                         latest,
+                        crash: self.common.scope.create_rw_signal(None),
+                        kept_versions: self
+                            .common
+                            .scope
+                            .create_rw_signal(Vec::new()),
                     };
                     installed.insert(volt_id, data.clone());
 
@@ -276,6 +318,7 @@ pub fn volt_installed(&self, volt: &VoltMetadata, icon: &Option<Vec<u8>>) {
             .unwrap();
 
         if existing {
+            volt_data.crash.set(None);
             volt_data.meta.set(volt.clone());
             volt_data.icon.set(
                 icon.as_ref()
@@ -283,11 +326,25 @@ pub fn volt_installed(&self, volt: &VoltMetadata, icon: &Option<Vec<u8>>) {
             );
         }
 
+        let kept_versions = volt_data.kept_versions;
+        let send = create_ext_action(self.common.scope, move |versions| {
+            kept_versions.set(versions);
+        });
+        let local_volt_id = volt_id.clone();
+        std::thread::spawn(move || {
+            send(kept_volt_versions(&local_volt_id));
+        });
+
         let latest = volt_data.latest;
-        if !is_latest {
-            let url = format!(
-                "https://plugins.lapce.dev/api/v1/plugins/{}/{}/latest",
-                volt.author, volt.name
+        if let Some(version) = self.pinned_version(&volt_id) {
+            // a pinned volt is only ever updated to the version it's pinned to
+            let mut info = latest.get_untracked();
+            info.version = version;
+            latest.set(info);
+        } else if !is_latest {
+            let url = registry_url(
+                &self.registry(),
+                &format!("/{}/{}/latest", volt.author, volt.name),
             );
             let send = create_ext_action(self.common.scope, move |info| {
                 if let Some(info) = info {
@@ -308,6 +365,7 @@ pub fn volt_removed(&self, volt: &VoltInfo) {
         self.installed.update(|installed| {
             installed.swap_remove(&id);
         });
+        self.remove_volt_contributions(&id);
 
         if self.disabled.with_untracked(|d| d.contains(&id)) {
             self.disabled.update(|d| {
@@ -334,6 +392,54 @@ pub fn volt_removed(&self, volt: &VoltInfo) {
         }
     }
 
+    pub fn volt_crashed(&self, id: &VoltID, message: String) {
+        if let Some(volt) = self.installed.with_untracked(|i| i.get(id).cloned()) {
+            volt.crash.set(Some(message));
+        }
+        // The commands and status bar items are added again if the volt is
+        // restarted
+        self.remove_volt_contributions(id);
+    }
+
+    pub fn volt_command_registered(&self, command: RegisteredVoltCommand) {
+        let config = self.common.config.get_untracked();
+        self.common.keypress.update(|keypress| {
+            keypress.register_volt_command(command, &config);
+        });
+    }
+
+    pub fn set_volt_status_bar_item(
+        &self,
+        volt_id: VoltID,
+        item: VoltStatusBarItem,
+    ) {
+        self.status_items.update(|items| {
+            items.insert((volt_id, item.id.clone()), item);
+        });
+    }
+
+    pub fn remove_volt_status_bar_item(&self, volt_id: VoltID, id: String) {
+        self.status_items.update(|items| {
+            items.shift_remove(&(volt_id, id));
+        });
+    }
+
+    /// Remove the commands and status bar items a volt added.
+    fn remove_volt_contributions(&self, id: &VoltID) {
+        let config = self.common.config.get_untracked();
+        self.common.keypress.update(|keypress| {
+            keypress.remove_volt_commands(id, &config);
+        });
+        if self
+            .status_items
+            .with_untracked(|items| items.keys().any(|(volt_id, _)| volt_id == id))
+        {
+            self.status_items.update(|items| {
+                items.retain(|(volt_id, _), _| volt_id != id);
+            });
+        }
+    }
+
     fn load_available_volts(
         &self,
         query: &str,
@@ -352,6 +458,8 @@ fn load_available_volts(
         let query_id = self.available.query_id;
         let current_query_id = self.available.query_id.get_untracked();
         let all = self.all;
+        let registry = self.registry();
+        let icon_registry = registry.clone();
         let send =
             create_ext_action(self.common.scope, move |new: Result<VoltsInfo>| {
                 loading.set(false);
@@ -371,8 +479,10 @@ fn load_available_volts(
                                 });
                                 {
                                     let volt = volt.clone();
+                                    let registry = icon_registry.clone();
                                     std::thread::spawn(move || {
-                                        let result = Self::load_icon(&volt);
+                                        let result =
+                                            Self::load_icon(&registry, &volt);
                                         send(result);
                                     });
                                 }
@@ -406,15 +516,68 @@ fn load_available_volts(
 
         let query = query.to_string();
         std::thread::spawn(move || {
-            let volts = Self::query_volts(&query, offset);
+            let volts = Self::query_volts(&registry, &query, offset);
             send(volts);
         });
     }
 
-    fn load_icon(volt: &VoltInfo) -> Result<VoltIcon> {
-        let url = format!(
-            "https://plugins.lapce.dev/api/v1/plugins/{}/{}/{}/icon?id={}",
-            volt.author, volt.name, volt.version, volt.updated_at_ts
+    /// The plugin registry volts are installed from.
+    fn registry(&self) -> String {
+        self.common
+            .config
+            .get_untracked()
+            .core
+            .plugin_registry
+            .clone()
+    }
+
+    fn pinned_version(&self, id: &VoltID) -> Option<String> {
+        self.common
+            .config
+            .get_untracked()
+            .core
+            .pinned_plugins
+            .get(&id.to_string())
+            .cloned()
+    }
+
+    /// Pin a volt to a version in the user settings, or unpin it.
+    fn pin_volt(&self, id: &VoltID, version: Option<String>) {
+        let mut pinned = self
+            .common
+            .config
+            .with_untracked(|config| config.core.pinned_plugins.clone());
+        match version {
+            Some(version) => pinned.insert(id.to_string(), version),
+            None => pinned.remove(&id.to_string()),
+        };
+        let pinned: toml_edit::InlineTable = pinned.into_iter().sorted().collect();
+        LapceConfig::update_file(
+            "core",
+            "pinned-plugins",
+            toml_edit::Value::InlineTable(pinned),
+        );
+    }
+
+    /// A volt the proxy couldn't install, roll back or update.
+    pub fn volt_install_error(&self, volt: &VoltInfo, error: &str) {
+        self.set_installing(&volt.id(), false);
+        self.common
+            .internal_command
+            .send(InternalCommand::ShowAlert {
+                title: format!("Could not install {}", volt.display_name),
+                msg: error.to_string(),
+                buttons: Vec::new(),
+            });
+    }
+
+    fn load_icon(registry: &str, volt: &VoltInfo) -> Result<VoltIcon> {
+        let url = registry_url(
+            registry,
+            &format!(
+                "/{}/{}/{}/icon?id={}",
+                volt.author, volt.name, volt.version, volt.updated_at_ts
+            ),
         );
 
         let cache_file_path = Directory::cache_directory().map(|cache_dir| {
@@ -453,9 +616,9 @@ fn download_readme(
         volt: &VoltInfo,
         config: &LapceConfig,
     ) -> Result<Vec<MarkdownContent>> {
-        let url = format!(
-            "https://plugins.lapce.dev/api/v1/plugins/{}/{}/{}/readme",
-            volt.author, volt.name, volt.version
+        let url = registry_url(
+            &config.core.plugin_registry,
+            &format!("/{}/{}/{}/readme", volt.author, volt.name, volt.version),
         );
         let resp = lapce_proxy::get_url(&url, None)?;
         if resp.status() != 200 {
@@ -467,10 +630,8 @@ fn download_readme(
         Ok(text)
     }
 
-    fn query_volts(query: &str, offset: usize) -> Result<VoltsInfo> {
-        let url = format!(
-            "https://plugins.lapce.dev/api/v1/plugins?q={query}&offset={offset}"
-        );
+    fn query_volts(registry: &str, query: &str, offset: usize) -> Result<VoltsInfo> {
+        let url = registry_url(registry, &format!("?q={query}&offset={offset}"));
         let plugins: VoltsInfo = lapce_proxy::get_url(url, None)?.json()?;
         Ok(plugins)
     }
@@ -495,31 +656,295 @@ pub fn load_more_available(&self, core_rpc: CoreRpcHandler) {
         self.load_available_volts(&query, offset, core_rpc);
     }
 
-    pub fn install_volt(&self, info: VoltInfo) {
+    fn set_installing(&self, id: &VoltID, installing: bool) {
         self.available.volts.with_untracked(|volts| {
-            if let Some(volt) = volts.get(&info.id()) {
-                volt.installing.set(true);
+            if let Some(volt) = volts.get(id) {
+                volt.installing.set(installing);
             };
         });
+    }
+
+    pub fn install_volt(&self, info: VoltInfo) {
+        self.set_installing(&info.id(), true);
+        let registry = self.registry();
         if info.wasm {
-            self.common.proxy.install_volt(info);
-        } else {
+            // Wasm volts only get the permissions they ask for, which are
+            // confirmed before the volt is installed
             let plugin = self.clone();
-            let send = create_ext_action(self.common.scope, move |result| {
-                if let Ok((meta, icon)) = result {
-                    plugin.volt_installed(&meta, &icon);
-                }
-            });
+            let local_info = info.clone();
+            let source = VoltSource::Registry(registry.clone());
+            let send = create_ext_action(
+                self.common.scope,
+                move |result: Result<VoltMetadata>| {
+                    plugin.set_installing(&local_info.id(), false);
+                    match result {
+                        Ok(meta) => plugin
+                            .confirm_volt_permissions(local_info, &meta, source),
+                        Err(err) => tracing::error!("{:?}", err),
+                    }
+                },
+            );
             std::thread::spawn(move || {
-                let download = || -> Result<(VoltMetadata, Option<Vec<u8>>)> {
-                    let download_volt_result = download_volt(&info);
-                    let meta = download_volt_result?;
-                    let icon = volt_icon(&meta);
-                    Ok((meta, icon))
-                };
-                send(download());
+                send(download_volt_metadata(&registry, &info));
             });
+        } else {
+            self.download_and_install_volt(info, registry);
+        }
+    }
+
+    /// Install a volt without wasm from the registry, which the app can load
+    /// by itself.
+    fn download_and_install_volt(&self, info: VoltInfo, registry: String) {
+        let plugin = self.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            if let Ok((meta, icon)) = result {
+                plugin.volt_installed(&meta, &icon);
+            }
+        });
+        std::thread::spawn(move || {
+            let download = || -> Result<(VoltMetadata, Option<Vec<u8>>)> {
+                let download_volt_result = download_volt(&registry, &info, None);
+                let meta = download_volt_result?;
+                let icon = volt_icon(&meta);
+                Ok((meta, icon))
+            };
+            send(download());
+        });
+    }
+
+    /// Update every installed volt to its latest version, or to the version
+    /// it's pinned to, after previewing the changelogs of the updates.
+    pub fn update_all_volts(&self) {
+        let updates = self.installed.with_untracked(|installed| {
+            installed
+                .values()
+                .filter_map(|volt| {
+                    let meta = volt.meta.get_untracked();
+                    let latest = volt.latest.get_untracked();
+                    (meta.version != latest.version).then_some((meta, latest))
+                })
+                .collect::<Vec<_>>()
+        });
+        if updates.is_empty() {
+            self.common
+                .internal_command
+                .send(InternalCommand::ShowAlert {
+                    title: "All plugins are up to date".to_string(),
+                    msg: String::new(),
+                    buttons: Vec::new(),
+                });
+            return;
+        }
+
+        let registry = self.registry();
+        let plugin = self.clone();
+        let local_registry = registry.clone();
+        let send = create_ext_action(self.common.scope, move |previews| {
+            plugin.confirm_update_all(previews, local_registry);
+        });
+        std::thread::spawn(move || {
+            let previews = updates
+                .into_iter()
+                .map(|(meta, latest)| {
+                    let update = download_volt_update(&registry, &latest);
+                    (meta, latest, update)
+                })
+                .collect::<Vec<_>>();
+            send(previews);
+        });
+    }
+
+    fn confirm_update_all(
+        &self,
+        previews: Vec<(
+            VoltMetadata,
+            VoltInfo,
+            Result<(VoltMetadata, Option<String>)>,
+        )>,
+        registry: String,
+    ) {
+        let mut msg = String::new();
+        let mut updates = Vec::new();
+        for (meta, latest, update) in previews {
+            msg.push_str(&format!(
+                "{} {} → {}\n",
+                meta.display_name, meta.version, latest.version
+            ));
+            match update {
+                Ok((new_meta, changelog)) => {
+                    if let Some(changelog) = changelog {
+                        msg.push_str(&changelog);
+                        msg.push('\n');
+                    }
+                    if new_meta.wasm.is_some()
+                        && (meta.wasm.is_none()
+                            || new_meta.permissions != meta.permissions)
+                    {
+                        msg.push_str(&volt_permissions_message(&new_meta));
+                        msg.push('\n');
+                    }
+                    let approved =
+                        new_meta.wasm.is_some().then(|| new_meta.permissions());
+                    updates.push((latest, approved));
+                }
+                Err(err) => {
+                    tracing::error!("{:?}", err);
+                    msg.push_str("Could not download the update\n");
+                }
+            }
+            msg.push('\n');
+        }
+
+        let plugin = self.clone();
+        let internal_command = self.common.internal_command;
+        let buttons = if updates.is_empty() {
+            Vec::new()
+        } else {
+            vec![AlertButton {
+                text: "Update All".to_string(),
+                action: Rc::new(move || {
+                    internal_command.send(InternalCommand::HideAlert);
+                    for (info, approved) in updates.iter().cloned() {
+                        plugin.set_installing(&info.id(), true);
+                        if info.wasm {
+                            plugin.common.proxy.install_volt(
+                                info,
+                                VoltSource::Registry(registry.clone()),
+                                approved,
+                            );
+                        } else {
+                            plugin.download_and_install_volt(info, registry.clone());
+                        }
+                    }
+                }),
+            }]
+        };
+        internal_command.send(InternalCommand::ShowAlert {
+            title: "Update Plugins?".to_string(),
+            msg: msg.trim_end().to_string(),
+            buttons,
+        });
+    }
+
+    /// Install a previous version of a volt, kept on disk when it was updated.
+    pub fn roll_back_volt(&self, meta: VoltMetadata, version: String) {
+        self.set_installing(&meta.id(), true);
+        if meta.wasm.is_some() {
+            let mut info = meta.info();
+            info.version = version.clone();
+            self.common.proxy.install_volt(
+                info,
+                VoltSource::Previous(version),
+                None,
+            );
+            return;
         }
+
+        let plugin = self.clone();
+        let display_name = meta.display_name.clone();
+        let id = meta.id();
+        let send =
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok((meta, icon)) => plugin.volt_installed(&meta, &icon),
+                Err(err) => {
+                    tracing::error!("{:?}", err);
+                    plugin.set_installing(&id, false);
+                    plugin.common.internal_command.send(
+                        InternalCommand::ShowAlert {
+                            title: format!("Could not roll back {display_name}"),
+                            msg: format!("{err}"),
+                            buttons: Vec::new(),
+                        },
+                    );
+                }
+            });
+        std::thread::spawn(move || {
+            let restore = || -> Result<(VoltMetadata, Option<Vec<u8>>)> {
+                let meta = restore_volt_version(&meta.id(), &version)?;
+                let icon = volt_icon(&meta);
+                Ok((meta, icon))
+            };
+            send(restore());
+        });
+    }
+
+    /// Install a packaged volt archive or a volt directory, e.g. on a machine
+    /// that can't reach the plugin registry.
+    pub fn install_volt_from_path(&self, path: PathBuf) {
+        let plugin = self.clone();
+        let local_path = path.clone();
+        let send = create_ext_action(
+            self.common.scope,
+            move |result: Result<VoltMetadata>| match result {
+                Ok(meta) if meta.wasm.is_some() => plugin.confirm_volt_permissions(
+                    meta.info(),
+                    &meta,
+                    VoltSource::Path(local_path),
+                ),
+                Ok(_) => plugin.unpack_volt_from_path(local_path),
+                Err(err) => plugin.volt_install_failed(&local_path, err),
+            },
+        );
+        std::thread::spawn(move || {
+            send(volt_metadata_from_path(&path));
+        });
+    }
+
+    /// Install a volt without wasm, which the app can load by itself.
+    fn unpack_volt_from_path(&self, path: PathBuf) {
+        let plugin = self.clone();
+        let local_path = path.clone();
+        let send =
+            create_ext_action(self.common.scope, move |result| match result {
+                Ok((meta, icon)) => plugin.volt_installed(&meta, &icon),
+                Err(err) => plugin.volt_install_failed(&local_path, err),
+            });
+        std::thread::spawn(move || {
+            let install = || -> Result<(VoltMetadata, Option<Vec<u8>>)> {
+                let meta = unpack_volt_from_path(&path, None)?;
+                let icon = volt_icon(&meta);
+                Ok((meta, icon))
+            };
+            send(install());
+        });
+    }
+
+    fn volt_install_failed(&self, path: &Path, err: anyhow::Error) {
+        tracing::error!("{:?}", err);
+        self.common
+            .internal_command
+            .send(InternalCommand::ShowAlert {
+                title: format!("Could not install {}", path.display()),
+                msg: err.to_string(),
+                buttons: Vec::new(),
+            });
+    }
+
+    fn confirm_volt_permissions(
+        &self,
+        info: VoltInfo,
+        meta: &VoltMetadata,
+        source: VoltSource,
+    ) {
+        let plugin = self.clone();
+        let internal_command = self.common.internal_command;
+        let approved = meta.permissions();
+        internal_command.send(InternalCommand::ShowAlert {
+            title: format!("Install {}?", meta.display_name),
+            msg: volt_permissions_message(meta),
+            buttons: vec![AlertButton {
+                text: "Install".to_string(),
+                action: Rc::new(move || {
+                    internal_command.send(InternalCommand::HideAlert);
+                    plugin.set_installing(&info.id(), true);
+                    plugin.common.proxy.install_volt(
+                        info.clone(),
+                        source.clone(),
+                        Some(approved.clone()),
+                    );
+                }),
+            }],
+        });
     }
 
     pub fn plugin_disabled(&self, id: &VoltID) -> bool {
@@ -542,8 +967,9 @@ pub fn enable_volt(&self, volt: VoltInfo) {
     pub fn disable_volt(&self, volt: VoltInfo) {
         let id = volt.id();
         self.disabled.update(|d| {
-            d.insert(id);
+            d.insert(id.clone());
         });
+        self.remove_volt_contributions(&id);
         self.common.proxy.disable_volt(volt);
         let db: Arc<LapceDb> = use_context().unwrap();
         db.save_disabled_volts(self.disabled.get_untracked().into_iter().collect());
@@ -567,8 +993,9 @@ pub fn enable_volt_for_ws(&self, volt: VoltInfo) {
     pub fn disable_volt_for_ws(&self, volt: VoltInfo) {
         let id = volt.id();
         self.workspace_disabled.update(|d| {
-            d.insert(id);
+            d.insert(id.clone());
         });
+        self.remove_volt_contributions(&id);
         self.common.proxy.disable_volt(volt);
         let db: Arc<LapceDb> = use_context().unwrap();
         db.save_workspace_disabled_volts(
@@ -604,6 +1031,12 @@ pub fn uninstall_volt(&self, volt: VoltMetadata) {
     }
 
     pub fn reload_volt(&self, volt: VoltMetadata) {
+        if let Some(installed) = self
+            .installed
+            .with_untracked(|i| i.get(&volt.id()).cloned())
+        {
+            installed.crash.set(None);
+        }
         self.common.proxy.reload_volt(volt);
     }
 
@@ -621,7 +1054,51 @@ pub fn plugin_controls(&self, meta: VoltMetadata, latest: VoltInfo) -> Menu {
                 }))
                 .separator();
         }
+        let versions = self
+            .installed
+            .with_untracked(|installed| {
+                installed
+                    .get(&volt_id)
+                    .map(|volt| volt.kept_versions.get_untracked())
+            })
+            .unwrap_or_default();
+        if !versions.is_empty() {
+            for version in versions {
+                menu = menu.entry(
+                    MenuItem::new(format!("Roll Back to v{version}")).action({
+                        let plugin = self.clone();
+                        let meta = meta.clone();
+                        move || {
+                            plugin.roll_back_volt(meta.clone(), version.clone());
+                        }
+                    }),
+                );
+            }
+            menu = menu.separator();
+        }
+        menu = match self.pinned_version(&volt_id) {
+            Some(version) => {
+                menu.entry(MenuItem::new(format!("Unpin From v{version}")).action({
+                    let plugin = self.clone();
+                    let volt_id = volt_id.clone();
+                    move || {
+                        plugin.pin_volt(&volt_id, None);
+                    }
+                }))
+            }
+            None => menu.entry(
+                MenuItem::new(format!("Pin to v{}", meta.version)).action({
+                    let plugin = self.clone();
+                    let volt_id = volt_id.clone();
+                    let version = meta.version.clone();
+                    move || {
+                        plugin.pin_volt(&volt_id, Some(version.clone()));
+                    }
+                }),
+            ),
+        };
         menu = menu
+            .separator()
             .entry(MenuItem::new("Reload Plugin").action({
                 let plugin = self.clone();
                 let meta = meta.clone();
@@ -698,6 +1175,26 @@ pub fn plugin_controls(&self, meta: VoltMetadata, latest: VoltInfo) -> Menu {
     }
 }
 
+fn volt_permissions_message(meta: &VoltMetadata) -> String {
+    let Some(permissions) = meta.permissions.as_ref() else {
+        return "This plugin doesn't declare its permissions, so it gets the access plugins had before permissions existed. It can make network requests, read all environment variables and run processes, including language servers.".to_string();
+    };
+    let list = |items: &[String]| {
+        if items.is_empty() {
+            "none".to_string()
+        } else {
+            items.join(", ")
+        }
+    };
+    format!(
+        "This plugin asks for the following permissions:\n\nNetwork: {}\nEnvironment variables: {}\nFilesystem: {}\nRun processes: {}",
+        list(&permissions.network),
+        list(&permissions.env),
+        list(&permissions.filesystem),
+        if permissions.process { "yes" } else { "no" },
+    )
+}
+
 pub fn plugin_info_view(plugin: PluginData, volt: VoltID) -> impl View {
     let config = plugin.common.config;
     let header_rect = create_rw_signal(Rect::ZERO);
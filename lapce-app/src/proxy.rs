@@ -66,6 +66,7 @@ pub fn new_proxy(
                 });
                 proxy_rpc.initialize(
                     workspace.path.clone(),
+                    workspace.folders.clone(),
                     disabled_volts,
                     extra_plugin_paths,
                     plugin_configurations,
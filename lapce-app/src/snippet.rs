@@ -1,9 +1,22 @@
 use core::fmt;
-use std::{fmt::Display, str::FromStr};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use anyhow::Error;
+use floem::{
+    ext_event::create_ext_action,
+    reactive::{RwSignal, Scope, SignalUpdate},
+};
+use indexmap::IndexMap;
+use lapce_core::{directory::Directory, language::LapceLanguage};
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde::Deserialize;
+
+use crate::workspace::{LapceWorkspace, LapceWorkspaceType};
 
 #[derive(Debug, PartialEq)]
 pub enum SnippetElement {
@@ -280,6 +293,341 @@ pub fn elements_tabs(
     }
 }
 
+/// Replace the variables in a snippet, `$NAME`, `${NAME}` and `${NAME:default}`,
+/// with their values. Unknown variables become their default, or their name
+/// when they have none. Transforms like `${NAME/regex/format/}` are ignored.
+pub fn resolve_variables(
+    body: &str,
+    value: &impl Fn(&str) -> Option<String>,
+) -> String {
+    let mut result = String::new();
+    let mut rest = body;
+    while let Some(i) = rest.find(['$', '\\']) {
+        result.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if rest.starts_with('\\') {
+            // Keep the escaped character as it is
+            let len = 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+            result.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+
+        let braced = rest[1..].starts_with('{');
+        let name_start = if braced { 2 } else { 1 };
+        let name_len = rest[name_start..]
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(rest.len() - name_start);
+        let name = &rest[name_start..name_start + name_len];
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            // A tabstop, a placeholder or a lone `$`
+            result.push('$');
+            rest = &rest[1..];
+            continue;
+        }
+
+        let name_end = name_start + name_len;
+        let (default, end) = if braced {
+            let close = match rest[name_end..].chars().next() {
+                Some('}') => Some(name_end),
+                Some(':') | Some('/') => {
+                    closing_brace(&rest[name_end..]).map(|close| name_end + close)
+                }
+                _ => None,
+            };
+            let Some(close) = close else {
+                result.push('$');
+                rest = &rest[1..];
+                continue;
+            };
+            let default = rest[name_end..close]
+                .strip_prefix(':')
+                .map(|default| resolve_variables(default, value));
+            (default, close + 1)
+        } else {
+            (None, name_end)
+        };
+
+        match value(name) {
+            Some(value) => escape(&value, &mut result),
+            None => match default {
+                Some(default) => result.push_str(&default),
+                None => escape(name, &mut result),
+            },
+        }
+        rest = &rest[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// The offset of the `}` that closes a `${`, skipping nested braces and
+/// escaped characters.
+fn closing_brace(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Escape the characters that have a meaning in snippets.
+fn escape(text: &str, buf: &mut String) {
+    for c in text.chars() {
+        if matches!(c, '$' | '}' | '\\') {
+            buf.push('\\');
+        }
+        buf.push(c);
+    }
+}
+
+/// The values of the variables that snippets can refer to
+#[derive(Clone, Debug, Default)]
+pub struct SnippetVariables {
+    pub path: Option<PathBuf>,
+    pub workspace: Option<PathBuf>,
+    pub line_index: usize,
+    pub current_line: String,
+    pub current_word: String,
+    pub selected_text: String,
+    pub clipboard: Option<String>,
+}
+
+impl SnippetVariables {
+    pub fn get(&self, name: &str) -> Option<String> {
+        let now = chrono::Local::now();
+        let path = self.path.as_deref();
+        let value = match name {
+            "TM_FILENAME" => file_name(path?.file_name()?),
+            "TM_FILENAME_BASE" => file_name(path?.file_stem()?),
+            "TM_DIRECTORY" => path?.parent()?.to_string_lossy().to_string(),
+            "TM_FILEPATH" => path?.to_string_lossy().to_string(),
+            "RELATIVE_FILEPATH" => {
+                let path = path?;
+                self.workspace
+                    .as_deref()
+                    .and_then(|workspace| path.strip_prefix(workspace).ok())
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .to_string()
+            }
+            "WORKSPACE_NAME" => file_name(self.workspace.as_deref()?.file_name()?),
+            "WORKSPACE_FOLDER" => {
+                self.workspace.as_deref()?.to_string_lossy().to_string()
+            }
+            "TM_LINE_INDEX" => self.line_index.to_string(),
+            "TM_LINE_NUMBER" => (self.line_index + 1).to_string(),
+            "TM_CURRENT_LINE" => self.current_line.clone(),
+            "TM_CURRENT_WORD" => self.current_word.clone(),
+            "TM_SELECTED_TEXT" => self.selected_text.clone(),
+            "CLIPBOARD" => self.clipboard.clone()?,
+            "CURRENT_YEAR" => now.format("%Y").to_string(),
+            "CURRENT_YEAR_SHORT" => now.format("%y").to_string(),
+            "CURRENT_MONTH" => now.format("%m").to_string(),
+            "CURRENT_MONTH_NAME" => now.format("%B").to_string(),
+            "CURRENT_MONTH_NAME_SHORT" => now.format("%b").to_string(),
+            "CURRENT_DATE" => now.format("%d").to_string(),
+            "CURRENT_DAY_NAME" => now.format("%A").to_string(),
+            "CURRENT_DAY_NAME_SHORT" => now.format("%a").to_string(),
+            "CURRENT_HOUR" => now.format("%H").to_string(),
+            "CURRENT_MINUTE" => now.format("%M").to_string(),
+            "CURRENT_SECOND" => now.format("%S").to_string(),
+            "CURRENT_SECONDS_UNIX" => now.timestamp().to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+fn file_name(name: &std::ffi::OsStr) -> String {
+    name.to_string_lossy().to_string()
+}
+
+/// A snippet from a user or workspace snippet file
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserSnippet {
+    pub name: String,
+    pub prefixes: Vec<String>,
+    pub body: String,
+    pub description: Option<String>,
+    /// The languages the snippet is offered in, or every language if `None`
+    pub languages: Option<Vec<LapceLanguage>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrMany::One(s) => vec![s],
+            OneOrMany::Many(v) => v,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SnippetDefinition {
+    prefix: Option<OneOrMany>,
+    body: OneOrMany,
+    description: Option<String>,
+    /// The comma separated languages of the snippet, in a file that isn't
+    /// for a single language
+    scope: Option<String>,
+}
+
+impl UserSnippet {
+    /// Parse a snippet file, which maps the snippet names to their definitions.
+    /// The snippets are for `language` if the file is named after one.
+    pub fn parse_file(
+        content: &str,
+        language: Option<LapceLanguage>,
+    ) -> anyhow::Result<Vec<UserSnippet>> {
+        let definitions: IndexMap<String, SnippetDefinition> =
+            serde_json::from_str(content)?;
+        Ok(definitions
+            .into_iter()
+            .map(|(name, definition)| {
+                let languages = match language {
+                    Some(language) => Some(vec![language]),
+                    None => definition.scope.as_deref().map(|scope| {
+                        scope
+                            .split(',')
+                            .filter_map(|s| LapceLanguage::from_name(s.trim()))
+                            .collect()
+                    }),
+                };
+                UserSnippet {
+                    prefixes: definition
+                        .prefix
+                        .map(OneOrMany::into_vec)
+                        .unwrap_or_else(|| vec![name.clone()]),
+                    body: definition.body.into_vec().join("\n"),
+                    description: definition.description,
+                    languages,
+                    name,
+                }
+            })
+            .collect())
+    }
+
+    pub fn applies_to(&self, language: LapceLanguage) -> bool {
+        self.languages
+            .as_ref()
+            .map(|languages| languages.contains(&language))
+            .unwrap_or(true)
+    }
+
+    /// The body with the lines after the first indented by `indent`, and the
+    /// variables resolved.
+    pub fn resolve(&self, indent: &str, variables: &SnippetVariables) -> String {
+        resolve_body(&self.body, indent, variables)
+    }
+}
+
+/// A snippet body with the lines after the first indented by `indent`, and
+/// the variables resolved.
+pub fn resolve_body(
+    body: &str,
+    indent: &str,
+    variables: &SnippetVariables,
+) -> String {
+    let body = body.replace('\n', &format!("\n{indent}"));
+    resolve_variables(&body, &|name| variables.get(name))
+}
+
+/// The folders user snippets are loaded from: the user's snippets folder, and
+/// the `.lapce/snippets` folder of each root of a local workspace.
+pub fn user_snippets_dirs(
+    workspace: &LapceWorkspace,
+    folders: &[PathBuf],
+) -> Vec<PathBuf> {
+    let workspace_dirs: Vec<PathBuf> = match workspace.kind {
+        LapceWorkspaceType::Local => workspace
+            .path
+            .iter()
+            .chain(folders)
+            .map(|root| root.join(".lapce").join("snippets"))
+            .collect(),
+        _ => Vec::new(),
+    };
+    Directory::snippets_directory()
+        .into_iter()
+        .chain(workspace_dirs)
+        .collect()
+}
+
+/// Load the snippets from `dirs` in the background, and set them on
+/// `snippets`. Files named after a language, like `rust.json`, hold the
+/// snippets of that language, and other `.json` or `.code-snippets` files can
+/// scope their snippets with `scope`.
+pub fn load_user_snippets(
+    scope: Scope,
+    dirs: Vec<PathBuf>,
+    snippets: RwSignal<Vec<UserSnippet>>,
+) {
+    let send = create_ext_action(scope, move |loaded| snippets.set(loaded));
+    let result = std::thread::Builder::new()
+        .name("LoadUserSnippets".to_owned())
+        .spawn(move || {
+            send(
+                dirs.iter()
+                    .flat_map(|dir| load_snippets_dir(dir))
+                    .collect::<Vec<_>>(),
+            );
+        });
+    if let Err(err) = result {
+        tracing::error!("{:?}", err);
+    }
+}
+
+fn load_snippets_dir(dir: &Path) -> Vec<UserSnippet> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().and_then(|e| e.to_str()).is_some_and(|e| {
+                e.eq_ignore_ascii_case("json") || e == "code-snippets"
+            })
+        })
+        .collect();
+    paths.sort();
+
+    let mut snippets = Vec::new();
+    for path in paths {
+        let language = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(LapceLanguage::from_name);
+        let result = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| UserSnippet::parse_file(&content, language));
+        match result {
+            Ok(file_snippets) => snippets.extend(file_snippets),
+            Err(err) => {
+                tracing::error!("{:?}", err.context(path.display().to_string()));
+            }
+        }
+    }
+    snippets
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -856,4 +1204,100 @@ fn test_extract_text() {
             Snippet::extract_text(s, end + 1, &['$', '{', '}', '\\'], &[])
         );
     }
+
+    #[test]
+    fn test_resolve_variables() {
+        let value = |name: &str| match name {
+            "TM_FILENAME" => Some("main.rs".to_string()),
+            "CURRENT_YEAR" => Some("2026".to_string()),
+            "TM_SELECTED_TEXT" => Some("a $b}".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(
+            resolve_variables("// $TM_FILENAME, ${CURRENT_YEAR}", &value),
+            "// main.rs, 2026"
+        );
+        assert_eq!(
+            resolve_variables("${1:$TM_FILENAME} $0", &value),
+            "${1:main.rs} $0"
+        );
+        assert_eq!(
+            resolve_variables("${UNKNOWN:${2:x}} ${MISSING} \\$HOME", &value),
+            "${2:x} MISSING \\$HOME"
+        );
+        assert_eq!(
+            resolve_variables("${TM_FILENAME/(.*)/${1:/upcase}/}", &value),
+            "main.rs"
+        );
+        assert_eq!(
+            resolve_variables("[$TM_SELECTED_TEXT]", &value),
+            "[a \\$b\\}]"
+        );
+
+        let snippet =
+            Snippet::from_str(&resolve_variables("${1:$TM_SELECTED_TEXT}", &value))
+                .unwrap();
+        assert_eq!(snippet.text(), "a $b}");
+    }
+
+    #[test]
+    fn test_parse_snippet_file() {
+        let content = r#"{
+            "Print": {
+                "prefix": ["pr", "print"],
+                "body": ["println!(\"$1\");", "$0"],
+                "description": "Print a line"
+            },
+            "Header": {
+                "body": "// Copyright $CURRENT_YEAR",
+                "scope": "rust, python, nolanguage"
+            },
+            "Anywhere": {
+                "prefix": "any",
+                "body": "any"
+            }
+        }"#;
+
+        let snippets = UserSnippet::parse_file(content, None).unwrap();
+        assert_eq!(snippets.len(), 3);
+        assert_eq!(snippets[0].name, "Print");
+        assert_eq!(snippets[0].prefixes, vec!["pr", "print"]);
+        assert_eq!(snippets[0].body, "println!(\"$1\");\n$0");
+        assert_eq!(snippets[0].description.as_deref(), Some("Print a line"));
+        assert_eq!(snippets[1].prefixes, vec!["Header"]);
+        assert_eq!(
+            snippets[1].languages,
+            Some(vec![LapceLanguage::Rust, LapceLanguage::Python])
+        );
+        assert!(snippets[2].applies_to(LapceLanguage::Go));
+        assert!(!snippets[1].applies_to(LapceLanguage::Go));
+
+        let snippets =
+            UserSnippet::parse_file(content, Some(LapceLanguage::Go)).unwrap();
+        assert!(snippets.iter().all(|s| s.applies_to(LapceLanguage::Go)));
+        assert!(!snippets[2].applies_to(LapceLanguage::Rust));
+
+        assert_eq!(
+            snippets[0].resolve("    ", &SnippetVariables::default()),
+            "println!(\"$1\");\n    $0"
+        );
+    }
+
+    #[test]
+    fn test_user_snippets_dirs() {
+        let workspace = LapceWorkspace {
+            path: Some(PathBuf::from("/project")),
+            ..Default::default()
+        };
+        let dirs = user_snippets_dirs(&workspace, &[PathBuf::from("/other")]);
+        // after the user's snippets folder
+        assert_eq!(
+            dirs[dirs.len() - 2..],
+            [
+                PathBuf::from("/project/.lapce/snippets"),
+                PathBuf::from("/other/.lapce/snippets"),
+            ]
+        );
+    }
 }
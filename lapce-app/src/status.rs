@@ -17,10 +17,11 @@
 use lsp_types::{DiagnosticSeverity, ProgressToken};
 
 use crate::{
-    app::clickable_icon,
+    app::{clickable_icon, tooltip_label},
     command::LapceWorkbenchCommand,
     config::{LapceConfig, color::LapceColor, icon::LapceIcons},
     editor::EditorData,
+    keypress::macros::MacroRecording,
     listener::Listener,
     palette::kind::PaletteKind,
     panel::{kind::PanelKind, position::PanelContainerPosition},
@@ -71,6 +72,7 @@ pub fn status(
     };
 
     let progresses = window_tab_data.progresses;
+    let macros = window_tab_data.common.keypress.get_untracked().macros;
     let mode = create_memo(move |_| window_tab_data.mode());
     let pointer_down = floem::reactive::create_rw_signal(false);
 
@@ -124,6 +126,26 @@ pub fn status(
                     .align_items(Some(AlignItems::Center))
                     .selectable(false)
             }),
+            label(move || {
+                macros.recording.with(|recording| match recording {
+                    Some(MacroRecording {
+                        register: Some(register),
+                        ..
+                    }) => format!("Recording @{register}"),
+                    _ => "Recording Macro".to_string(),
+                })
+            })
+            .style(move |s| {
+                s.display(if macros.recording.with(Option::is_some) {
+                    Display::Flex
+                } else {
+                    Display::None
+                })
+                .margin_left(10.0)
+                .height_pct(100.0)
+                .align_items(Some(AlignItems::Center))
+                .selectable(false)
+            }),
             stack((
                 svg(move || config.get().ui_svg(LapceIcons::SCM)).style(move |s| {
                     let config = config.get();
@@ -226,6 +248,7 @@ pub fn status(
                 })
             },
             progress_view(config, progresses),
+            volt_items_view(window_tab_data.clone()),
         ))
         .style(|s| {
             s.height_pct(100.0)
@@ -357,6 +380,18 @@ pub fn status(
                 palette_clone.run(PaletteKind::Line);
             });
             let palette_clone = palette.clone();
+            let encoding_info = status_text(config, editor, move || {
+                if let Some(editor) = editor.get() {
+                    let doc = editor.doc_signal().get();
+                    doc.encoding.get().to_string()
+                } else {
+                    String::new()
+                }
+            })
+            .on_click_stop(move |_| {
+                palette_clone.run(PaletteKind::ReopenWithEncoding);
+            });
+            let palette_clone = palette.clone();
             let line_ending_info = status_text(config, editor, move || {
                 if let Some(editor) = editor.get() {
                     let doc = editor.doc_signal().get();
@@ -380,7 +415,7 @@ pub fn status(
             .on_click_stop(move |_| {
                 palette_clone.run(PaletteKind::Language);
             });
-            (cursor_info, line_ending_info, language_info)
+            (cursor_info, encoding_info, line_ending_info, language_info)
         })
         .style(|s| {
             s.height_pct(100.0)
@@ -437,6 +472,62 @@ fn progress_view(
     .style(move |s| s.flex_row().height_pct(100.0).min_width(0.0))
 }
 
+/// The items volts added to the status bar, running the volt's command when
+/// clicked.
+fn volt_items_view(window_tab_data: Rc<WindowTabData>) -> impl View {
+    let config = window_tab_data.common.config;
+    let status_items = window_tab_data.plugin.status_items;
+    let common = window_tab_data.common.clone();
+    dyn_stack(
+        move || status_items.get(),
+        |(key, item)| (key.clone(), item.clone()),
+        move |((volt_id, _), item)| {
+            let clickable = item.command.is_some();
+            let command = item.command;
+            let common = common.clone();
+            let text = item.text.clone();
+            let view = label(move || text.clone())
+                .on_click_stop(move |_| {
+                    let Some(command) = command.as_ref() else {
+                        return;
+                    };
+                    // Only the volt's own commands can be run from its items
+                    let cmd = common
+                        .keypress
+                        .with_untracked(|k| k.volt_command(&volt_id, command));
+                    if let Some(cmd) = cmd {
+                        common.lapce_command.send(cmd);
+                    }
+                })
+                .style(move |s| {
+                    let config = config.get();
+                    s.height_pct(100.0)
+                        .min_width(0.0)
+                        .padding_horiz(10.0)
+                        .text_ellipsis()
+                        .selectable(false)
+                        .items_center()
+                        .color(config.color(LapceColor::STATUS_FOREGROUND))
+                        .apply_if(clickable, |s| {
+                            s.hover(|s| {
+                                s.cursor(CursorStyle::Pointer).background(
+                                    config
+                                        .color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                                )
+                            })
+                        })
+                });
+            match item.tooltip {
+                Some(tooltip) => {
+                    tooltip_label(config, view, move || tooltip.clone()).into_any()
+                }
+                None => view.into_any(),
+            }
+        },
+    )
+    .style(move |s| s.flex_row().height_pct(100.0).min_width(0.0))
+}
+
 fn status_text<S: std::fmt::Display + 'static>(
     config: ReadSignal<Arc<LapceConfig>>,
     editor: Memo<Option<EditorData>>,
@@ -1,4 +1,10 @@
-use std::{collections::HashMap, path::PathBuf, rc::Rc, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    rc::Rc,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use alacritty_terminal::{
     Term,
@@ -23,15 +29,19 @@
     dap_types::RunDebugConfig,
     terminal::{TermId, TerminalProfile},
 };
+use lsp_types::{Diagnostic, Position, Range};
 use parking_lot::RwLock;
 use url::Url;
 
 use super::{
     event::TermEvent,
+    link::{TERMINAL_PROBLEM_SOURCE, find_problems, resolve_path},
     raw::{EventProxy, RawTerminal},
 };
 use crate::{
-    command::{CommandExecuted, CommandKind, InternalCommand},
+    command::{
+        CommandExecuted, CommandKind, InternalCommand, LapceWorkbenchCommand,
+    },
     debug::{RunDebugMode, RunDebugProcess},
     keypress::{KeyPressFocus, condition::Condition},
     window_tab::CommonData,
@@ -49,6 +59,9 @@ pub struct TerminalData {
     pub visual_mode: RwSignal<VisualMode>,
     pub raw: RwSignal<Arc<RwLock<RawTerminal>>>,
     pub run_debug: RwSignal<Option<RunDebugProcess>>,
+    /// The profile of a terminal kept in the terminal server, to reattach to
+    /// it with
+    pub session: Option<TerminalProfile>,
     pub common: Rc<CommonData>,
 }
 
@@ -260,27 +273,10 @@ fn run_command(
                         },
                     );
                 }
-                FocusCommand::SearchForward => {
-                    // if let Some(search_string) = self.find.search_string.as_ref() {
-                    //     let mut raw = self.terminal.raw.lock();
-                    //     let term = &mut raw.term;
-                    //     self.terminal.search_next(
-                    //         term,
-                    //         search_string,
-                    //         Direction::Right,
-                    //     );
-                    // }
-                }
-                FocusCommand::SearchBackward => {
-                    // if let Some(search_string) = self.find.search_string.as_ref() {
-                    //     let mut raw = self.terminal.raw.lock();
-                    //     let term = &mut raw.term;
-                    //     self.terminal.search_next(
-                    //         term,
-                    //         search_string,
-                    //         Direction::Left,
-                    //     );
-                    // }
+                FocusCommand::Search => {
+                    self.common
+                        .workbench_command
+                        .send(LapceWorkbenchCommand::FindInTerminal);
                 }
                 _ => return CommandExecuted::No,
             },
@@ -331,6 +327,18 @@ pub fn new_run_debug(
 
         let launch_error = cx.create_rw_signal(None);
 
+        let mut profile = profile;
+        if run_debug.is_none()
+            && cfg!(unix)
+            && common.config.get_untracked().terminal.persistent_sessions
+        {
+            profile
+                .get_or_insert_with(TerminalProfile::default)
+                .session
+                .get_or_insert_with(|| new_session_name(term_id));
+        }
+        let session = profile.clone().filter(|profile| profile.session.is_some());
+
         let raw = Self::new_raw_terminal(
             &workspace,
             term_id,
@@ -352,6 +360,7 @@ pub fn new_run_debug(
             raw,
             title,
             run_debug,
+            session,
             mode,
             visual_mode,
             common,
@@ -413,8 +422,14 @@ fn new_raw_terminal(
 
             profile.command = Some(run_debug.program);
             profile.arguments = run_debug.args;
+        } else {
+            profile.shell_integration =
+                common.config.get_untracked().terminal.shell_integration;
         }
 
+        raw.write().workdir =
+            profile.workdir.as_ref().and_then(|w| w.to_file_path().ok());
+
         {
             let raw = raw.clone();
             if let Err(err) =
@@ -644,6 +659,98 @@ pub fn wheel_scroll(&self, delta: f64) {
         }
     }
 
+    /// Scroll the previous or the next prompt of the shell to the top of the
+    /// view.
+    pub fn jump_to_prompt(&self, next: bool) {
+        let raw = self.raw.get_untracked();
+        let mut raw = raw.write();
+        let raw = &mut *raw;
+        let display_offset = raw.term.grid().display_offset() as i32;
+        let top = -display_offset;
+        let integration = &raw.shell_integration;
+        let mut prompts = integration
+            .visible_commands()
+            .filter_map(|command| integration.grid_line(command.prompt_line))
+            .map(|line| line.0);
+        let target = if next {
+            prompts.find(|line| *line > top)
+        } else {
+            prompts.filter(|line| *line < top).last()
+        };
+        match target {
+            Some(line) => {
+                raw.term
+                    .scroll_display(Scroll::Delta(-line - display_offset));
+            }
+            None if next => raw.term.scroll_display(Scroll::Bottom),
+            None => {}
+        }
+        self.common.view_id.get_untracked().request_paint();
+    }
+
+    /// Copy the output of the command at the top of the view, or of the last
+    /// command when the view is at the bottom.
+    pub fn copy_command_output(&self) {
+        let raw = self.raw.get_untracked();
+        let raw = raw.read();
+        let integration = &raw.shell_integration;
+        let top = -(raw.term.grid().display_offset() as i32);
+        let command = if top == 0 {
+            integration
+                .visible_commands()
+                .filter(|command| command.output_line.is_some())
+                .last()
+        } else {
+            integration
+                .visible_commands()
+                .filter(|command| {
+                    integration
+                        .grid_line(command.prompt_line)
+                        .is_some_and(|line| line.0 <= top)
+                })
+                .last()
+        };
+        if let Some(output) =
+            command.and_then(|command| integration.output(command, &raw.term))
+        {
+            let mut clipboard = SystemClipboard::new();
+            clipboard.put_string(output);
+        }
+    }
+
+    /// Find the errors and warnings printed in the terminal, in files that
+    /// exist, on another thread as the whole scrollback is scanned.
+    pub fn problems(
+        &self,
+        send: impl FnOnce(Vec<(PathBuf, Diagnostic)>) + Send + 'static,
+    ) {
+        let raw = self.raw.get_untracked();
+        let workspace_path = self.workspace.path.clone();
+        std::thread::spawn(move || {
+            let raw = raw.read();
+            let dirs = raw.link_dirs(workspace_path.as_deref());
+            let problems = find_problems(&raw.text())
+                .into_iter()
+                .filter_map(|problem| {
+                    let path = resolve_path(&problem.path, &dirs)?;
+                    let position = Position::new(
+                        problem.line.saturating_sub(1),
+                        problem.column.saturating_sub(1),
+                    );
+                    let diagnostic = Diagnostic {
+                        range: Range::new(position, position),
+                        severity: Some(problem.severity),
+                        source: Some(TERMINAL_PROBLEM_SOURCE.to_string()),
+                        message: problem.message,
+                        ..Default::default()
+                    };
+                    Some((path, diagnostic))
+                })
+                .collect();
+            send(problems);
+        });
+    }
+
     fn toggle_visual(&self, visual_mode: VisualMode) {
         let config = self.common.config.get_untracked();
         if !config.core.modal {
@@ -738,7 +845,7 @@ pub fn new_process(&self, run_debug: Option<RunDebugProcess>) {
         self.run_debug.set(run_debug);
 
         let term_size = TermSize::new(width, height);
-        self.raw.get_untracked().write().term.resize(term_size);
+        self.raw.get_untracked().write().resize(term_size);
         self.common
             .proxy
             .terminal_resize(self.term_id, width, height);
@@ -759,6 +866,16 @@ pub fn stop(&self) {
     }
 }
 
+/// A name for a session in the terminal server that's unique across windows
+/// and restarts.
+fn new_session_name(term_id: TermId) -> String {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_nanos())
+        .unwrap_or_default();
+    format!("{}-{time:x}-{}", std::process::id(), term_id.0)
+}
+
 /// [`RunDebugConfig`] with expanded out program/arguments/etc. Used for creating the terminal.
 #[derive(Debug, Clone)]
 pub struct ExpandedRunDebug {
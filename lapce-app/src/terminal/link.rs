@@ -0,0 +1,256 @@
+use std::path::{Path, PathBuf};
+
+use lsp_types::DiagnosticSeverity;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// The source of the diagnostics found in the output of run terminals.
+pub const TERMINAL_PROBLEM_SOURCE: &str = "terminal";
+
+/// `File "main.py", line 10`, as in Python tracebacks
+static PYTHON_LOCATION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"File "(?P<path>[^"]+)", line (?P<line>\d+)"#).unwrap()
+});
+
+/// `src/main.rs:10:5`, as printed by rustc, gcc, clang and most other tools
+static LOCATION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?P<path>(?:[A-Za-z]:)?[\w.~/\\-]*[\w-]\.\w+):(?P<line>\d+)(?::(?P<column>\d+))?",
+    )
+    .unwrap()
+});
+
+static URL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"https?://[^\s"'<>`\])]+"#).unwrap());
+
+/// `error[E0425]: cannot find value`, the header of a rustc diagnostic
+static RUST_HEADER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?P<severity>error|warning)(?:\[\w+\])?: (?P<message>.+)$")
+        .unwrap()
+});
+
+/// `  --> src/main.rs:10:5`, the location of a rustc diagnostic
+static RUST_LOCATION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*--> (?P<path>.+):(?P<line>\d+):(?P<column>\d+)$").unwrap()
+});
+
+/// `main.c:10:5: error: expected ';'`, as printed by gcc and clang
+static GCC_PROBLEM: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(?P<path>[^\s:]+(?::\\[^\s:]+)?):(?P<line>\d+):(?P<column>\d+): (?:fatal )?(?P<severity>error|warning): (?P<message>.+)$",
+    )
+    .unwrap()
+});
+
+/// `ValueError: invalid literal`, the last line of a Python traceback
+static PYTHON_EXCEPTION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:[\w.]+\.)?\w*(?:Error|Exception|Interrupt|Exit)\b(?::.*)?$")
+        .unwrap()
+});
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TerminalLink {
+    /// A location in a file, with the line and column starting at 1
+    File {
+        path: String,
+        line: u32,
+        column: Option<u32>,
+    },
+    Url(String),
+}
+
+/// The link in a line of terminal output at the char `index`.
+pub fn link_at(text: &str, index: usize) -> Option<TerminalLink> {
+    let contains = |start: usize, end: usize| {
+        let char_start = text[..start].chars().count();
+        let char_end = char_start + text[start..end].chars().count();
+        (char_start..char_end).contains(&index)
+    };
+
+    if let Some(m) = URL.find_iter(text).find(|m| contains(m.start(), m.end())) {
+        return Some(TerminalLink::Url(m.as_str().to_string()));
+    }
+
+    for regex in [&*PYTHON_LOCATION, &*LOCATION] {
+        for captures in regex.captures_iter(text) {
+            let m = captures.get(0)?;
+            if !contains(m.start(), m.end()) {
+                continue;
+            }
+            return Some(TerminalLink::File {
+                path: captures["path"].to_string(),
+                line: captures["line"].parse().ok()?,
+                column: captures
+                    .name("column")
+                    .and_then(|column| column.as_str().parse().ok()),
+            });
+        }
+    }
+    None
+}
+
+/// Find a path printed in a terminal, relative to the first of `dirs` it
+/// exists in.
+pub fn resolve_path(path: &str, dirs: &[&Path]) -> Option<PathBuf> {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return path.exists().then(|| path.to_path_buf());
+    }
+    dirs.iter()
+        .map(|dir| dir.join(path))
+        .find(|path| path.exists())
+}
+
+/// An error or warning printed by a compiler or interpreter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TerminalProblem {
+    pub path: String,
+    /// Starting at 1
+    pub line: u32,
+    /// Starting at 1
+    pub column: u32,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+}
+
+/// Find the errors and warnings of rustc, gcc, clang and Python in terminal
+/// output.
+pub fn find_problems(text: &str) -> Vec<TerminalProblem> {
+    let mut problems = Vec::new();
+    let mut rust_header: Option<(DiagnosticSeverity, String)> = None;
+    let mut python_location: Option<(String, u32)> = None;
+
+    for line in text.lines() {
+        let line = line.trim_end();
+
+        if let Some(captures) = RUST_HEADER.captures(line) {
+            rust_header =
+                Some((severity(&captures["severity"]), captures["message"].into()));
+            continue;
+        }
+        if let Some(captures) = RUST_LOCATION.captures(line) {
+            if let Some((severity, message)) = rust_header.take() {
+                problems.push(TerminalProblem {
+                    path: captures["path"].to_string(),
+                    line: captures["line"].parse().unwrap_or(1),
+                    column: captures["column"].parse().unwrap_or(1),
+                    severity,
+                    message,
+                });
+            }
+            continue;
+        }
+        if let Some(captures) = GCC_PROBLEM.captures(line) {
+            problems.push(TerminalProblem {
+                path: captures["path"].to_string(),
+                line: captures["line"].parse().unwrap_or(1),
+                column: captures["column"].parse().unwrap_or(1),
+                severity: severity(&captures["severity"]),
+                message: captures["message"].to_string(),
+            });
+            continue;
+        }
+        if let Some(captures) = PYTHON_LOCATION.captures(line) {
+            // the innermost frame, which comes last, is where it was raised
+            python_location = Some((
+                captures["path"].to_string(),
+                captures["line"].parse().unwrap_or(1),
+            ));
+            continue;
+        }
+        if python_location.is_some() && PYTHON_EXCEPTION.is_match(line) {
+            if let Some((path, line_number)) = python_location.take() {
+                problems.push(TerminalProblem {
+                    path,
+                    line: line_number,
+                    column: 1,
+                    severity: DiagnosticSeverity::ERROR,
+                    message: line.to_string(),
+                });
+            }
+        }
+    }
+    problems
+}
+
+fn severity(severity: &str) -> DiagnosticSeverity {
+    if severity == "warning" {
+        DiagnosticSeverity::WARNING
+    } else {
+        DiagnosticSeverity::ERROR
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_at() {
+        let text = "  --> src/main.rs:10:5";
+        assert_eq!(link_at(text, 2), None);
+        assert_eq!(
+            link_at(text, 8),
+            Some(TerminalLink::File {
+                path: "src/main.rs".to_string(),
+                line: 10,
+                column: Some(5),
+            })
+        );
+
+        let text = r#"  File "/tmp/app.py", line 3, in <module>"#;
+        assert_eq!(
+            link_at(text, 5),
+            Some(TerminalLink::File {
+                path: "/tmp/app.py".to_string(),
+                line: 3,
+                column: None,
+            })
+        );
+
+        let text = "see https://lapce.dev/docs) for more";
+        assert_eq!(
+            link_at(text, 10),
+            Some(TerminalLink::Url("https://lapce.dev/docs".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_find_problems() {
+        let text = "\
+   Compiling app v0.1.0
+error[E0425]: cannot find value `x` in this scope
+ --> src/main.rs:2:5
+  |
+warning: unused variable: `y`
+  --> src/lib.rs:7:9
+error: could not compile `app`
+main.c:4:10: fatal error: foo.h: No such file or directory
+Traceback (most recent call last):
+  File \"app.py\", line 8, in <module>
+    main()
+  File \"lib/util.py\", line 2, in main
+    int('a')
+ValueError: invalid literal for int() with base 10: 'a'
+";
+        let problems = find_problems(text);
+        let locations: Vec<_> = problems
+            .iter()
+            .map(|p| (p.path.as_str(), p.line, p.column, p.severity))
+            .collect();
+        assert_eq!(
+            locations,
+            vec![
+                ("src/main.rs", 2, 5, DiagnosticSeverity::ERROR),
+                ("src/lib.rs", 7, 9, DiagnosticSeverity::WARNING),
+                ("main.c", 4, 10, DiagnosticSeverity::ERROR),
+                ("lib/util.py", 2, 1, DiagnosticSeverity::ERROR),
+            ]
+        );
+        assert_eq!(problems[0].message, "cannot find value `x` in this scope");
+        assert_eq!(
+            problems[3].message,
+            "ValueError: invalid literal for int() with base 10: 'a'"
+        );
+    }
+}
@@ -1,6 +1,9 @@
 pub mod data;
 pub mod event;
+pub mod link;
 pub mod panel;
 pub mod raw;
+pub mod search;
+pub mod shell_integration;
 pub mod tab;
 pub mod view;
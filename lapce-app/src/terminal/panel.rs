@@ -2,7 +2,9 @@
 
 use floem::{
     ext_event::create_ext_action,
-    reactive::{Memo, RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
+    reactive::{
+        Memo, RwSignal, Scope, SignalGet, SignalUpdate, SignalWith, use_context,
+    },
 };
 use lapce_core::mode::Mode;
 use lapce_rpc::{
@@ -13,8 +15,11 @@
     terminal::{TermId, TerminalProfile},
 };
 
-use super::{data::TerminalData, tab::TerminalTabData};
+use serde::{Deserialize, Serialize};
+
+use super::{data::TerminalData, search::TerminalSearchData, tab::TerminalTabData};
 use crate::{
+    db::LapceDb,
     debug::{
         DapData, DapVariable, RunDebugConfigs, RunDebugData, RunDebugMode,
         RunDebugProcess, ScopeOrVar,
@@ -27,6 +32,26 @@
     workspace::LapceWorkspace,
 };
 
+/// The terminals kept in the terminal server, by tab, to reattach to when the
+/// workspace is opened again.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TerminalSessionsInfo {
+    pub active: usize,
+    pub tabs: Vec<TerminalTabSessionsInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TerminalTabSessionsInfo {
+    pub active: usize,
+    pub terminals: Vec<TerminalSessionInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TerminalSessionInfo {
+    pub title: String,
+    pub profile: TerminalProfile,
+}
+
 pub struct TerminalTabInfo {
     pub active: usize,
     pub tabs: im::Vector<(RwSignal<usize>, TerminalTabData)>,
@@ -37,6 +62,7 @@ pub struct TerminalPanelData {
     pub cx: Scope,
     pub workspace: Arc<LapceWorkspace>,
     pub tab_info: RwSignal<TerminalTabInfo>,
+    pub search: TerminalSearchData,
     pub debug: RunDebugData,
     pub breakline: Memo<Option<(usize, PathBuf)>>,
     pub common: Rc<CommonData>,
@@ -47,18 +73,41 @@ impl TerminalPanelData {
     pub fn new(
         workspace: Arc<LapceWorkspace>,
         profile: Option<TerminalProfile>,
+        sessions: Option<TerminalSessionsInfo>,
         common: Rc<CommonData>,
         main_split: MainSplitData,
     ) -> Self {
-        let terminal_tab =
-            TerminalTabData::new(workspace.clone(), profile, common.clone());
-
         let cx = common.scope;
 
-        let tabs =
-            im::vector![(terminal_tab.scope.create_rw_signal(0), terminal_tab)];
-        let tab_info = TerminalTabInfo { active: 0, tabs };
+        let sessions = sessions
+            .filter(|_| common.config.get_untracked().terminal.persistent_sessions)
+            .unwrap_or_default();
+        let tabs: im::Vector<_> = sessions
+            .tabs
+            .iter()
+            .filter(|tab| !tab.terminals.is_empty())
+            .map(|tab| {
+                let terminal_tab = TerminalTabData::new_sessions(
+                    workspace.clone(),
+                    tab,
+                    common.clone(),
+                );
+                (terminal_tab.scope.create_rw_signal(0), terminal_tab)
+            })
+            .collect();
+        let tab_info = if tabs.is_empty() {
+            let terminal_tab =
+                TerminalTabData::new(workspace.clone(), profile, common.clone());
+            let tabs =
+                im::vector![(terminal_tab.scope.create_rw_signal(0), terminal_tab)];
+            TerminalTabInfo { active: 0, tabs }
+        } else {
+            let active = sessions.active.min(tabs.len() - 1);
+            TerminalTabInfo { active, tabs }
+        };
         let tab_info = cx.create_rw_signal(tab_info);
+        let search =
+            TerminalSearchData::new(cx, tab_info, &main_split, common.clone());
 
         let debug = RunDebugData::new(cx, common.breakpoints);
 
@@ -104,15 +153,73 @@ pub fn new(
             })
         };
 
-        Self {
+        let panel = Self {
             cx,
             workspace,
             tab_info,
+            search,
             debug,
             breakline,
             common,
             main_split,
+        };
+
+        {
+            let panel = panel.clone();
+            let db: Arc<LapceDb> = use_context().unwrap();
+            cx.create_effect(move |last: Option<TerminalSessionsInfo>| {
+                let sessions = panel.sessions_info();
+                let changed = match &last {
+                    Some(last) => last != &sessions,
+                    None => !sessions.tabs.is_empty(),
+                };
+                if changed {
+                    db.save_terminal_sessions(
+                        panel.workspace.clone(),
+                        sessions.clone(),
+                    );
+                }
+                sessions
+            });
         }
+
+        panel
+    }
+
+    /// The terminals of the panel that are kept in the terminal server.
+    pub fn sessions_info(&self) -> TerminalSessionsInfo {
+        self.tab_info.with(|info| {
+            let mut sessions = TerminalSessionsInfo::default();
+            for (index, (_, tab)) in info.tabs.iter().enumerate() {
+                let active = tab.active.get();
+                let mut tab_sessions = TerminalTabSessionsInfo {
+                    active: 0,
+                    terminals: Vec::new(),
+                };
+                tab.terminals.with(|terminals| {
+                    for (index, (_, terminal)) in terminals.iter().enumerate() {
+                        let Some(profile) = terminal.session.clone() else {
+                            continue;
+                        };
+                        if index == active {
+                            tab_sessions.active = tab_sessions.terminals.len();
+                        }
+                        tab_sessions.terminals.push(TerminalSessionInfo {
+                            title: terminal.title.get_untracked(),
+                            profile,
+                        });
+                    }
+                });
+                if tab_sessions.terminals.is_empty() {
+                    continue;
+                }
+                if index == info.active {
+                    sessions.active = sessions.tabs.len();
+                }
+                sessions.tabs.push(tab_sessions);
+            }
+            sessions
+        })
     }
 
     pub fn active_tab(&self, tracked: bool) -> Option<TerminalTabData> {
@@ -144,6 +251,11 @@ pub fn key_down<'a>(
             self.new_tab(None);
         }
 
+        if self.search.visible.get_untracked() && self.search.focused.get_untracked()
+        {
+            return Some(keypress.key_down(event, &self.search));
+        }
+
         let tab = self.active_tab(false);
         let terminal = tab.and_then(|tab| tab.active_terminal(false));
         if let Some(terminal) = terminal {
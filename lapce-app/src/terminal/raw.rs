@@ -1,4 +1,7 @@
-use std::sync::mpsc::Sender;
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+};
 
 use alacritty_terminal::{
     Term,
@@ -14,7 +17,10 @@
 };
 use lapce_rpc::{proxy::ProxyRpcHandler, terminal::TermId};
 
-use super::event::TermNotification;
+use super::{
+    event::TermNotification,
+    shell_integration::{SCROLLBACK_LINES, ShellIntegration},
+};
 
 pub struct EventProxy {
     term_id: TermId,
@@ -55,6 +61,9 @@ pub struct RawTerminal {
     pub parser: ansi::Processor,
     pub term: Term<EventProxy>,
     pub scroll_delta: f64,
+    pub shell_integration: ShellIntegration,
+    /// The directory the terminal was started in
+    pub workdir: Option<PathBuf>,
 }
 
 impl RawTerminal {
@@ -65,6 +74,7 @@ pub fn new(
     ) -> Self {
         let config = alacritty_terminal::term::Config {
             semantic_escape_chars: ",│`|\"' ()[]{}<>\t".to_string(),
+            scrolling_history: SCROLLBACK_LINES,
             ..Default::default()
         };
         let event_proxy = EventProxy {
@@ -81,15 +91,46 @@ pub fn new(
             parser,
             term,
             scroll_delta: 0.0,
+            shell_integration: ShellIntegration::default(),
+            workdir: None,
         }
     }
 
     pub fn update_content(&mut self, content: Vec<u8>) {
         for byte in content {
-            self.parser.advance(&mut self.term, byte);
+            self.shell_integration
+                .advance(&mut self.parser, &mut self.term, byte);
         }
     }
 
+    pub fn resize(&mut self, size: TermSize) {
+        self.shell_integration.resize(&mut self.term, size);
+    }
+
+    /// All the text of the terminal, including the scrollback.
+    pub fn text(&self) -> String {
+        self.term.bounds_to_string(
+            Point::new(self.term.topmost_line(), Column(0)),
+            Point::new(self.term.bottommost_line(), self.term.last_column()),
+        )
+    }
+
+    /// The directories the paths printed in the terminal are relative to,
+    /// the current one of the shell first.
+    pub fn link_dirs<'a>(
+        &'a self,
+        workspace_path: Option<&'a Path>,
+    ) -> Vec<&'a Path> {
+        self.shell_integration
+            .cwd
+            .as_deref()
+            .map(Path::new)
+            .into_iter()
+            .chain(self.workdir.as_deref())
+            .chain(workspace_path)
+            .collect()
+    }
+
     pub fn output(&self, line_num: usize) -> Vec<String> {
         let grid = self.term.grid();
         let mut lines = Vec::with_capacity(5);
@@ -130,6 +171,36 @@ pub fn output(&self, line_num: usize) -> Vec<String> {
     }
 }
 
+/// The text of the line `point` is in, including the rows it's wrapped over,
+/// and the char index of `point` in it.
+pub fn line_at<T>(term: &Term<T>, point: Point) -> (String, usize) {
+    let start = term.line_search_left(point);
+    let end = term.line_search_right(point);
+    let grid = term.grid();
+    let mut text = String::new();
+    let mut index = 0;
+    for line in start.line.0..=end.line.0 {
+        let row = &grid[Line(line)];
+        for column in 0..term.columns() {
+            let cell = &row[Column(column)];
+            if Point::new(Line(line), Column(column)) == point {
+                // the spacer of a wide char belongs to the char
+                index = text.chars().count().saturating_sub(
+                    cell.flags.contains(Flags::WIDE_CHAR_SPACER) as usize,
+                );
+            }
+            if cell.flags.intersects(
+                Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER,
+            ) {
+                continue;
+            }
+            text.push(cell.c);
+            text.extend(cell.zerowidth().into_iter().flatten());
+        }
+    }
+    (text, index)
+}
+
 pub fn visible_regex_match_iter<'a, EventProxy>(
     term: &'a Term<EventProxy>,
     regex: &'a mut RegexSearch,
@@ -147,3 +218,33 @@ pub fn visible_regex_match_iter<'a, EventProxy>(
 }
 /// todo:should be improved
 pub const MAX_SEARCH_LINES: usize = 100;
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{event::VoidListener, term::Config};
+
+    use super::*;
+
+    #[test]
+    fn test_line_at() {
+        let mut term =
+            Term::new(Config::default(), &TermSize::new(10, 5), VoidListener);
+        let mut parser = ansi::Processor::new();
+        for byte in "\u{4e2d}\u{6587} src/main.rs:1\r\nnext".bytes() {
+            parser.advance(&mut term, byte);
+        }
+
+        // the wide chars take two columns, and the line is wrapped
+        let (text, index) = line_at(&term, Point::new(Line(0), Column(9)));
+        assert_eq!(text.trim_end(), "\u{4e2d}\u{6587} src/main.rs:1");
+        assert_eq!(text.chars().nth(index), Some('m'));
+        let (_, index) = line_at(&term, Point::new(Line(1), Column(3)));
+        assert_eq!(text.chars().nth(index), Some('.'));
+        let (_, index) = line_at(&term, Point::new(Line(0), Column(1)));
+        assert_eq!(index, 0);
+
+        let (text, index) = line_at(&term, Point::new(Line(2), Column(1)));
+        assert_eq!(text.trim_end(), "next");
+        assert_eq!(index, 1);
+    }
+}
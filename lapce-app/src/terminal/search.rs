@@ -0,0 +1,365 @@
+use std::{
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use alacritty_terminal::{
+    Term,
+    grid::Dimensions,
+    index::{Boundary, Column, Direction, Point, Side},
+    selection::{Selection, SelectionType},
+    term::search::{Match, RegexSearch},
+};
+use floem::{
+    ext_event::create_ext_action,
+    keyboard::Modifiers,
+    reactive::{RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
+};
+use lapce_core::{command::FocusCommand, mode::Mode, selection};
+use lapce_xi_rope::Rope;
+
+use super::{data::TerminalData, panel::TerminalTabInfo};
+use crate::{
+    command::{CommandExecuted, CommandKind, LapceCommand},
+    editor::EditorData,
+    keypress::{KeyPressFocus, condition::Condition},
+    main_split::MainSplitData,
+    window_tab::CommonData,
+};
+
+/// Counting stops here, so a search for a common word stays fast.
+const MAX_MATCHES: usize = 10_000;
+
+/// The find bar of the terminal panel, searching the scrollback of the
+/// active terminal.
+#[derive(Clone)]
+pub struct TerminalSearchData {
+    pub visible: RwSignal<bool>,
+    /// Whether the find bar has the keyboard, rather than the terminal
+    pub focused: RwSignal<bool>,
+    pub editor: EditorData,
+    pub case_sensitive: RwSignal<bool>,
+    pub is_regex: RwSignal<bool>,
+    /// The focused match, if any, and the number of matches
+    pub position: RwSignal<(Option<usize>, usize)>,
+    /// Bumped with every search, so that the matches counted for an older
+    /// one are ignored
+    counting: Arc<AtomicU64>,
+    tab_info: RwSignal<TerminalTabInfo>,
+    scope: Scope,
+}
+
+impl KeyPressFocus for TerminalSearchData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::SearchFocus | Condition::PanelFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: Modifiers,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Focus(FocusCommand::SearchForward) => {
+                self.search(Direction::Right);
+            }
+            CommandKind::Focus(FocusCommand::SearchBackward) => {
+                self.search(Direction::Left);
+            }
+            CommandKind::Focus(FocusCommand::ClearSearch)
+            | CommandKind::Focus(FocusCommand::ModalClose) => {
+                self.close();
+            }
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                return self.editor.run_command(command, count, mods);
+            }
+            _ => return CommandExecuted::No,
+        }
+        CommandExecuted::Yes
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.editor.receive_char(c);
+    }
+}
+
+impl TerminalSearchData {
+    pub fn new(
+        cx: Scope,
+        tab_info: RwSignal<TerminalTabInfo>,
+        main_split: &MainSplitData,
+        common: Rc<CommonData>,
+    ) -> Self {
+        let search = Self {
+            visible: cx.create_rw_signal(false),
+            focused: cx.create_rw_signal(false),
+            editor: main_split.editors.make_local(cx, common),
+            case_sensitive: cx.create_rw_signal(false),
+            is_regex: cx.create_rw_signal(false),
+            position: cx.create_rw_signal((None, 0)),
+            counting: Arc::new(AtomicU64::new(0)),
+            tab_info,
+            scope: cx,
+        };
+
+        {
+            let search = search.clone();
+            let buffer = search.editor.doc().buffer;
+            cx.create_effect(move |_| {
+                buffer.track();
+                search.case_sensitive.track();
+                search.is_regex.track();
+                if !search.visible.get() {
+                    return;
+                }
+                // search again from the end of the output as the pattern changes
+                if let Some(terminal) = search.terminal() {
+                    terminal.raw.get_untracked().write().term.selection = None;
+                }
+                search.search(Direction::Left);
+            });
+        }
+
+        search
+    }
+
+    /// The terminal that's searched.
+    pub fn terminal(&self) -> Option<TerminalData> {
+        let tab = self.tab_info.with_untracked(|info| {
+            info.tabs
+                .get(info.active)
+                .or_else(|| info.tabs.last())
+                .map(|(_, tab)| tab.clone())
+        })?;
+        tab.active_terminal(false)
+    }
+
+    /// Show the find bar, searching for the selected text, if any.
+    pub fn open(&self) {
+        let selected = self.terminal().and_then(|terminal| {
+            let raw = terminal.raw.get_untracked();
+            let raw = raw.read();
+            raw.term
+                .selection_to_string()
+                .filter(|text| !text.is_empty() && !text.contains('\n'))
+        });
+        if let Some(text) = selected {
+            let len = text.len();
+            self.editor.doc().reload(Rope::from(text), true);
+            self.editor.cursor().update(|cursor| {
+                cursor.set_insert(selection::Selection::region(0, len))
+            });
+        }
+        self.visible.set(true);
+        self.focused.set(true);
+    }
+
+    pub fn close(&self) {
+        self.visible.set(false);
+        self.focused.set(false);
+        self.set_position((None, 0));
+    }
+
+    fn set_position(&self, position: (Option<usize>, usize)) {
+        self.counting.fetch_add(1, Ordering::Relaxed);
+        self.position.set(position);
+    }
+
+    /// The regex to search with, unless the pattern is empty or invalid.
+    pub fn regex(&self) -> Option<RegexSearch> {
+        let pattern = self.editor.doc().buffer.with_untracked(|b| b.to_string());
+        if pattern.is_empty() {
+            return None;
+        }
+        let pattern = if self.is_regex.get_untracked() {
+            pattern
+        } else {
+            regex::escape(&pattern)
+        };
+        let flags = if self.case_sensitive.get_untracked() {
+            "(?-i)"
+        } else {
+            "(?i)"
+        };
+        RegexSearch::new(&format!("{flags}{pattern}")).ok()
+    }
+
+    /// Select the next match in `direction` from the selected one, or from
+    /// the cursor, and scroll to it.
+    pub fn search(&self, direction: Direction) {
+        let Some(terminal) = self.terminal() else {
+            return;
+        };
+        let Some(mut regex) = self.regex() else {
+            self.set_position((None, 0));
+            return;
+        };
+        let raw = terminal.raw.get_untracked();
+        let mut raw = raw.write();
+        let term = &mut raw.term;
+
+        let selection = term.selection.as_ref().and_then(|s| s.to_range(term));
+        let (origin, side) = match (selection, direction) {
+            (Some(range), Direction::Right) => {
+                (range.end.add(term, Boundary::None, 1), Side::Left)
+            }
+            (Some(range), Direction::Left) => {
+                (range.start.sub(term, Boundary::None, 1), Side::Right)
+            }
+            (None, _) => (term.grid().cursor.point, Side::Left),
+        };
+        let Some(found) =
+            term.search_next(&mut regex, origin, direction, side, None)
+        else {
+            term.selection = None;
+            drop(raw);
+            self.set_position((None, 0));
+            terminal.common.view_id.get_untracked().request_paint();
+            return;
+        };
+
+        let mut selection =
+            Selection::new(SelectionType::Simple, *found.start(), Side::Left);
+        selection.update(*found.end(), Side::Right);
+        term.selection = Some(selection);
+        term.scroll_to_point(*found.start());
+        drop(raw);
+        terminal.common.view_id.get_untracked().request_paint();
+        self.count_matches(&terminal, regex, *found.start());
+    }
+
+    /// Count the matches on another thread, as the whole scrollback is
+    /// searched, and find the position of the one at `start` among them.
+    fn count_matches(
+        &self,
+        terminal: &TerminalData,
+        mut regex: RegexSearch,
+        start: Point,
+    ) {
+        let id = self.counting.fetch_add(1, Ordering::Relaxed) + 1;
+        let counting = self.counting.clone();
+        let position = self.position;
+        let send = {
+            let counting = counting.clone();
+            create_ext_action(self.scope, move |found| {
+                if counting.load(Ordering::Relaxed) == id {
+                    position.set(found);
+                }
+            })
+        };
+        let raw = terminal.raw.get_untracked();
+        std::thread::spawn(move || {
+            // a newer search is running already
+            if counting.load(Ordering::Relaxed) != id {
+                return;
+            }
+            let raw = raw.read();
+            let matches = all_matches(&raw.term, &mut regex);
+            let current = matches.iter().position(|m| *m.start() == start);
+            send((current, matches.len()));
+        });
+    }
+}
+
+/// The matches from the top of the scrollback to the bottom.
+pub fn all_matches<T>(term: &Term<T>, regex: &mut RegexSearch) -> Vec<Match> {
+    matches_between(
+        term,
+        regex,
+        Point::new(term.topmost_line(), Column(0)),
+        Point::new(term.bottommost_line(), term.last_column()),
+    )
+}
+
+/// The matches between two points of the grid.
+pub fn matches_between<T>(
+    term: &Term<T>,
+    regex: &mut RegexSearch,
+    mut start: Point,
+    end: Point,
+) -> Vec<Match> {
+    let mut matches = Vec::new();
+    while matches.len() < MAX_MATCHES && start <= end {
+        let Some(found) = term.regex_search_right(regex, start, end) else {
+            break;
+        };
+        start = found.end().add(term, Boundary::None, 1);
+        let wrapped = start <= *found.end();
+        matches.push(found);
+        if wrapped {
+            break;
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{
+        event::VoidListener,
+        index::Line,
+        term::{Config, test::TermSize},
+        vte::ansi::Processor,
+    };
+
+    use super::*;
+
+    fn term(text: &str) -> Term<VoidListener> {
+        let mut term =
+            Term::new(Config::default(), &TermSize::new(10, 3), VoidListener);
+        let mut parser = Processor::new();
+        for byte in text.bytes() {
+            parser.advance(&mut term, byte);
+        }
+        term
+    }
+
+    fn starts(matches: &[Match]) -> Vec<(i32, usize)> {
+        matches
+            .iter()
+            .map(|m| (m.start().line.0, m.start().column.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_between() {
+        // the first lines are in the scrollback
+        let term = term("foo bar\r\nbar\r\nfoo\r\nfoofoo foo\r\n");
+        let mut regex = RegexSearch::new("foo").unwrap();
+
+        let matches = all_matches(&term, &mut regex);
+        assert_eq!(
+            starts(&matches),
+            vec![(-2, 0), (0, 0), (1, 0), (1, 3), (1, 7)]
+        );
+        let last = matches.last().unwrap();
+        assert_eq!(*last.end(), Point::new(Line(1), Column(9)));
+
+        // only the lines in between
+        let matches = matches_between(
+            &term,
+            &mut regex,
+            Point::new(Line(-1), Column(0)),
+            Point::new(Line(0), Column(9)),
+        );
+        assert_eq!(starts(&matches), vec![(0, 0)]);
+
+        let mut regex = RegexSearch::new("(?i)BAR").unwrap();
+        assert_eq!(
+            starts(&all_matches(&term, &mut regex)),
+            vec![(-2, 4), (-1, 0)]
+        );
+        let mut regex = RegexSearch::new("baz").unwrap();
+        assert!(all_matches(&term, &mut regex).is_empty());
+    }
+}
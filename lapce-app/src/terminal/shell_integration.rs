@@ -0,0 +1,677 @@
+use alacritty_terminal::{
+    Term,
+    grid::Dimensions,
+    index::{Column, Line, Point},
+    term::{TermMode, cell::Flags},
+};
+
+/// The scrollback of the terminals, in lines.
+pub const SCROLLBACK_LINES: usize = 10_000;
+/// How many commands are remembered per terminal.
+const MAX_COMMANDS: usize = 1_000;
+/// Longer OSC sequences are cut, as no shell integration one needs more.
+const MAX_OSC_LEN: usize = 4096;
+
+/// A command run in a shell with the shell integration loaded.
+///
+/// Lines are counted from the top of the scrollback, including the lines
+/// that have been dropped from it since.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TerminalCommand {
+    pub prompt_line: usize,
+    /// The first line of the output, once the command runs
+    pub output_line: Option<usize>,
+    /// The line after the output, once the command finished
+    pub end_line: Option<usize>,
+    pub command: Option<String>,
+    pub exit_code: Option<i32>,
+}
+
+/// What an OSC 133 / OSC 633 sequence marks.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ShellMark {
+    PromptStart,
+    CommandStart,
+    OutputStart,
+    CommandEnd(Option<i32>),
+    CommandLine(String),
+    Cwd(String),
+}
+
+#[derive(Default)]
+enum OscState {
+    #[default]
+    Ground,
+    Escape,
+    Csi,
+    Osc,
+    OscEscape,
+}
+
+/// How a byte of the output may scroll the grid.
+#[derive(Debug, PartialEq, Eq)]
+enum Scroll {
+    /// A line feed, IND or NEL, which scrolls at the bottom of the screen
+    LineFeed,
+    /// The end of a printed character, which scrolls when it wraps at the
+    /// bottom of the screen
+    Char,
+    /// `CSI Ps S`, which scrolls the screen up
+    Lines(usize),
+}
+
+/// Tracks the commands in the output of a shell from the sequences the
+/// bundled shell integration scripts print around prompts and commands.
+#[derive(Default)]
+pub struct ShellIntegration {
+    state: OscState,
+    osc: Vec<u8>,
+    /// The parameters of the CSI sequence being scanned
+    csi: Vec<u8>,
+    /// The continuation bytes left of the UTF-8 character being scanned
+    utf8_remaining: u8,
+    pub commands: Vec<TerminalCommand>,
+    pub cwd: Option<String>,
+    history_size: usize,
+    dropped: usize,
+    /// Whether a shell integration mark has been seen
+    active: bool,
+}
+
+impl ShellIntegration {
+    /// Feed a byte of the pty output to the terminal, keeping track of the
+    /// shell integration marks in it.
+    pub fn advance<T: alacritty_terminal::event::EventListener>(
+        &mut self,
+        parser: &mut alacritty_terminal::vte::ansi::Processor,
+        term: &mut Term<T>,
+        byte: u8,
+    ) {
+        let alt_screen = term.mode().contains(TermMode::ALT_SCREEN);
+        let at_bottom = Self::cursor_at_bottom(term);
+        let column = term.grid().cursor.point.column;
+
+        parser.advance(term, byte);
+
+        let scroll = self.scan_scroll(byte);
+        if !term.mode().contains(TermMode::ALT_SCREEN) {
+            let history_size = term.grid().history_size();
+            if !alt_screen {
+                let scrolled = match scroll {
+                    Some(Scroll::LineFeed) if at_bottom => 1,
+                    // the character wrapped to a new line at the bottom
+                    Some(Scroll::Char)
+                        if at_bottom && term.grid().cursor.point.column < column =>
+                    {
+                        1
+                    }
+                    Some(Scroll::Lines(lines)) => lines,
+                    _ => 0,
+                };
+                let pushed = self.history_size + scrolled;
+                // the lines pushed out of a full scrollback are dropped
+                self.dropped += pushed.saturating_sub(SCROLLBACK_LINES);
+                let expected = pushed.min(SCROLLBACK_LINES);
+                if history_size < expected {
+                    // the scrollback was cleared
+                    self.dropped += expected - history_size;
+                }
+            }
+            self.history_size = history_size;
+        }
+
+        if let Some(mark) = self.scan(byte) {
+            self.mark(mark, term);
+        }
+    }
+
+    /// Resize the terminal, moving the lines of the commands along with the
+    /// reflow of the wrapped lines.
+    pub fn resize<T: alacritty_terminal::event::EventListener>(
+        &mut self,
+        term: &mut Term<T>,
+        size: impl Dimensions,
+    ) {
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            term.resize(size);
+            return;
+        }
+        let before = logical_lines(term);
+        term.resize(size);
+        let after = logical_lines(term);
+        self.history_size = term.grid().history_size();
+
+        let mut starts = Vec::new();
+        for (row, logical) in after.iter().enumerate() {
+            if *logical == starts.len() {
+                starts.push(row);
+            }
+        }
+        let count = |lines: &[usize]| lines.last().map_or(0, |l| l + 1);
+        // the lines pushed out of a full scrollback by the reflow
+        let lost = count(&before).saturating_sub(count(&after));
+        let dropped = self.dropped;
+        if lost > 0 {
+            self.dropped += before.len();
+        }
+        let base = self.dropped;
+        let remap = |line: &mut usize| {
+            let Some(row) = line.checked_sub(dropped) else {
+                return;
+            };
+            let row = match before.get(row) {
+                Some(logical) => match logical.checked_sub(lost) {
+                    Some(logical) => starts[logical],
+                    None => {
+                        *line = dropped;
+                        return;
+                    }
+                },
+                // below the cursor, so it stays as far from it
+                None => (row + after.len()).saturating_sub(before.len()),
+            };
+            *line = base + row;
+        };
+        for command in &mut self.commands {
+            remap(&mut command.prompt_line);
+            if let Some(line) = command.output_line.as_mut() {
+                remap(line);
+            }
+            if let Some(line) = command.end_line.as_mut() {
+                remap(line);
+            }
+        }
+    }
+
+    /// Whether the shell integration is loaded in the terminal.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn cursor_at_bottom<T>(term: &Term<T>) -> bool {
+        term.grid().cursor.point.line.0 + 1 >= term.screen_lines() as i32
+    }
+
+    /// How the byte may have scrolled the grid, from the sequence it ends.
+    ///
+    /// Called before [`Self::scan`], which moves on to the next state.
+    fn scan_scroll(&mut self, byte: u8) -> Option<Scroll> {
+        match self.state {
+            OscState::Ground | OscState::Csi if (0x0a..=0x0c).contains(&byte) => {
+                Some(Scroll::LineFeed)
+            }
+            OscState::Ground => match byte {
+                0x20..=0x7e => {
+                    self.utf8_remaining = 0;
+                    Some(Scroll::Char)
+                }
+                0xc0..=0xdf => {
+                    self.utf8_remaining = 1;
+                    None
+                }
+                0xe0..=0xef => {
+                    self.utf8_remaining = 2;
+                    None
+                }
+                0xf0..=0xf7 => {
+                    self.utf8_remaining = 3;
+                    None
+                }
+                0x80..=0xbf if self.utf8_remaining > 0 => {
+                    self.utf8_remaining -= 1;
+                    (self.utf8_remaining == 0).then_some(Scroll::Char)
+                }
+                _ => {
+                    self.utf8_remaining = 0;
+                    None
+                }
+            },
+            // IND and NEL
+            OscState::Escape if matches!(byte, b'D' | b'E') => {
+                Some(Scroll::LineFeed)
+            }
+            OscState::Csi if byte == b'S' => {
+                let params = std::str::from_utf8(&self.csi).ok()?;
+                if !params.bytes().all(|b| b.is_ascii_digit() || b == b';') {
+                    // a private sequence
+                    return None;
+                }
+                let lines = params
+                    .split(';')
+                    .next()
+                    .and_then(|lines| lines.parse().ok())
+                    .unwrap_or(0);
+                Some(Scroll::Lines(lines.clamp(1, SCROLLBACK_LINES)))
+            }
+            _ => None,
+        }
+    }
+
+    fn scan(&mut self, byte: u8) -> Option<ShellMark> {
+        match self.state {
+            OscState::Ground => {
+                if byte == 0x1b {
+                    self.state = OscState::Escape;
+                }
+            }
+            OscState::Escape => {
+                self.state = match byte {
+                    b']' => {
+                        self.osc.clear();
+                        OscState::Osc
+                    }
+                    b'[' => {
+                        self.csi.clear();
+                        OscState::Csi
+                    }
+                    0x1b => OscState::Escape,
+                    _ => OscState::Ground,
+                };
+            }
+            OscState::Csi => match byte {
+                0x1b => self.state = OscState::Escape,
+                0x18 | 0x1a => self.state = OscState::Ground,
+                // parameters and intermediates
+                0x20..=0x3f => {
+                    if self.csi.len() < MAX_OSC_LEN {
+                        self.csi.push(byte);
+                    }
+                }
+                // the final byte
+                0x40..=0x7e => self.state = OscState::Ground,
+                // control characters are executed within the sequence
+                _ => {}
+            },
+            OscState::Osc => match byte {
+                // BEL
+                0x07 => {
+                    self.state = OscState::Ground;
+                    return parse_mark(&self.osc);
+                }
+                0x1b => self.state = OscState::OscEscape,
+                // CAN and SUB cancel the sequence
+                0x18 | 0x1a => self.state = OscState::Ground,
+                _ => {
+                    if self.osc.len() < MAX_OSC_LEN {
+                        self.osc.push(byte);
+                    }
+                }
+            },
+            OscState::OscEscape => {
+                // ST
+                if byte == b'\\' {
+                    self.state = OscState::Ground;
+                    return parse_mark(&self.osc);
+                }
+                self.state = if byte == b']' {
+                    self.osc.clear();
+                    OscState::Osc
+                } else {
+                    OscState::Ground
+                };
+            }
+        }
+        None
+    }
+
+    fn mark<T>(&mut self, mark: ShellMark, term: &Term<T>) {
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            return;
+        }
+        if !matches!(mark, ShellMark::Cwd(_)) {
+            self.active = true;
+        }
+        let cursor = term.grid().cursor.point;
+        let line = self.line(cursor.line);
+        match mark {
+            ShellMark::PromptStart => {
+                // a prompt drawn again, e.g. after ctrl+c, replaces the one
+                // nothing was run from
+                if self
+                    .commands
+                    .last()
+                    .is_some_and(|command| command.output_line.is_none())
+                {
+                    self.commands.pop();
+                }
+                self.commands.push(TerminalCommand {
+                    prompt_line: line,
+                    ..Default::default()
+                });
+                if self.commands.len() > MAX_COMMANDS {
+                    self.commands.remove(0);
+                }
+            }
+            ShellMark::CommandStart => {}
+            ShellMark::OutputStart => {
+                if let Some(command) = self.commands.last_mut() {
+                    if command.output_line.is_none() {
+                        command.output_line = Some(line);
+                    }
+                }
+            }
+            ShellMark::CommandEnd(exit_code) => {
+                if let Some(command) = self.commands.last_mut() {
+                    if command.output_line.is_some() && command.end_line.is_none() {
+                        let end_line =
+                            if cursor.column.0 > 0 { line + 1 } else { line };
+                        command.end_line = Some(end_line);
+                        command.exit_code = exit_code;
+                    }
+                }
+            }
+            ShellMark::CommandLine(text) => {
+                if let Some(command) = self.commands.last_mut() {
+                    command.command = Some(text);
+                }
+            }
+            ShellMark::Cwd(cwd) => {
+                self.cwd = Some(cwd);
+            }
+        }
+    }
+
+    /// The line of a command for a line of the grid.
+    pub fn line(&self, line: Line) -> usize {
+        (self.history_size as i32 + line.0).max(0) as usize + self.dropped
+    }
+
+    /// The line of the grid for a line of a command, if it's still in the
+    /// scrollback.
+    pub fn grid_line(&self, line: usize) -> Option<Line> {
+        let line = line.checked_sub(self.dropped)?;
+        Some(Line(line as i32 - self.history_size as i32))
+    }
+
+    /// The commands still in the scrollback.
+    pub fn visible_commands(&self) -> impl Iterator<Item = &TerminalCommand> {
+        self.commands
+            .iter()
+            .filter(|command| command.prompt_line >= self.dropped)
+    }
+
+    /// The output of a command, as far as it's still in the scrollback.
+    pub fn output<T>(
+        &self,
+        command: &TerminalCommand,
+        term: &Term<T>,
+    ) -> Option<String> {
+        let start = self.grid_line(command.output_line?.max(self.dropped))?;
+        let end = match command.end_line {
+            Some(end_line) => self.grid_line(end_line)?,
+            None => term.grid().cursor.point.line + 1,
+        };
+        if end <= start {
+            return Some(String::new());
+        }
+        let text = term.bounds_to_string(
+            Point::new(start, Column(0)),
+            Point::new(end - 1, term.last_column()),
+        );
+        Some(text.trim_end().to_string())
+    }
+}
+
+/// The index of the line each row of the grid up to the cursor belongs to,
+/// as rows wrapped to the next one belong to the same line.
+fn logical_lines<T>(term: &Term<T>) -> Vec<usize> {
+    let grid = term.grid();
+    let last_column = term.last_column();
+    let mut logical = 0;
+    (grid.topmost_line().0..=grid.cursor.point.line.0)
+        .map(|line| {
+            let index = logical;
+            if !grid[Line(line)][last_column]
+                .flags
+                .contains(Flags::WRAPLINE)
+            {
+                logical += 1;
+            }
+            index
+        })
+        .collect()
+}
+
+fn parse_mark(osc: &[u8]) -> Option<ShellMark> {
+    let osc = std::str::from_utf8(osc).ok()?;
+    let rest = osc
+        .strip_prefix("133;")
+        .or_else(|| osc.strip_prefix("633;"))?;
+    let (kind, arg) = match rest.split_once(';') {
+        Some((kind, arg)) => (kind, Some(arg)),
+        None => (rest, None),
+    };
+    let mark = match kind {
+        "A" => ShellMark::PromptStart,
+        "B" => ShellMark::CommandStart,
+        "C" => ShellMark::OutputStart,
+        "D" => ShellMark::CommandEnd(
+            arg.and_then(|arg| arg.split(';').next()?.parse().ok()),
+        ),
+        // a nonce may follow the escaped command line
+        "E" => ShellMark::CommandLine(unescape(
+            arg.unwrap_or_default()
+                .split(';')
+                .next()
+                .unwrap_or_default(),
+        )),
+        "P" => ShellMark::Cwd(unescape(arg?.strip_prefix("Cwd=")?)),
+        _ => return None,
+    };
+    Some(mark)
+}
+
+/// Undo the escaping of `\` as `\\` and of other characters as `\xHH`.
+fn unescape(s: &str) -> String {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        match rest {
+            [b'\\', tail @ ..] => {
+                bytes.push(b'\\');
+                rest = tail;
+            }
+            [b'x', hi, lo, tail @ ..] => {
+                match u8::from_str_radix(
+                    &format!("{}{}", *hi as char, *lo as char),
+                    16,
+                ) {
+                    Ok(value) => {
+                        bytes.push(value);
+                        rest = tail;
+                    }
+                    Err(_) => bytes.push(b'\\'),
+                }
+            }
+            _ => bytes.push(b'\\'),
+        }
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{
+        event::VoidListener,
+        term::{Config, test::TermSize},
+        vte::ansi::Processor,
+    };
+
+    use super::*;
+
+    struct Shell {
+        integration: ShellIntegration,
+        parser: Processor,
+        term: Term<VoidListener>,
+    }
+
+    impl Shell {
+        fn new(columns: usize, lines: usize) -> Self {
+            let config = Config {
+                scrolling_history: SCROLLBACK_LINES,
+                ..Default::default()
+            };
+            Self {
+                integration: ShellIntegration::default(),
+                parser: Processor::new(),
+                term: Term::new(
+                    config,
+                    &TermSize::new(columns, lines),
+                    VoidListener,
+                ),
+            }
+        }
+
+        fn feed(&mut self, bytes: &[u8]) {
+            for byte in bytes {
+                self.integration
+                    .advance(&mut self.parser, &mut self.term, *byte);
+            }
+        }
+
+        /// Print a prompt, and the text of its line in the grid.
+        fn prompt(&mut self) -> String {
+            self.feed(b"\x1b]133;A\x07$ \x1b]133;B\x07");
+            let command = self.integration.commands.last().unwrap();
+            let line = self.integration.grid_line(command.prompt_line).unwrap();
+            self.term
+                .bounds_to_string(
+                    Point::new(line, Column(0)),
+                    Point::new(line, self.term.last_column()),
+                )
+                .trim_end()
+                .to_string()
+        }
+    }
+
+    #[test]
+    fn test_track_command() {
+        let mut shell = Shell::new(20, 5);
+        assert_eq!(shell.prompt(), "$");
+        assert!(shell.integration.is_active());
+        shell.feed(b"ls\r\n\x1b]133;C\x07");
+        for i in 0..10 {
+            shell.feed(format!("file{i}\r\n").as_bytes());
+        }
+        shell.feed(b"\x1b]133;D;1\x07");
+        let command = &shell.integration.commands[0];
+        assert_eq!(command.prompt_line, 0);
+        assert_eq!(command.output_line, Some(1));
+        assert_eq!(command.end_line, Some(11));
+        assert_eq!(command.exit_code, Some(1));
+        let output = shell.integration.output(command, &shell.term).unwrap();
+        assert!(output.starts_with("file0\n"));
+        assert!(output.ends_with("file9"));
+    }
+
+    #[test]
+    fn test_track_scrolls() {
+        let mut shell = Shell::new(20, 5);
+        for _ in 0..10 {
+            // lines wrapped at the bottom of the screen
+            shell.feed(&[b'x'; 50]);
+            shell.feed(b"\r\n");
+            assert_eq!(shell.prompt(), "$");
+            // IND and NEL
+            shell.feed(b"\r\x1bD\x1bE");
+            assert_eq!(shell.prompt(), "$");
+            // scroll up
+            shell.feed(b"\x1b[3S\x1b[S\r");
+            assert_eq!(shell.prompt(), "$");
+            // wide characters wrapped early
+            shell.feed("ü\u{4e2d}".repeat(10).as_bytes());
+            shell.feed(b"\r\n");
+            assert_eq!(shell.prompt(), "$");
+        }
+    }
+
+    #[test]
+    fn test_track_full_scrollback() {
+        let mut shell = Shell::new(20, 5);
+        shell.prompt();
+        for _ in 0..SCROLLBACK_LINES + 10 {
+            shell.feed(b"line\r\n");
+        }
+        assert_eq!(shell.integration.visible_commands().count(), 0);
+        for _ in 0..10 {
+            shell.feed(&[b'x'; 50]);
+            shell.feed(b"\r\n");
+            assert_eq!(shell.prompt(), "$");
+            shell.feed(b"\r\n\x1b[2S\r");
+            assert_eq!(shell.prompt(), "$");
+        }
+        assert_eq!(shell.integration.history_size, SCROLLBACK_LINES);
+    }
+
+    #[test]
+    fn test_track_clear_and_resize() {
+        let mut shell = Shell::new(20, 5);
+        for _ in 0..20 {
+            shell.feed(&[b'x'; 50]);
+            shell.feed(b"\r\n");
+        }
+        assert_eq!(shell.prompt(), "$");
+        let dropped = shell.integration.dropped;
+        // the reflow of the longer lines shrinks the scrollback
+        shell
+            .integration
+            .resize(&mut shell.term, TermSize::new(60, 5));
+        assert_eq!(shell.integration.dropped, dropped);
+        assert_eq!(shell.integration.visible_commands().count(), 1);
+        assert_eq!(shell.prompt(), "$");
+        shell
+            .integration
+            .resize(&mut shell.term, TermSize::new(15, 5));
+        assert_eq!(shell.prompt(), "$");
+
+        // clear the screen and the scrollback
+        shell.feed(b"\x1b[H\x1b[2J\x1b[3J");
+        assert_eq!(shell.integration.visible_commands().count(), 0);
+        assert_eq!(shell.prompt(), "$");
+    }
+
+    #[test]
+    fn test_scan_marks() {
+        let mut integration = ShellIntegration::default();
+        let marks = scan_all(
+            &mut integration,
+            b"\x1b]133;A\x07$ \x1b]133;B\x1b\\ls\r\n\x1b]633;E;ls -l\x07\x1b]133;C\x07out\r\n\x1b]133;D;2\x07",
+        );
+        assert_eq!(
+            marks,
+            vec![
+                ShellMark::PromptStart,
+                ShellMark::CommandStart,
+                ShellMark::CommandLine("ls -l".to_string()),
+                ShellMark::OutputStart,
+                ShellMark::CommandEnd(Some(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_ignores_other_sequences() {
+        let mut integration = ShellIntegration::default();
+        let marks = scan_all(
+            &mut integration,
+            b"\x1b]0;title\x07\x1b[31mred\x1b]8;;https://lapce.dev\x1b\\",
+        );
+        assert!(marks.is_empty());
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape(r"echo a\x3bb"), "echo a;b");
+        assert_eq!(unescape(r"a\\x3b"), r"a\x3b");
+        assert_eq!(unescape(r"line\x0anext"), "line\nnext");
+        assert_eq!(
+            parse_mark(br"633;P;Cwd=/home/a\x3bb"),
+            Some(ShellMark::Cwd("/home/a;b".to_string()))
+        );
+    }
+}
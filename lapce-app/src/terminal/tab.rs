@@ -1,9 +1,9 @@
 use std::{rc::Rc, sync::Arc};
 
-use floem::reactive::{RwSignal, Scope, SignalGet, SignalWith};
+use floem::reactive::{RwSignal, Scope, SignalGet, SignalUpdate, SignalWith};
 use lapce_rpc::terminal::TerminalProfile;
 
-use super::data::TerminalData;
+use super::{data::TerminalData, panel::TerminalTabSessionsInfo};
 use crate::{
     debug::RunDebugProcess, id::TerminalTabId, window_tab::CommonData,
     workspace::LapceWorkspace,
@@ -48,6 +48,39 @@ pub fn new_run_debug(
         }
     }
 
+    /// Reattach to the terminals of a tab kept in the terminal server.
+    pub fn new_sessions(
+        workspace: Arc<LapceWorkspace>,
+        info: &TerminalTabSessionsInfo,
+        common: Rc<CommonData>,
+    ) -> Self {
+        let cx = common.scope.create_child();
+        let terminals: im::Vector<_> = info
+            .terminals
+            .iter()
+            .map(|session| {
+                let terminal_data = TerminalData::new(
+                    cx,
+                    workspace.clone(),
+                    Some(session.profile.clone()),
+                    common.clone(),
+                );
+                terminal_data.title.set(session.title.clone());
+                (cx.create_rw_signal(0), terminal_data)
+            })
+            .collect();
+        let active = info.active.min(terminals.len().saturating_sub(1));
+        let terminals = cx.create_rw_signal(terminals);
+        let active = cx.create_rw_signal(active);
+        let terminal_tab_id = TerminalTabId::next();
+        Self {
+            scope: cx,
+            terminal_tab_id,
+            active,
+            terminals,
+        }
+    }
+
     pub fn active_terminal(&self, tracked: bool) -> Option<TerminalData> {
         let active = if tracked {
             self.active.get()
@@ -2,7 +2,7 @@
 
 use alacritty_terminal::{
     grid::Dimensions,
-    index::Side,
+    index::{Column, Line, Point as TermPoint, Side},
     selection::{Selection, SelectionType},
     term::{RenderableContent, cell::Flags, test::TermSize},
 };
@@ -13,7 +13,7 @@
     kurbo::Stroke,
     peniko::{
         Color,
-        kurbo::{Point, Rect, Size},
+        kurbo::{Circle, Point, Rect, Size},
     },
     pointer::PointerInputEvent,
     prelude::SignalTrack,
@@ -25,10 +25,14 @@
 use lapce_rpc::{proxy::ProxyRpcHandler, terminal::TermId};
 use lsp_types::Position;
 use parking_lot::RwLock;
-use regex::Regex;
 use unicode_width::UnicodeWidthChar;
 
-use super::{panel::TerminalPanelData, raw::RawTerminal};
+use super::{
+    link::{TerminalLink, link_at, resolve_path},
+    panel::TerminalPanelData,
+    raw::{RawTerminal, line_at},
+    search::{TerminalSearchData, matches_between},
+};
 use crate::{
     command::InternalCommand,
     config::{LapceConfig, color::LapceColor},
@@ -63,6 +67,9 @@ pub struct TerminalView {
     raw: Arc<RwLock<RawTerminal>>,
     mode: ReadSignal<Mode>,
     size: Size,
+    /// Whether the gutter is shown, which the size of the terminal leaves
+    /// room for
+    gutter: bool,
     is_focused: bool,
     config: ReadSignal<Arc<LapceConfig>>,
     run_config: ReadSignal<Option<RunDebugProcess>>,
@@ -70,7 +77,7 @@ pub struct TerminalView {
     launch_error: RwSignal<Option<String>>,
     internal_command: Listener<InternalCommand>,
     workspace: Arc<LapceWorkspace>,
-    hyper_regs: Vec<Regex>,
+    search: TerminalSearchData,
     previous_mouse_action: MouseAction,
     current_mouse_action: MouseAction,
 }
@@ -106,6 +113,19 @@ pub fn terminal_view(
 
     let proxy = terminal_panel_data.common.proxy.clone();
 
+    let search = terminal_panel_data.search.clone();
+    {
+        let search = search.clone();
+        create_effect(move |_| {
+            search.visible.track();
+            search.position.track();
+            search.case_sensitive.track();
+            search.is_regex.track();
+            search.editor.doc().buffer.track();
+            id.request_paint();
+        });
+    }
+
     create_effect(move |last| {
         let focus = terminal_panel_data.common.focus.get();
 
@@ -125,9 +145,6 @@ pub fn terminal_view(
         is_focused
     });
 
-    // for rust
-    let reg = regex::Regex::new("[\\w\\\\/-]+\\.(rs)?(toml)?:\\d+(:\\d+)?").unwrap();
-
     TerminalView {
         id,
         term_id,
@@ -137,11 +154,12 @@ pub fn terminal_view(
         proxy,
         run_config,
         size: Size::ZERO,
+        gutter: false,
         is_focused: false,
         launch_error,
         internal_command,
         workspace,
-        hyper_regs: vec![reg],
+        search,
         previous_mouse_action: Default::default(),
         current_mouse_action: Default::default(),
     }
@@ -165,52 +183,90 @@ fn terminal_size(&self) -> (usize, usize) {
         let config = self.config.get_untracked();
         let line_height = config.terminal_line_height() as f64;
         let char_width = self.char_size().width;
-        let width = (self.size.width / char_width).floor() as usize;
+        let width =
+            ((self.size.width - self.gutter_width()) / char_width).floor() as usize;
         let height = (self.size.height / line_height).floor() as usize;
         (width.max(1), height.max(1))
     }
 
+    /// Outline the matches of the find bar in the visible lines.
+    fn paint_search_matches(
+        &self,
+        cx: &mut PaintCx,
+        raw: &RawTerminal,
+        line_height: f64,
+        char_width: f64,
+        config: &LapceConfig,
+    ) {
+        if !self.search.visible.get_untracked()
+            || self.search.terminal().map(|t| t.term_id) != Some(self.term_id)
+        {
+            return;
+        }
+        let Some(mut regex) = self.search.regex() else {
+            return;
+        };
+        let term = &raw.term;
+        let display_offset = term.grid().display_offset() as i32;
+        let start = TermPoint::new(Line(-display_offset), Column(0));
+        let end = TermPoint::new(
+            Line(term.screen_lines() as i32 - 1 - display_offset),
+            term.last_column(),
+        );
+        let color = config.color(LapceColor::TERMINAL_FOREGROUND);
+        for found in matches_between(term, &mut regex, start, end) {
+            let (first, last) = (found.start(), found.end());
+            for line in first.line.0..=last.line.0 {
+                let x0 = if line == first.line.0 {
+                    first.column.0
+                } else {
+                    0
+                };
+                let x1 = if line == last.line.0 {
+                    last.column.0 + 1
+                } else {
+                    term.columns()
+                };
+                let y0 = (line + display_offset) as f64 * line_height;
+                let rect = Rect::new(
+                    x0 as f64 * char_width,
+                    y0,
+                    x1 as f64 * char_width,
+                    y0 + line_height,
+                );
+                cx.stroke(&rect, color, &Stroke::new(1.0));
+            }
+        }
+    }
+
     fn click(&self, pos: Point) -> Option<()> {
         let raw = self.raw.read();
         let position = self.get_terminal_point(pos);
-        let start_point = raw.term.semantic_search_left(position);
-        let end_point = raw.term.semantic_search_right(position);
-        let mut selection =
-            Selection::new(SelectionType::Simple, start_point, Side::Left);
-        selection.update(end_point, Side::Right);
-        selection.include_all();
-        if let Some(selection) = selection.to_range(&raw.term) {
-            let content = raw.term.bounds_to_string(selection.start, selection.end);
-            if let Some(match_str) =
-                self.hyper_regs.iter().find_map(|x| x.find(&content))
-            {
-                let hyperlink = match_str.as_str();
-                let content: Vec<&str> = hyperlink.split(':').collect();
-                let (file, line, col) = (
-                    content.first()?,
-                    content.get(1).and_then(|x: &&str| x.parse::<u32>().ok())?,
-                    content
-                        .get(2)
-                        .and_then(|x: &&str| x.parse::<u32>().ok())
-                        .unwrap_or(0),
-                );
-                let parent_path = self.workspace.path.as_ref()?;
+        // the whole line, as a path can be wrapped over several rows
+        let (content, index) = line_at(&raw.term, position);
+        match link_at(&content, index)? {
+            TerminalLink::Url(uri) => {
+                self.internal_command
+                    .send(InternalCommand::OpenWebUri { uri });
+            }
+            TerminalLink::File { path, line, column } => {
+                let dirs = raw.link_dirs(self.workspace.path.as_deref());
+                let path = resolve_path(&path, &dirs)?;
                 self.internal_command.send(InternalCommand::JumpToLocation {
                     location: EditorLocation {
-                        path: parent_path.join(file),
+                        path,
                         position: Some(EditorPosition::Position(Position::new(
                             line.saturating_sub(1),
-                            col.saturating_sub(1),
+                            column.unwrap_or(1).saturating_sub(1),
                         ))),
                         scroll_offset: None,
                         ignore_unconfirmed: false,
                         same_editor_tab: false,
                     },
                 });
-                return Some(());
             }
         }
-        None
+        Some(())
     }
 
     fn update_mouse_action_by_down(&mut self, mouse: &PointerInputEvent) {
@@ -313,7 +369,8 @@ fn update_mouse_action_by_up(&mut self, mouse: &PointerInputEvent) {
 
     fn get_terminal_point(&self, pos: Point) -> alacritty_terminal::index::Point {
         let raw = self.raw.read();
-        let col = (pos.x / self.char_size().width) as usize;
+        let col = ((pos.x - self.gutter_width()).max(0.0) / self.char_size().width)
+            as usize;
         let line_no = pos.y as i32
             / (self.config.get().terminal_line_height() as i32)
             - raw.term.grid().display_offset() as i32;
@@ -323,6 +380,89 @@ fn get_terminal_point(&self, pos: Point) -> alacritty_terminal::index::Point {
         )
     }
 
+    /// The width of the gutter with the exit codes of the commands, when the
+    /// shell integration is loaded.
+    fn gutter_width(&self) -> f64 {
+        if self.gutter {
+            self.char_size().width * 3.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Whether the gutter should be shown, once the shell integration
+    /// marked a command.
+    fn show_gutter(&self, raw: &RawTerminal) -> bool {
+        let is_shell = self.run_config.with_untracked(|r| r.is_none());
+        is_shell
+            && self.config.get_untracked().terminal.shell_integration
+            && raw.shell_integration.is_active()
+    }
+
+    fn paint_gutter(
+        &self,
+        cx: &mut PaintCx,
+        raw: &RawTerminal,
+        line_height: f64,
+        char_size: Size,
+        config: &LapceConfig,
+    ) {
+        let gutter_width = self.gutter_width();
+        if gutter_width == 0.0 {
+            return;
+        }
+
+        let font_family = config.terminal_font_family();
+        let family: Vec<FamilyOwned> =
+            FamilyOwned::parse_list(font_family).collect();
+        let attrs = Attrs::new()
+            .family(&family)
+            .font_size(config.terminal_font_size() as f32 * 0.8)
+            .color(config.color(LapceColor::LAPCE_ERROR));
+
+        let integration = &raw.shell_integration;
+        let display_offset = raw.term.grid().display_offset() as i32;
+        let screen_lines = raw.term.screen_lines() as i32;
+        for command in integration.visible_commands() {
+            let Some(exit_code) = command.exit_code else {
+                continue;
+            };
+            let Some(line) = integration.grid_line(command.prompt_line) else {
+                continue;
+            };
+            let row = line.0 + display_offset;
+            if row < 0 || row >= screen_lines {
+                continue;
+            }
+            let y = row as f64 * line_height;
+            if exit_code == 0 {
+                let center = Point::new(gutter_width / 2.0, y + line_height / 2.0);
+                let radius = char_size.width.min(line_height) / 4.0;
+                cx.fill(
+                    &Circle::new(center, radius),
+                    config.color(LapceColor::EDITOR_DIM),
+                    0.0,
+                );
+            } else {
+                let text = if (-99..=999).contains(&exit_code) {
+                    exit_code.to_string()
+                } else {
+                    "!".to_string()
+                };
+                let mut text_layout = TextLayout::new();
+                text_layout.set_text(&text, AttrsList::new(attrs.clone()));
+                let size = text_layout.size();
+                cx.draw_text(
+                    &text_layout,
+                    Point::new(
+                        (gutter_width - size.width) / 2.0,
+                        y + (line_height - size.height) / 2.0,
+                    ),
+                );
+            }
+        }
+    }
+
     fn paint_content(
         &self,
         cx: &mut PaintCx,
@@ -611,11 +751,13 @@ fn compute_layout(
         if size.is_zero_area() {
             return None;
         }
-        if size != self.size {
+        let gutter = self.show_gutter(&self.raw.read());
+        if size != self.size || gutter != self.gutter {
             self.size = size;
+            self.gutter = gutter;
             let (width, height) = self.terminal_size();
             let term_size = TermSize::new(width, height);
-            self.raw.write().term.resize(term_size);
+            self.raw.write().resize(term_size);
             self.proxy.terminal_resize(self.term_id, width, height);
         }
 
@@ -651,12 +793,17 @@ fn paint(&mut self, cx: &mut floem::context::PaintCx) {
         }
 
         let raw = self.raw.read();
+        if self.show_gutter(&raw) != self.gutter {
+            // the terminal is resized for the gutter first
+            self.id.request_layout();
+        }
+        self.paint_gutter(cx, &raw, line_height, char_size, &config);
+        cx.save();
+        cx.offset((self.gutter_width(), 0.0));
+
         let term = &raw.term;
         let content = term.renderable_content();
 
-        // let mut search = RegexSearch::new("[\\w\\\\?]+\\.rs:\\d+:\\d+").unwrap();
-        // self.hyper_matches = visible_regex_match_iter(term, &mut search).collect();
-
         if let Some(selection) = content.selection.as_ref() {
             let start_line = selection.start.line.0 + content.display_offset as i32;
             let start_line = if start_line < 0 {
@@ -703,63 +850,8 @@ fn paint(&mut self, cx: &mut floem::context::PaintCx) {
         }
 
         self.paint_content(cx, content, line_height, char_size, &config);
-        // if data.find.visual {
-        //     if let Some(search_string) = data.find.search_string.as_ref() {
-        //         if let Ok(dfas) = RegexSearch::new(&regex::escape(search_string)) {
-        //             let mut start = alacritty_terminal::index::Point::new(
-        //                 alacritty_terminal::index::Line(
-        //                     -(content.display_offset as i32),
-        //                 ),
-        //                 alacritty_terminal::index::Column(0),
-        //             );
-        //             let end_line = (start.line + term.screen_lines())
-        //                 .min(term.bottommost_line());
-        //             let mut max_lines = (end_line.0 - start.line.0) as usize;
-
-        //             while let Some(m) = term.search_next(
-        //                 &dfas,
-        //                 start,
-        //                 Direction::Right,
-        //                 Side::Left,
-        //                 Some(max_lines),
-        //             ) {
-        //                 let match_start = m.start();
-        //                 if match_start.line.0 < start.line.0
-        //                     || (match_start.line.0 == start.line.0
-        //                         && match_start.column.0 < start.column.0)
-        //                 {
-        //                     break;
-        //                 }
-        //                 let x = match_start.column.0 as f64 * char_width;
-        //                 let y = (match_start.line.0 as f64
-        //                     + content.display_offset as f64)
-        //                     * line_height;
-        //                 let rect = Rect::ZERO
-        //                     .with_origin(Point::new(x, y))
-        //                     .with_size(Size::new(
-        //                         (m.end().column.0 - m.start().column.0
-        //                             + term.grid()[*m.end()].c.width().unwrap_or(1))
-        //                             as f64
-        //                             * char_width,
-        //                         line_height,
-        //                     ));
-        //                 cx.stroke(
-        //                     &rect,
-        //                     config.get_color(LapceColor::TERMINAL_FOREGROUND),
-        //                     1.0,
-        //                 );
-        //                 start = *m.end();
-        //                 if start.column.0 < term.last_column() {
-        //                     start.column.0 += 1;
-        //                 } else if start.line.0 < term.bottommost_line() {
-        //                     start.column.0 = 0;
-        //                     start.line.0 += 1;
-        //                 }
-        //                 max_lines = (end_line.0 - start.line.0) as usize;
-        //             }
-        //         }
-        //     }
-        // }
+        self.paint_search_matches(cx, &raw, line_height, char_width, &config);
+        cx.restore();
     }
 }
 
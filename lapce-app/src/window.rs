@@ -353,7 +353,7 @@ pub fn info(&self) -> WindowInfo {
             .window_tabs
             .get_untracked()
             .iter()
-            .map(|(_, t)| (*t.workspace).clone())
+            .map(|(_, t)| t.current_workspace())
             .collect();
         WindowInfo {
             size: self.common.size.get_untracked(),
@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashSet},
     env,
     path::{Path, PathBuf},
     rc::Rc,
@@ -13,9 +13,9 @@
 use alacritty_terminal::vte::ansi::Handler;
 use floem::{
     ViewId,
-    action::{TimerToken, open_file, remove_overlay},
+    action::{TimerToken, open_file, remove_overlay, save_as},
     ext_event::{create_ext_action, create_signal_from_channel},
-    file::FileDialogOptions,
+    file::{FileDialogOptions, FileInfo},
     keyboard::Modifiers,
     kurbo::Size,
     peniko::kurbo::{Point, Rect, Vec2},
@@ -31,8 +31,13 @@
 use indexmap::IndexMap;
 use itertools::Itertools;
 use lapce_core::{
-    command::FocusCommand, cursor::CursorAffinity, directory::Directory, meta,
-    mode::Mode, register::Register,
+    command::FocusCommand,
+    cursor::{Cursor, CursorAffinity, CursorMode},
+    directory::Directory,
+    meta,
+    mode::Mode,
+    register::Register,
+    selection::Selection,
 };
 use lapce_rpc::{
     RpcError,
@@ -45,8 +50,8 @@
     terminal::TermId,
 };
 use lsp_types::{
-    CodeActionOrCommand, CodeLens, Diagnostic, ProgressParams, ProgressToken,
-    ShowMessageParams,
+    CodeActionOrCommand, CodeLens, Diagnostic, MessageType, ProgressParams,
+    ProgressToken, ShowMessageParams,
 };
 use serde_json::Value;
 use tracing::{Level, debug, error, event};
@@ -69,27 +74,36 @@
     file_explorer::data::FileExplorerData,
     find::Find,
     global_search::GlobalSearchData,
+    history::LocalHistory,
     hover::HoverData,
     id::WindowTabId,
     inline_completion::InlineCompletionData,
-    keypress::{EventRef, KeyPressData, KeyPressFocus, condition::Condition},
+    keypress::{
+        EventRef, KeyPressData, KeyPressFocus,
+        condition::Condition,
+        macros::{MacroStep, macro_to_toml},
+    },
     listener::Listener,
     lsp::path_from_url,
     main_split::{MainSplitData, SplitData, SplitDirection, SplitMoveDirection},
-    palette::{DEFAULT_RUN_TOML, PaletteData, PaletteStatus, kind::PaletteKind},
+    palette::{
+        DEFAULT_RUN_TOML, PaletteData, PaletteStatus, VoltPrompt, kind::PaletteKind,
+    },
     panel::{
         call_hierarchy_view::{CallHierarchyData, CallHierarchyItemData},
         data::{PanelData, PanelSection, default_panel_order},
         kind::PanelKind,
         position::PanelContainerPosition,
     },
-    plugin::PluginData,
+    plugin::{PluginData, RegisteredVoltCommand},
     proxy::{ProxyData, new_proxy},
     rename::RenameData,
+    snippet::{UserSnippet, load_user_snippets, user_snippets_dirs},
     source_control::SourceControlData,
     terminal::{
         event::{TermEvent, TermNotification, terminal_update_process},
         panel::TerminalPanelData,
+        shell_integration::ShellIntegration,
     },
     tracing::*,
     window::WindowCommonData,
@@ -129,6 +143,9 @@ pub struct WorkProgress {
 #[derive(Clone)]
 pub struct CommonData {
     pub workspace: Arc<LapceWorkspace>,
+    /// The additional root folders of the workspace, which can change while it
+    /// is open
+    pub workspace_folders: RwSignal<Vec<PathBuf>>,
     pub scope: Scope,
     pub focus: RwSignal<Focus>,
     pub keypress: RwSignal<KeyPressData>,
@@ -152,11 +169,26 @@ pub struct CommonData {
     pub proxy_status: RwSignal<Option<ProxyStatus>>,
     pub mouse_hover_timer: RwSignal<TimerToken>,
     pub breakpoints: RwSignal<BTreeMap<PathBuf, BTreeMap<usize, LapceBreakpoint>>>,
+    pub local_history: LocalHistory,
+    /// The snippets from the user's and the workspace's snippet files
+    pub snippets: RwSignal<Vec<UserSnippet>>,
     // the current focused view which will receive keyboard events
     pub keyboard_focus: RwSignal<Option<ViewId>>,
     pub window_common: Rc<WindowCommonData>,
 }
 
+impl CommonData {
+    /// The folders the user snippets are loaded from
+    pub fn snippets_dirs(&self) -> Vec<PathBuf> {
+        user_snippets_dirs(&self.workspace, &self.workspace_folders.get_untracked())
+    }
+
+    /// Load the user snippets again in the background.
+    pub fn reload_snippets(&self) {
+        load_user_snippets(self.scope, self.snippets_dirs(), self.snippets);
+    }
+}
+
 impl std::fmt::Debug for CommonData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("CommonData")
@@ -364,6 +396,7 @@ pub fn new(
 
         let common = Rc::new(CommonData {
             workspace: workspace.clone(),
+            workspace_folders: cx.create_rw_signal(workspace.folders.clone()),
             scope: cx,
             keypress,
             focus,
@@ -387,10 +420,24 @@ pub fn new(
             mouse_hover_timer: cx.create_rw_signal(TimerToken::INVALID),
             window_origin: cx.create_rw_signal(Point::ZERO),
             breakpoints: cx.create_rw_signal(BTreeMap::new()),
+            local_history: LocalHistory::new(cx),
+            snippets: cx.create_rw_signal(Vec::new()),
             keyboard_focus: cx.create_rw_signal(None),
             window_common: window_common.clone(),
         });
 
+        {
+            // The completion items of the snippets are built again once they
+            // reload.
+            let completion = common.completion;
+            let snippets = common.snippets;
+            cx.create_effect(move |_| {
+                snippets.track();
+                completion.update(|completion| completion.snippet_cache.clear());
+            });
+        }
+        common.reload_snippets();
+
         let main_split = MainSplitData::new(cx, common.clone());
         let code_action =
             cx.create_rw_signal(CodeActionData::new(cx, common.clone()));
@@ -422,6 +469,14 @@ pub fn new(
             });
         }
 
+        if workspace.path.is_some() {
+            if common.config.get_untracked().editor.hot_exit {
+                main_split
+                    .restore_backups(db.get_backups(&workspace).unwrap_or_default());
+            }
+            main_split.backup_periodically(workspace.clone(), db.clone(), None);
+        }
+
         let palette = PaletteData::new(
             cx,
             workspace.clone(),
@@ -487,6 +542,7 @@ pub fn new(
         let terminal = TerminalPanelData::new(
             workspace.clone(),
             common.config.get_untracked().terminal.get_default_profile(),
+            db.get_terminal_sessions(&workspace).ok(),
             common.clone(),
             main_split.clone(),
         );
@@ -660,6 +716,7 @@ pub fn reload_config(&self) {
             }
         }
         self.set_config.set(Arc::new(config.clone()));
+        self.common.reload_snippets();
         if !change_plugins.is_empty() {
             self.common
                 .proxy
@@ -740,16 +797,21 @@ pub fn run_workbench_command(
                     } else {
                         options
                     };
+                    let db: Arc<LapceDb> = use_context().unwrap();
                     open_file(options, move |file| {
                         if let Some(mut file) = file {
+                            let Some(path) = file.path.pop() else {
+                                tracing::error!("No path");
+                                return;
+                            };
+                            let folders = db.recent_workspace_folders(
+                                &LapceWorkspaceType::Local,
+                                &path,
+                            );
                             let workspace = LapceWorkspace {
                                 kind: LapceWorkspaceType::Local,
-                                path: Some(if let Some(path) = file.path.pop() {
-                                    path
-                                } else {
-                                    tracing::error!("No path");
-                                    return;
-                                }),
+                                path: Some(path),
+                                folders,
                                 last_open: std::time::SystemTime::now()
                                     .duration_since(std::time::UNIX_EPOCH)
                                     .unwrap()
@@ -767,11 +829,50 @@ pub fn run_workbench_command(
                     let workspace = LapceWorkspace {
                         kind: LapceWorkspaceType::Local,
                         path: None,
+                        folders: Vec::new(),
                         last_open: 0,
                     };
                     window_command.send(WindowCommand::SetWorkspace { workspace });
                 }
             }
+            AddFolderToWorkspace => {
+                if !self.workspace.kind.is_remote() {
+                    let window_command = self.common.window_common.window_command;
+                    let internal_command = self.common.internal_command;
+                    let has_workspace = self.workspace.path.is_some();
+                    let options = FileDialogOptions::new()
+                        .title("Choose a folder to add")
+                        .select_directories();
+                    let db: Arc<LapceDb> = use_context().unwrap();
+                    open_file(options, move |file| {
+                        let Some(path) = file.and_then(|mut file| file.path.pop())
+                        else {
+                            return;
+                        };
+                        if has_workspace {
+                            internal_command
+                                .send(InternalCommand::AddWorkspaceFolder { path });
+                        } else {
+                            // Without an open folder the new one becomes the workspace
+                            let folders = db.recent_workspace_folders(
+                                &LapceWorkspaceType::Local,
+                                &path,
+                            );
+                            let workspace = LapceWorkspace {
+                                kind: LapceWorkspaceType::Local,
+                                path: Some(path),
+                                folders,
+                                last_open: std::time::SystemTime::now()
+                                    .duration_since(std::time::UNIX_EPOCH)
+                                    .unwrap()
+                                    .as_secs(),
+                            };
+                            window_command
+                                .send(WindowCommand::SetWorkspace { workspace });
+                        }
+                    });
+                }
+            }
             OpenFile => {
                 if !self.workspace.kind.is_remote() {
                     let internal_command = self.common.internal_command;
@@ -793,6 +894,20 @@ pub fn run_workbench_command(
             NewFile => {
                 self.main_split.new_file();
             }
+            OpenInHexEditor => {
+                if let Some(editor_data) = self.main_split.active_editor.get() {
+                    if let DocContent::File { path, .. } =
+                        editor_data.doc().content.get_untracked()
+                    {
+                        self.main_split.open_hex_editor(path);
+                    }
+                }
+            }
+            OpenMarkdownPreviewToTheSide => {
+                if let Some(editor_data) = self.main_split.active_editor.get() {
+                    self.main_split.open_markdown_preview(&editor_data);
+                }
+            }
             RevealActiveFileInFileExplorer => {
                 if let Some(editor_data) = self.main_split.active_editor.get() {
                     let doc = editor_data.doc();
@@ -910,6 +1025,27 @@ pub fn run_workbench_command(
                     open_uri(&dir);
                 }
             }
+            InstallVoltFromFile | InstallVoltFromDirectory => {
+                if !self.workspace.kind.is_remote() {
+                    let plugin = self.plugin.clone();
+                    let options = if cmd == InstallVoltFromFile {
+                        FileDialogOptions::new().title("Choose a packaged plugin")
+                    } else {
+                        FileDialogOptions::new()
+                            .title("Choose a plugin folder")
+                            .select_directories()
+                    };
+                    open_file(options, move |file| {
+                        if let Some(path) = file.and_then(|mut file| file.path.pop())
+                        {
+                            plugin.install_volt_from_path(path);
+                        }
+                    });
+                }
+            }
+            UpdateAllVolts => {
+                self.plugin.update_all_volts();
+            }
             OpenGrammarsDirectory => {
                 if let Some(dir) = Directory::grammars_directory() {
                     open_uri(&dir);
@@ -1081,6 +1217,76 @@ pub fn run_workbench_command(
                 }
                 self.common.focus.set(Focus::Panel(PanelKind::Terminal));
             }
+            PreviousTerminalPrompt | NextTerminalPrompt => {
+                if let Some(terminal) = self
+                    .terminal
+                    .active_tab(false)
+                    .and_then(|tab| tab.active_terminal(false))
+                {
+                    terminal.jump_to_prompt(cmd == NextTerminalPrompt);
+                }
+            }
+            CopyTerminalCommandOutput => {
+                if let Some(terminal) = self
+                    .terminal
+                    .active_tab(false)
+                    .and_then(|tab| tab.active_terminal(false))
+                {
+                    terminal.copy_command_output();
+                }
+            }
+            FindInTerminal => {
+                if !self.panel.is_panel_visible(&PanelKind::Terminal) {
+                    self.panel.show_panel(&PanelKind::Terminal);
+                }
+                self.common.focus.set(Focus::Panel(PanelKind::Terminal));
+                self.terminal.search.open();
+            }
+            SaveTerminalScrollback => {
+                let Some(terminal) = self
+                    .terminal
+                    .active_tab(false)
+                    .and_then(|tab| tab.active_terminal(false))
+                else {
+                    return;
+                };
+                let text = terminal.raw.get_untracked().read().text();
+                let messages = self.messages;
+                save_as(
+                    FileDialogOptions::new().title("Save Terminal Scrollback"),
+                    move |file: Option<FileInfo>| {
+                        let Some(path) = file.and_then(|mut file| file.path.pop())
+                        else {
+                            return;
+                        };
+                        if let Err(err) = std::fs::write(&path, &text) {
+                            tracing::error!("{:?}", err);
+                            messages.update(|messages| {
+                                messages.push((
+                                    "Save Terminal Scrollback".to_string(),
+                                    ShowMessageParams {
+                                        typ: MessageType::ERROR,
+                                        message: format!(
+                                            "Failed to save {}: {err}",
+                                            path.display()
+                                        ),
+                                    },
+                                ));
+                            });
+                        }
+                    },
+                );
+            }
+            OpenTerminalScrollback => {
+                if let Some(terminal) = self
+                    .terminal
+                    .active_tab(false)
+                    .and_then(|tab| tab.active_terminal(false))
+                {
+                    let text = terminal.raw.get_untracked().read().text();
+                    self.main_split.open_scratch(text);
+                }
+            }
 
             // ==== Remote ====
             ConnectSshHost => {
@@ -1096,6 +1302,7 @@ pub fn run_workbench_command(
                         workspace: LapceWorkspace {
                             kind: LapceWorkspaceType::Local,
                             path: None,
+                            folders: Vec::new(),
                             last_open: 0,
                         },
                     },
@@ -1139,6 +1346,55 @@ pub fn run_workbench_command(
             ChangeFileLineEnding => {
                 self.palette.run(PaletteKind::LineEnding);
             }
+            ReopenWithEncoding => {
+                self.palette.run(PaletteKind::ReopenWithEncoding);
+            }
+            SaveWithEncoding => {
+                self.palette.run(PaletteKind::SaveWithEncoding);
+            }
+            InsertSnippet => {
+                self.palette.run(PaletteKind::Snippet);
+            }
+            PasteFromHistory => {
+                self.palette.run(PaletteKind::ClipboardHistory);
+            }
+            StartMacroRecording => {
+                self.common
+                    .keypress
+                    .get_untracked()
+                    .macros
+                    .start_recording(None);
+            }
+            StopMacroRecording => {
+                self.common.keypress.get_untracked().macros.stop_recording();
+            }
+            ReplayMacro => {
+                self.replay_last_macro(1);
+            }
+            ReplayMacroNTimes => match data.as_ref().and_then(Value::as_u64) {
+                Some(times) => self.replay_last_macro(times as usize),
+                None => self.palette.run(PaletteKind::MacroRepeat),
+            },
+            ReplayMacroOnSelectedLines => {
+                self.replay_macro_on_selected_lines();
+            }
+            SaveMacro => match data.as_ref().and_then(Value::as_str) {
+                Some(name) => self.save_macro(name),
+                None => self.palette.run(PaletteKind::MacroName),
+            },
+            RunMacro => match data.as_ref().and_then(Value::as_str) {
+                Some(name) => {
+                    let steps = self.common.config.with_untracked(|config| {
+                        config.macros.get(name).cloned()
+                    });
+                    if let Some(steps) = steps {
+                        self.replay_macro(&steps, 1);
+                    }
+                }
+                None => self.palette.run(PaletteKind::Macro),
+            },
+            // Handled by `KeyPressData`, as they need the focused editor
+            RecordMacroRegister | ReplayMacroRegister | SelectRegister => {}
             DiffFiles => self.palette.run(PaletteKind::DiffFiles),
 
             // ==== Running / Debugging ====
@@ -1202,6 +1458,15 @@ pub fn run_workbench_command(
                     toml_edit::Value::from(1.0),
                 );
             }
+            ToggleMinimap => {
+                let show_minimap =
+                    self.common.config.get_untracked().editor.show_minimap;
+                LapceConfig::update_file(
+                    "editor",
+                    "show-minimap",
+                    toml_edit::Value::from(!show_minimap),
+                );
+            }
 
             ToggleMaximizedPanel => {
                 if let Some(data) = data {
@@ -1288,6 +1553,58 @@ pub fn run_workbench_command(
             ToggleSearchVisual => {
                 self.toggle_panel_visual(PanelKind::Search);
             }
+            ShowLanguageServers => {
+                self.show_panel(PanelKind::LanguageServer);
+            }
+            ShowLocalHistory => {
+                self.show_panel(PanelKind::LocalHistory);
+            }
+            RestartLanguageServers => {
+                let plugin_ids: Vec<PluginId> = self
+                    .plugin
+                    .language_servers
+                    .with_untracked(|servers| servers.keys().copied().collect());
+                for plugin_id in plugin_ids {
+                    self.common.proxy.restart_language_server(plugin_id);
+                }
+            }
+            ExecuteVoltCommand => {
+                let Some(volt_command) = data.and_then(|data| {
+                    serde_json::from_value::<RegisteredVoltCommand>(data).ok()
+                }) else {
+                    return;
+                };
+                let context = self
+                    .main_split
+                    .active_editor
+                    .get_untracked()
+                    .map(|editor| editor.volt_command_context())
+                    .unwrap_or_default();
+                let messages = self.messages;
+                let title = volt_command.command.title.clone();
+                let send = create_ext_action(
+                    self.scope,
+                    move |response: Result<ProxyResponse, RpcError>| {
+                        if let Err(err) = response {
+                            messages.update(|messages| {
+                                messages.push((
+                                    title,
+                                    ShowMessageParams {
+                                        typ: MessageType::ERROR,
+                                        message: err.message,
+                                    },
+                                ));
+                            });
+                        }
+                    },
+                );
+                self.common.proxy.execute_volt_command(
+                    volt_command.plugin_id,
+                    volt_command.command.command,
+                    context,
+                    send,
+                );
+            }
             FocusEditor => {
                 self.common.focus.set(Focus::Workbench);
             }
@@ -1643,6 +1960,31 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             InternalCommand::ReloadFileExplorer => {
                 self.file_explorer.reload();
             }
+            InternalCommand::AddWorkspaceFolder { path } => {
+                if self.file_explorer.is_root(&path) {
+                    return;
+                }
+                self.common
+                    .workspace_folders
+                    .update(|folders| folders.push(path.clone()));
+                self.file_explorer.add_root(path.clone());
+                self.common
+                    .proxy
+                    .update_workspace_folders(vec![path], Vec::new());
+                self.save_workspace_folders();
+                self.common.reload_snippets();
+            }
+            InternalCommand::RemoveWorkspaceFolder { path } => {
+                self.common
+                    .workspace_folders
+                    .update(|folders| folders.retain(|folder| folder != &path));
+                self.file_explorer.remove_root(&path);
+                self.common
+                    .proxy
+                    .update_workspace_folders(Vec::new(), vec![path]);
+                self.save_workspace_folders();
+                self.common.reload_snippets();
+            }
             InternalCommand::TestPathCreation { new_path } => {
                 let naming = self.file_explorer.naming;
 
@@ -1732,10 +2074,36 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                     },
                 );
 
+                // Let language servers update imports and other references to the
+                // renamed item before it is moved on disk.
+                let main_split = self.main_split.clone();
+                let proxy = self.common.proxy.clone();
+                let rename_from = current_path.clone();
+                let rename_to = new_path.clone();
+                let will_rename = create_ext_action(
+                    self.scope,
+                    move |response: Result<ProxyResponse, RpcError>| {
+                        match response {
+                            Ok(ProxyResponse::WillRenameFilesResponse { edits }) => {
+                                for edit in edits.iter() {
+                                    main_split.apply_workspace_edit(edit);
+                                }
+                            }
+                            Ok(_) => {}
+                            Err(err) => {
+                                tracing::error!("{:?}", err);
+                            }
+                        }
+                        proxy.rename_path(rename_from, rename_to, send);
+                    },
+                );
+
                 self.file_explorer.naming.update(Naming::set_pending);
-                self.common
-                    .proxy
-                    .rename_path(current_path.clone(), new_path, send);
+                self.common.proxy.will_rename_files(
+                    current_path,
+                    new_path,
+                    will_rename,
+                );
             }
             InternalCommand::FinishNewNode { is_dir, path } => {
                 let file_explorer = self.file_explorer.clone();
@@ -2010,6 +2378,12 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                 left_path,
                 right_path,
             } => self.main_split.open_diff_files(left_path, right_path),
+            InternalCommand::OpenHexEditor { path } => {
+                self.main_split.open_hex_editor(path);
+            }
+            InternalCommand::OpenMarkdownLink { source, path } => {
+                self.main_split.open_markdown_link(&source, path);
+            }
             InternalCommand::ExecuteProcess { program, arguments } => {
                 let mut cmd = match std::process::Command::new(program)
                     .args(arguments)
@@ -2057,7 +2431,9 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                     error!("cound not find terminal data: index={terminal_index}");
                     return;
                 };
-                raw.write().term.reset_state();
+                let mut raw = raw.write();
+                raw.term.reset_state();
+                raw.shell_integration = ShellIntegration::default();
                 view_id.request_paint();
             }
             InternalCommand::StopTerminal { term_id } => {
@@ -2097,6 +2473,7 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                     *file_diffs = diff
                         .diffs
                         .iter()
+                        .chain(diff.folders.iter().flat_map(|f| f.diffs.iter()))
                         .cloned()
                         .map(|diff| {
                             let checked =
@@ -2159,7 +2536,10 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                 if params.is_ok() {
                     // todo filter by language
                     self.main_split.docs.with_untracked(|x| {
-                        for doc in x.values() {
+                        // Large files are kept from the language servers
+                        for doc in
+                            x.values().filter(|doc| !doc.large_file.get_untracked())
+                        {
                             doc.get_code_lens();
                             doc.get_document_symbol();
                             doc.get_semantic_styles();
@@ -2169,6 +2549,30 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                     });
                 }
             }
+            CoreNotification::SemanticTokensRefresh => {
+                self.main_split.docs.with_untracked(|docs| {
+                    for doc in docs.values() {
+                        doc.get_semantic_styles();
+                    }
+                });
+            }
+            CoreNotification::LanguageServerStatus { server } => {
+                self.plugin.language_servers.update(|servers| {
+                    servers.insert(server.plugin_id, server.clone());
+                });
+            }
+            CoreNotification::LanguageServerStopped { plugin_id } => {
+                self.plugin.language_servers.update(|servers| {
+                    if let Some(server) = servers.get_mut(plugin_id) {
+                        server.running = false;
+                    }
+                });
+            }
+            CoreNotification::LanguageServerRemoved { plugin_id } => {
+                self.plugin.language_servers.update(|servers| {
+                    servers.shift_remove(plugin_id);
+                });
+            }
             CoreNotification::TerminalProcessStopped { term_id, exit_code } => {
                 debug!("TerminalProcessStopped {:?}, {:?}", term_id, exit_code);
                 if let Err(err) = self
@@ -2178,6 +2582,17 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                 {
                     tracing::error!("{:?}", err);
                 }
+                if let Some(terminal) = self.terminal.get_terminal(term_id) {
+                    if terminal.run_debug.with_untracked(|r| r.is_some())
+                        && self.common.config.get_untracked().terminal.run_problems
+                    {
+                        let main_split = self.main_split.clone();
+                        let send = create_ext_action(self.scope, move |problems| {
+                            main_split.set_terminal_problems(problems);
+                        });
+                        terminal.problems(send);
+                    }
+                }
                 self.terminal.terminal_stopped(term_id, *exit_code);
                 if self
                     .terminal
@@ -2262,8 +2677,63 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
             CoreNotification::VoltInstalled { volt, icon } => {
                 self.plugin.volt_installed(volt, icon);
             }
+            CoreNotification::VoltInstalling { volt, error } => {
+                if !error.is_empty() {
+                    self.plugin.volt_install_error(volt, error);
+                }
+            }
             CoreNotification::VoltRemoved { volt, .. } => {
                 self.plugin.volt_removed(volt);
+                self.main_split.clear_volt_decorations(&volt.id());
+            }
+            CoreNotification::VoltCrashed { volt_id, message } => {
+                self.plugin.volt_crashed(volt_id, message.to_owned());
+                self.main_split.clear_volt_decorations(volt_id);
+            }
+            CoreNotification::VoltDecorations {
+                volt_id,
+                path,
+                key,
+                decorations,
+            } => {
+                self.main_split.set_volt_decorations(
+                    volt_id.clone(),
+                    path,
+                    key.clone(),
+                    decorations.clone(),
+                );
+            }
+            CoreNotification::SetVoltStatusBarItem { volt_id, item } => {
+                self.plugin
+                    .set_volt_status_bar_item(volt_id.clone(), item.clone());
+            }
+            CoreNotification::RemoveVoltStatusBarItem { volt_id, id } => {
+                self.plugin
+                    .remove_volt_status_bar_item(volt_id.clone(), id.clone());
+            }
+            CoreNotification::ShowVoltPrompt {
+                prompt_id,
+                title,
+                placeholder,
+                items,
+            } => {
+                self.palette.show_volt_prompt(VoltPrompt {
+                    id: *prompt_id,
+                    title: title.clone(),
+                    placeholder: placeholder.clone(),
+                    items: items.clone(),
+                });
+            }
+            CoreNotification::VoltCommandRegistered {
+                plugin_id,
+                volt_id,
+                command,
+            } => {
+                self.plugin.volt_command_registered(RegisteredVoltCommand {
+                    plugin_id: *plugin_id,
+                    volt_id: volt_id.clone(),
+                    command: command.clone(),
+                });
             }
             CoreNotification::WorkDoneProgress { progress } => {
                 self.update_progress(progress);
@@ -2375,6 +2845,95 @@ pub fn key_down<'a>(&self, event: impl Into<EventRef<'a>> + Copy) -> bool {
         }
     }
 
+    /// Replay the steps of a macro on the active editor.
+    fn replay_macro(&self, steps: &[MacroStep], times: usize) {
+        let Some(editor) = self.main_split.active_editor.get_untracked() else {
+            return;
+        };
+        self.common
+            .keypress
+            .get_untracked()
+            .replay_macro(steps, times, &editor);
+    }
+
+    fn replay_last_macro(&self, times: usize) {
+        let keypress = self.common.keypress.get_untracked();
+        if let Some(steps) = keypress.macros.last.get_untracked() {
+            self.replay_macro(&steps, times);
+        }
+    }
+
+    /// Replay the last macro from the start of each line of the selection of
+    /// the active editor.
+    fn replay_macro_on_selected_lines(&self) {
+        let keypress = self.common.keypress.get_untracked();
+        let Some(steps) = keypress.macros.last.get_untracked() else {
+            return;
+        };
+        let Some(editor) = self.main_split.active_editor.get_untracked() else {
+            return;
+        };
+        let doc = editor.doc();
+        let cursor = editor.cursor().get_untracked();
+        let lines: BTreeSet<usize> = doc.buffer.with_untracked(|buffer| {
+            cursor
+                .edit_selection(buffer)
+                .regions()
+                .iter()
+                .flat_map(|region| {
+                    let start = buffer.line_of_offset(region.min());
+                    let mut end = buffer.line_of_offset(region.max());
+                    // A selection ending at the start of a line doesn't
+                    // include that line.
+                    if end > start && buffer.offset_of_line(end) == region.max() {
+                        end -= 1;
+                    }
+                    start..=end
+                })
+                .collect()
+        });
+
+        let modal = self.common.config.get_untracked().core.modal;
+        // Start from the last line, so the edits don't move the lines left.
+        for line in lines.into_iter().rev() {
+            let offset = doc
+                .buffer
+                .with_untracked(|buffer| buffer.offset_of_line(line));
+            editor.cursor().set(if modal {
+                Cursor::new(CursorMode::Normal(offset), None, None)
+            } else {
+                Cursor::new(CursorMode::Insert(Selection::caret(offset)), None, None)
+            });
+            keypress.replay_macro(&steps, 1, &editor);
+        }
+    }
+
+    /// Save the last macro in the settings under the given name.
+    fn save_macro(&self, name: &str) {
+        let keypress = self.common.keypress.get_untracked();
+        let Some(steps) = keypress.macros.last.get_untracked() else {
+            return;
+        };
+        if LapceConfig::update_file("macros", name, macro_to_toml(&steps)).is_none()
+        {
+            error!("Failed to save macro {name}");
+        }
+    }
+
+    /// The workspace including any root folders added since it was opened.
+    pub fn current_workspace(&self) -> LapceWorkspace {
+        let mut workspace = (*self.workspace).clone();
+        workspace.folders = self.common.workspace_folders.get_untracked();
+        workspace
+    }
+
+    fn save_workspace_folders(&self) {
+        let db: Arc<LapceDb> = use_context().unwrap();
+        if let Err(err) = db.update_recent_workspace(&self.current_workspace()) {
+            tracing::error!("{:?}", err);
+        }
+    }
+
     pub fn workspace_info(&self) -> WorkspaceInfo {
         let main_split_data = self
             .main_split
@@ -2636,7 +3195,9 @@ fn toggle_panel_focus(&self, kind: PanelKind) {
             | PanelKind::CallHierarchy
             | PanelKind::DocumentSymbol
             | PanelKind::References
-            | PanelKind::Implementation => {
+            | PanelKind::Implementation
+            | PanelKind::LanguageServer
+            | PanelKind::LocalHistory => {
                 // Some panels don't accept focus (yet). Fall back to visibility check
                 // in those cases.
                 self.panel.is_panel_visible(&kind)
@@ -2812,12 +3373,17 @@ pub fn open_paths(&self, paths: &[PathObject]) {
         let (folders, files): (Vec<&PathObject>, Vec<&PathObject>) =
             paths.iter().partition(|p| p.is_dir);
 
+        let db: Arc<LapceDb> = use_context().unwrap();
         for folder in folders {
             self.common.window_common.window_command.send(
                 WindowCommand::NewWorkspaceTab {
                     workspace: LapceWorkspace {
                         kind: self.workspace.kind.clone(),
                         path: Some(folder.path.clone()),
+                        folders: db.recent_workspace_folders(
+                            &self.workspace.kind,
+                            &folder.path,
+                        ),
                         last_open: 0,
                     },
                     end: false,
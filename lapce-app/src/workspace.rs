@@ -1,5 +1,10 @@
-use std::{collections::HashMap, fmt::Display, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
+use lapce_rpc::file::workspace_root_of;
 use serde::{Deserialize, Serialize};
 
 use crate::{debug::LapceBreakpoint, main_split::SplitInfo, panel::data::PanelInfo};
@@ -105,10 +110,30 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 pub struct LapceWorkspace {
     pub kind: LapceWorkspaceType,
     pub path: Option<PathBuf>,
+    /// Additional root folders of a multi-root workspace
+    #[serde(default)]
+    pub folders: Vec<PathBuf>,
     pub last_open: u64,
 }
 
 impl LapceWorkspace {
+    /// `path` relative to the root folder that contains it. Paths in one of
+    /// the additional `folders` keep that folder's name as their first
+    /// component, to tell them apart from those of the primary folder.
+    pub fn relative_path(&self, folders: &[PathBuf], path: &Path) -> PathBuf {
+        let roots: Vec<PathBuf> =
+            self.path.iter().chain(folders.iter()).cloned().collect();
+        let Some(root) = workspace_root_of(&roots, path) else {
+            return path.to_path_buf();
+        };
+        let suffix = path.strip_prefix(root).unwrap_or(path);
+        if self.path.as_deref() == Some(root) {
+            suffix.to_path_buf()
+        } else {
+            Path::new(root.file_name().unwrap_or(root.as_os_str())).join(suffix)
+        }
+    }
+
     pub fn display(&self) -> Option<String> {
         let path = self.path.as_ref()?;
         let path = path
@@ -135,6 +160,7 @@ fn default() -> Self {
         Self {
             kind: LapceWorkspaceType::Local,
             path: None,
+            folders: Vec::new(),
             last_open: 0,
         }
     }
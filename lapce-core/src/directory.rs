@@ -126,6 +126,38 @@ pub fn plugins_directory() -> Option<PathBuf> {
         }
     }
 
+    /// Get the path to the folder with the previous versions of the plugins,
+    /// kept when a plugin is updated so that it can be rolled back
+    pub fn plugin_versions_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::data_local_directory() {
+            let dir = dir.join("plugin-versions");
+            if !dir.exists() {
+                if let Err(err) = std::fs::create_dir(&dir) {
+                    tracing::error!("{:?}", err);
+                }
+            }
+            Some(dir)
+        } else {
+            None
+        }
+    }
+
+    /// Get the path to the folder with the shell integration scripts, which
+    /// are written there when a terminal starts
+    pub fn shell_integration_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::data_local_directory() {
+            let dir = dir.join("shell-integration");
+            if !dir.exists() {
+                if let Err(err) = std::fs::create_dir(&dir) {
+                    tracing::error!("{:?}", err);
+                }
+            }
+            Some(dir)
+        } else {
+            None
+        }
+    }
+
     // Config directory contain only configuration files
     pub fn config_directory() -> Option<PathBuf> {
         match Self::project_dirs() {
@@ -146,6 +178,11 @@ pub fn local_socket() -> Option<PathBuf> {
         Self::data_local_directory().map(|dir| dir.join("local.sock"))
     }
 
+    /// The socket of the process that keeps terminal sessions running.
+    pub fn terminal_server_socket() -> Option<PathBuf> {
+        Self::data_local_directory().map(|dir| dir.join("terminals.sock"))
+    }
+
     pub fn updates_directory() -> Option<PathBuf> {
         if let Some(dir) = Self::data_local_directory() {
             let dir = dir.join("updates");
@@ -160,6 +197,52 @@ pub fn updates_directory() -> Option<PathBuf> {
         }
     }
 
+    /// Get the path to the folder with the backups of unsaved documents
+    pub fn backups_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::data_local_directory() {
+            let dir = dir.join("backups");
+            if !dir.exists() {
+                if let Err(err) = std::fs::create_dir(&dir) {
+                    tracing::error!("{:?}", err);
+                }
+            }
+            Some(dir)
+        } else {
+            None
+        }
+    }
+
+    /// Get the path to the folder with the local history, the snapshots of
+    /// files taken when they are saved or changed on disk
+    pub fn local_history_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::data_local_directory() {
+            let dir = dir.join("history");
+            if !dir.exists() {
+                if let Err(err) = std::fs::create_dir(&dir) {
+                    tracing::error!("{:?}", err);
+                }
+            }
+            Some(dir)
+        } else {
+            None
+        }
+    }
+
+    /// Get the path to the folder with the user's snippet files
+    pub fn snippets_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::config_directory() {
+            let dir = dir.join("snippets");
+            if !dir.exists() {
+                if let Err(err) = std::fs::create_dir(&dir) {
+                    tracing::error!("{:?}", err);
+                }
+            }
+            Some(dir)
+        } else {
+            None
+        }
+    }
+
     pub fn queries_directory() -> Option<PathBuf> {
         if let Some(dir) = Self::config_directory() {
             let dir = dir.join("queries");
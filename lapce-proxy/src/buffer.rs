@@ -3,33 +3,105 @@
     ffi::OsString,
     fs,
     fs::File,
-    io::{Read, Write},
+    io::{Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
     time::SystemTime,
 };
 
 use anyhow::{Result, anyhow};
+use encoding_rs::{
+    BIG5, EUC_JP, EUC_KR, Encoding, GBK, SHIFT_JIS, UTF_8, UTF_16BE, UTF_16LE,
+    WINDOWS_1252,
+};
 use floem_editor_core::buffer::rope_text::CharIndicesJoin;
 use lapce_core::encoding::offset_utf8_to_utf16;
-use lapce_rpc::buffer::BufferId;
+use lapce_rpc::buffer::{BufferId, FileEncoding};
 use lapce_xi_rope::{RopeDelta, interval::IntervalBounds, rope::Rope};
 use lsp_types::*;
+use sha2::{Digest, Sha256};
+
+/// The size of the chunks a buffer in large file mode is sent to the editor in.
+const LARGE_FILE_CHUNK_SIZE: usize = 4 * 1024 * 1024;
 
 #[derive(Clone)]
 pub struct Buffer {
     pub language_id: &'static str,
     pub read_only: bool,
+    /// Whether the buffer is in large file mode, which sends its content in
+    /// chunks and keeps it from the language servers
+    pub large: bool,
     pub id: BufferId,
     pub rope: Rope,
     pub path: PathBuf,
     pub rev: u64,
     pub mod_time: Option<SystemTime>,
+    pub encoding: FileEncoding,
+    /// The hash of the file on disk, if it could be read
+    pub disk_hash: Option<String>,
+    /// Whether the file isn't text, so it's opened in the hex editor instead
+    pub binary: bool,
+    /// How far a buffer in large file mode has read its file
+    large_read: LargeFileRead,
+}
+
+/// How far a buffer in large file mode has read its file, which it does a
+/// chunk at a time as the editor asks for them.
+#[derive(Clone, Copy, Default)]
+struct LargeFileRead {
+    /// The offset in the file of the next chunk
+    offset: u64,
+    done: bool,
+    /// Whether bytes that aren't UTF-8 were replaced, so that the buffer
+    /// can't be saved
+    lossy: bool,
 }
 
 impl Buffer {
-    pub fn new(id: BufferId, path: PathBuf) -> Buffer {
-        let (s, read_only) = match load_file(&path) {
-            Ok(s) => (s, false),
+    pub fn new(id: BufferId, path: PathBuf, large_file_size: Option<u64>) -> Buffer {
+        let large = large_file_size.is_some_and(|size| {
+            fs::metadata(&path).is_ok_and(|metadata| metadata.len() > size)
+        });
+        let mut encoding = FileEncoding::default();
+        let mut disk_hash = None;
+        let mut binary = false;
+        let mut large_read = LargeFileRead {
+            done: !large,
+            ..LargeFileRead::default()
+        };
+        // Large files are read a chunk at a time, unless they aren't UTF-8, and
+        // aren't hashed, as they aren't backed up.
+        let loaded = if large {
+            match read_chunk(&path, 0, LARGE_FILE_CHUNK_SIZE) {
+                Ok(Some(chunk)) => {
+                    large_read = LargeFileRead {
+                        offset: chunk.len,
+                        done: chunk.done,
+                        lossy: chunk.lossy,
+                    };
+                    Ok(LoadedFile {
+                        content: chunk.content,
+                        encoding: FileEncoding {
+                            name: UTF_8.name().to_string(),
+                            bom: chunk.bom,
+                        },
+                        disk_hash: None,
+                    })
+                }
+                Ok(None) => {
+                    large_read.done = true;
+                    read_file(&path, None, false)
+                }
+                Err(err) => Err(err),
+            }
+        } else {
+            read_file(&path, None, true)
+        };
+        let (s, read_only) = match loaded {
+            Ok(file) => {
+                encoding = file.encoding;
+                disk_hash = file.disk_hash;
+                (file.content, false)
+            }
             Err(err) => {
                 use std::io::ErrorKind;
                 match err.downcast_ref::<std::io::Error>() {
@@ -43,7 +115,11 @@ pub fn new(id: BufferId, path: PathBuf) -> Buffer {
                         }
                         _ => (format!("Not supported: {err}"), true),
                     },
-                    None => (format!("Not supported: {err}"), true),
+                    // Errors other than IO ones are from decoding
+                    None => {
+                        binary = true;
+                        (format!("Not supported: {err}"), true)
+                    }
                 }
             }
         };
@@ -55,10 +131,15 @@ pub fn new(id: BufferId, path: PathBuf) -> Buffer {
             id,
             rope,
             read_only,
+            large,
             path,
             language_id,
             rev,
             mod_time,
+            encoding,
+            disk_hash,
+            binary,
+            large_read,
         }
     }
 
@@ -70,46 +151,27 @@ pub fn save(&mut self, rev: u64, create_parents: bool) -> Result<()> {
         if self.rev != rev {
             return Err(anyhow!("not the right rev"));
         }
-        let bak_extension = self.path.extension().map_or_else(
-            || OsString::from("bak"),
-            |ext| {
-                let mut ext = ext.to_os_string();
-                ext.push(".bak");
-                ext
-            },
-        );
-        let path = if self.path.is_symlink() {
-            self.path.canonicalize()?
-        } else {
-            self.path.clone()
-        };
-        let new_file = !path.exists();
-
-        let bak_file_path = &path.with_extension(bak_extension);
-        if !new_file {
-            fs::copy(&path, bak_file_path)?;
+        // Changes to large files aren't reloaded, so saving one that changed,
+        // like a log that grew, would drop what was added.
+        if self.large && get_mod_time(&self.path) != self.mod_time {
+            return Err(anyhow!(
+                "the file changed on disk since it was opened, reopen it to save"
+            ));
         }
-
-        if create_parents {
-            if let Some(parent) = path.parent() {
-                fs::create_dir_all(parent)?;
-            }
+        if !self.large_read.done {
+            return Err(anyhow!("the file hasn't finished loading"));
         }
-
-        let mut f = fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&path)?;
-        for chunk in self.rope.iter_chunks(..self.rope.len()) {
-            f.write_all(chunk.as_bytes())?;
+        if self.large_read.lossy {
+            return Err(anyhow!(
+                "the file isn't valid UTF-8, which large files are saved as"
+            ));
         }
-
-        self.mod_time = get_mod_time(&path);
-        if !new_file {
-            fs::remove_file(bak_file_path)?;
+        let content = encode(&self.rope.to_string(), &self.encoding)?;
+        write_file(&self.path, &content, create_parents)?;
+        self.mod_time = get_mod_time(&self.path);
+        if !self.large {
+            self.disk_hash = Some(disk_hash(&content));
         }
-
         Ok(())
     }
 
@@ -183,6 +245,30 @@ pub fn char_indices_iter<T: IntervalBounds>(
         CharIndicesJoin::new(self.rope.iter_chunks(range).map(str::char_indices))
     }
 
+    /// The chunk of the content from `offset` that is sent to the editor, and
+    /// whether it is the last one. In large file mode, the chunk after the
+    /// content read so far is read from the file and added to it.
+    pub fn chunk(&mut self, offset: usize) -> Result<(String, bool)> {
+        let offset = offset.min(self.len());
+        if offset < self.len() || self.large_read.done {
+            let end = chunk_end(&self.rope, offset, LARGE_FILE_CHUNK_SIZE);
+            return Ok((
+                self.slice_to_cow(offset..end).into_owned(),
+                end == self.len() && self.large_read.done,
+            ));
+        }
+
+        let chunk =
+            read_chunk(&self.path, self.large_read.offset, LARGE_FILE_CHUNK_SIZE)?
+                .ok_or_else(|| anyhow!("the file is no longer text"))?;
+        self.large_read.offset += chunk.len;
+        self.large_read.done = chunk.done;
+        self.large_read.lossy |= chunk.lossy;
+        let len = self.len();
+        self.rope.edit(len..len, chunk.content.as_str());
+        Ok((chunk.content, chunk.done))
+    }
+
     pub fn len(&self) -> usize {
         self.rope.len()
     }
@@ -192,22 +278,360 @@ pub fn is_empty(&self) -> bool {
     }
 }
 
-pub fn load_file(path: &Path) -> Result<String> {
-    read_path_to_string(path)
+/// The end of a chunk of about `size` bytes from `offset`, which is at the start
+/// of a line when the chunk contains a line break, so that the lines already
+/// sent don't change.
+fn chunk_end(rope: &Rope, offset: usize, size: usize) -> usize {
+    let end = offset.saturating_add(size);
+    if end >= rope.len() {
+        return rope.len();
+    }
+    let line_start = rope.offset_of_line(rope.line_of_offset(end));
+    if line_start > offset {
+        line_start
+    } else {
+        rope.at_or_next_codepoint_boundary(end)
+            .unwrap_or_else(|| rope.len())
+    }
+}
+
+/// A chunk of a file read in large file mode
+struct FileChunk {
+    content: String,
+    /// The number of bytes of the file the chunk was read from
+    len: u64,
+    /// Whether the chunk reaches the end of the file
+    done: bool,
+    /// Whether bytes that aren't UTF-8 were replaced
+    lossy: bool,
+    /// Whether the file starts with a UTF-8 byte order mark, which isn't part
+    /// of the content
+    bom: bool,
+}
+
+/// Read a chunk of about `size` bytes of a file from `offset`, as UTF-8. Like
+/// [`chunk_end`], the chunk ends after a line break when it has one, or else
+/// between characters. The first chunk is `None` if the file doesn't look like
+/// UTF-8 text, so that it can be decoded whole instead.
+fn read_chunk(path: &Path, offset: u64, size: usize) -> Result<Option<FileChunk>> {
+    let mut file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::with_capacity(size);
+    file.take(size as u64).read_to_end(&mut bytes)?;
+
+    let done = offset + bytes.len() as u64 >= file_len;
+    if !done {
+        let end = match bytes.iter().rposition(|b| *b == b'\n') {
+            Some(line_break) => line_break + 1,
+            None => utf8_boundary(&bytes),
+        };
+        bytes.truncate(end);
+    }
+    let len = bytes.len() as u64;
+
+    let bom = offset == 0 && bytes.starts_with(b"\xEF\xBB\xBF");
+    if offset == 0 && !bom {
+        let other_bom = Encoding::for_bom(&bytes).is_some();
+        let not_utf8 =
+            std::str::from_utf8(&bytes).is_err_and(|err| err.error_len().is_some());
+        if other_bom || not_utf8 || detect_utf16(&bytes).is_some() {
+            return Ok(None);
+        }
+    }
+    if bom {
+        bytes.drain(..3);
+    }
+    // NUL is valid UTF-8, but text files don't have it
+    if offset == 0 && bytes.contains(&0) {
+        return Err(anyhow!("the file is binary"));
+    }
+
+    let (content, lossy) = match String::from_utf8(bytes) {
+        Ok(content) => (content, false),
+        Err(err) => (String::from_utf8_lossy(err.as_bytes()).into_owned(), true),
+    };
+    Ok(Some(FileChunk {
+        content,
+        len,
+        done,
+        lossy,
+        bom,
+    }))
+}
+
+/// The length of `bytes` without a UTF-8 character that is cut off at its end.
+fn utf8_boundary(bytes: &[u8]) -> usize {
+    let len = bytes.len();
+    let Some(start) = (len.saturating_sub(4)..len)
+        .rev()
+        .find(|&i| bytes[i] & 0xC0 != 0x80)
+    else {
+        return len;
+    };
+    let char_len = match bytes[start] {
+        b if b < 0x80 => 1,
+        b if b >= 0xF0 => 4,
+        b if b >= 0xE0 => 3,
+        _ => 2,
+    };
+    if start + char_len > len { start } else { len }
+}
+
+/// Write `content` to the file at `path`, keeping a backup of the file until
+/// it is written.
+pub fn write_file(path: &Path, content: &[u8], create_parents: bool) -> Result<()> {
+    let bak_extension = path.extension().map_or_else(
+        || OsString::from("bak"),
+        |ext| {
+            let mut ext = ext.to_os_string();
+            ext.push(".bak");
+            ext
+        },
+    );
+    let path = if path.is_symlink() {
+        path.canonicalize()?
+    } else {
+        path.to_path_buf()
+    };
+    let new_file = !path.exists();
+
+    let bak_file_path = &path.with_extension(bak_extension);
+    if !new_file {
+        fs::copy(&path, bak_file_path)?;
+    }
+
+    if create_parents {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    let mut f = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(&path)?;
+    f.write_all(content)?;
+
+    if !new_file {
+        fs::remove_file(bak_file_path)?;
+    }
+
+    Ok(())
+}
+
+/// A file read from disk
+pub struct LoadedFile {
+    pub content: String,
+    pub encoding: FileEncoding,
+    /// The hash of the bytes of the file, unless it wasn't hashed
+    pub disk_hash: Option<String>,
 }
 
-pub fn read_path_to_string<P: AsRef<Path>>(path: P) -> Result<String> {
-    let path = path.as_ref();
+pub fn load_file(
+    path: &Path,
+    encoding: Option<&FileEncoding>,
+) -> Result<LoadedFile> {
+    read_file(path, encoding, true)
+}
 
+fn read_file(
+    path: &Path,
+    encoding: Option<&FileEncoding>,
+    hash: bool,
+) -> Result<LoadedFile> {
     let mut file = File::open(path)?;
     // Read the file in as bytes
     let mut buffer = Vec::new();
     file.read_to_end(&mut buffer)?;
 
-    // Parse the file contents as utf8
-    let contents = String::from_utf8(buffer)?;
+    let (content, encoding) = match encoding {
+        Some(encoding) => (decode(&buffer, encoding)?, encoding.clone()),
+        None => detect_and_decode(&buffer)?,
+    };
+    Ok(LoadedFile {
+        content,
+        encoding,
+        disk_hash: hash.then(|| disk_hash(&buffer)),
+    })
+}
+
+/// Write the bytes of a file edited in the hex editor, returning their hash.
+/// Unless the file is new, it must still hash to `expected_hash`, the hash
+/// of the bytes the edits were made to, so that changes made on disk since
+/// aren't overwritten.
+pub fn save_file_bytes(
+    path: &Path,
+    content: &[u8],
+    expected_hash: Option<&str>,
+) -> Result<String> {
+    if let Some(expected_hash) = expected_hash {
+        if fs::read(path).is_ok_and(|bytes| disk_hash(&bytes) != expected_hash) {
+            return Err(anyhow!("the file changed on disk since it was opened"));
+        }
+    }
+    write_file(path, content, false)?;
+    Ok(disk_hash(content))
+}
+
+/// The hash of the bytes of a file, which the editor compares to tell
+/// whether the file changed on disk since it was opened.
+pub fn disk_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Decode the bytes of a file, detecting their encoding from the byte order
+/// mark, or else by which encoding decodes them without errors, falling back
+/// to windows-1252 for text in none of them. Bytes that aren't text in any
+/// of these, or that have a NUL byte outside of UTF-16, like those of a
+/// binary file, are an error, so that saving doesn't write them back changed.
+pub fn detect_and_decode(bytes: &[u8]) -> Result<(String, FileEncoding)> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let encoding = FileEncoding {
+            name: encoding.name().to_string(),
+            bom: true,
+        };
+        return Ok((decode(bytes, &encoding)?, encoding));
+    }
+
+    // ASCII in UTF-16 is valid UTF-8 too, but with a zero in every other byte
+    if let Some(encoding) = detect_utf16(bytes) {
+        let encoding = FileEncoding {
+            name: encoding.name().to_string(),
+            bom: false,
+        };
+        return Ok((decode(bytes, &encoding)?, encoding));
+    }
+
+    // NUL is valid UTF-8, but text files don't have it
+    if bytes.contains(&0) {
+        return Err(anyhow!("the file is binary"));
+    }
+
+    if let Ok(content) = std::str::from_utf8(bytes) {
+        return Ok((content.to_string(), FileEncoding::new(UTF_8.name())));
+    }
+
+    for encoding in [SHIFT_JIS, EUC_JP, EUC_KR, GBK, BIG5] {
+        let Some(content) =
+            encoding.decode_without_bom_handling_and_without_replacement(bytes)
+        else {
+            continue;
+        };
+        // bytes of Latin text often happen to be valid in the CJK
+        // encodings too, so only pick one when it yields its own script
+        if content.chars().any(|c| is_cjk(encoding, c)) {
+            return Ok((
+                content.into_owned(),
+                FileEncoding {
+                    name: encoding.name().to_string(),
+                    bom: false,
+                },
+            ));
+        }
+    }
+
+    // windows-1252 maps every byte to a character, so the control characters
+    // tell binary files apart
+    let content = WINDOWS_1252
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .filter(|content| {
+            !content.chars().any(|c| {
+                c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c' | '\x1b')
+            })
+        })
+        .ok_or_else(|| anyhow!("the file isn't text in a known encoding"))?;
+    Ok((content.into_owned(), FileEncoding::new(WINDOWS_1252.name())))
+}
+
+/// Decode the bytes of a file in `encoding`, skipping its byte order mark.
+pub fn decode(bytes: &[u8], encoding: &FileEncoding) -> Result<String> {
+    let encoding = Encoding::for_label(encoding.name.as_bytes())
+        .ok_or_else(|| anyhow!("unknown encoding {}", encoding.name))?;
+    let bytes = match Encoding::for_bom(bytes) {
+        Some((bom_encoding, bom_len)) if bom_encoding == encoding => {
+            &bytes[bom_len..]
+        }
+        _ => bytes,
+    };
+    let (content, had_errors) = encoding.decode_without_bom_handling(bytes);
+    if had_errors {
+        return Err(anyhow!("the file isn't valid {}", encoding.name()));
+    }
+    Ok(content.into_owned())
+}
+
+/// Encode text in `encoding`, failing rather than losing the characters the
+/// encoding can't represent.
+pub fn encode(content: &str, encoding: &FileEncoding) -> Result<Vec<u8>> {
+    let name = &encoding.name;
+    let target = Encoding::for_label(name.as_bytes())
+        .ok_or_else(|| anyhow!("unknown encoding {name}"))?;
+
+    // encoding_rs only decodes UTF-16, so it's encoded here
+    let mut bytes = Vec::new();
+    if target == UTF_16LE || target == UTF_16BE {
+        let little_endian = target == UTF_16LE;
+        let bom = encoding.bom.then_some(0xFEFF);
+        for unit in bom.into_iter().chain(content.encode_utf16()) {
+            if little_endian {
+                bytes.extend(unit.to_le_bytes());
+            } else {
+                bytes.extend(unit.to_be_bytes());
+            }
+        }
+        return Ok(bytes);
+    }
+
+    if target == UTF_8 && encoding.bom {
+        bytes.extend([0xEF, 0xBB, 0xBF]);
+    }
+    let (encoded, _, had_errors) = target.encode(content);
+    if had_errors {
+        return Err(anyhow!(
+            "the file has characters that can't be saved in {name}"
+        ));
+    }
+    bytes.extend_from_slice(&encoded);
+    Ok(bytes)
+}
+
+/// UTF-16 without a byte order mark, recognized by the zero high bytes of
+/// ASCII characters.
+fn detect_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    if bytes.len() < 2 || bytes.len() % 2 == 1 {
+        return None;
+    }
+    let sample = &bytes[..bytes.len().min(4096)];
+    let pairs = sample.len() / 2;
+    let even_zeros = sample.iter().step_by(2).filter(|b| **b == 0).count();
+    let odd_zeros = sample
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|b| **b == 0)
+        .count();
+    if odd_zeros * 2 > pairs && even_zeros * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even_zeros * 2 > pairs && odd_zeros * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
 
-    Ok(contents.to_string())
+fn is_cjk(encoding: &'static Encoding, c: char) -> bool {
+    let kana = ('\u{3040}'..='\u{30ff}').contains(&c);
+    let hangul = ('\u{ac00}'..='\u{d7af}').contains(&c);
+    let han = ('\u{4e00}'..='\u{9fff}').contains(&c);
+    if encoding == SHIFT_JIS || encoding == EUC_JP {
+        kana
+    } else if encoding == EUC_KR {
+        hangul
+    } else {
+        han
+    }
 }
 
 pub fn language_id_from_path(path: &Path) -> Option<&'static str> {
@@ -347,3 +771,198 @@ pub fn get_mod_time<P: AsRef<Path>>(path: P) -> Option<SystemTime> {
         .and_then(|meta| meta.modified())
         .ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_encoding() {
+        let (content, encoding) = detect_and_decode("héllo".as_bytes()).unwrap();
+        assert_eq!(content, "héllo");
+        assert_eq!(encoding, FileEncoding::new("UTF-8"));
+
+        let (content, encoding) = detect_and_decode(b"\xEF\xBB\xBFhi").unwrap();
+        assert_eq!(content, "hi");
+        assert_eq!(encoding.to_string(), "UTF-8 with BOM");
+
+        let (content, encoding) = detect_and_decode(b"caf\xE9 cr\xE8me").unwrap();
+        assert_eq!(content, "café crème");
+        assert_eq!(encoding.name, "windows-1252");
+
+        let (content, encoding) = detect_and_decode(b"h\0i\0\n\0").unwrap();
+        assert_eq!(content, "hi\n");
+        assert_eq!(
+            encoding,
+            FileEncoding {
+                name: "UTF-16LE".to_string(),
+                bom: false,
+            }
+        );
+
+        let (content, encoding) = detect_and_decode(b"\x82\xB1\x82\xF1").unwrap();
+        assert_eq!(content, "こん");
+        assert_eq!(encoding.name, "Shift_JIS");
+
+        // binary files aren't decoded as windows-1252
+        assert!(detect_and_decode(b"\x7fELF\x02\x01\x01\0\0\0\xff").is_err());
+        assert!(detect_and_decode(b"caf\xE9\0\x01").is_err());
+        assert!(detect_and_decode(b"text\0with a nul").is_err());
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(decode(b"caf\xE9", &FileEncoding::new("UTF-8")).is_err());
+        assert_eq!(
+            decode(b"caf\xE9", &FileEncoding::new("windows-1252")).unwrap(),
+            "café"
+        );
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        for name in ["UTF-8", "UTF-16LE", "UTF-16BE", "Shift_JIS"] {
+            let encoding = FileEncoding::new(name);
+            let bytes = encode("こんにちは\n", &encoding).unwrap();
+            assert_eq!(decode(&bytes, &encoding).unwrap(), "こんにちは\n");
+            assert_eq!(detect_and_decode(&bytes).unwrap().1, encoding);
+        }
+        assert!(encode("こんにちは", &FileEncoding::new("windows-1252")).is_err());
+    }
+
+    #[test]
+    fn test_chunk_end() {
+        let rope = Rope::from("one\ntwo\nthree");
+        assert_eq!(chunk_end(&rope, 0, 6), 4);
+        assert_eq!(chunk_end(&rope, 4, 6), 8);
+        assert_eq!(chunk_end(&rope, 8, 6), 13);
+
+        // Without a line break the chunk ends between characters.
+        let rope = Rope::from("ééé");
+        assert_eq!(chunk_end(&rope, 0, 3), 4);
+        assert_eq!(chunk_end(&rope, 4, 3), 6);
+    }
+
+    #[test]
+    fn test_disk_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        let encoding = FileEncoding::new("UTF-16LE");
+        fs::write(&path, encode("hello", &encoding).unwrap()).unwrap();
+
+        // The hash is of the bytes on disk, not of the decoded text.
+        let mut buffer = Buffer::new(BufferId::next(), path.clone(), None);
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(buffer.disk_hash, Some(disk_hash(&bytes)));
+        assert_ne!(buffer.disk_hash, Some(disk_hash(b"hello")));
+
+        buffer.rope = Rope::from("bye");
+        buffer.save(buffer.rev, false).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(buffer.disk_hash, Some(disk_hash(&bytes)));
+
+        let missing = Buffer::new(BufferId::next(), dir.path().join("gone"), None);
+        assert_eq!(missing.disk_hash, None);
+    }
+
+    #[test]
+    fn test_binary_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("image.png");
+        fs::write(&path, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        let buffer = Buffer::new(BufferId::next(), path, None);
+        assert!(buffer.binary);
+        assert!(buffer.read_only);
+
+        let missing = Buffer::new(BufferId::next(), dir.path().join("gone"), None);
+        assert!(!missing.binary);
+    }
+
+    #[test]
+    fn test_save_file_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        fs::write(&path, [0, 1, 2]).unwrap();
+        let hash = disk_hash(&[0, 1, 2]);
+
+        let saved = save_file_bytes(&path, &[0, 1, 3], Some(&hash)).unwrap();
+        assert_eq!(saved, disk_hash(&[0, 1, 3]));
+        assert_eq!(fs::read(&path).unwrap(), [0, 1, 3]);
+
+        // The file no longer has the bytes the edits were made to.
+        assert!(save_file_bytes(&path, &[9], Some(&hash)).is_err());
+        assert_eq!(fs::read(&path).unwrap(), [0, 1, 3]);
+        save_file_bytes(&path, &[9], None).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), [9]);
+    }
+
+    #[test]
+    fn test_read_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.txt");
+        fs::write(&path, "\u{feff}one\ntwo\nthree").unwrap();
+
+        // The chunks end after a line break, and the byte order mark is left
+        // out of the content.
+        let chunk = read_chunk(&path, 0, 12).unwrap().unwrap();
+        assert_eq!(chunk.content, "one\ntwo\n");
+        assert_eq!(chunk.len, 11);
+        assert!(chunk.bom && !chunk.done);
+        let chunk = read_chunk(&path, chunk.len, 12).unwrap().unwrap();
+        assert_eq!(chunk.content, "three");
+        assert!(chunk.done);
+
+        // Without a line break the chunk ends between characters.
+        fs::write(&path, "ééé").unwrap();
+        let chunk = read_chunk(&path, 0, 3).unwrap().unwrap();
+        assert_eq!(chunk.content, "é");
+        assert_eq!(chunk.len, 2);
+
+        fs::write(&path, encode("hi", &FileEncoding::new("UTF-16LE")).unwrap())
+            .unwrap();
+        assert!(read_chunk(&path, 0, 12).unwrap().is_none());
+        fs::write(&path, b"text\0with a nul").unwrap();
+        assert!(read_chunk(&path, 0, 12).is_err());
+    }
+
+    #[test]
+    fn test_large_file_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.txt");
+        fs::write(&path, "one\n").unwrap();
+
+        let mut buffer = Buffer::new(BufferId::next(), path.clone(), Some(0));
+        assert_eq!(buffer.chunk(0).unwrap(), ("one\n".to_string(), true));
+        // A chunk that was read already is sent again from the buffer.
+        assert_eq!(buffer.chunk(2).unwrap(), ("e\n".to_string(), true));
+        assert_eq!(buffer.chunk(4).unwrap(), (String::new(), true));
+
+        fs::write(&path, b"caf\xE9\n").unwrap();
+        let buffer = Buffer::new(BufferId::next(), path, Some(0));
+        assert_eq!(buffer.rope.to_string(), "café\n");
+        assert_eq!(buffer.encoding.name, "windows-1252");
+    }
+
+    #[test]
+    fn test_large_file_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.txt");
+        fs::write(&path, "one\n").unwrap();
+
+        let mut buffer = Buffer::new(BufferId::next(), path.clone(), Some(0));
+        assert!(buffer.large);
+        assert_eq!(buffer.disk_hash, None);
+        buffer.save(buffer.rev, false).unwrap();
+
+        // A log that grew since it was opened isn't cut back on save.
+        fs::write(&path, "one\ntwo\n").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() + std::time::Duration::from_secs(10))
+            .unwrap();
+        assert!(buffer.save(buffer.rev, false).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+    }
+}
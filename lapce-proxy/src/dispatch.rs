@@ -24,13 +24,13 @@
     RequestId, RpcError,
     buffer::BufferId,
     core::{CoreNotification, CoreRpcHandler, FileChanged},
-    file::FileNodeItem,
+    file::{FileNodeItem, workspace_root_of},
     file_line::FileLine,
     proxy::{
         ProxyHandler, ProxyNotification, ProxyRequest, ProxyResponse,
         ProxyRpcHandler, SearchMatch,
     },
-    source_control::{DiffInfo, FileDiff},
+    source_control::{DiffInfo, FileDiff, FolderDiffInfo},
     style::{LineStyle, SemanticStyles},
     terminal::TermId,
 };
@@ -43,7 +43,7 @@
 use parking_lot::Mutex;
 
 use crate::{
-    buffer::{Buffer, get_mod_time, load_file},
+    buffer::{Buffer, disk_hash, get_mod_time, load_file, save_file_bytes},
     plugin::{PluginCatalogRpcHandler, catalog::PluginCatalog},
     terminal::{Terminal, TerminalSender},
     watcher::{FileWatcher, Notify, WatchToken},
@@ -51,13 +51,19 @@
 
 const OPEN_FILE_EVENT_TOKEN: WatchToken = WatchToken(1);
 const WORKSPACE_EVENT_TOKEN: WatchToken = WatchToken(2);
+/// How long a rename waits for servers to answer `workspace/willRenameFiles`.
+const WILL_RENAME_FILES_TIMEOUT: Duration = Duration::from_secs(2);
 
 pub struct Dispatcher {
     workspace: Option<PathBuf>,
+    /// Additional root folders of a multi-root workspace
+    workspace_folders: Arc<Mutex<Vec<PathBuf>>>,
     pub proxy_rpc: ProxyRpcHandler,
     core_rpc: CoreRpcHandler,
     catalog_rpc: PluginCatalogRpcHandler,
     buffers: HashMap<PathBuf, Buffer>,
+    /// The hashes of the files open in the hex editor, as last read or saved
+    byte_files: HashMap<PathBuf, String>,
     terminals: HashMap<TermId, TerminalSender>,
     file_watcher: FileWatcher,
     window_id: usize,
@@ -70,6 +76,7 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
         match rpc {
             Initialize {
                 workspace,
+                workspace_folders,
                 disabled_volts,
                 extra_plugin_paths,
                 plugin_configurations,
@@ -79,14 +86,16 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 self.window_id = window_id;
                 self.tab_id = tab_id;
                 self.workspace = workspace;
+                *self.workspace_folders.lock() = workspace_folders.clone();
+                self.catalog_rpc.set_workspace_folders(workspace_folders);
                 self.file_watcher.notify(FileWatchNotifier::new(
                     self.workspace.clone(),
+                    self.workspace_folders.clone(),
                     self.core_rpc.clone(),
                     self.proxy_rpc.clone(),
                 ));
-                if let Some(workspace) = self.workspace.as_ref() {
-                    self.file_watcher
-                        .watch(workspace, true, WORKSPACE_EVENT_TOKEN);
+                for root in self.workspace_roots() {
+                    self.file_watcher.watch(&root, true, WORKSPACE_EVENT_TOKEN);
                 }
 
                 let plugin_rpc = self.catalog_rpc.clone();
@@ -118,15 +127,39 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
             }
             OpenFileChanged { path } => {
                 if path.exists() {
+                    if let Some(hash) = self.byte_files.get_mut(&path) {
+                        match fs::read(&path) {
+                            Ok(content) => {
+                                let disk_hash = disk_hash(&content);
+                                if *hash != disk_hash {
+                                    hash.clone_from(&disk_hash);
+                                    self.core_rpc.open_file_changed(
+                                        path.clone(),
+                                        FileChanged::Bytes { content, disk_hash },
+                                    );
+                                }
+                            }
+                            Err(err) => {
+                                tracing::error!("{:?}", err);
+                            }
+                        }
+                    }
                     if let Some(buffer) = self.buffers.get(&path) {
-                        if get_mod_time(&buffer.path) == buffer.mod_time {
+                        // Large files aren't reloaded, as they are often logs
+                        // that keep growing.
+                        if buffer.large
+                            || get_mod_time(&buffer.path) == buffer.mod_time
+                        {
                             return;
                         }
-                        match load_file(&buffer.path) {
-                            Ok(content) => {
+                        match load_file(&buffer.path, Some(&buffer.encoding)) {
+                            Ok(file) => {
                                 self.core_rpc.open_file_changed(
                                     path,
-                                    FileChanged::Change(content),
+                                    FileChanged::Change {
+                                        content: file.content,
+                                        disk_hash: file.disk_hash,
+                                    },
                                 );
                             }
                             Err(err) => {
@@ -139,6 +172,7 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     }
                 } else {
                     self.buffers.remove(&path);
+                    self.byte_files.remove(&path);
                     self.core_rpc.open_file_changed(path, FileChanged::Delete);
                 }
             }
@@ -160,8 +194,11 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
             }
             Shutdown {} => {
                 self.catalog_rpc.shutdown();
-                for (_, sender) in self.terminals.iter() {
-                    sender.send(Msg::Shutdown);
+                for (_, sender) in self.terminals.drain() {
+                    // the terminals in the terminal server are only detached
+                    if !sender.is_session() {
+                        sender.send(Msg::Shutdown);
+                    }
                 }
                 self.proxy_rpc.shutdown();
             }
@@ -169,6 +206,9 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 let buffer = self.buffers.get_mut(&path).unwrap();
                 let old_text = buffer.rope.clone();
                 buffer.update(&delta, rev);
+                if buffer.large {
+                    return;
+                }
                 self.catalog_rpc.did_change_text_document(
                     &path,
                     rev,
@@ -183,6 +223,18 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 }
             }
             NewTerminal { term_id, profile } => {
+                #[cfg(unix)]
+                if let Some(session) = profile.session.clone() {
+                    let sender = crate::terminal_server::attach_terminal(
+                        term_id,
+                        session,
+                        profile,
+                        self.core_rpc.clone(),
+                    );
+                    self.terminals.insert(term_id, sender);
+                    return;
+                }
+
                 let mut terminal = match Terminal::new(term_id, profile, 50, 10) {
                     Ok(terminal) => terminal,
                     Err(e) => {
@@ -313,9 +365,13 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     tracing::error!("{:?}", err);
                 }
             }
-            InstallVolt { volt } => {
+            InstallVolt {
+                volt,
+                source,
+                approved,
+            } => {
                 let catalog_rpc = self.catalog_rpc.clone();
-                if let Err(err) = catalog_rpc.install_volt(volt) {
+                if let Err(err) = catalog_rpc.install_volt(volt, source, approved) {
                     tracing::error!("{:?}", err);
                 }
             }
@@ -335,8 +391,47 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     tracing::error!("{:?}", err);
                 }
             }
+            UpdateWorkspaceFolders { added, removed } => {
+                {
+                    let mut folders = self.workspace_folders.lock();
+                    folders.retain(|folder| !removed.contains(folder));
+                    for folder in added.iter() {
+                        if !folders.contains(folder) {
+                            folders.push(folder.clone());
+                        }
+                    }
+                }
+                for folder in removed.iter() {
+                    self.file_watcher.unwatch(folder, WORKSPACE_EVENT_TOKEN);
+                }
+                for folder in added.iter() {
+                    self.file_watcher.watch(folder, true, WORKSPACE_EVENT_TOKEN);
+                }
+                self.catalog_rpc
+                    .did_change_workspace_folders(&added, &removed);
+
+                if let Some(workspace) = self.workspace.clone() {
+                    let folders = self.workspace_folders.lock().clone();
+                    let core_rpc = self.core_rpc.clone();
+                    thread::spawn(move || {
+                        if let Some(diff) = workspace_diff(&workspace, &folders) {
+                            core_rpc.diff_info(diff);
+                        }
+                    });
+                }
+            }
             GitCommit { message, diffs } => {
-                if let Some(workspace) = self.workspace.as_ref() {
+                // Each root folder can be its own repository, so commit the
+                // changes of every folder separately.
+                let roots = self.workspace_roots();
+                let mut folder_diffs: IndexMap<&Path, Vec<FileDiff>> =
+                    IndexMap::new();
+                for diff in diffs {
+                    if let Some(root) = workspace_root_of(&roots, diff.path()) {
+                        folder_diffs.entry(root).or_default().push(diff);
+                    }
+                }
+                for (workspace, diffs) in folder_diffs {
                     match git_commit(workspace, &message, diffs) {
                         Ok(()) => (),
                         Err(e) => {
@@ -360,11 +455,15 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 }
             }
             GitDiscardFilesChanges { files } => {
-                if let Some(workspace) = self.workspace.as_ref() {
-                    match git_discard_files_changes(
-                        workspace,
-                        files.iter().map(AsRef::as_ref),
-                    ) {
+                let roots = self.workspace_roots();
+                let mut folder_files: IndexMap<&Path, Vec<&Path>> = IndexMap::new();
+                for file in files.iter() {
+                    if let Some(root) = workspace_root_of(&roots, file) {
+                        folder_files.entry(root).or_default().push(file.as_path());
+                    }
+                }
+                for (workspace, files) in folder_files {
+                    match git_discard_files_changes(workspace, files.into_iter()) {
                         Ok(()) => (),
                         Err(e) => eprintln!("{e:?}"),
                     }
@@ -386,6 +485,23 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     }
                 }
             }
+            RestartLanguageServer { plugin_id } => {
+                if let Err(err) = self.catalog_rpc.restart_language_server(plugin_id)
+                {
+                    tracing::error!("{:?}", err);
+                }
+            }
+            AnswerVoltPrompt { prompt_id, index } => {
+                self.catalog_rpc.answer_volt_prompt(prompt_id, index);
+            }
+            SetLanguageServerTrace { plugin_id, enabled } => {
+                if let Err(err) = self
+                    .catalog_rpc
+                    .set_language_server_trace(plugin_id, enabled)
+                {
+                    tracing::error!("{:?}", err);
+                }
+            }
             LspCancel { id } => {
                 self.catalog_rpc.send_notification(
                     None,
@@ -404,45 +520,119 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
     fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
         use ProxyRequest::*;
         match rpc {
-            NewBuffer { buffer_id, path } => {
-                let buffer = Buffer::new(buffer_id, path.clone());
-                let content = buffer.rope.to_string();
+            NewBuffer {
+                buffer_id,
+                path,
+                large_file_size,
+            } => {
+                let mut buffer =
+                    Buffer::new(buffer_id, path.clone(), large_file_size);
                 let read_only = buffer.read_only;
-                self.catalog_rpc.did_open_document(
-                    &path,
-                    buffer.language_id.to_string(),
-                    buffer.rev as i32,
-                    content.clone(),
-                );
+                let encoding = buffer.encoding.clone();
+                let large_file = buffer.large;
+                let disk_hash = buffer.disk_hash.clone();
+                let binary = buffer.binary;
+                // Large files are sent in chunks and kept from the language
+                // servers.
+                let content = if large_file {
+                    buffer.chunk(0).map(|(chunk, _)| chunk).unwrap_or_default()
+                } else {
+                    let content = buffer.rope.to_string();
+                    self.catalog_rpc.did_open_document(
+                        &path,
+                        buffer.language_id.to_string(),
+                        buffer.rev as i32,
+                        content.clone(),
+                    );
+                    content
+                };
                 self.file_watcher.watch(&path, false, OPEN_FILE_EVENT_TOKEN);
                 self.buffers.insert(path, buffer);
                 self.respond_rpc(
                     id,
-                    Ok(ProxyResponse::NewBufferResponse { content, read_only }),
+                    Ok(ProxyResponse::NewBufferResponse {
+                        content,
+                        read_only,
+                        encoding,
+                        large_file,
+                        disk_hash,
+                        binary,
+                    }),
                 );
             }
-            BufferHead { path } => {
-                let result = if let Some(workspace) = self.workspace.as_ref() {
-                    let result = file_get_head(workspace, &path);
-                    if let Ok((_blob_id, content)) = result {
-                        Ok(ProxyResponse::BufferHeadResponse {
-                            version: "head".to_string(),
-                            content,
+            LoadBufferChunk { path, offset } => {
+                let result = match self.buffers.get_mut(&path) {
+                    Some(buffer) => buffer
+                        .chunk(offset)
+                        .map(|(content, done)| {
+                            ProxyResponse::LoadBufferChunkResponse { content, done }
                         })
-                    } else {
-                        Err(RpcError {
+                        .map_err(|err| RpcError {
                             code: 0,
-                            message: "can't get file head".to_string(),
+                            message: err.to_string(),
+                        }),
+                    None => Err(RpcError {
+                        code: 0,
+                        message: "buffer isn't open".to_string(),
+                    }),
+                };
+                self.respond_rpc(id, result);
+            }
+            ReopenWithEncoding { path, encoding } => {
+                let result = match self.buffers.get_mut(&path) {
+                    Some(buffer) if buffer.large => Err(RpcError {
+                        code: 0,
+                        message: "can't reopen a large file with another encoding"
+                            .to_string(),
+                    }),
+                    Some(buffer) => load_file(&path, Some(&encoding))
+                        .map(|file| {
+                            buffer.encoding = file.encoding.clone();
+                            buffer.disk_hash.clone_from(&file.disk_hash);
+                            ProxyResponse::NewBufferResponse {
+                                content: file.content,
+                                read_only: buffer.read_only,
+                                encoding: file.encoding,
+                                large_file: false,
+                                disk_hash: file.disk_hash,
+                                binary: false,
+                            }
                         })
-                    }
-                } else {
-                    Err(RpcError {
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        }),
+                    None => Err(RpcError {
                         code: 0,
-                        message: "no workspace set".to_string(),
-                    })
+                        message: "buffer isn't open".to_string(),
+                    }),
                 };
                 self.respond_rpc(id, result);
             }
+            BufferHead { path } => {
+                let roots = self.workspace_roots();
+                let result =
+                    if let Some(workspace) = workspace_root_of(&roots, &path) {
+                        let result = file_get_head(workspace, &path);
+                        if let Ok((_blob_id, content)) = result {
+                            Ok(ProxyResponse::BufferHeadResponse {
+                                version: "head".to_string(),
+                                content,
+                            })
+                        } else {
+                            Err(RpcError {
+                                code: 0,
+                                message: "can't get file head".to_string(),
+                            })
+                        }
+                    } else {
+                        Err(RpcError {
+                            code: 0,
+                            message: "no workspace set".to_string(),
+                        })
+                    };
+                self.respond_rpc(id, result);
+            }
             GlobalSearch {
                 pattern,
                 case_sensitive,
@@ -452,7 +642,7 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 static WORKER_ID: AtomicU64 = AtomicU64::new(0);
                 let our_id = WORKER_ID.fetch_add(1, Ordering::SeqCst) + 1;
 
-                let workspace = self.workspace.clone();
+                let roots = self.workspace_roots();
                 let buffers = self
                     .buffers
                     .iter()
@@ -468,7 +658,7 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                         search_in_path(
                             our_id,
                             &WORKER_ID,
-                            workspace
+                            roots
                                 .iter()
                                 .flat_map(|w| ignore::Walk::new(w).flatten())
                                 .chain(
@@ -503,6 +693,22 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
+            ExecuteVoltCommand {
+                plugin_id,
+                command,
+                context,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.execute_volt_command(
+                    plugin_id,
+                    command,
+                    context,
+                    move |result| {
+                        let result = result.map(|_| ProxyResponse::Success {});
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
             GetHover {
                 request_id,
                 path,
@@ -532,7 +738,8 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 );
             }
             GitGetRemoteFileUrl { file } => {
-                if let Some(workspace) = self.workspace.as_ref() {
+                let roots = self.workspace_roots();
+                if let Some(workspace) = workspace_root_of(&roots, &file) {
                     match git_get_remote_file_url(workspace, &file) {
                         Ok(s) => self.proxy_rpc.handle_response(
                             id,
@@ -643,14 +850,13 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
-            GetSemanticTokens { path } => {
+            GetSemanticTokens { path, range } => {
                 let buffer = self.buffers.get(&path).unwrap();
                 let text = buffer.rope.clone();
                 let rev = buffer.rev;
                 let len = buffer.len();
                 let local_path = path.clone();
                 let proxy_rpc = self.proxy_rpc.clone();
-                let catalog_rpc = self.catalog_rpc.clone();
 
                 let handle_tokens =
                     move |result: Result<Vec<LineStyle>, RpcError>| match result {
@@ -672,22 +878,11 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                         }
                     };
 
-                let proxy_rpc = self.proxy_rpc.clone();
                 self.catalog_rpc.get_semantic_tokens(
-                    &path,
-                    move |plugin_id, result| match result {
-                        Ok(result) => {
-                            catalog_rpc.format_semantic_tokens(
-                                plugin_id,
-                                result,
-                                text,
-                                Box::new(handle_tokens),
-                            );
-                        }
-                        Err(e) => {
-                            proxy_rpc.handle_response(id, Err(e));
-                        }
-                    },
+                    path,
+                    range,
+                    text,
+                    Box::new(handle_tokens),
                 );
             }
             GetCodeActions {
@@ -767,10 +962,11 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 );
             }
             GetFiles { .. } => {
-                let workspace = self.workspace.clone();
+                let roots = self.workspace_roots();
                 let proxy_rpc = self.proxy_rpc.clone();
                 thread::spawn(move || {
-                    let result = if let Some(workspace) = workspace {
+                    let mut items = Vec::new();
+                    for workspace in roots {
                         let git_folder =
                             ignore::overrides::OverrideBuilder::new(&workspace)
                                 .add("!.git/")
@@ -791,7 +987,6 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                                 .build(),
                         };
 
-                        let mut items = Vec::new();
                         for path in walker.flatten() {
                             if let Some(file_type) = path.file_type() {
                                 if file_type.is_file() {
@@ -799,10 +994,8 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                                 }
                             }
                         }
-                        Ok(ProxyResponse::GetFilesResponse { items })
-                    } else {
-                        Ok(ProxyResponse::GetFilesResponse { items: Vec::new() })
-                    };
+                    }
+                    let result = Ok(ProxyResponse::GetFilesResponse { items });
                     proxy_rpc.handle_response(id, result);
                 });
             }
@@ -856,14 +1049,20 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 rev,
                 path,
                 create_parents,
+                encoding,
             } => {
                 let buffer = self.buffers.get_mut(&path).unwrap();
+                buffer.encoding = encoding;
                 let result = buffer
                     .save(rev, create_parents)
                     .map(|_r| {
-                        self.catalog_rpc
-                            .did_save_text_document(&path, buffer.rope.clone());
-                        ProxyResponse::SaveResponse {}
+                        if !buffer.large {
+                            self.catalog_rpc
+                                .did_save_text_document(&path, buffer.rope.clone());
+                        }
+                        ProxyResponse::SaveResponse {
+                            disk_hash: buffer.disk_hash.clone(),
+                        }
                     })
                     .map_err(|e| RpcError {
                         code: 0,
@@ -877,13 +1076,17 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 rev,
                 content,
                 create_parents,
+                encoding,
             } => {
-                let mut buffer = Buffer::new(buffer_id, path.clone());
+                let mut buffer = Buffer::new(buffer_id, path.clone(), None);
                 buffer.rope = Rope::from(content);
                 buffer.rev = rev;
+                buffer.encoding = encoding;
                 let result = buffer
                     .save(rev, create_parents)
-                    .map(|_| ProxyResponse::Success {})
+                    .map(|_| ProxyResponse::SaveResponse {
+                        disk_hash: buffer.disk_hash.clone(),
+                    })
                     .map_err(|e| RpcError {
                         code: 0,
                         message: e.to_string(),
@@ -891,6 +1094,38 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 self.buffers.insert(path, buffer);
                 self.respond_rpc(id, result);
             }
+            ReadFileBytes { path } => {
+                let result = fs::read(&path)
+                    .map(|content| {
+                        let disk_hash = disk_hash(&content);
+                        self.file_watcher.watch(&path, false, OPEN_FILE_EVENT_TOKEN);
+                        self.byte_files.insert(path, disk_hash.clone());
+                        ProxyResponse::ReadFileBytesResponse { content, disk_hash }
+                    })
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+                self.respond_rpc(id, result);
+            }
+            SaveFileBytes {
+                path,
+                content,
+                disk_hash,
+            } => {
+                let result = save_file_bytes(&path, &content, disk_hash.as_deref())
+                    .map(|disk_hash| {
+                        self.byte_files.insert(path, disk_hash.clone());
+                        ProxyResponse::SaveResponse {
+                            disk_hash: Some(disk_hash),
+                        }
+                    })
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+                self.respond_rpc(id, result);
+            }
             CreateFile { path } => {
                 let result = path
                     .parent()
@@ -906,24 +1141,33 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                         code: 0,
                         message: e.to_string(),
                     });
+                if result.is_ok() {
+                    self.catalog_rpc.did_create_files(&[path]);
+                }
                 self.respond_rpc(id, result);
             }
             CreateDirectory { path } => {
-                let result = std::fs::create_dir_all(path)
+                let result = std::fs::create_dir_all(&path)
                     .map(|_| ProxyResponse::Success {})
                     .map_err(|e| RpcError {
                         code: 0,
                         message: e.to_string(),
                     });
+                if result.is_ok() {
+                    self.catalog_rpc.did_create_files(&[path]);
+                }
                 self.respond_rpc(id, result);
             }
             TrashPath { path } => {
-                let result = trash::delete(path)
+                let result = trash::delete(&path)
                     .map(|_| ProxyResponse::Success {})
                     .map_err(|e| RpcError {
                         code: 0,
                         message: e.to_string(),
                     });
+                if result.is_ok() {
+                    self.catalog_rpc.did_delete_files(&[path]);
+                }
                 self.respond_rpc(id, result);
             }
             DuplicatePath {
@@ -948,13 +1192,16 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                             return;
                         }
                     }
-                    std::fs::copy(existing_path, new_path)
+                    std::fs::copy(existing_path, &new_path)
                         .map(|_| ProxyResponse::Success {})
                         .map_err(|e| RpcError {
                             code: 0,
                             message: e.to_string(),
                         })
                 };
+                if result.is_ok() {
+                    self.catalog_rpc.did_create_files(&[new_path]);
+                }
                 self.respond_rpc(id, result);
             }
             RenamePath { from, to } => {
@@ -1030,12 +1277,40 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                             }
                         }
 
+                        self.catalog_rpc.did_rename_files(&from, &to);
+
                         ProxyResponse::CreatePathResponse { path: to }
                     })
                     .map_err(|message| RpcError { code: 0, message });
 
                 self.respond_rpc(id, result);
             }
+            WillRenameFiles { from, to } => {
+                let (tx, rx) = crossbeam_channel::bounded(1);
+                self.catalog_rpc
+                    .will_rename_files(&from, &to, move |result| {
+                        if let Err(err) = tx.send(result) {
+                            tracing::error!("{:?}", err);
+                        }
+                    });
+
+                // Never let a slow or stuck server block the rename itself.
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let edits = match rx.recv_timeout(WILL_RENAME_FILES_TIMEOUT) {
+                        Ok(Ok(edits)) => edits,
+                        Ok(Err(err)) => {
+                            tracing::error!("{:?}", err);
+                            Vec::new()
+                        }
+                        Err(_) => Vec::new(),
+                    };
+                    proxy_rpc.handle_response(
+                        id,
+                        Ok(ProxyResponse::WillRenameFilesResponse { edits }),
+                    );
+                });
+            }
             TestCreateAtPath { path } => {
                 // This performs a best effort test to see if an attempt to create an item at
                 // `path` or rename an item to `path` will succeed.
@@ -1217,10 +1492,12 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
 
         Self {
             workspace: None,
+            workspace_folders: Arc::new(Mutex::new(Vec::new())),
             proxy_rpc,
             core_rpc,
             catalog_rpc: plugin_rpc,
             buffers: HashMap::new(),
+            byte_files: HashMap::new(),
             terminals: HashMap::new(),
             file_watcher,
             window_id: 1,
@@ -1228,14 +1505,25 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
         }
     }
 
+    /// The primary workspace folder followed by any additional root folders.
+    fn workspace_roots(&self) -> Vec<PathBuf> {
+        self.workspace
+            .iter()
+            .cloned()
+            .chain(self.workspace_folders.lock().iter().cloned())
+            .collect()
+    }
+
     fn respond_rpc(&self, id: RequestId, result: Result<ProxyResponse, RpcError>) {
         self.proxy_rpc.handle_response(id, result);
     }
 
     fn get_buffer_or_insert(&mut self, path: PathBuf) -> &mut Buffer {
-        self.buffers
-            .entry(path.clone())
-            .or_insert(Buffer::new(BufferId::next(), path))
+        self.buffers.entry(path.clone()).or_insert(Buffer::new(
+            BufferId::next(),
+            path,
+            None,
+        ))
     }
 }
 
@@ -1243,6 +1531,7 @@ struct FileWatchNotifier {
     core_rpc: CoreRpcHandler,
     proxy_rpc: ProxyRpcHandler,
     workspace: Option<PathBuf>,
+    workspace_folders: Arc<Mutex<Vec<PathBuf>>>,
     workspace_fs_change_handler: Arc<Mutex<Option<Sender<bool>>>>,
     last_diff: Arc<Mutex<DiffInfo>>,
 }
@@ -1256,11 +1545,13 @@ fn notify(&self, events: Vec<(WatchToken, notify::Event)>) {
 impl FileWatchNotifier {
     fn new(
         workspace: Option<PathBuf>,
+        workspace_folders: Arc<Mutex<Vec<PathBuf>>>,
         core_rpc: CoreRpcHandler,
         proxy_rpc: ProxyRpcHandler,
     ) -> Self {
         let notifier = Self {
             workspace,
+            workspace_folders,
             core_rpc,
             proxy_rpc,
             workspace_fs_change_handler: Arc::new(Mutex::new(None)),
@@ -1268,10 +1559,11 @@ fn new(
         };
 
         if let Some(workspace) = notifier.workspace.clone() {
+            let folders = notifier.workspace_folders.lock().clone();
             let core_rpc = notifier.core_rpc.clone();
             let last_diff = notifier.last_diff.clone();
             thread::spawn(move || {
-                if let Some(diff) = git_diff_new(&workspace) {
+                if let Some(diff) = workspace_diff(&workspace, &folders) {
                     core_rpc.diff_info(diff.clone());
                     *last_diff.lock() = diff;
                 }
@@ -1341,6 +1633,7 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
         let local_handler = self.workspace_fs_change_handler.clone();
         let core_rpc = self.core_rpc.clone();
         let workspace = self.workspace.clone().unwrap();
+        let folders = self.workspace_folders.lock().clone();
         let last_diff = self.last_diff.clone();
         thread::spawn(move || {
             thread::sleep(Duration::from_millis(500));
@@ -1359,7 +1652,7 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
             if explorer_change {
                 core_rpc.workspace_file_change();
             }
-            if let Some(diff) = git_diff_new(&workspace) {
+            if let Some(diff) = workspace_diff(&workspace, &folders) {
                 let mut last_diff = last_diff.lock();
                 if diff != *last_diff {
                     core_rpc.diff_info(diff.clone());
@@ -1613,9 +1906,33 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
         branches,
         tags,
         diffs: file_diffs,
+        folders: Vec::new(),
     })
 }
 
+/// The git status of the primary workspace folder, along with that of every
+/// additional root folder.
+fn workspace_diff(workspace: &Path, folders: &[PathBuf]) -> Option<DiffInfo> {
+    let folders: Vec<FolderDiffInfo> = folders
+        .iter()
+        .filter_map(|folder| {
+            let diff = git_diff_new(folder)?;
+            Some(FolderDiffInfo {
+                path: folder.clone(),
+                head: diff.head,
+                diffs: diff.diffs,
+            })
+        })
+        .collect();
+    let mut diff = match git_diff_new(workspace) {
+        Some(diff) => diff,
+        None if folders.is_empty() => return None,
+        None => DiffInfo::default(),
+    };
+    diff.folders = folders;
+    Some(diff)
+}
+
 fn file_get_head(workspace_path: &Path, path: &Path) -> Result<(String, String)> {
     let repo = Repository::discover(workspace_path)?;
     let head = repo.head()?;
@@ -5,6 +5,8 @@
 pub mod dispatch;
 pub mod plugin;
 pub mod terminal;
+#[cfg(unix)]
+pub mod terminal_server;
 pub mod watcher;
 
 use std::{
@@ -34,6 +36,10 @@ struct Cli {
     #[clap(short, long, action, hide = true)]
     proxy: bool,
 
+    /// Run the process that keeps terminal sessions alive
+    #[clap(long, action, hide = true)]
+    terminal_server: bool,
+
     /// Paths to file(s) and/or folder(s) to open.
     /// When path is a file (that exists or not),
     /// it accepts `path:line:column` syntax
@@ -45,6 +51,11 @@ struct Cli {
 
 pub fn mainloop() {
     let cli = Cli::parse();
+    if cli.terminal_server {
+        #[cfg(unix)]
+        terminal_server::mainloop();
+        return;
+    }
     if !cli.proxy {
         if let Err(e) = cli::try_open_in_existing_process(&cli.paths) {
             error!("failed to open path(s): {e}");
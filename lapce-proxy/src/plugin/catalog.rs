@@ -1,12 +1,13 @@
 use std::{
     borrow::Cow,
-    collections::HashMap,
-    path::PathBuf,
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
     sync::{
         Arc,
         atomic::{AtomicUsize, Ordering},
     },
     thread,
+    time::{Duration, Instant},
 };
 
 use lapce_rpc::{
@@ -18,8 +19,9 @@
 };
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
-    DidOpenTextDocumentParams, MessageType, SemanticTokens, ShowMessageParams,
-    TextDocumentIdentifier, TextDocumentItem, VersionedTextDocumentIdentifier,
+    DidOpenTextDocumentParams, MessageType, Range, RenameFilesParams,
+    ShowMessageParams, TextDocumentIdentifier, TextDocumentItem,
+    VersionedTextDocumentIdentifier, WorkspaceEdit,
     notification::DidOpenTextDocument, request::Request,
 };
 use parking_lot::Mutex;
@@ -29,13 +31,29 @@
 use super::{
     PluginCatalogNotification, PluginCatalogRpcHandler,
     dap::{DapClient, DapRpcHandler, DebuggerData},
-    psp::{ClonableCallback, PluginServerRpc, PluginServerRpcHandler, RpcCallback},
+    lsp::LspServerConfig,
+    psp::{
+        ClonableCallback, FileOperation, PluginServerRpc, PluginServerRpcHandler,
+        RpcCallback,
+    },
     wasi::{load_all_volts, start_volt},
 };
-use crate::plugin::{
-    install_volt, psp::PluginHandlerNotification, wasi::enable_volt,
+use crate::{
+    buffer::language_id_from_path,
+    plugin::{install_volt, psp::PluginHandlerNotification, wasi::enable_volt},
 };
 
+/// Volts which crash this many times in a row aren't restarted anymore.
+const MAX_VOLT_RESTARTS: u32 = 5;
+/// A crash this long after the previous one doesn't count as in a row.
+const VOLT_CRASH_RESET: Duration = Duration::from_secs(300);
+
+/// The recent crashes of a volt, to restart it with a growing delay.
+struct VoltCrashes {
+    count: u32,
+    last: Instant,
+}
+
 pub struct PluginCatalog {
     workspace: Option<PathBuf>,
     plugin_rpc: PluginCatalogRpcHandler,
@@ -45,6 +63,10 @@ pub struct PluginCatalog {
     plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
     unactivated_volts: HashMap<VoltID, VoltMetadata>,
     open_files: HashMap<PathBuf, String>,
+    /// The languages and roots of the servers from the `[lsp.<language>]`
+    /// settings that are running
+    started_lsp_servers: HashSet<(String, Option<PathBuf>)>,
+    volt_crashes: HashMap<VoltID, VoltCrashes>,
 }
 
 impl PluginCatalog {
@@ -64,6 +86,8 @@ pub fn new(
             debuggers: HashMap::new(),
             unactivated_volts: HashMap::new(),
             open_files: HashMap::new(),
+            started_lsp_servers: HashSet::new(),
+            volt_crashes: HashMap::new(),
         };
 
         thread::spawn(move || {
@@ -221,6 +245,12 @@ fn start_unactivated_volts(&mut self, to_be_activated: Vec<VoltID>) {
     }
 
     fn check_unactivated_volts(&mut self) {
+        let roots: Vec<PathBuf> = self
+            .workspace
+            .iter()
+            .cloned()
+            .chain(self.plugin_rpc.workspace_folders())
+            .collect();
         let to_be_activated: Vec<VoltID> = self
             .unactivated_volts
             .iter()
@@ -239,7 +269,7 @@ fn check_unactivated_volts(&mut self) {
                     return Some(id.clone());
                 }
 
-                if let Some(workspace) = self.workspace.as_ref() {
+                if !roots.is_empty() {
                     if let Some(globs) = meta
                         .activation
                         .as_ref()
@@ -259,10 +289,11 @@ fn check_unactivated_volts(&mut self) {
                         match builder.build() {
                             Ok(matcher) => {
                                 if !matcher.is_empty() {
-                                    for entry in walkdir::WalkDir::new(workspace)
-                                        .into_iter()
-                                        .flatten()
-                                    {
+                                    for entry in roots.iter().flat_map(|root| {
+                                        walkdir::WalkDir::new(root)
+                                            .into_iter()
+                                            .flatten()
+                                    }) {
                                         if matcher.is_match(entry.path()) {
                                             return Some(id.clone());
                                         }
@@ -282,6 +313,64 @@ fn check_unactivated_volts(&mut self) {
         self.start_unactivated_volts(to_be_activated);
     }
 
+    /// The language servers declared in the `[lsp.<language>]` sections of
+    /// the settings
+    fn lsp_servers(&self) -> HashMap<String, LspServerConfig> {
+        let Some(servers) = self.plugin_configurations.get("lsp") else {
+            return HashMap::new();
+        };
+        servers
+            .iter()
+            .filter_map(|(language, config)| {
+                match serde_json::from_value(config.clone()) {
+                    Ok(config) => Some((language.clone(), config)),
+                    Err(err) => {
+                        tracing::error!("[lsp.{language}] {:?}", err);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn start_lsp_servers(&mut self, language_id: &str, path: &Path) {
+        let roots: Vec<PathBuf> = self
+            .workspace
+            .iter()
+            .cloned()
+            .chain(self.plugin_rpc.workspace_folders())
+            .collect();
+        for (language, config) in self.lsp_servers() {
+            if !config.handles(&language, language_id, path) {
+                continue;
+            }
+            let key = (language, config.find_root(path, &roots));
+            if self.started_lsp_servers.contains(&key) {
+                continue;
+            }
+            match config.start(self.plugin_rpc.clone(), &key.0, key.1.clone()) {
+                Ok(_) => {
+                    self.started_lsp_servers.insert(key);
+                }
+                Err(err) => {
+                    tracing::error!("{:?}", err);
+                }
+            }
+        }
+    }
+
+    fn stop_lsp_server(&mut self, language: &str) {
+        self.started_lsp_servers.retain(|(l, _)| l != language);
+        let volt_id = LspServerConfig::volt_id(language);
+        let ids: Vec<PluginId> = self.plugins.keys().cloned().collect();
+        for id in ids {
+            if self.plugins.get(&id).unwrap().volt_id == volt_id {
+                let plugin = self.plugins.remove(&id).unwrap();
+                plugin.shutdown();
+            }
+        }
+    }
+
     pub fn handle_did_open_text_document(&mut self, document: TextDocumentItem) {
         match document.uri.to_file_path() {
             Ok(path) => {
@@ -307,6 +396,9 @@ pub fn handle_did_open_text_document(&mut self, document: TextDocumentItem) {
         self.start_unactivated_volts(to_be_activated);
 
         let path = document.uri.to_file_path().ok();
+        if let Some(path) = path.as_ref() {
+            self.start_lsp_servers(&document.language_id, path);
+        }
         for (_, plugin) in self.plugins.iter() {
             plugin.server_notification(
                 DidOpenTextDocument::METHOD,
@@ -358,24 +450,90 @@ pub fn handle_did_change_text_document(
         }
     }
 
-    pub fn format_semantic_tokens(
+    /// Collect the edits every interested server wants applied before the
+    /// rename, calling `f` once all of them have answered.
+    pub fn handle_will_rename_files(
         &self,
-        plugin_id: PluginId,
-        tokens: SemanticTokens,
+        params: RenameFilesParams,
+        f: Box<dyn RpcCallback<Vec<WorkspaceEdit>, RpcError>>,
+    ) {
+        if self.plugins.is_empty() {
+            f.call(Ok(Vec::new()));
+            return;
+        }
+
+        let remaining = Arc::new(AtomicUsize::new(self.plugins.len()));
+        let edits = Arc::new(Mutex::new(Vec::new()));
+        let f = Arc::new(Mutex::new(Some(f)));
+        for (_, plugin) in self.plugins.iter() {
+            let remaining = remaining.clone();
+            let edits = edits.clone();
+            let f = f.clone();
+            plugin.handle_rpc(PluginServerRpc::WillRenameFiles {
+                params: params.clone(),
+                f: Box::new(move |result: Result<Value, RpcError>| {
+                    match result.map(serde_json::from_value::<Option<WorkspaceEdit>>)
+                    {
+                        Ok(Ok(Some(edit))) => edits.lock().push(edit),
+                        Ok(Ok(None)) => {}
+                        Ok(Err(err)) => tracing::error!("{:?}", err),
+                        Err(err) => tracing::error!("{:?}", err),
+                    }
+                    if remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+                        if let Some(f) = f.lock().take() {
+                            f.call(Ok(std::mem::take(&mut *edits.lock())));
+                        }
+                    }
+                }),
+            });
+        }
+    }
+
+    pub fn handle_did_file_operation(&self, operation: FileOperation) {
+        for (_, plugin) in self.plugins.iter() {
+            plugin.handle_rpc(PluginServerRpc::DidFileOperation {
+                operation: operation.clone(),
+            });
+        }
+    }
+
+    /// Request the semantic styles of a document from every plugin, the first
+    /// successful response is used.
+    pub fn handle_semantic_tokens(
+        &self,
+        path: PathBuf,
+        range: Option<Range>,
         text: Rope,
         f: Box<dyn RpcCallback<Vec<LineStyle>, RpcError>>,
     ) {
-        if let Some(plugin) = self.plugins.get(&plugin_id) {
-            plugin.handle_rpc(PluginServerRpc::FormatSemanticTokens {
-                tokens,
-                text,
-                f,
-            });
-        } else {
+        if self.plugins.is_empty() {
             f.call(Err(RpcError {
                 code: 0,
-                message: "plugin doesn't exist".to_string(),
+                message: "no plugin available".to_string(),
             }));
+            return;
+        }
+
+        let language_id = language_id_from_path(&path).unwrap_or("").to_string();
+        let remaining = Arc::new(AtomicUsize::new(self.plugins.len()));
+        let f = Arc::new(Mutex::new(Some(f)));
+        for (_, plugin) in self.plugins.iter() {
+            let remaining = remaining.clone();
+            let f = f.clone();
+            plugin.handle_rpc(PluginServerRpc::SemanticTokens {
+                path: path.clone(),
+                language_id: language_id.clone(),
+                range,
+                text: text.clone(),
+                f: Box::new(move |result: Result<Vec<LineStyle>, RpcError>| {
+                    let last = remaining.fetch_sub(1, Ordering::AcqRel) == 1;
+                    if result.is_ok() || last {
+                        if let Some(f) = f.lock().take() {
+                            f.call(result);
+                        }
+                    }
+                }),
+            });
         }
     }
 
@@ -486,7 +644,22 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
             }
             UpdatePluginConfigs(configs) => {
                 tracing::debug!("UpdatePluginConfigs {:?}", configs);
+                let old_servers = self.lsp_servers();
                 self.plugin_configurations = configs;
+                let new_servers = self.lsp_servers();
+                for (language, config) in old_servers {
+                    if new_servers.get(&language) != Some(&config) {
+                        self.stop_lsp_server(&language);
+                    }
+                }
+                let open_files: Vec<(PathBuf, String)> = self
+                    .open_files
+                    .iter()
+                    .map(|(path, language_id)| (path.clone(), language_id.clone()))
+                    .collect();
+                for (path, language_id) in open_files {
+                    self.start_lsp_servers(&language_id, &path);
+                }
             }
             PluginServerLoaded(plugin) => {
                 // TODO: check if the server has did open registered
@@ -527,17 +700,22 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                     }
                 }
             }
-            InstallVolt(volt) => {
-                tracing::debug!("InstallVolt {:?}", volt);
+            InstallVolt(volt, source, approved) => {
+                tracing::debug!("InstallVolt {:?} from {:?}", volt, source);
                 let workspace = self.workspace.clone();
                 let configurations =
                     self.plugin_configurations.get(&volt.name).cloned();
                 let catalog_rpc = self.plugin_rpc.clone();
                 catalog_rpc.stop_volt(volt.clone());
                 thread::spawn(move || {
-                    if let Err(err) =
-                        install_volt(catalog_rpc, workspace, configurations, volt)
-                    {
+                    if let Err(err) = install_volt(
+                        catalog_rpc,
+                        workspace,
+                        configurations,
+                        volt,
+                        source,
+                        approved,
+                    ) {
                         tracing::error!("{:?}", err);
                     }
                 });
@@ -545,6 +723,21 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
             ReloadVolt(volt) => {
                 tracing::debug!("ReloadVolt {:?}", volt);
                 let volt_id = volt.id();
+                self.volt_crashes.remove(&volt_id);
+                let ids: Vec<PluginId> = self.plugins.keys().cloned().collect();
+                for id in ids {
+                    if self.plugins.get(&id).unwrap().volt_id == volt_id {
+                        let plugin = self.plugins.remove(&id).unwrap();
+                        plugin.shutdown();
+                    }
+                }
+                if let Err(err) = self.plugin_rpc.unactivated_volts(vec![volt]) {
+                    tracing::error!("{:?}", err);
+                }
+            }
+            VoltCrashed(volt, message) => {
+                tracing::debug!("VoltCrashed {:?} {}", volt, message);
+                let volt_id = volt.id();
                 let ids: Vec<PluginId> = self.plugins.keys().cloned().collect();
                 for id in ids {
                     if self.plugins.get(&id).unwrap().volt_id == volt_id {
@@ -552,6 +745,47 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                         plugin.shutdown();
                     }
                 }
+
+                let now = Instant::now();
+                let crashes = self.volt_crashes.entry(volt_id.clone()).or_insert(
+                    VoltCrashes {
+                        count: 0,
+                        last: now,
+                    },
+                );
+                if now.duration_since(crashes.last) > VOLT_CRASH_RESET {
+                    crashes.count = 0;
+                }
+                crashes.count += 1;
+                crashes.last = now;
+
+                let message = if crashes.count > MAX_VOLT_RESTARTS {
+                    self.volt_crashes.remove(&volt_id);
+                    format!(
+                        "{message}, the plugin kept crashing and won't be restarted"
+                    )
+                } else {
+                    let delay = Duration::from_secs(1 << (crashes.count - 1));
+                    let plugin_rpc = self.plugin_rpc.clone();
+                    thread::spawn(move || {
+                        thread::sleep(delay);
+                        if let Err(err) = plugin_rpc.restart_crashed_volt(volt) {
+                            tracing::error!("{:?}", err);
+                        }
+                    });
+                    format!("{message}, restarting in {}s", delay.as_secs())
+                };
+                self.plugin_rpc.core_rpc.volt_crashed(volt_id, message);
+            }
+            RestartCrashedVolt(volt) => {
+                tracing::debug!("RestartCrashedVolt {:?}", volt);
+                let volt_id = volt.id();
+                // The volt was stopped or reloaded while waiting for the restart
+                if !self.volt_crashes.contains_key(&volt_id)
+                    || self.plugins.values().any(|p| p.volt_id == volt_id)
+                {
+                    return;
+                }
                 if let Err(err) = self.plugin_rpc.unactivated_volts(vec![volt]) {
                     tracing::error!("{:?}", err);
                 }
@@ -559,6 +793,7 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
             StopVolt(volt) => {
                 tracing::debug!("StopVolt {:?}", volt);
                 let volt_id = volt.id();
+                self.volt_crashes.remove(&volt_id);
                 let ids: Vec<PluginId> = self.plugins.keys().cloned().collect();
                 for id in ids {
                     if self.plugins.get(&id).unwrap().volt_id == volt_id {
@@ -567,6 +802,21 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                     }
                 }
             }
+            RestartLanguageServer(plugin_id) => {
+                tracing::debug!("RestartLanguageServer {:?}", plugin_id);
+                if let Some(plugin) = self.plugins.remove(&plugin_id) {
+                    plugin.handle_rpc(PluginServerRpc::Handler(
+                        PluginHandlerNotification::Restart,
+                    ));
+                }
+            }
+            SetLanguageServerTrace(plugin_id, enabled) => {
+                if let Some(plugin) = self.plugins.get(&plugin_id) {
+                    plugin.handle_rpc(PluginServerRpc::Handler(
+                        PluginHandlerNotification::SetTrace(enabled),
+                    ));
+                }
+            }
             EnableVolt(volt) => {
                 tracing::debug!("EnableVolt {:?}", volt);
                 let volt_id = volt.id();
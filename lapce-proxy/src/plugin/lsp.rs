@@ -1,36 +1,40 @@
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 use std::{
+    fs::{self, File},
     io::{BufRead, BufReader, BufWriter, Write},
     path::{Path, PathBuf},
     process::{self, Child, Command, Stdio},
     sync::Arc,
     thread,
+    time::Instant,
 };
 
 use anyhow::{Result, anyhow};
 use jsonrpc_lite::{Id, Params};
-use lapce_core::meta;
+use lapce_core::{directory::Directory, meta};
 use lapce_rpc::{
     RpcError,
-    plugin::{PluginId, VoltID},
+    file::workspace_root_of,
+    plugin::{LanguageServerInfo, PluginId, VoltID, VoltPermissions},
     style::LineStyle,
 };
 use lapce_xi_rope::Rope;
 use lsp_types::{
     notification::{Initialized, Notification},
-    request::{Initialize, Request},
+    request::{Initialize, RegisterCapability, Request, UnregisterCapability},
     *,
 };
 use parking_lot::Mutex;
+use serde::Deserialize;
 use serde_json::Value;
 
 use super::{
     client_capabilities,
     psp::{
-        PluginHandlerNotification, PluginHostHandler, PluginServerHandler,
-        PluginServerRpcHandler, ResponseSender, RpcCallback,
-        handle_plugin_server_message,
+        FileOperation, PluginHandlerNotification, PluginHostHandler,
+        PluginServerHandler, PluginServerRpc, PluginServerRpcHandler,
+        ResponseSender, RpcCallback, handle_plugin_server_message,
     },
 };
 use crate::{buffer::Buffer, plugin::PluginCatalogRpcHandler};
@@ -58,6 +62,71 @@ pub enum LspRpc {
     },
 }
 
+/// Everything needed to start a language server again after it was shut down
+#[derive(Clone)]
+struct LspLaunch {
+    document_selector: DocumentSelector,
+    workspace: Option<PathBuf>,
+    volt_id: VoltID,
+    volt_display_name: String,
+    spawned_by: Option<PluginId>,
+    pwd: Option<PathBuf>,
+    server_uri: Url,
+    args: Vec<String>,
+    options: Option<Value>,
+}
+
+/// The log the messages exchanged with a language server are written to,
+/// while tracing is enabled
+#[derive(Clone, Default)]
+struct LspTrace(Arc<Mutex<Option<(PathBuf, Instant, File)>>>);
+
+impl LspTrace {
+    fn path(&self) -> Option<PathBuf> {
+        self.0.lock().as_ref().map(|(path, _, _)| path.clone())
+    }
+
+    /// Start writing the log of the `server` binary of a volt, which has its
+    /// own file as a volt can start more than one server.
+    fn enable(&self, volt_id: &VoltID, server: &str) -> Result<()> {
+        let folder = Directory::logs_directory()
+            .ok_or_else(|| anyhow!("can't get logs directory"))?
+            .join("lsp");
+        fs::create_dir_all(&folder)?;
+        let server: String = server
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let path =
+            folder.join(format!("{}.{}.{server}.log", volt_id.author, volt_id.name));
+        let file = File::options().create(true).append(true).open(&path)?;
+        *self.0.lock() = Some((path, Instant::now(), file));
+        Ok(())
+    }
+
+    fn disable(&self) {
+        *self.0.lock() = None;
+    }
+
+    fn write(&self, direction: &str, message: &str) {
+        if let Some((_, start, file)) = self.0.lock().as_mut() {
+            if let Err(err) = writeln!(
+                file,
+                "[{:.3}] {direction} {message}",
+                start.elapsed().as_secs_f64()
+            ) {
+                tracing::error!("{:?}", err);
+            }
+        }
+    }
+}
+
 pub struct LspClient {
     plugin_rpc: PluginCatalogRpcHandler,
     server_rpc: PluginServerRpcHandler,
@@ -65,6 +134,8 @@ pub struct LspClient {
     workspace: Option<PathBuf>,
     host: PluginHostHandler,
     options: Option<Value>,
+    launch: LspLaunch,
+    trace: LspTrace,
 }
 
 impl PluginServerHandler for LspClient {
@@ -95,6 +166,13 @@ fn handle_handler_notification(
             Shutdown => {
                 self.shutdown();
             }
+            Restart => {
+                self.restart();
+            }
+            SetTrace(enabled) => {
+                self.set_trace(enabled);
+                self.send_status();
+            }
             SpawnedPluginLoaded { .. } => {}
         }
     }
@@ -106,7 +184,13 @@ fn handle_host_request(
         params: Params,
         resp: ResponseSender,
     ) {
+        let registration = method == RegisterCapability::METHOD
+            || method == UnregisterCapability::METHOD;
         self.host.handle_request(id, method, params, resp);
+        if registration {
+            // The features listed for the server can change
+            self.send_status();
+        }
     }
 
     fn handle_host_notification(
@@ -159,13 +243,28 @@ fn handle_did_change_text_document(
         );
     }
 
-    fn format_semantic_tokens(
+    fn handle_semantic_tokens(
         &self,
-        tokens: SemanticTokens,
+        path: PathBuf,
+        language_id: String,
+        range: Option<Range>,
         text: Rope,
         f: Box<dyn RpcCallback<Vec<LineStyle>, RpcError>>,
     ) {
-        self.host.format_semantic_tokens(tokens, text, f);
+        self.host
+            .handle_semantic_tokens(path, language_id, range, text, f);
+    }
+
+    fn handle_will_rename_files(
+        &self,
+        params: RenameFilesParams,
+        f: Box<dyn RpcCallback<Value, RpcError>>,
+    ) {
+        self.host.handle_will_rename_files(params, f);
+    }
+
+    fn handle_did_file_operation(&self, operation: FileOperation) {
+        self.host.handle_did_file_operation(operation);
     }
 }
 
@@ -184,6 +283,17 @@ fn new(
         args: Vec<String>,
         options: Option<Value>,
     ) -> Result<Self> {
+        let launch = LspLaunch {
+            document_selector: document_selector.clone(),
+            workspace: workspace.clone(),
+            volt_id: volt_id.clone(),
+            volt_display_name: volt_display_name.clone(),
+            spawned_by,
+            pwd: pwd.clone(),
+            server_uri: server_uri.clone(),
+            args: args.clone(),
+            options: options.clone(),
+        };
         let server = match server_uri.scheme() {
             "file" => {
                 let path = server_uri.to_file_path().map_err(|_| anyhow!(""))?;
@@ -214,6 +324,8 @@ fn new(
             plugin_id,
             io_tx.clone(),
         );
+        let trace = LspTrace::default();
+        let local_trace = trace.clone();
         thread::spawn(move || {
             for msg in io_rx {
                 if msg
@@ -225,6 +337,7 @@ fn new(
                 }
                 if let Ok(msg) = serde_json::to_string(&msg) {
                     tracing::debug!("write to lsp: {}", msg);
+                    local_trace.write("-->", &msg);
                     let msg =
                         format!("Content-Length: {}\r\n\r\n{}", msg.len(), msg);
                     if let Err(err) = writer.write(msg.as_bytes()) {
@@ -241,6 +354,7 @@ fn new(
         let core_rpc = plugin_rpc.core_rpc.clone();
         let volt_id_closure = volt_id.clone();
         let name = volt_display_name.clone();
+        let local_trace = trace.clone();
         thread::spawn(move || {
             let mut reader = Box::new(BufReader::new(stdout));
             loop {
@@ -249,6 +363,7 @@ fn new(
                         if !message_str.contains("$/progress") {
                             tracing::debug!("read from lsp: {}", message_str);
                         }
+                        local_trace.write("<--", &message_str);
                         if let Some(resp) = handle_plugin_server_message(
                             &local_server_rpc,
                             &message_str,
@@ -268,6 +383,7 @@ fn new(
                                 volt_id_closure.author, volt_id_closure.name
                             )),
                         );
+                        core_rpc.language_server_stopped(local_server_rpc.plugin_id);
                         return;
                     }
                 };
@@ -306,6 +422,7 @@ fn new(
             pwd,
             volt_id,
             volt_display_name,
+            VoltPermissions::unrestricted(),
             document_selector,
             plugin_rpc.core_rpc.clone(),
             server_rpc.clone(),
@@ -319,6 +436,8 @@ fn new(
             workspace,
             host,
             options,
+            launch,
+            trace,
         })
     }
 
@@ -349,13 +468,82 @@ pub fn start(
             args,
             options,
         )?;
-        let plugin_id = lsp.server_rpc.plugin_id;
+        Ok(lsp.spawn())
+    }
 
-        let rpc = lsp.server_rpc.clone();
+    fn spawn(mut self) -> PluginId {
+        let plugin_id = self.server_rpc.plugin_id;
+        let rpc = self.server_rpc.clone();
         thread::spawn(move || {
-            rpc.mainloop(&mut lsp);
+            rpc.mainloop(&mut self);
         });
-        Ok(plugin_id)
+        plugin_id
+    }
+
+    /// Replaces this server with a new process started with the same command.
+    /// The trace log stays enabled if it was.
+    fn restart(&mut self) {
+        self.shutdown();
+        // to end the mainloop of this instance
+        self.server_rpc.handle_rpc(PluginServerRpc::Shutdown);
+
+        let launch = self.launch.clone();
+        match Self::new(
+            self.plugin_rpc.clone(),
+            launch.document_selector,
+            launch.workspace,
+            launch.volt_id,
+            launch.volt_display_name,
+            launch.spawned_by,
+            None,
+            launch.pwd,
+            launch.server_uri,
+            launch.args,
+            launch.options,
+        ) {
+            Ok(lsp) => {
+                if self.trace.path().is_some() {
+                    lsp.set_trace(true);
+                }
+                lsp.spawn();
+            }
+            Err(err) => {
+                tracing::error!("{:?}", err);
+            }
+        }
+    }
+
+    fn set_trace(&self, enabled: bool) {
+        if !enabled {
+            self.trace.disable();
+        } else if let Err(err) =
+            self.trace.enable(&self.launch.volt_id, &self.server_name())
+        {
+            tracing::error!("{:?}", err);
+        }
+    }
+
+    /// The name of the server binary
+    fn server_name(&self) -> String {
+        let uri = &self.launch.server_uri;
+        let path = uri.path();
+        Path::new(path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string())
+    }
+
+    fn send_status(&mut self) {
+        let running = matches!(self.process.try_wait(), Ok(None));
+        let server = LanguageServerInfo {
+            plugin_id: self.server_rpc.plugin_id,
+            volt_id: self.launch.volt_id.clone(),
+            name: self.launch.volt_display_name.clone(),
+            running,
+            capabilities: self.host.capabilities(),
+            trace_log: self.trace.path(),
+        };
+        self.plugin_rpc.core_rpc.language_server_status(server);
     }
 
     fn initialize(&mut self) {
@@ -367,16 +555,11 @@ fn initialize(&mut self) {
         #[allow(deprecated)]
         let params = InitializeParams {
             process_id: Some(process::id()),
-            root_uri: root_uri.clone(),
+            root_uri,
             initialization_options: self.options.clone(),
             capabilities: client_capabilities(),
             trace: Some(TraceValue::Verbose),
-            workspace_folders: root_uri.map(|uri| {
-                vec![WorkspaceFolder {
-                    name: uri.as_str().to_string(),
-                    uri,
-                }]
-            }),
+            workspace_folders: self.host.workspace_folders(),
             client_info: Some(ClientInfo {
                 name: meta::NAME.to_owned(),
                 version: Some(meta::VERSION.to_owned()),
@@ -410,6 +593,8 @@ fn initialize(&mut self) {
                 {
                     self.server_rpc.shutdown();
                     self.shutdown();
+                } else {
+                    self.send_status();
                 }
             }
             Err(err) => {
@@ -429,6 +614,9 @@ fn initialize(&mut self) {
     }
 
     fn shutdown(&mut self) {
+        self.plugin_rpc
+            .core_rpc
+            .language_server_removed(self.server_rpc.plugin_id);
         if let Err(err) = self.process.kill() {
             tracing::error!("{:?}", err);
         }
@@ -460,6 +648,136 @@ fn process(
     }
 }
 
+/// A language server declared in the `[lsp.<language>]` section of the
+/// settings, which is started by the plugin catalog without a volt.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LspServerConfig {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub initialization_options: Option<Value>,
+    /// Files matching these globs are handled by the server even if their
+    /// language id is different
+    #[serde(default)]
+    pub file_globs: Vec<String>,
+    /// The server is started in the closest ancestor of the opened file
+    /// containing one of these, and in the workspace otherwise
+    #[serde(default)]
+    pub root_markers: Vec<String>,
+}
+
+impl LspServerConfig {
+    /// The id the server is registered under, so that it can be stopped the
+    /// same way as the servers started by a volt
+    pub fn volt_id(language: &str) -> VoltID {
+        VoltID {
+            author: "lsp".to_string(),
+            name: language.to_string(),
+        }
+    }
+
+    /// The documents the server handles, which are only those inside `root`
+    /// if it has one, so that each root gets its own server.
+    pub fn document_selector(
+        &self,
+        language: &str,
+        root: Option<&Path>,
+    ) -> DocumentSelector {
+        let root = root.map(glob_escape);
+        let in_root = |glob: &str| match &root {
+            Some(root) => format!("{root}/{glob}"),
+            None => glob.to_string(),
+        };
+        let mut selector = vec![lsp_types::DocumentFilter {
+            language: Some(language.to_string()),
+            scheme: None,
+            pattern: root.as_ref().map(|_| in_root("**")),
+        }];
+        selector.extend(self.file_globs.iter().map(|glob| {
+            lsp_types::DocumentFilter {
+                language: None,
+                scheme: None,
+                pattern: Some(in_root(&format!(
+                    "**/{}",
+                    glob.trim_start_matches("**/")
+                ))),
+            }
+        }));
+        selector
+    }
+
+    /// Whether a document with this language id and path should start the
+    /// server
+    pub fn handles(&self, language: &str, language_id: &str, path: &Path) -> bool {
+        language == language_id
+            || self
+                .document_selector(language, None)
+                .iter()
+                .map(DocumentFilter::from_lsp_filter_loose)
+                .any(|filter| {
+                    filter
+                        .pattern
+                        .as_ref()
+                        .map(|pattern| pattern.is_match(path))
+                        .unwrap_or(false)
+                })
+    }
+
+    /// The closest ancestor of `path` containing one of the root markers, or
+    /// else the workspace root folder that contains it.
+    pub fn find_root(&self, path: &Path, roots: &[PathBuf]) -> Option<PathBuf> {
+        path.ancestors()
+            .skip(1)
+            .find(|dir| {
+                self.root_markers
+                    .iter()
+                    .any(|marker| dir.join(marker).exists())
+            })
+            .or_else(|| workspace_root_of(roots, path))
+            .map(Path::to_path_buf)
+    }
+
+    pub fn start(
+        &self,
+        plugin_rpc: PluginCatalogRpcHandler,
+        language: &str,
+        root: Option<PathBuf>,
+    ) -> Result<PluginId> {
+        let server_uri = Url::parse(&format!("urn:{}", self.command))?;
+        LspClient::start(
+            plugin_rpc,
+            self.document_selector(language, root.as_deref()),
+            root,
+            Self::volt_id(language),
+            format!("{language} language server"),
+            None,
+            None,
+            None,
+            server_uri,
+            self.args.clone(),
+            self.initialization_options.clone(),
+        )
+    }
+}
+
+/// `path` as a glob that only matches itself, with `/` separators.
+fn glob_escape(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut glob = String::with_capacity(path.len());
+    for c in path.trim_end_matches('/').chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '{' | '}') {
+            glob.push('[');
+            glob.push(c);
+            glob.push(']');
+        } else {
+            glob.push(c);
+        }
+    }
+    glob
+}
+
 pub struct DocumentFilter {
     /// The document must have this language id, if it exists
     pub language_id: Option<String>,
@@ -555,3 +873,89 @@ pub fn get_change_for_sync_kind(
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> LspServerConfig {
+        LspServerConfig {
+            command: "example-language-server".to_string(),
+            args: Vec::new(),
+            initialization_options: None,
+            file_globs: vec!["**/*.example".to_string()],
+            root_markers: vec!["example.toml".to_string()],
+        }
+    }
+
+    fn selector_matches(
+        selector: &DocumentSelector,
+        language_id: &str,
+        path: &Path,
+    ) -> bool {
+        selector
+            .iter()
+            .map(DocumentFilter::from_lsp_filter_loose)
+            .any(|filter| {
+                filter
+                    .language_id
+                    .as_deref()
+                    .is_none_or(|language| language == language_id)
+                    && filter
+                        .pattern
+                        .as_ref()
+                        .is_none_or(|pattern| pattern.is_match(path))
+            })
+    }
+
+    #[test]
+    fn test_handles() {
+        let config = config();
+        assert!(config.handles("example", "example", Path::new("/a/b.txt")));
+        assert!(config.handles("example", "plaintext", Path::new("/a/b.example")));
+        assert!(!config.handles("example", "rust", Path::new("/a/b.rs")));
+    }
+
+    #[test]
+    fn test_document_selector_root() {
+        let config = config();
+        let selector =
+            config.document_selector("example", Some(Path::new("/a/[x]")));
+        assert!(selector_matches(
+            &selector,
+            "example",
+            Path::new("/a/[x]/src/main.ex")
+        ));
+        assert!(selector_matches(
+            &selector,
+            "plaintext",
+            Path::new("/a/[x]/src/b.example")
+        ));
+        assert!(!selector_matches(
+            &selector,
+            "example",
+            Path::new("/a/y/main.ex")
+        ));
+    }
+
+    #[test]
+    fn test_find_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let nested = dir.join("crate").join("src");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(dir.join("crate").join("example.toml"), "").unwrap();
+
+        let config = config();
+        let roots = [dir.to_path_buf()];
+        assert_eq!(
+            config.find_root(&nested.join("main.ex"), &roots),
+            Some(dir.join("crate"))
+        );
+        assert_eq!(
+            config.find_root(&dir.join("other.ex"), &roots),
+            Some(dir.to_path_buf())
+        );
+        assert_eq!(config.find_root(Path::new("/elsewhere/x.ex"), &roots), None);
+    }
+}
@@ -8,9 +8,10 @@
     borrow::Cow,
     collections::HashMap,
     fs,
+    io::Read,
     path::{Path, PathBuf},
     sync::{
-        Arc,
+        Arc, LazyLock,
         atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
     },
     time::Duration,
@@ -20,12 +21,16 @@
 use crossbeam_channel::{Receiver, Sender};
 use dyn_clone::DynClone;
 use flate2::read::GzDecoder;
+use indexmap::IndexMap;
 use lapce_core::directory::Directory;
 use lapce_rpc::{
     RequestId, RpcError,
     core::CoreRpcHandler,
     dap_types::{self, DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
-    plugin::{PluginId, VoltInfo, VoltMetadata},
+    plugin::{
+        ExecuteVoltCommandParams, PluginId, VoltCommandContext, VoltID, VoltInfo,
+        VoltMetadata, VoltPermissions, VoltSource,
+    },
     proxy::ProxyRpcHandler,
     style::LineStyle,
     terminal::TermId,
@@ -40,8 +45,10 @@
     CodeActionResponse, CodeLens, CodeLensParams, CompletionClientCapabilities,
     CompletionItem, CompletionItemCapability,
     CompletionItemCapabilityResolveSupport, CompletionParams, CompletionResponse,
-    Diagnostic, DocumentFormattingParams, DocumentSymbolClientCapabilities,
-    DocumentSymbolParams, DocumentSymbolResponse, FoldingRange,
+    CreateFilesParams, DeleteFilesParams, Diagnostic,
+    DidChangeWorkspaceFoldersParams, DocumentFormattingParams,
+    DocumentSymbolClientCapabilities, DocumentSymbolParams, DocumentSymbolResponse,
+    FileCreate, FileDelete, FileRename, FoldingRange,
     FoldingRangeClientCapabilities, FoldingRangeParams, FormattingOptions,
     GotoCapability, GotoDefinitionParams, GotoDefinitionResponse, Hover,
     HoverClientCapabilities, HoverParams, InlayHint, InlayHintClientCapabilities,
@@ -50,8 +57,9 @@
     MessageActionItemCapabilities, ParameterInformationSettings,
     PartialResultParams, Position, PrepareRenameResponse,
     PublishDiagnosticsClientCapabilities, Range, ReferenceContext, ReferenceParams,
-    RenameParams, SelectionRange, SelectionRangeParams, SemanticTokens,
-    SemanticTokensClientCapabilities, SemanticTokensParams,
+    RenameFilesParams, RenameParams, SelectionRange, SelectionRangeParams,
+    SemanticTokensClientCapabilities, SemanticTokensClientCapabilitiesRequests,
+    SemanticTokensFullOptions, SemanticTokensWorkspaceClientCapabilities,
     ShowMessageRequestClientCapabilities, SignatureHelp,
     SignatureHelpClientCapabilities, SignatureHelpParams,
     SignatureInformationSettings, SymbolInformation, TextDocumentClientCapabilities,
@@ -59,7 +67,10 @@
     TextDocumentSyncClientCapabilities, TextEdit, Url,
     VersionedTextDocumentIdentifier, WindowClientCapabilities,
     WorkDoneProgressParams, WorkspaceClientCapabilities, WorkspaceEdit,
-    WorkspaceSymbolClientCapabilities, WorkspaceSymbolParams,
+    WorkspaceFileOperationsClientCapabilities, WorkspaceFolder,
+    WorkspaceFoldersChangeEvent, WorkspaceSymbolClientCapabilities,
+    WorkspaceSymbolParams,
+    notification::{DidChangeWorkspaceFolders, Notification},
     request::{
         CallHierarchyIncomingCalls, CallHierarchyPrepare, CodeActionRequest,
         CodeActionResolveRequest, CodeLensRequest, CodeLensResolve, Completion,
@@ -68,7 +79,7 @@
         GotoTypeDefinitionParams, GotoTypeDefinitionResponse, HoverRequest,
         InlayHintRequest, InlineCompletionRequest, PrepareRenameRequest, References,
         Rename, Request, ResolveCompletionItem, SelectionRangeRequest,
-        SemanticTokensFullRequest, SignatureHelpRequest, WorkspaceSymbolRequest,
+        SignatureHelpRequest, WorkspaceSymbolRequest,
     },
 };
 use parking_lot::Mutex;
@@ -80,7 +91,10 @@
 use self::{
     catalog::PluginCatalog,
     dap::DapRpcHandler,
-    psp::{ClonableCallback, PluginServerRpcHandler, RpcCallback},
+    psp::{
+        ClonableCallback, ExecuteVoltCommand, FileOperation, PluginServerRpcHandler,
+        RpcCallback,
+    },
     wasi::{load_volt, start_volt},
 };
 use crate::buffer::language_id_from_path;
@@ -107,9 +121,9 @@ pub enum PluginCatalogRpc {
         path: Option<PathBuf>,
         check: bool,
     },
-    FormatSemanticTokens {
-        plugin_id: PluginId,
-        tokens: SemanticTokens,
+    SemanticTokens {
+        path: PathBuf,
+        range: Option<Range>,
         text: Rope,
         f: Box<dyn RpcCallback<Vec<LineStyle>, RpcError>>,
     },
@@ -144,6 +158,13 @@ pub enum PluginCatalogRpc {
         text_document: TextDocumentIdentifier,
         text: Rope,
     },
+    WillRenameFiles {
+        params: RenameFilesParams,
+        f: Box<dyn RpcCallback<Vec<WorkspaceEdit>, RpcError>>,
+    },
+    DidFileOperation {
+        operation: FileOperation,
+    },
     Handler(PluginCatalogNotification),
     RemoveVolt {
         volt: VoltInfo,
@@ -157,10 +178,14 @@ pub enum PluginCatalogNotification {
     UpdatePluginConfigs(HashMap<String, HashMap<String, serde_json::Value>>),
     UnactivatedVolts(Vec<VoltMetadata>),
     PluginServerLoaded(PluginServerRpcHandler),
-    InstallVolt(VoltInfo),
+    InstallVolt(VoltInfo, VoltSource, Option<VoltPermissions>),
     StopVolt(VoltInfo),
     EnableVolt(VoltInfo),
     ReloadVolt(VoltMetadata),
+    VoltCrashed(VoltMetadata, String),
+    RestartCrashedVolt(VoltMetadata),
+    RestartLanguageServer(PluginId),
+    SetLanguageServerTrace(PluginId, bool),
     DapLoaded(DapRpcHandler),
     DapDisconnected(DapId),
     DapStart {
@@ -221,10 +246,15 @@ pub struct PluginCatalogRpcHandler {
     proxy_rpc: ProxyRpcHandler,
     plugin_tx: Sender<PluginCatalogRpc>,
     plugin_rx: Arc<Mutex<Option<Receiver<PluginCatalogRpc>>>>,
-    #[allow(dead_code)]
     id: Arc<AtomicU64>,
     #[allow(dead_code, clippy::type_complexity)]
     pending: Arc<Mutex<HashMap<u64, Sender<Result<Value, RpcError>>>>>,
+    /// Volt prompts waiting for the user to pick an item
+    #[allow(clippy::type_complexity)]
+    prompts: Arc<Mutex<HashMap<u64, Box<dyn FnOnce(Option<usize>) + Send>>>>,
+    /// Additional root folders of a multi-root workspace, shared with every
+    /// plugin so that servers started later are initialized with all of them.
+    workspace_folders: Arc<Mutex<Vec<PathBuf>>>,
 }
 
 impl PluginCatalogRpcHandler {
@@ -237,9 +267,55 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
             plugin_rx: Arc::new(Mutex::new(Some(plugin_rx))),
             id: Arc::new(AtomicU64::new(0)),
             pending: Arc::new(Mutex::new(HashMap::new())),
+            prompts: Arc::new(Mutex::new(HashMap::new())),
+            workspace_folders: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
+    pub fn workspace_folders(&self) -> Vec<PathBuf> {
+        self.workspace_folders.lock().clone()
+    }
+
+    pub fn set_workspace_folders(&self, folders: Vec<PathBuf>) {
+        *self.workspace_folders.lock() = folders;
+    }
+
+    /// Update the additional workspace folders and let the running servers know.
+    pub fn did_change_workspace_folders(
+        &self,
+        added: &[PathBuf],
+        removed: &[PathBuf],
+    ) {
+        {
+            let mut folders = self.workspace_folders.lock();
+            folders.retain(|folder| !removed.contains(folder));
+            for folder in added {
+                if !folders.contains(folder) {
+                    folders.push(folder.clone());
+                }
+            }
+        }
+
+        let params = DidChangeWorkspaceFoldersParams {
+            event: WorkspaceFoldersChangeEvent {
+                added: added.iter().filter_map(|p| workspace_folder(p)).collect(),
+                removed: removed
+                    .iter()
+                    .filter_map(|p| workspace_folder(p))
+                    .collect(),
+            },
+        };
+        // Only sent to the servers that asked for the changes
+        self.send_notification(
+            None,
+            DidChangeWorkspaceFolders::METHOD,
+            params,
+            None,
+            None,
+            true,
+        );
+    }
+
     #[allow(dead_code)]
     fn handle_response(&self, id: RequestId, result: Result<Value, RpcError>) {
         if let Some(chan) = { self.pending.lock().remove(&id) } {
@@ -294,13 +370,13 @@ pub fn mainloop(&self, plugin: &mut PluginCatalog) {
                 PluginCatalogRpc::Handler(notification) => {
                     plugin.handle_notification(notification);
                 }
-                PluginCatalogRpc::FormatSemanticTokens {
-                    plugin_id,
-                    tokens,
+                PluginCatalogRpc::SemanticTokens {
+                    path,
+                    range,
                     text,
                     f,
                 } => {
-                    plugin.format_semantic_tokens(plugin_id, tokens, text, f);
+                    plugin.handle_semantic_tokens(path, range, text, f);
                 }
                 PluginCatalogRpc::DidOpenTextDocument { document } => {
                     plugin.handle_did_open_text_document(document);
@@ -333,6 +409,12 @@ pub fn mainloop(&self, plugin: &mut PluginCatalog) {
                         new_text,
                     );
                 }
+                PluginCatalogRpc::WillRenameFiles { params, f } => {
+                    plugin.handle_will_rename_files(params, f);
+                }
+                PluginCatalogRpc::DidFileOperation { operation } => {
+                    plugin.handle_did_file_operation(operation);
+                }
                 PluginCatalogRpc::DapVariable {
                     dap_id,
                     reference,
@@ -482,21 +564,20 @@ pub(crate) fn send_notification<P: Serialize>(
         }
     }
 
-    pub fn format_semantic_tokens(
+    /// Get the semantic styles of the document, or only of `range` if given.
+    pub fn get_semantic_tokens(
         &self,
-        plugin_id: PluginId,
-        tokens: SemanticTokens,
+        path: PathBuf,
+        range: Option<Range>,
         text: Rope,
         f: Box<dyn RpcCallback<Vec<LineStyle>, RpcError>>,
     ) {
-        if let Err(err) =
-            self.plugin_tx.send(PluginCatalogRpc::FormatSemanticTokens {
-                plugin_id,
-                tokens,
-                text,
-                f,
-            })
-        {
+        if let Err(err) = self.plugin_tx.send(PluginCatalogRpc::SemanticTokens {
+            path,
+            range,
+            text,
+            f,
+        }) {
             tracing::error!("{:?}", err);
         }
     }
@@ -544,6 +625,66 @@ pub fn did_change_text_document(
         }
     }
 
+    /// Ask the servers for the edits to apply before `from` is renamed to `to`.
+    pub fn will_rename_files(
+        &self,
+        from: &Path,
+        to: &Path,
+        f: impl FnOnce(Result<Vec<WorkspaceEdit>, RpcError>) + Send + 'static,
+    ) {
+        let files = file_renames(from, to, &dir_children(from));
+        if files.is_empty() {
+            f(Ok(Vec::new()));
+            return;
+        }
+        let params = RenameFilesParams { files };
+        if let Err(err) = self.plugin_tx.send(PluginCatalogRpc::WillRenameFiles {
+            params,
+            f: Box::new(f),
+        }) {
+            tracing::error!("{:?}", err);
+        }
+    }
+
+    pub fn did_create_files(&self, paths: &[PathBuf]) {
+        let files = paths
+            .iter()
+            .filter_map(|path| Url::from_file_path(path).ok())
+            .map(|uri| FileCreate {
+                uri: uri.to_string(),
+            })
+            .collect();
+        self.did_file_operation(FileOperation::Create(CreateFilesParams { files }));
+    }
+
+    pub fn did_rename_files(&self, from: &Path, to: &Path) {
+        let files = file_renames(from, to, &dir_children(to));
+        if files.is_empty() {
+            return;
+        }
+        self.did_file_operation(FileOperation::Rename(RenameFilesParams { files }));
+    }
+
+    pub fn did_delete_files(&self, paths: &[PathBuf]) {
+        let files = paths
+            .iter()
+            .filter_map(|path| Url::from_file_path(path).ok())
+            .map(|uri| FileDelete {
+                uri: uri.to_string(),
+            })
+            .collect();
+        self.did_file_operation(FileOperation::Delete(DeleteFilesParams { files }));
+    }
+
+    fn did_file_operation(&self, operation: FileOperation) {
+        if let Err(err) = self
+            .plugin_tx
+            .send(PluginCatalogRpc::DidFileOperation { operation })
+        {
+            tracing::error!("{:?}", err);
+        }
+    }
+
     pub fn get_definition(
         &self,
         path: &Path,
@@ -1034,32 +1175,6 @@ pub fn rename(
         );
     }
 
-    pub fn get_semantic_tokens(
-        &self,
-        path: &Path,
-        cb: impl FnOnce(PluginId, Result<SemanticTokens, RpcError>)
-        + Clone
-        + Send
-        + 'static,
-    ) {
-        let uri = Url::from_file_path(path).unwrap();
-        let method = SemanticTokensFullRequest::METHOD;
-        let params = SemanticTokensParams {
-            text_document: TextDocumentIdentifier { uri },
-            work_done_progress_params: WorkDoneProgressParams::default(),
-            partial_result_params: PartialResultParams::default(),
-        };
-        let language_id =
-            Some(language_id_from_path(path).unwrap_or("").to_string());
-        self.send_request_to_all_plugins(
-            method,
-            params,
-            language_id,
-            Some(path.to_path_buf()),
-            cb,
-        );
-    }
-
     pub fn get_selection_range(
         &self,
         path: &Path,
@@ -1196,6 +1311,56 @@ pub fn completion_resolve(
         );
     }
 
+    /// Ask the user to pick one of `items`, `f` gets the index of the picked
+    /// item or `None` if the prompt was dismissed.
+    pub fn show_volt_prompt(
+        &self,
+        title: String,
+        placeholder: String,
+        items: Vec<String>,
+        f: impl FnOnce(Option<usize>) + Send + 'static,
+    ) {
+        let prompt_id = self.id.fetch_add(1, Ordering::Relaxed);
+        self.prompts.lock().insert(prompt_id, Box::new(f));
+        self.core_rpc
+            .show_volt_prompt(prompt_id, title, placeholder, items);
+    }
+
+    pub fn answer_volt_prompt(&self, prompt_id: u64, index: Option<usize>) {
+        let f = self.prompts.lock().remove(&prompt_id);
+        if let Some(f) = f {
+            f(index);
+        }
+    }
+
+    pub fn execute_volt_command(
+        &self,
+        plugin_id: PluginId,
+        command: String,
+        mut context: VoltCommandContext,
+        cb: impl FnOnce(Result<Value, RpcError>) + Send + Clone + 'static,
+    ) {
+        if context.language_id.is_none() {
+            context.language_id = context
+                .uri
+                .as_ref()
+                .and_then(|uri| uri.to_file_path().ok())
+                .and_then(|path| language_id_from_path(&path))
+                .map(|id| id.to_string());
+        }
+        let method = ExecuteVoltCommand::METHOD;
+        self.send_request(
+            Some(plugin_id),
+            None,
+            method,
+            ExecuteVoltCommandParams { command, context },
+            None,
+            None,
+            false,
+            move |_, result| cb(result),
+        );
+    }
+
     pub fn signature_help(
         &self,
         request_id: usize,
@@ -1326,8 +1491,15 @@ pub fn update_plugin_configs(
         ))
     }
 
-    pub fn install_volt(&self, volt: VoltInfo) -> Result<()> {
-        self.catalog_notification(PluginCatalogNotification::InstallVolt(volt))
+    pub fn install_volt(
+        &self,
+        volt: VoltInfo,
+        source: VoltSource,
+        approved: Option<VoltPermissions>,
+    ) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::InstallVolt(
+            volt, source, approved,
+        ))
     }
 
     pub fn stop_volt(&self, volt: VoltInfo) {
@@ -1368,6 +1540,34 @@ pub fn reload_volt(&self, volt: VoltMetadata) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::ReloadVolt(volt))
     }
 
+    pub fn volt_crashed(&self, volt: VoltMetadata, message: String) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::VoltCrashed(
+            volt, message,
+        ))
+    }
+
+    pub fn restart_crashed_volt(&self, volt: VoltMetadata) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::RestartCrashedVolt(
+            volt,
+        ))
+    }
+
+    pub fn restart_language_server(&self, plugin_id: PluginId) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::RestartLanguageServer(
+            plugin_id,
+        ))
+    }
+
+    pub fn set_language_server_trace(
+        &self,
+        plugin_id: PluginId,
+        enabled: bool,
+    ) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::SetLanguageServerTrace(
+            plugin_id, enabled,
+        ))
+    }
+
     pub fn enable_volt(&self, volt: VoltInfo) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::EnableVolt(volt))
     }
@@ -1552,12 +1752,251 @@ pub fn volt_icon(volt: &VoltMetadata) -> Option<Vec<u8>> {
     std::fs::read(icon).ok()
 }
 
-pub fn download_volt(volt: &VoltInfo) -> Result<VoltMetadata> {
-    let url = format!(
-        "https://plugins.lapce.dev/api/v1/plugins/{}/{}/{}/download",
-        volt.author, volt.name, volt.version
-    );
+/// The url of an endpoint of the plugin api of a registry, e.g.
+/// `https://plugins.lapce.dev`.
+pub fn registry_url(registry: &str, endpoint: &str) -> String {
+    format!(
+        "{}/api/v1/plugins{endpoint}",
+        registry.trim_end_matches('/')
+    )
+}
+
+/// How many previous versions of a volt are kept on disk for rolling back.
+const KEPT_VOLT_VERSIONS: usize = 3;
+
+/// How many volt archives downloaded to preview a volt are kept, so they are
+/// installed without downloading them again.
+const KEPT_VOLT_ARCHIVES: usize = 8;
+
+/// The volt archives downloaded to preview a volt, by their download url.
+static VOLT_ARCHIVES: LazyLock<Mutex<IndexMap<String, Vec<u8>>>> =
+    LazyLock::new(|| Mutex::new(IndexMap::new()));
+
+/// Download and install a volt from the registry. `approved` are the
+/// permissions the user agreed to, a volt with wasm is only installed if it
+/// asks for exactly those.
+pub fn download_volt(
+    registry: &str,
+    volt: &VoltInfo,
+    approved: Option<&VoltPermissions>,
+) -> Result<VoltMetadata> {
+    let staging_dir = volt_staging_dir(&volt.id())?;
+    let meta = download_volt_to(registry, volt, &staging_dir)
+        .and_then(|meta| check_volt_permissions(&meta, approved))
+        .and_then(|_| replace_installed_volt(&volt.id(), &staging_dir));
+    remove_staging_dir(&staging_dir);
+    meta
+}
+
+/// Download the volt to a temporary directory to read its metadata, e.g. the
+/// permissions it asks for, without installing it.
+pub fn download_volt_metadata(
+    registry: &str,
+    volt: &VoltInfo,
+) -> Result<VoltMetadata> {
+    inspect_volt_download(registry, volt, |_, meta| meta)
+}
+
+/// Download the volt to a temporary directory to read its metadata and the
+/// newest entry of its changelog, to preview an update.
+pub fn download_volt_update(
+    registry: &str,
+    volt: &VoltInfo,
+) -> Result<(VoltMetadata, Option<String>)> {
+    inspect_volt_download(registry, volt, |dir, meta| (meta, volt_changelog(dir)))
+}
+
+fn inspect_volt_download<T>(
+    registry: &str,
+    volt: &VoltInfo,
+    f: impl FnOnce(&Path, VoltMetadata) -> T,
+) -> Result<T> {
+    let url = volt_download_url(registry, volt);
+    let archive = fetch_volt_archive(&url)?;
+    let dir = tempfile::tempdir()?;
+    unpack_volt_bytes(&archive, dir.path())?;
+    let result = f(dir.path(), load_volt(dir.path())?);
+
+    let mut archives = VOLT_ARCHIVES.lock();
+    archives.insert(url, archive);
+    while archives.len() > KEPT_VOLT_ARCHIVES {
+        archives.shift_remove_index(0);
+    }
+    Ok(result)
+}
+
+/// Check that a volt with wasm asks for the permissions the user approved,
+/// which might have changed since they were shown.
+fn check_volt_permissions(
+    meta: &VoltMetadata,
+    approved: Option<&VoltPermissions>,
+) -> Result<()> {
+    if meta.wasm.is_none() || approved == Some(&meta.permissions()) {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "the permissions of {} changed since they were approved",
+            meta.display_name
+        ))
+    }
+}
+
+/// The newest entry of the `CHANGELOG.md` of a volt, i.e. its first `## `
+/// section.
+fn volt_changelog(dir: &Path) -> Option<String> {
+    let path = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .find(|p| {
+            p.file_name()
+                .map(|n| n.to_string_lossy().eq_ignore_ascii_case("changelog.md"))
+                .unwrap_or(false)
+        })?;
+    let text = fs::read_to_string(path).ok()?;
+    let entry = text
+        .lines()
+        .skip_while(|line| !line.starts_with("## "))
+        .enumerate()
+        .take_while(|(i, line)| *i == 0 || !line.starts_with("## "))
+        .map(|(_, line)| line)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let entry = entry.trim();
+    if entry.is_empty() {
+        None
+    } else {
+        Some(entry.to_string())
+    }
+}
+
+/// The previous versions of a volt kept on disk, newest first.
+pub fn kept_volt_versions(id: &VoltID) -> Vec<String> {
+    let Some(dir) = Directory::plugin_versions_directory() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir.join(id.to_string())) else {
+        return Vec::new();
+    };
+    let mut versions = entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    versions.sort_by(|a, b| compare_versions(b, a));
+    versions
+}
+
+/// Orders semantic versions by their precedence, after the versions which
+/// aren't semantic versions, which are ordered as text.
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let version = |v: &str| semver::Version::parse(v).ok();
+    version(a).cmp(&version(b)).then_with(|| a.cmp(b))
+}
+
+/// Install a previous version of a volt kept on disk, keeping the installed
+/// version in its place.
+pub fn restore_volt_version(id: &VoltID, version: &str) -> Result<VoltMetadata> {
+    let kept_dir = Directory::plugin_versions_directory()
+        .ok_or_else(|| anyhow!("can't get plugin versions directory"))?
+        .join(id.to_string())
+        .join(version);
+    if !kept_dir.is_dir() {
+        return Err(anyhow!("version {version} of {id} is not kept on disk"));
+    }
+    let staging_dir = volt_staging_dir(id)?;
+    fs::rename(&kept_dir, &staging_dir)?;
+    let meta = replace_installed_volt(id, &staging_dir);
+    if meta.is_err() && !kept_dir.exists() && staging_dir.exists() {
+        if let Err(err) = fs::rename(&staging_dir, &kept_dir) {
+            tracing::error!("{:?}", err);
+        }
+    }
+    remove_staging_dir(&staging_dir);
+    meta
+}
+
+/// An empty directory next to the kept versions, so that it's on the same
+/// filesystem as the plugin directory and can be moved into it.
+fn volt_staging_dir(id: &VoltID) -> Result<PathBuf> {
+    let dir = Directory::plugin_versions_directory()
+        .ok_or_else(|| anyhow!("can't get plugin versions directory"))?
+        .join(format!(".staging-{id}"));
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    Ok(dir)
+}
+
+fn remove_staging_dir(dir: &Path) {
+    if dir.exists() {
+        if let Err(err) = fs::remove_dir_all(dir) {
+            tracing::error!("{:?}", err);
+        }
+    }
+}
+
+/// Move the volt in `new_dir` into the plugin directory, keeping the
+/// installed version of the volt, if any, for rolling back.
+fn replace_installed_volt(id: &VoltID, new_dir: &Path) -> Result<VoltMetadata> {
+    load_volt(new_dir)?;
+    let plugin_dir = Directory::plugins_directory()
+        .ok_or_else(|| anyhow!("can't get plugin directory"))?
+        .join(id.to_string());
+    if plugin_dir.exists() {
+        keep_volt_version(id, &plugin_dir)?;
+    }
+    fs::rename(new_dir, &plugin_dir)?;
+    load_volt(&plugin_dir)
+}
+
+fn keep_volt_version(id: &VoltID, plugin_dir: &Path) -> Result<()> {
+    let Ok(meta) = load_volt(plugin_dir) else {
+        // nothing worth rolling back to
+        fs::remove_dir_all(plugin_dir)?;
+        return Ok(());
+    };
+    let versions_dir = Directory::plugin_versions_directory()
+        .ok_or_else(|| anyhow!("can't get plugin versions directory"))?
+        .join(id.to_string());
+    fs::create_dir_all(&versions_dir)?;
+    let kept_dir = versions_dir.join(&meta.version);
+    if kept_dir.exists() {
+        fs::remove_dir_all(&kept_dir)?;
+    }
+    fs::rename(plugin_dir, &kept_dir)?;
+
+    for version in kept_volt_versions(id).into_iter().skip(KEPT_VOLT_VERSIONS) {
+        if let Err(err) = fs::remove_dir_all(versions_dir.join(version)) {
+            tracing::error!("{:?}", err);
+        }
+    }
+    Ok(())
+}
+
+fn download_volt_to(
+    registry: &str,
+    volt: &VoltInfo,
+    plugin_dir: &Path,
+) -> Result<VoltMetadata> {
+    // The archive downloaded to preview the volt is the one installed
+    let url = volt_download_url(registry, volt);
+    let archive = match VOLT_ARCHIVES.lock().shift_remove(&url) {
+        Some(archive) => archive,
+        None => fetch_volt_archive(&url)?,
+    };
+    unpack_volt_bytes(&archive, plugin_dir)?;
+    load_volt(plugin_dir)
+}
 
+fn volt_download_url(registry: &str, volt: &VoltInfo) -> String {
+    registry_url(
+        registry,
+        &format!("/{}/{}/{}/download", volt.author, volt.name, volt.version),
+    )
+}
+
+fn fetch_volt_archive(url: &str) -> Result<Vec<u8>> {
     let resp = crate::get_url(url, None)?;
     if !resp.status().is_success() {
         return Err(anyhow!("can't download plugin"));
@@ -1566,38 +2005,129 @@ pub fn download_volt(volt: &VoltInfo) -> Result<VoltMetadata> {
     // this is the s3 url
     let url = resp.text()?;
 
-    let mut resp = crate::get_url(url, None)?;
+    let resp = crate::get_url(url, None)?;
     if !resp.status().is_success() {
         return Err(anyhow!("can't download plugin"));
     }
+    Ok(resp.bytes()?.to_vec())
+}
 
-    let is_zstd = resp
-        .headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        == Some("application/zstd");
+/// Read the metadata of a packaged volt archive or a volt directory, checking
+/// that it is a valid volt, without installing it.
+pub fn volt_metadata_from_path(path: &Path) -> Result<VoltMetadata> {
+    if path.is_dir() {
+        return validate_volt(path);
+    }
+    let dir = tempfile::tempdir()?;
+    unpack_volt_archive(path, dir.path())?;
+    validate_volt(dir.path())
+}
 
-    let id = volt.id();
-    let plugin_dir = Directory::plugins_directory()
-        .ok_or_else(|| anyhow!("can't get plugin directory"))?
-        .join(id.to_string());
-    if let Err(err) = fs::remove_dir_all(&plugin_dir) {
-        tracing::error!("{:?}", err);
+/// Install a packaged volt archive or a volt directory into the plugin
+/// directory, replacing an installed version of the volt. `approved` are the
+/// permissions the user agreed to, like for [`download_volt`].
+pub fn unpack_volt_from_path(
+    path: &Path,
+    approved: Option<&VoltPermissions>,
+) -> Result<VoltMetadata> {
+    let meta = volt_metadata_from_path(path)?;
+    let staging_dir = volt_staging_dir(&meta.id())?;
+    let meta = if path.is_dir() {
+        copy_dir(path, &staging_dir)
+    } else {
+        unpack_volt_archive(path, &staging_dir)
     }
-    fs::create_dir_all(&plugin_dir)?;
+    .and_then(|_| validate_volt(&staging_dir))
+    .and_then(|staged| check_volt_permissions(&staged, approved))
+    .and_then(|_| replace_installed_volt(&meta.id(), &staging_dir));
+    remove_staging_dir(&staging_dir);
+    meta
+}
+
+/// Unpack a volt archive, a tar file optionally compressed with zstd or gzip.
+fn unpack_volt_archive(path: &Path, dir: &Path) -> Result<()> {
+    unpack_volt_bytes(&fs::read(path)?, dir)
+}
+
+fn unpack_volt_bytes(archive: &[u8], dir: &Path) -> Result<()> {
+    const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+    const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+    fs::create_dir_all(dir)?;
 
-    if is_zstd {
-        let tar = zstd::Decoder::new(&mut resp).unwrap();
-        let mut archive = Archive::new(tar);
-        archive.unpack(&plugin_dir)?;
+    if archive.starts_with(&ZSTD_MAGIC) {
+        Archive::new(zstd::Decoder::new(archive)?).unpack(dir)?;
+    } else if archive.starts_with(&GZIP_MAGIC) {
+        Archive::new(GzDecoder::new(archive)).unpack(dir)?;
     } else {
-        let tar = GzDecoder::new(&mut resp);
-        let mut archive = Archive::new(tar);
-        archive.unpack(&plugin_dir)?;
+        Archive::new(archive).unpack(dir)?;
+    }
+    Ok(())
+}
+
+/// Check that `dir` has a `volt.toml` naming the volt, and that every file it
+/// points to is in the volt and is what it's declared as.
+fn validate_volt(dir: &Path) -> Result<VoltMetadata> {
+    let dir = dir.canonicalize()?;
+    let declared: VoltMetadata =
+        toml::from_str(&fs::read_to_string(dir.join("volt.toml"))?)?;
+    if declared.name.is_empty() || declared.author.is_empty() {
+        return Err(anyhow!(
+            "volt.toml is missing the name or author of the volt"
+        ));
+    }
+
+    let volt_file = |file: &str| -> Result<PathBuf> {
+        let path = dir
+            .join(file)
+            .canonicalize()
+            .map_err(|_| anyhow!("{file} is missing from the volt"))?;
+        if !path.starts_with(&dir) || !path.is_file() {
+            return Err(anyhow!("{file} is not a file of the volt"));
+        }
+        Ok(path)
+    };
+    if let Some(wasm) = declared.wasm.as_ref() {
+        let mut magic = [0; 4];
+        fs::File::open(volt_file(wasm)?)?
+            .read_exact(&mut magic)
+            .ok()
+            .filter(|_| &magic == b"\0asm")
+            .ok_or_else(|| anyhow!("{wasm} is not a wasm module"))?;
+    }
+    for theme in declared
+        .color_themes
+        .iter()
+        .chain(declared.icon_themes.iter())
+        .flatten()
+    {
+        toml::from_str::<toml::Value>(&fs::read_to_string(volt_file(theme)?)?)
+            .map_err(|err| anyhow!("{theme} is not a valid theme: {err}"))?;
+    }
+    if let Some(icon) = declared.icon.as_ref() {
+        volt_file(icon)?;
     }
 
-    let meta = load_volt(&plugin_dir)?;
-    Ok(meta)
+    load_volt(&dir)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            // Copying what a link points to could take files from outside
+            // the volt into the folder its wasm can read
+            tracing::warn!("skipped symlink {:?} in volt", entry.path());
+        } else if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
 }
 
 pub fn install_volt(
@@ -1605,14 +2135,26 @@ pub fn install_volt(
     workspace: Option<PathBuf>,
     configurations: Option<HashMap<String, serde_json::Value>>,
     volt: VoltInfo,
+    source: VoltSource,
+    approved: Option<VoltPermissions>,
 ) -> Result<()> {
-    let download_volt_result = download_volt(&volt);
-    if download_volt_result.is_err() {
-        catalog_rpc
-            .core_rpc
-            .volt_installing(volt, "Could not download Plugin".to_string());
+    let approved = approved.as_ref();
+    let install_result = match &source {
+        VoltSource::Registry(registry) => download_volt(registry, &volt, approved),
+        VoltSource::Path(path) => unpack_volt_from_path(path, approved),
+        VoltSource::Previous(version) => restore_volt_version(&volt.id(), version),
+    };
+    if let Err(err) = &install_result {
+        let message = match source {
+            VoltSource::Registry(_) => "Could not download Plugin".to_string(),
+            VoltSource::Path(_) => format!("Could not install Plugin: {err}"),
+            VoltSource::Previous(_) => {
+                format!("Could not roll back Plugin: {err}")
+            }
+        };
+        catalog_rpc.core_rpc.volt_installing(volt, message);
     }
-    let meta = download_volt_result?;
+    let meta = install_result?;
     let local_catalog_rpc = catalog_rpc.clone();
     let local_meta = meta.clone();
 
@@ -1663,6 +2205,16 @@ pub fn remove_volt(
     Ok(())
 }
 
+/// The LSP representation of a workspace root folder.
+pub(crate) fn workspace_folder(path: &Path) -> Option<WorkspaceFolder> {
+    let uri = Url::from_directory_path(path).ok()?;
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| uri.as_str().to_string());
+    Some(WorkspaceFolder { uri, name })
+}
+
 fn client_capabilities() -> ClientCapabilities {
     // https://github.com/rust-lang/rust-analyzer/blob/master/docs/dev/lsp-extensions.md#server-status
     let mut experimental = Map::new();
@@ -1741,6 +2293,12 @@ fn client_capabilities() -> ClientCapabilities {
                 ..Default::default()
             }),
             semantic_tokens: Some(SemanticTokensClientCapabilities {
+                requests: SemanticTokensClientCapabilitiesRequests {
+                    range: Some(true),
+                    full: Some(SemanticTokensFullOptions::Delta {
+                        delta: Some(true),
+                    }),
+                },
                 ..Default::default()
             }),
             type_definition: Some(GotoCapability {
@@ -1789,9 +2347,218 @@ fn client_capabilities() -> ClientCapabilities {
             }),
             configuration: Some(false),
             workspace_folders: Some(true),
+            semantic_tokens: Some(SemanticTokensWorkspaceClientCapabilities {
+                refresh_support: Some(true),
+            }),
+            file_operations: Some(WorkspaceFileOperationsClientCapabilities {
+                dynamic_registration: Some(true),
+                did_create: Some(true),
+                will_create: Some(false),
+                did_rename: Some(true),
+                will_rename: Some(true),
+                did_delete: Some(true),
+                will_delete: Some(false),
+            }),
             ..Default::default()
         }),
         experimental: Some(experimental.into()),
         ..Default::default()
     }
 }
+
+/// The paths inside `dir` relative to it, or none if it isn't a directory.
+fn dir_children(dir: &Path) -> Vec<PathBuf> {
+    if !dir.is_dir() {
+        return Vec::new();
+    }
+    walkdir::WalkDir::new(dir)
+        .min_depth(1)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            entry.path().strip_prefix(dir).ok().map(Path::to_path_buf)
+        })
+        .collect()
+}
+
+/// The renames to report when `from` is renamed to `to`, where renaming a
+/// directory also renames each of its `children`.
+fn file_renames(from: &Path, to: &Path, children: &[PathBuf]) -> Vec<FileRename> {
+    std::iter::once((from.to_path_buf(), to.to_path_buf()))
+        .chain(
+            children
+                .iter()
+                .map(|child| (from.join(child), to.join(child))),
+        )
+        .filter_map(|(old, new)| {
+            let old_uri = Url::from_file_path(old).ok()?;
+            let new_uri = Url::from_file_path(new).ok()?;
+            Some(FileRename {
+                old_uri: old_uri.to_string(),
+                new_uri: new_uri.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_renames() {
+        let from = Path::new("/project/src/old");
+        let to = Path::new("/project/src/new");
+        let renames = file_renames(
+            from,
+            to,
+            &[PathBuf::from("a.rs"), PathBuf::from("inner/b.rs")],
+        );
+        let pairs: Vec<_> = renames
+            .iter()
+            .map(|rename| (rename.old_uri.as_str(), rename.new_uri.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("file:///project/src/old", "file:///project/src/new"),
+                (
+                    "file:///project/src/old/a.rs",
+                    "file:///project/src/new/a.rs"
+                ),
+                (
+                    "file:///project/src/old/inner/b.rs",
+                    "file:///project/src/new/inner/b.rs"
+                ),
+            ]
+        );
+    }
+    #[test]
+    fn test_check_volt_permissions() {
+        let meta: VoltMetadata = toml::from_str(
+            r#"
+            name = "plugin"
+            version = "0.1"
+            display-name = "Plugin"
+            author = "Author"
+            description = "Useful plugin"
+            wasm = "plugin.wasm"
+
+            [permissions]
+            network = ["https://github.com"]
+            "#,
+        )
+        .unwrap();
+        let approved = meta.permissions();
+        assert!(check_volt_permissions(&meta, Some(&approved)).is_ok());
+        assert!(check_volt_permissions(&meta, None).is_err());
+
+        let changed = VoltPermissions {
+            process: true,
+            ..approved.clone()
+        };
+        assert!(check_volt_permissions(&meta, Some(&changed)).is_err());
+
+        let meta = VoltMetadata { wasm: None, ..meta };
+        assert!(check_volt_permissions(&meta, None).is_ok());
+    }
+
+    fn write_volt(dir: &Path, extra: &str) {
+        fs::write(
+            dir.join("volt.toml"),
+            format!(
+                r#"
+                name = "plugin"
+                version = "0.1"
+                display-name = "Plugin"
+                author = "Author"
+                description = "Useful plugin"
+                {extra}
+                "#
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_validate_volt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path();
+        fs::write(path.join("plugin.wasm"), b"\0asm\x01\0\0\0").unwrap();
+        fs::write(path.join("dark.toml"), "[color-theme]\nname = \"Dark\"").unwrap();
+        write_volt(
+            path,
+            r#"wasm = "plugin.wasm"
+                color-themes = ["dark.toml"]"#,
+        );
+        let meta = validate_volt(path).unwrap();
+        assert_eq!(meta.name, "plugin");
+        assert_eq!(meta.color_themes.map(|themes| themes.len()), Some(1));
+
+        write_volt(path, r#"color-themes = ["light.toml"]"#);
+        assert!(validate_volt(path).is_err());
+
+        let outside = tempfile::NamedTempFile::new().unwrap();
+        write_volt(
+            path,
+            &format!("color-themes = [{:?}]", outside.path().to_string_lossy()),
+        );
+        assert!(validate_volt(path).is_err());
+
+        fs::write(path.join("dark.toml"), "not a theme = =").unwrap();
+        write_volt(path, r#"color-themes = ["dark.toml"]"#);
+        assert!(validate_volt(path).is_err());
+
+        fs::write(path.join("plugin.wasm"), "not wasm").unwrap();
+        write_volt(path, r#"wasm = "plugin.wasm""#);
+        assert!(validate_volt(path).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_dir_skips_symlinks() {
+        let from = tempfile::tempdir().unwrap();
+        let to = tempfile::tempdir().unwrap();
+        let outside = tempfile::NamedTempFile::new().unwrap();
+        fs::write(outside.path(), "secret").unwrap();
+        fs::create_dir(from.path().join("themes")).unwrap();
+        fs::write(from.path().join("themes").join("dark.toml"), "").unwrap();
+        std::os::unix::fs::symlink(outside.path(), from.path().join("key")).unwrap();
+
+        copy_dir(from.path(), to.path()).unwrap();
+        assert!(to.path().join("themes").join("dark.toml").exists());
+        assert!(!to.path().join("key").exists());
+    }
+
+    #[test]
+    fn test_volt_metadata_from_archive() {
+        let volt_dir = tempfile::tempdir().unwrap();
+        write_volt(volt_dir.path(), "");
+        let archive = tempfile::NamedTempFile::new().unwrap();
+        let mut builder = tar::Builder::new(archive.reopen().unwrap());
+        builder
+            .append_path_with_name(volt_dir.path().join("volt.toml"), "volt.toml")
+            .unwrap();
+        builder.finish().unwrap();
+
+        let meta = volt_metadata_from_path(archive.path()).unwrap();
+        assert_eq!(meta.id().to_string(), "Author.plugin");
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        let mut versions = vec!["0.10.0", "nightly", "0.2.0", "0.2", "1.0.0-rc.1"];
+        versions.sort_by(|a, b| compare_versions(a, b));
+        assert_eq!(
+            versions,
+            ["0.2", "nightly", "0.2.0", "0.10.0", "1.0.0-rc.1"]
+        );
+
+        // a total order, consistent however the versions are compared
+        for a in &versions {
+            for b in &versions {
+                assert_eq!(compare_versions(a, b), compare_versions(b, a).reverse());
+            }
+        }
+    }
+}
@@ -1,6 +1,9 @@
+#[cfg(test)]
+mod tests;
+
 use std::{
     borrow::Cow,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
     sync::{
         Arc,
@@ -18,23 +21,35 @@
 use lapce_rpc::{
     RpcError,
     core::{CoreRpcHandler, ServerStatusParams},
-    plugin::{PluginId, VoltID},
+    plugin::{
+        ExecuteVoltCommandParams, PluginId, RemoveStatusBarItemParams,
+        SetDecorationsParams, ShowQuickPickParams, VoltCommand, VoltID,
+        VoltPermissions, VoltStatusBarItem,
+    },
     style::{LineStyle, Style},
 };
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
-    CancelParams, CodeActionProviderCapability, DidChangeTextDocumentParams,
-    DidSaveTextDocumentParams, DocumentSelector, FoldingRangeProviderCapability,
-    HoverProviderCapability, ImplementationProviderCapability, InitializeResult,
-    LogMessageParams, MessageType, OneOf, ProgressParams, PublishDiagnosticsParams,
-    Range, Registration, RegistrationParams, SemanticTokens, SemanticTokensLegend,
+    CancelParams, CodeActionProviderCapability, CreateFilesParams,
+    DeleteFilesParams, DidChangeTextDocumentParams, DidSaveTextDocumentParams,
+    DocumentSelector, FileOperationPatternKind, FileOperationRegistrationOptions,
+    FoldingRangeProviderCapability, HoverProviderCapability,
+    ImplementationProviderCapability, InitializeResult, LogMessageParams,
+    MessageType, OneOf, PartialResultParams, ProgressParams,
+    PublishDiagnosticsParams, Range, Registration, RegistrationParams,
+    RenameFilesParams, SemanticToken, SemanticTokens, SemanticTokensDeltaParams,
+    SemanticTokensEdit, SemanticTokensFullDeltaResult, SemanticTokensFullOptions,
+    SemanticTokensLegend, SemanticTokensOptions, SemanticTokensParams,
+    SemanticTokensRangeParams, SemanticTokensRangeResult, SemanticTokensResult,
     SemanticTokensServerCapabilities, ServerCapabilities, ShowMessageParams,
-    TextDocumentContentChangeEvent, TextDocumentIdentifier,
-    TextDocumentSaveRegistrationOptions, TextDocumentSyncCapability,
-    TextDocumentSyncKind, TextDocumentSyncSaveOptions,
-    VersionedTextDocumentIdentifier,
+    ShowMessageRequestParams, TextDocumentContentChangeEvent,
+    TextDocumentIdentifier, TextDocumentSaveRegistrationOptions,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncSaveOptions,
+    UnregistrationParams, Url, VersionedTextDocumentIdentifier,
+    WorkDoneProgressParams, WorkspaceFolder,
     notification::{
-        Cancel, DidChangeTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+        Cancel, DidChangeTextDocument, DidChangeWorkspaceFolders, DidCreateFiles,
+        DidDeleteFiles, DidOpenTextDocument, DidRenameFiles, DidSaveTextDocument,
         Initialized, LogMessage, Notification, Progress, PublishDiagnostics,
         ShowMessage,
     },
@@ -45,8 +60,10 @@
         GotoImplementation, GotoTypeDefinition, HoverRequest, Initialize,
         InlayHintRequest, InlineCompletionRequest, PrepareRenameRequest, References,
         RegisterCapability, Rename, ResolveCompletionItem, SelectionRangeRequest,
-        SemanticTokensFullRequest, SignatureHelpRequest, WorkDoneProgressCreate,
-        WorkspaceSymbolRequest,
+        SemanticTokensFullDeltaRequest, SemanticTokensFullRequest,
+        SemanticTokensRangeRequest, SemanticTokensRefresh, ShowMessageRequest,
+        SignatureHelpRequest, UnregisterCapability, WillRenameFiles,
+        WorkDoneProgressCreate, WorkspaceSymbolRequest,
     },
 };
 use parking_lot::Mutex;
@@ -63,6 +80,7 @@
 use super::{
     PluginCatalogRpcHandler,
     lsp::{DocumentFilter, LspClient},
+    workspace_folder,
 };
 
 pub enum ResponseHandler<Resp, Error> {
@@ -105,11 +123,87 @@ fn call(self: Box<F>, result: Result<Resp, Error>) {
     }
 }
 
+/// A file system change made by the user from within Lapce, such as creating,
+/// renaming or deleting a file in the file explorer.
+#[derive(Clone)]
+pub enum FileOperation {
+    Create(CreateFilesParams),
+    Rename(RenameFilesParams),
+    Delete(DeleteFilesParams),
+}
+
+impl FileOperation {
+    /// The `workspace/did*Files` notification that reports this operation.
+    pub fn did_method(&self) -> &'static str {
+        match self {
+            FileOperation::Create(_) => DidCreateFiles::METHOD,
+            FileOperation::Rename(_) => DidRenameFiles::METHOD,
+            FileOperation::Delete(_) => DidDeleteFiles::METHOD,
+        }
+    }
+}
+
+/// Sent by a volt to add a command to the command palette, optionally with a
+/// default keybinding.
+pub enum RegisterCommand {}
+
+impl Request for RegisterCommand {
+    type Params = VoltCommand;
+    type Result = ();
+    const METHOD: &'static str = "lapce/registerCommand";
+}
+
+/// Sent by a volt to decorate a document, see [`SetDecorationsParams`].
+pub enum SetDecorations {}
+
+impl Request for SetDecorations {
+    type Params = SetDecorationsParams;
+    type Result = ();
+    const METHOD: &'static str = "lapce/setDecorations";
+}
+
+/// Sent by a volt to add or replace an item in the status bar.
+pub enum SetStatusBarItem {}
+
+impl Request for SetStatusBarItem {
+    type Params = VoltStatusBarItem;
+    type Result = ();
+    const METHOD: &'static str = "lapce/setStatusBarItem";
+}
+
+pub enum RemoveStatusBarItem {}
+
+impl Request for RemoveStatusBarItem {
+    type Params = RemoveStatusBarItemParams;
+    type Result = ();
+    const METHOD: &'static str = "lapce/removeStatusBarItem";
+}
+
+/// Sent by a volt to let the user pick one of a list of items.
+pub enum ShowQuickPick {}
+
+impl Request for ShowQuickPick {
+    type Params = ShowQuickPickParams;
+    type Result = Option<usize>;
+    const METHOD: &'static str = "lapce/showQuickPick";
+}
+
+/// Sent to a volt when the user runs one of the commands it registered.
+pub enum ExecuteVoltCommand {}
+
+impl Request for ExecuteVoltCommand {
+    type Params = ExecuteVoltCommandParams;
+    type Result = Option<Value>;
+    const METHOD: &'static str = "lapce/executeCommand";
+}
+
 #[allow(clippy::large_enum_variant)]
 pub enum PluginHandlerNotification {
     Initialize,
     InitializeResult(InitializeResult),
     Shutdown,
+    Restart,
+    SetTrace(bool),
 
     SpawnedPluginLoaded { plugin_id: PluginId },
 }
@@ -162,11 +256,20 @@ pub enum PluginServerRpc {
             )>,
         >,
     },
-    FormatSemanticTokens {
-        tokens: SemanticTokens,
+    SemanticTokens {
+        path: PathBuf,
+        language_id: String,
+        range: Option<Range>,
         text: Rope,
         f: Box<dyn RpcCallback<Vec<LineStyle>, RpcError>>,
     },
+    WillRenameFiles {
+        params: RenameFilesParams,
+        f: Box<dyn RpcCallback<Value, RpcError>>,
+    },
+    DidFileOperation {
+        operation: FileOperation,
+    },
 }
 
 #[derive(Clone)]
@@ -261,12 +364,20 @@ fn handle_did_change_text_document(
             )>,
         >,
     );
-    fn format_semantic_tokens(
+    fn handle_semantic_tokens(
         &self,
-        tokens: SemanticTokens,
+        path: PathBuf,
+        language_id: String,
+        range: Option<Range>,
         text: Rope,
         f: Box<dyn RpcCallback<Vec<LineStyle>, RpcError>>,
     );
+    fn handle_will_rename_files(
+        &self,
+        params: RenameFilesParams,
+        f: Box<dyn RpcCallback<Value, RpcError>>,
+    );
+    fn handle_did_file_operation(&self, operation: FileOperation);
 }
 
 impl PluginServerRpcHandler {
@@ -534,8 +645,26 @@ pub fn mainloop<H>(&self, handler: &mut H)
                         change,
                     );
                 }
-                PluginServerRpc::FormatSemanticTokens { tokens, text, f } => {
-                    handler.format_semantic_tokens(tokens, text, f);
+                PluginServerRpc::SemanticTokens {
+                    path,
+                    language_id,
+                    range,
+                    text,
+                    f,
+                } => {
+                    handler.handle_semantic_tokens(
+                        path,
+                        language_id,
+                        range,
+                        text,
+                        f,
+                    );
+                }
+                PluginServerRpc::WillRenameFiles { params, f } => {
+                    handler.handle_will_rename_files(params, f);
+                }
+                PluginServerRpc::DidFileOperation { operation } => {
+                    handler.handle_did_file_operation(operation);
                 }
                 PluginServerRpc::Handler(notification) => {
                     handler.handle_handler_notification(notification)
@@ -618,6 +747,11 @@ struct SaveRegistration {
 #[derive(Default)]
 struct ServerRegistrations {
     save: Option<SaveRegistration>,
+    /// Dynamically registered file operations, keyed by their method
+    file_operations: HashMap<String, FileOperationRegistrationOptions>,
+    workspace_folders: bool,
+    /// The other methods registered dynamically, whose options aren't used
+    methods: HashSet<String>,
 }
 
 pub struct PluginHostHandler {
@@ -625,6 +759,7 @@ pub struct PluginHostHandler {
     volt_display_name: String,
     pwd: Option<PathBuf>,
     pub(crate) workspace: Option<PathBuf>,
+    permissions: VoltPermissions,
     document_selector: Vec<DocumentFilter>,
     core_rpc: CoreRpcHandler,
     catalog_rpc: PluginCatalogRpcHandler,
@@ -635,6 +770,10 @@ pub struct PluginHostHandler {
     /// Language servers that this plugin has spawned.  
     /// Note that these plugin ids could be 'dead' if the LSP died/exited.  
     spawned_lsp: HashMap<PluginId, SpawnedLspInfo>,
+
+    /// The last full semantic tokens of each document, kept so that
+    /// `semanticTokens/full/delta` edits can be applied to them.
+    semantic_tokens: Arc<Mutex<HashMap<Url, SemanticTokens>>>,
 }
 
 impl PluginHostHandler {
@@ -644,6 +783,7 @@ pub fn new(
         pwd: Option<PathBuf>,
         volt_id: VoltID,
         volt_display_name: String,
+        permissions: VoltPermissions,
         document_selector: DocumentSelector,
         core_rpc: CoreRpcHandler,
         server_rpc: PluginServerRpcHandler,
@@ -658,6 +798,7 @@ pub fn new(
             workspace,
             volt_id,
             volt_display_name,
+            permissions,
             document_selector,
             core_rpc,
             catalog_rpc,
@@ -665,9 +806,21 @@ pub fn new(
             server_capabilities: ServerCapabilities::default(),
             server_registrations: ServerRegistrations::default(),
             spawned_lsp: HashMap::new(),
+            semantic_tokens: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    /// All root folders of the workspace, to be sent when initializing a server.
+    pub fn workspace_folders(&self) -> Option<Vec<WorkspaceFolder>> {
+        let workspace = self.workspace.as_ref()?;
+        Some(
+            std::iter::once(workspace.clone())
+                .chain(self.catalog_rpc.workspace_folders())
+                .filter_map(|path| workspace_folder(&path))
+                .collect(),
+        )
+    }
+
     pub fn document_supported(
         &self,
         language_id: Option<&str>,
@@ -696,6 +849,9 @@ pub fn document_supported(
     }
 
     pub fn method_registered(&mut self, method: &str) -> bool {
+        if self.server_registrations.methods.contains(method) {
+            return true;
+        }
         match method {
             Initialize::METHOD => true,
             Initialized::METHOD => true,
@@ -858,10 +1014,54 @@ pub fn method_registered(&mut self, method: &str) -> bool {
             CallHierarchyIncomingCalls::METHOD => {
                 self.server_capabilities.call_hierarchy_provider.is_some()
             }
+            DidChangeWorkspaceFolders::METHOD => {
+                self.server_registrations.workspace_folders
+                    || self
+                        .server_capabilities
+                        .workspace
+                        .as_ref()
+                        .and_then(|w| w.workspace_folders.as_ref())
+                        .and_then(|f| f.change_notifications.as_ref())
+                        .is_some_and(|c| match c {
+                            OneOf::Left(is_capable) => *is_capable,
+                            // An id to unregister the notifications with
+                            OneOf::Right(_) => true,
+                        })
+            }
             _ => false,
         }
     }
 
+    /// The names of the features the server supports, in the order they are
+    /// listed in the language server panel.
+    pub fn capabilities(&mut self) -> Vec<String> {
+        [
+            ("Completion", Completion::METHOD),
+            ("Hover", HoverRequest::METHOD),
+            ("Signature Help", SignatureHelpRequest::METHOD),
+            ("Definition", GotoDefinition::METHOD),
+            ("Type Definition", GotoTypeDefinition::METHOD),
+            ("Implementation", GotoImplementation::METHOD),
+            ("References", References::METHOD),
+            ("Document Symbols", DocumentSymbolRequest::METHOD),
+            ("Workspace Symbols", WorkspaceSymbolRequest::METHOD),
+            ("Code Actions", CodeActionRequest::METHOD),
+            ("Code Lens", CodeLensRequest::METHOD),
+            ("Formatting", Formatting::METHOD),
+            ("Rename", Rename::METHOD),
+            ("Folding Ranges", FoldingRangeRequest::METHOD),
+            ("Selection Ranges", SelectionRangeRequest::METHOD),
+            ("Semantic Tokens", SemanticTokensFullRequest::METHOD),
+            ("Inlay Hints", InlayHintRequest::METHOD),
+            ("Inline Completion", InlineCompletionRequest::METHOD),
+            ("Call Hierarchy", CallHierarchyPrepare::METHOD),
+        ]
+        .into_iter()
+        .filter(|(_, method)| self.method_registered(method))
+        .map(|(name, _)| name.to_string())
+        .collect()
+    }
+
     fn check_save_capability(&self, language_id: &str, path: &Path) -> (bool, bool) {
         if self.document_supported(Some(language_id), Some(path)) {
             let (should_send, include_text) = self
@@ -900,6 +1100,79 @@ fn check_save_capability(&self, language_id: &str, path: &Path) -> (bool, bool)
         (false, false)
     }
 
+    /// The filters a server registered for a file operation method, either
+    /// statically in its capabilities or dynamically afterwards.
+    fn file_operation_registration(
+        &self,
+        method: &str,
+    ) -> Option<&FileOperationRegistrationOptions> {
+        if let Some(options) = self.server_registrations.file_operations.get(method)
+        {
+            return Some(options);
+        }
+
+        let file_operations = self
+            .server_capabilities
+            .workspace
+            .as_ref()?
+            .file_operations
+            .as_ref()?;
+        match method {
+            WillRenameFiles::METHOD => file_operations.will_rename.as_ref(),
+            DidRenameFiles::METHOD => file_operations.did_rename.as_ref(),
+            DidCreateFiles::METHOD => file_operations.did_create.as_ref(),
+            DidDeleteFiles::METHOD => file_operations.did_delete.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Whether the server asked to be told about file operations on `uri`
+    fn file_operation_matches(&self, method: &str, uri: &str) -> bool {
+        let Some(options) = self.file_operation_registration(method) else {
+            return false;
+        };
+        let Some(path) = Url::parse(uri).ok().and_then(|u| u.to_file_path().ok())
+        else {
+            return false;
+        };
+
+        options.filters.iter().any(|filter| {
+            if filter
+                .scheme
+                .as_deref()
+                .is_some_and(|scheme| scheme != "file")
+            {
+                return false;
+            }
+
+            let pattern = &filter.pattern;
+            // The kind can only be checked for paths that exist, which isn't the
+            // case for deleted files or the target of a rename that hasn't happened yet
+            if path.exists() {
+                let is_dir = path.is_dir();
+                match pattern.matches {
+                    Some(FileOperationPatternKind::File) if is_dir => return false,
+                    Some(FileOperationPatternKind::Folder) if !is_dir => {
+                        return false;
+                    }
+                    _ => {}
+                }
+            }
+
+            let ignore_case = pattern
+                .options
+                .as_ref()
+                .and_then(|o| o.ignore_case)
+                .unwrap_or(false);
+            globset::GlobBuilder::new(&pattern.glob)
+                .case_insensitive(ignore_case)
+                .literal_separator(true)
+                .build()
+                .map(|glob| glob.compile_matcher().is_match(&path))
+                .unwrap_or(false)
+        })
+    }
+
     fn register_capabilities(&mut self, registrations: Vec<Registration>) {
         for registration in registrations {
             if let Err(err) = self.register_capability(registration) {
@@ -929,16 +1202,45 @@ fn register_capability(&mut self, registration: Registration) -> Result<()> {
                         .unwrap_or_default(),
                 });
             }
+            DidChangeWorkspaceFolders::METHOD => {
+                self.server_registrations.workspace_folders = true;
+            }
+            WillRenameFiles::METHOD
+            | DidRenameFiles::METHOD
+            | DidCreateFiles::METHOD
+            | DidDeleteFiles::METHOD => {
+                let options = registration
+                    .register_options
+                    .ok_or_else(|| anyhow!("don't have options"))?;
+                let options: FileOperationRegistrationOptions =
+                    serde_json::from_value(options)?;
+                self.server_registrations
+                    .file_operations
+                    .insert(registration.method, options);
+            }
             _ => {
-                eprintln!(
-                    "don't handle register capability for {}",
-                    registration.method
-                );
+                self.server_registrations
+                    .methods
+                    .insert(registration.method);
             }
         }
         Ok(())
     }
 
+    fn unregister_capability(&mut self, method: &str) {
+        let registrations = &mut self.server_registrations;
+        match method {
+            DidSaveTextDocument::METHOD => registrations.save = None,
+            DidChangeWorkspaceFolders::METHOD => {
+                registrations.workspace_folders = false;
+            }
+            _ => {
+                registrations.file_operations.remove(method);
+                registrations.methods.remove(method);
+            }
+        }
+    }
+
     pub fn handle_request(
         &mut self,
         _id: Id,
@@ -961,13 +1263,26 @@ pub fn process_request(
             WorkDoneProgressCreate::METHOD => {
                 resp.send_null();
             }
+            SemanticTokensRefresh::METHOD => {
+                resp.send_null();
+                self.core_rpc.semantic_tokens_refresh();
+            }
             RegisterCapability::METHOD => {
                 let params: RegistrationParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 self.register_capabilities(params.registrations);
                 resp.send_null();
             }
+            UnregisterCapability::METHOD => {
+                let params: UnregistrationParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                for unregistration in params.unregisterations {
+                    self.unregister_capability(&unregistration.method);
+                }
+                resp.send_null();
+            }
             ExecuteProcess::METHOD => {
+                self.check_process_permission()?;
                 let params: ExecuteProcessParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 let output = std::process::Command::new(params.program)
@@ -981,6 +1296,7 @@ pub fn process_request(
                 });
             }
             RegisterDebuggerType::METHOD => {
+                self.check_process_permission()?;
                 let params: RegisterDebuggerTypeParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 self.catalog_rpc.register_debugger_type(
@@ -991,6 +1307,7 @@ pub fn process_request(
                 resp.send_null();
             }
             StartLspServer::METHOD => {
+                self.check_process_permission()?;
                 let params: StartLspServerParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 let workspace = self.workspace.clone();
@@ -1021,6 +1338,73 @@ pub fn process_request(
                     }
                 });
             }
+            RegisterCommand::METHOD => {
+                let params: VoltCommand =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.catalog_rpc.core_rpc.volt_command_registered(
+                    self.server_rpc.plugin_id,
+                    self.volt_id.clone(),
+                    params,
+                );
+                resp.send_null();
+            }
+            SetDecorations::METHOD => {
+                let params: SetDecorationsParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let path = params
+                    .uri
+                    .to_file_path()
+                    .map_err(|_| anyhow!("{} is not a file", params.uri))?;
+                self.catalog_rpc.core_rpc.volt_decorations(
+                    self.volt_id.clone(),
+                    path,
+                    params.key,
+                    params.decorations,
+                );
+                resp.send_null();
+            }
+            SetStatusBarItem::METHOD => {
+                let item: VoltStatusBarItem =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.catalog_rpc
+                    .core_rpc
+                    .set_volt_status_bar_item(self.volt_id.clone(), item);
+                resp.send_null();
+            }
+            RemoveStatusBarItem::METHOD => {
+                let params: RemoveStatusBarItemParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.catalog_rpc
+                    .core_rpc
+                    .remove_volt_status_bar_item(self.volt_id.clone(), params.id);
+                resp.send_null();
+            }
+            ShowMessageRequest::METHOD => {
+                let params: ShowMessageRequestParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let actions = params.actions.unwrap_or_default();
+                let items = actions.iter().map(|a| a.title.clone()).collect();
+                self.catalog_rpc.show_volt_prompt(
+                    format!("Plugin: {}", self.volt_display_name),
+                    params.message,
+                    items,
+                    move |index| {
+                        resp.send(index.and_then(|i| actions.get(i).cloned()));
+                    },
+                );
+            }
+            ShowQuickPick::METHOD => {
+                let params: ShowQuickPickParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.catalog_rpc.show_volt_prompt(
+                    format!("Plugin: {}", self.volt_display_name),
+                    params.placeholder.unwrap_or_default(),
+                    params.items,
+                    move |index| {
+                        resp.send(index);
+                    },
+                );
+            }
             SendLspNotification::METHOD => {
                 let params: SendLspNotificationParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
@@ -1087,6 +1471,26 @@ pub fn process_request(
         Ok(())
     }
 
+    /// Volts can only run processes if they were granted the permission, the
+    /// user is told when a volt is refused.
+    fn check_process_permission(&self) -> Result<()> {
+        if self.permissions.process {
+            return Ok(());
+        }
+        let message = format!(
+            "{} doesn't have the permission to run processes, so it can't start its language server or debugger. Add `process = true` to the `[permissions]` section of its volt.toml to allow it.",
+            self.volt_display_name
+        );
+        self.core_rpc.show_message(
+            self.volt_display_name.clone(),
+            ShowMessageParams {
+                typ: MessageType::ERROR,
+                message: message.clone(),
+            },
+        );
+        Err(anyhow!(message))
+    }
+
     pub fn handle_notification(
         &mut self,
         method: String,
@@ -1104,6 +1508,7 @@ pub fn handle_notification(
                     ),
                     Some(format!("lapce_proxy::plugin::psp::{}::{}::StartLspServer", self.volt_id.author, self.volt_id.name)),
                 );
+                self.check_process_permission()?;
 
                 let params: StartLspServerParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
@@ -1292,22 +1697,312 @@ pub fn handle_did_change_text_document(
         );
     }
 
-    pub fn format_semantic_tokens(
+    pub fn handle_semantic_tokens(
         &self,
-        tokens: SemanticTokens,
+        path: PathBuf,
+        language_id: String,
+        range: Option<Range>,
         text: Rope,
         f: Box<dyn RpcCallback<Vec<LineStyle>, RpcError>>,
     ) {
-        let result = format_semantic_styles(
-            &text,
-            self.server_capabilities.semantic_tokens_provider.as_ref(),
-            &tokens,
-        )
-        .ok_or_else(|| RpcError {
-            code: 0,
-            message: "can't get styles".to_string(),
+        let provider =
+            match self.server_capabilities.semantic_tokens_provider.as_ref() {
+                Some(provider)
+                    if self.document_supported(Some(&language_id), Some(&path)) =>
+                {
+                    provider.clone()
+                }
+                _ => {
+                    // The document may have been supported when its tokens
+                    // were cached
+                    if let Ok(uri) = Url::from_file_path(&path) {
+                        self.semantic_tokens.lock().remove(&uri);
+                    }
+                    f.call(Err(RpcError {
+                        code: 0,
+                        message: "semantic tokens not supported".to_string(),
+                    }));
+                    return;
+                }
+            };
+        let Ok(uri) = Url::from_file_path(&path) else {
+            f.call(Err(RpcError {
+                code: 0,
+                message: "invalid path".to_string(),
+            }));
+            return;
+        };
+        let options = semantic_tokens_options(&provider);
+        let range_supported = options.range == Some(true);
+        let delta_supported = matches!(
+            options.full,
+            Some(SemanticTokensFullOptions::Delta { delta: Some(true) })
+        );
+        let text_document = TextDocumentIdentifier { uri: uri.clone() };
+        let format = move |tokens: Result<SemanticTokens, RpcError>| {
+            let styles = tokens.and_then(|tokens| {
+                format_semantic_styles(&text, Some(&provider), &tokens).ok_or_else(
+                    || RpcError {
+                        code: 0,
+                        message: "can't get styles".to_string(),
+                    },
+                )
+            });
+            f.call(styles);
+        };
+
+        if let Some(range) = range {
+            if !range_supported {
+                format(Err(RpcError {
+                    code: 0,
+                    message: "semantic tokens range not supported".to_string(),
+                }));
+                return;
+            }
+            let params = SemanticTokensRangeParams {
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+                text_document,
+                range,
+            };
+            self.server_rpc.server_request_async(
+                SemanticTokensRangeRequest::METHOD,
+                params,
+                Some(language_id),
+                Some(path),
+                false,
+                move |result| {
+                    let tokens =
+                        parse_response::<Option<SemanticTokensRangeResult>>(result)
+                            .map(|result| match result {
+                                Some(SemanticTokensRangeResult::Tokens(tokens)) => {
+                                    tokens
+                                }
+                                Some(SemanticTokensRangeResult::Partial(
+                                    partial,
+                                )) => SemanticTokens {
+                                    result_id: None,
+                                    data: partial.data,
+                                },
+                                None => SemanticTokens::default(),
+                            });
+                    format(tokens);
+                },
+            );
+            return;
+        }
+
+        let cache = self.semantic_tokens.clone();
+        let previous_result_id = if delta_supported {
+            cache
+                .lock()
+                .get(&uri)
+                .and_then(|tokens| tokens.result_id.clone())
+        } else {
+            None
+        };
+
+        if let Some(previous_result_id) = previous_result_id {
+            let params = SemanticTokensDeltaParams {
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+                text_document,
+                previous_result_id: previous_result_id.clone(),
+            };
+            self.server_rpc.server_request_async(
+                SemanticTokensFullDeltaRequest::METHOD,
+                params,
+                Some(language_id),
+                Some(path),
+                false,
+                move |result| {
+                    let result = parse_response::<
+                        Option<SemanticTokensFullDeltaResult>,
+                    >(result);
+                    let mut cache = cache.lock();
+                    let tokens = match result {
+                        Ok(Some(SemanticTokensFullDeltaResult::Tokens(tokens))) => {
+                            Ok(tokens)
+                        }
+                        Ok(Some(SemanticTokensFullDeltaResult::TokensDelta(
+                            delta,
+                        ))) => apply_cached_semantic_tokens_edits(
+                            &mut cache,
+                            &uri,
+                            &previous_result_id,
+                            delta.result_id,
+                            delta.edits,
+                        ),
+                        Ok(Some(
+                            SemanticTokensFullDeltaResult::PartialTokensDelta {
+                                edits,
+                            },
+                        )) => apply_cached_semantic_tokens_edits(
+                            &mut cache,
+                            &uri,
+                            &previous_result_id,
+                            None,
+                            edits,
+                        ),
+                        Ok(None) => Ok(SemanticTokens::default()),
+                        Err(err) => Err(err),
+                    };
+                    match &tokens {
+                        Ok(tokens) if tokens.result_id.is_some() => {
+                            cache.insert(uri, tokens.clone());
+                        }
+                        _ => {
+                            cache.remove(&uri);
+                        }
+                    }
+                    drop(cache);
+                    format(tokens);
+                },
+            );
+            return;
+        }
+
+        let params = SemanticTokensParams {
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+            text_document,
+        };
+        self.server_rpc.server_request_async(
+            SemanticTokensFullRequest::METHOD,
+            params,
+            Some(language_id),
+            Some(path),
+            false,
+            move |result| {
+                let tokens = parse_response::<Option<SemanticTokensResult>>(result)
+                    .map(|result| match result {
+                        Some(SemanticTokensResult::Tokens(tokens)) => tokens,
+                        Some(SemanticTokensResult::Partial(partial)) => {
+                            SemanticTokens {
+                                result_id: None,
+                                data: partial.data,
+                            }
+                        }
+                        None => SemanticTokens::default(),
+                    });
+                if delta_supported {
+                    let mut cache = cache.lock();
+                    match &tokens {
+                        Ok(tokens) if tokens.result_id.is_some() => {
+                            cache.insert(uri, tokens.clone());
+                        }
+                        _ => {
+                            cache.remove(&uri);
+                        }
+                    }
+                }
+                format(tokens);
+            },
+        );
+    }
+
+    pub fn handle_will_rename_files(
+        &self,
+        params: RenameFilesParams,
+        f: Box<dyn RpcCallback<Value, RpcError>>,
+    ) {
+        let files: Vec<_> = params
+            .files
+            .into_iter()
+            .filter(|file| {
+                self.file_operation_matches(WillRenameFiles::METHOD, &file.old_uri)
+            })
+            .collect();
+        if files.is_empty() {
+            f.call(Ok(Value::Null));
+            return;
+        }
+
+        self.server_rpc.server_request_async(
+            WillRenameFiles::METHOD,
+            RenameFilesParams { files },
+            None,
+            None,
+            false,
+            move |result| f.call(result),
+        );
+    }
+
+    pub fn handle_did_file_operation(&self, operation: FileOperation) {
+        self.evict_semantic_tokens(&operation);
+        let method = operation.did_method();
+        let params = match operation {
+            FileOperation::Create(params) => {
+                let files: Vec<_> = params
+                    .files
+                    .into_iter()
+                    .filter(|file| self.file_operation_matches(method, &file.uri))
+                    .collect();
+                if files.is_empty() {
+                    return;
+                }
+                serde_json::to_value(CreateFilesParams { files })
+            }
+            FileOperation::Rename(params) => {
+                let files: Vec<_> = params
+                    .files
+                    .into_iter()
+                    .filter(|file| {
+                        self.file_operation_matches(method, &file.old_uri)
+                            || self.file_operation_matches(method, &file.new_uri)
+                    })
+                    .collect();
+                if files.is_empty() {
+                    return;
+                }
+                serde_json::to_value(RenameFilesParams { files })
+            }
+            FileOperation::Delete(params) => {
+                let files: Vec<_> = params
+                    .files
+                    .into_iter()
+                    .filter(|file| self.file_operation_matches(method, &file.uri))
+                    .collect();
+                if files.is_empty() {
+                    return;
+                }
+                serde_json::to_value(DeleteFilesParams { files })
+            }
+        };
+
+        match params {
+            Ok(params) => {
+                self.server_rpc
+                    .server_notification(method, params, None, None, false);
+            }
+            Err(err) => {
+                tracing::error!("{:?}", err);
+            }
+        }
+    }
+
+    /// Drop the cached semantic tokens of the documents that a rename or a
+    /// delete took away, including those in a folder that went with it.
+    fn evict_semantic_tokens(&self, operation: &FileOperation) {
+        let uris: Vec<&str> = match operation {
+            FileOperation::Create(_) => return,
+            FileOperation::Rename(params) => params
+                .files
+                .iter()
+                .map(|file| file.old_uri.as_str())
+                .collect(),
+            FileOperation::Delete(params) => {
+                params.files.iter().map(|file| file.uri.as_str()).collect()
+            }
+        };
+        self.semantic_tokens.lock().retain(|cached, _| {
+            let cached = cached.as_str();
+            !uris.iter().any(|uri| {
+                cached
+                    .strip_prefix(uri)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
         });
-        f.call(result);
     }
 
     pub fn handle_spawned_plugin_loaded(&mut self, plugin_id: PluginId) {
@@ -1426,15 +2121,67 @@ fn format_semantic_styles(
     Some(highlights)
 }
 
-fn semantic_tokens_legend(
+fn parse_response<T: serde::de::DeserializeOwned>(
+    result: Result<Value, RpcError>,
+) -> Result<T, RpcError> {
+    result.and_then(|value| {
+        serde_json::from_value(value).map_err(|err| RpcError {
+            code: 0,
+            message: err.to_string(),
+        })
+    })
+}
+
+/// Apply the edits of a `semanticTokens/full/delta` response to the cached
+/// tokens of the document, if they are still the ones the delta was computed
+/// against.
+fn apply_cached_semantic_tokens_edits(
+    cache: &mut HashMap<Url, SemanticTokens>,
+    uri: &Url,
+    previous_result_id: &str,
+    result_id: Option<String>,
+    edits: Vec<SemanticTokensEdit>,
+) -> Result<SemanticTokens, RpcError> {
+    match cache.get_mut(uri) {
+        Some(tokens) if tokens.result_id.as_deref() == Some(previous_result_id) => {
+            apply_semantic_tokens_edits(&mut tokens.data, edits);
+            tokens.result_id = result_id;
+            Ok(tokens.clone())
+        }
+        _ => Err(RpcError {
+            code: 0,
+            message: "semantic tokens delta is out of date".to_string(),
+        }),
+    }
+}
+
+/// The `start` and `delete_count` of an edit count the integers of the
+/// encoded tokens, and every token is encoded as five integers.
+fn apply_semantic_tokens_edits(
+    data: &mut Vec<SemanticToken>,
+    mut edits: Vec<SemanticTokensEdit>,
+) {
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.start));
+    for edit in edits {
+        let start = (edit.start as usize / 5).min(data.len());
+        let end = ((edit.start + edit.delete_count) as usize / 5).min(data.len());
+        data.splice(start..end, edit.data.unwrap_or_default());
+    }
+}
+
+fn semantic_tokens_options(
     semantic_tokens_provider: &SemanticTokensServerCapabilities,
-) -> &SemanticTokensLegend {
+) -> &SemanticTokensOptions {
     match semantic_tokens_provider {
-        SemanticTokensServerCapabilities::SemanticTokensOptions(options) => {
-            &options.legend
-        }
+        SemanticTokensServerCapabilities::SemanticTokensOptions(options) => options,
         SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(
             options,
-        ) => &options.semantic_tokens_options.legend,
+        ) => &options.semantic_tokens_options,
     }
 }
+
+fn semantic_tokens_legend(
+    semantic_tokens_provider: &SemanticTokensServerCapabilities,
+) -> &SemanticTokensLegend {
+    &semantic_tokens_options(semantic_tokens_provider).legend
+}
@@ -0,0 +1,66 @@
+use lsp_types::{SemanticToken, SemanticTokensEdit};
+
+use super::apply_semantic_tokens_edits;
+
+fn token(delta_line: u32, delta_start: u32) -> SemanticToken {
+    SemanticToken {
+        delta_line,
+        delta_start,
+        length: 1,
+        token_type: 0,
+        token_modifiers_bitset: 0,
+    }
+}
+
+#[test]
+fn test_apply_semantic_tokens_edits() {
+    let mut data = vec![token(0, 0), token(1, 0), token(1, 4), token(2, 0)];
+    apply_semantic_tokens_edits(
+        &mut data,
+        vec![
+            // Replace the second token
+            SemanticTokensEdit {
+                start: 5,
+                delete_count: 5,
+                data: Some(vec![token(1, 2)]),
+            },
+            // Remove the last token and append two new ones
+            SemanticTokensEdit {
+                start: 15,
+                delete_count: 5,
+                data: Some(vec![token(3, 0), token(0, 6)]),
+            },
+            // Insert a token at the start
+            SemanticTokensEdit {
+                start: 0,
+                delete_count: 0,
+                data: Some(vec![token(0, 1)]),
+            },
+        ],
+    );
+    assert_eq!(
+        data,
+        vec![
+            token(0, 1),
+            token(0, 0),
+            token(1, 2),
+            token(1, 4),
+            token(3, 0),
+            token(0, 6),
+        ]
+    );
+}
+
+#[test]
+fn test_apply_semantic_tokens_edits_delete() {
+    let mut data = vec![token(0, 0), token(1, 0), token(1, 4)];
+    apply_semantic_tokens_edits(
+        &mut data,
+        vec![SemanticTokensEdit {
+            start: 5,
+            delete_count: 10,
+            data: None,
+        }],
+    );
+    assert_eq!(data, vec![token(0, 0)]);
+}
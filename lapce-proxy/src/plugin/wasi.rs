@@ -7,7 +7,7 @@
     io::{Read, Seek, Write},
     path::{Path, PathBuf},
     process,
-    sync::{Arc, RwLock},
+    sync::{Arc, OnceLock, RwLock},
     thread,
 };
 
@@ -21,22 +21,24 @@
 };
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
-    DocumentFilter, InitializeParams, InitializedParams,
+    DocumentFilter, InitializeParams, InitializedParams, RenameFilesParams,
     TextDocumentContentChangeEvent, TextDocumentIdentifier, Url,
-    VersionedTextDocumentIdentifier, WorkDoneProgressParams, WorkspaceFolder,
+    VersionedTextDocumentIdentifier, WorkDoneProgressParams,
     notification::Initialized, request::Initialize,
 };
 use parking_lot::Mutex;
 use psp_types::{Notification, Request};
 use serde_json::Value;
 use wasi_experimental_http_wasmtime::{HttpCtx, HttpState};
-use wasmtime_wasi::WasiCtxBuilder;
+use wasmtime::{StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::{WasiCtx, WasiCtxBuilder};
 
 use super::{
     PluginCatalogRpcHandler, client_capabilities,
     psp::{
-        PluginHandlerNotification, PluginHostHandler, PluginServerHandler,
-        PluginServerRpc, ResponseSender, RpcCallback, handle_plugin_server_message,
+        FileOperation, PluginHandlerNotification, PluginHostHandler,
+        PluginServerHandler, PluginServerRpc, ResponseSender, RpcCallback,
+        handle_plugin_server_message,
     },
     volt_icon,
 };
@@ -79,6 +81,41 @@ fn seek(&mut self, _pos: std::io::SeekFrom) -> std::io::Result<u64> {
     }
 }
 
+/// How much fuel a volt can use to handle a single message before it's
+/// interrupted, about a wasm instruction each, i.e. some seconds of CPU time.
+/// Unlike a wall-clock deadline, this doesn't count the time spent in host
+/// calls, e.g. waiting for an http response.
+const VOLT_FUEL_LIMIT: u64 = 10_000_000_000;
+/// The maximum size of the linear memory of a volt.
+const VOLT_MEMORY_LIMIT: usize = 512 * 1024 * 1024;
+
+/// The state of the wasm store of a volt.
+struct VoltCtx {
+    wasi: WasiCtx,
+    /// The hosts the volt can make http requests to
+    allowed_hosts: Vec<String>,
+    limits: StoreLimits,
+}
+
+/// The engine shared by all volts, which meters the fuel they use so volts
+/// stuck in a loop are interrupted.
+fn volt_engine() -> &'static wasmtime::Engine {
+    static ENGINE: OnceLock<wasmtime::Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        wasmtime::Engine::new(&config).unwrap()
+    })
+}
+
+/// Reset the fuel of the store to [`VOLT_FUEL_LIMIT`], before the volt handles
+/// a message.
+fn refuel_volt<T>(store: &mut wasmtime::Store<T>) -> Result<()> {
+    let remaining = store.consume_fuel(0)?;
+    store.add_fuel(VOLT_FUEL_LIMIT.saturating_sub(remaining))?;
+    Ok(())
+}
+
 pub struct Plugin {
     #[allow(dead_code)]
     id: PluginId,
@@ -117,6 +154,8 @@ fn handle_handler_notification(
             SpawnedPluginLoaded { plugin_id } => {
                 self.host.handle_spawned_plugin_loaded(plugin_id);
             }
+            // Only language servers can be restarted or traced
+            Restart | SetTrace(_) => {}
         }
     }
 
@@ -180,13 +219,28 @@ fn handle_did_change_text_document(
         );
     }
 
-    fn format_semantic_tokens(
+    fn handle_semantic_tokens(
         &self,
-        tokens: lsp_types::SemanticTokens,
+        path: PathBuf,
+        language_id: String,
+        range: Option<lsp_types::Range>,
         text: Rope,
         f: Box<dyn RpcCallback<Vec<LineStyle>, RpcError>>,
     ) {
-        self.host.format_semantic_tokens(tokens, text, f);
+        self.host
+            .handle_semantic_tokens(path, language_id, range, text, f);
+    }
+
+    fn handle_will_rename_files(
+        &self,
+        params: RenameFilesParams,
+        f: Box<dyn RpcCallback<Value, RpcError>>,
+    ) {
+        self.host.handle_will_rename_files(params, f);
+    }
+
+    fn handle_did_file_operation(&self, operation: FileOperation) {
+        self.host.handle_did_file_operation(operation);
     }
 }
 
@@ -202,18 +256,13 @@ fn initialize(&mut self) {
             InitializeParams {
                 process_id: Some(process::id()),
                 root_path: None,
-                root_uri: root_uri.clone(),
+                root_uri,
                 capabilities: client_capabilities(),
                 trace: None,
                 client_info: None,
                 locale: None,
                 initialization_options: configurations,
-                workspace_folders: root_uri.map(|uri| {
-                    vec![WorkspaceFolder {
-                        name: uri.as_str().to_string(),
-                        uri,
-                    }]
-                }),
+                workspace_folders: self.host.workspace_folders(),
                 work_done_progress_params: WorkDoneProgressParams::default(),
             },
             None,
@@ -361,7 +410,8 @@ pub fn find_all_volts(extra_plugin_paths: &[PathBuf]) -> Vec<VoltMetadata> {
 ///         icon_themes: None,
 ///         dir: parent_path.canonicalize().ok(),
 ///         activation: None,
-///         config: None
+///         config: None,
+///         permissions: None
 ///     }
 /// );
 /// let _ = std::fs::remove_file(parent_path.join("volt.toml"));
@@ -419,17 +469,18 @@ pub fn start_volt(
     plugin_rpc: PluginCatalogRpcHandler,
     meta: VoltMetadata,
 ) -> Result<()> {
-    let engine = wasmtime::Engine::default();
+    let engine = volt_engine();
     let module = wasmtime::Module::from_file(
-        &engine,
+        engine,
         meta.wasm
             .as_ref()
             .ok_or_else(|| anyhow!("no wasm in plugin"))?,
     )?;
-    let mut linker = wasmtime::Linker::new(&engine);
-    wasmtime_wasi::add_to_linker(&mut linker, |s| s)?;
-    HttpState::new()?.add_to_linker(&mut linker, |_| HttpCtx {
-        allowed_hosts: Some(vec!["insecure:allow-all".to_string()]),
+    let permissions = meta.permissions();
+    let mut linker = wasmtime::Linker::new(engine);
+    wasmtime_wasi::add_to_linker(&mut linker, |s: &mut VoltCtx| &mut s.wasi)?;
+    HttpState::new()?.add_to_linker(&mut linker, |s: &VoltCtx| HttpCtx {
+        allowed_hosts: Some(s.allowed_hosts.clone()),
         max_concurrent_requests: Some(100),
     })?;
 
@@ -464,8 +515,35 @@ pub fn start_volt(
     let stdin = Arc::new(RwLock::new(WasiPipe::new()));
     let stdout = Arc::new(RwLock::new(WasiPipe::new()));
     let stderr = Arc::new(RwLock::new(WasiPipe::new()));
-    let wasi = WasiCtxBuilder::new()
-        .inherit_env()?
+    let mut wasi = WasiCtxBuilder::new();
+    for (key, value) in std::env::vars() {
+        if permissions.env_allowed(&key) {
+            wasi.env(&key, &value)?;
+        }
+    }
+    for dir in &permissions.filesystem {
+        let dir = match workspace.as_ref() {
+            Some(workspace) => {
+                dir.replace("${workspace}", &workspace.to_string_lossy())
+            }
+            None if dir.contains("${workspace}") => continue,
+            None => dir.clone(),
+        };
+        // A missing folder, e.g. a cache that hasn't been made yet, leaves
+        // the volt without it rather than failing to load the volt
+        let opened = match wasmtime_wasi::Dir::open_ambient_dir(
+            &dir,
+            wasmtime_wasi::ambient_authority(),
+        ) {
+            Ok(opened) => opened,
+            Err(err) => {
+                tracing::warn!("{} can't access {dir}: {err}", meta.display_name);
+                continue;
+            }
+        };
+        wasi.preopened_dir(opened, &dir)?;
+    }
+    let wasi = wasi
         .env("VOLT_OS", std::env::consts::OS)?
         .env("VOLT_ARCH", std::env::consts::ARCH)?
         .env("VOLT_LIBC", volt_libc)?
@@ -492,7 +570,17 @@ pub fn start_volt(
             "/",
         )?
         .build();
-    let mut store = wasmtime::Store::new(&engine, wasi);
+    let mut store = wasmtime::Store::new(
+        engine,
+        VoltCtx {
+            wasi,
+            allowed_hosts: permissions.network.clone(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(VOLT_MEMORY_LIMIT)
+                .build(),
+        },
+    );
+    store.limiter(|ctx| &mut ctx.limits);
 
     let (io_tx, io_rx) = crossbeam_channel::unbounded();
     let rpc = PluginServerRpcHandler::new(meta.id(), None, None, io_tx);
@@ -522,16 +610,17 @@ pub fn start_volt(
     })?;
     linker.module(&mut store, "", &module)?;
     let local_rpc = rpc.clone();
+    let local_plugin_rpc = plugin_rpc.clone();
+    let local_meta = meta.clone();
     thread::spawn(move || {
         let mut exist_id = None;
-        {
-            let instance = linker.instantiate(&mut store, &module).unwrap();
+        let run = || -> Result<()> {
+            refuel_volt(&mut store)?;
+            let instance = linker.instantiate(&mut store, &module)?;
             let handle_rpc = instance
                 .get_func(&mut store, "handle_rpc")
-                .ok_or_else(|| anyhow!("can't convet to function"))
-                .unwrap()
-                .typed::<(), ()>(&mut store)
-                .unwrap();
+                .ok_or_else(|| anyhow!("can't convet to function"))?
+                .typed::<(), ()>(&mut store)?;
             for msg in io_rx {
                 if msg
                     .get_method()
@@ -546,9 +635,19 @@ pub fn start_volt(
                         tracing::error!("{:?}", err);
                     }
                 }
-                if let Err(err) = handle_rpc.call(&mut store, ()) {
-                    tracing::error!("{:?}", err);
-                }
+                // A trap leaves the instance in an unknown state, so the volt
+                // is restarted instead of handling more messages
+                refuel_volt(&mut store)?;
+                handle_rpc.call(&mut store, ())?;
+            }
+            Ok(())
+        };
+        if let Err(err) = run() {
+            tracing::error!("{:?}", err);
+            if let Err(err) = local_plugin_rpc
+                .volt_crashed(local_meta, err.root_cause().to_string())
+            {
+                tracing::error!("{:?}", err);
             }
         }
         if let Some(id) = exist_id {
@@ -564,6 +663,7 @@ pub fn start_volt(
             meta.dir.clone(),
             meta.id(),
             meta.display_name.clone(),
+            permissions,
             meta.activation
                 .iter()
                 .flat_map(|m| m.language.iter().flatten())
@@ -1,9 +1,9 @@
 use std::collections::HashMap;
 
-use lapce_rpc::plugin::VoltMetadata;
+use lapce_rpc::plugin::{VoltMetadata, VoltPermissions};
 use serde_json::{Value, json};
 
-use super::{load_volt, unflatten_map};
+use super::{VOLT_FUEL_LIMIT, load_volt, refuel_volt, unflatten_map, volt_engine};
 
 #[test]
 fn test_unflatten_map() {
@@ -145,7 +145,8 @@ fn test_load_volt() {
             icon_themes: Some(icon_themes_pathes),
             dir: parent_path.canonicalize().ok(),
             activation: None,
-            config: None
+            config: None,
+            permissions: None
         }
     );
 
@@ -205,7 +206,8 @@ fn test_load_volt() {
             icon_themes: Some(icon_themes_pathes),
             dir: parent_path.canonicalize().ok(),
             activation: None,
-            config: None
+            config: None,
+            permissions: None
         }
     );
 
@@ -231,7 +233,49 @@ fn test_load_volt() {
             icon_themes: Some(Vec::new()),
             dir: parent_path.canonicalize().ok(),
             activation: None,
-            config: None
+            config: None,
+            permissions: None
         }
     );
 }
+
+#[test]
+fn test_volt_permissions() {
+    let volt_metadata: VoltMetadata = toml::from_str(
+        r#"
+        name = "plugin"
+        version = "0.1"
+        display-name = "Plugin"
+        author = "Author"
+        description = "Useful plugin"
+
+        [permissions]
+        network = ["https://github.com"]
+        env = ["PATH"]
+        "#,
+    )
+    .unwrap();
+    let permissions = volt_metadata.permissions();
+    assert_eq!(permissions.network, vec!["https://github.com".to_string()]);
+    assert!(permissions.env_allowed("PATH"));
+    assert!(!permissions.env_allowed("HOME"));
+    assert!(permissions.filesystem.is_empty());
+    assert!(!permissions.process);
+
+    let volt_metadata = VoltMetadata {
+        permissions: None,
+        ..volt_metadata
+    };
+    assert_eq!(volt_metadata.permissions(), VoltPermissions::unrestricted());
+}
+
+#[test]
+fn test_refuel_volt() {
+    let mut store = wasmtime::Store::new(volt_engine(), ());
+    refuel_volt(&mut store).unwrap();
+    assert_eq!(store.consume_fuel(0).unwrap(), VOLT_FUEL_LIMIT);
+
+    store.consume_fuel(1000).unwrap();
+    refuel_volt(&mut store).unwrap();
+    assert_eq!(store.consume_fuel(0).unwrap(), VOLT_FUEL_LIMIT);
+}
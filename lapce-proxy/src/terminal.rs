@@ -1,9 +1,10 @@
 use std::{
     borrow::Cow,
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
+    fs,
     io::{self, ErrorKind, Read, Write},
     num::NonZeroUsize,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
 };
@@ -13,9 +14,10 @@
     event_loop::Msg,
     tty::{self, EventedPty, EventedReadWrite, Options, Shell, setup_env},
 };
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use crossbeam_channel::{Receiver, Sender};
 use directories::BaseDirs;
+use lapce_core::directory::Directory;
 use lapce_rpc::{
     core::CoreRpcHandler,
     terminal::{TermId, TerminalProfile},
@@ -24,6 +26,18 @@
 
 const READ_BUFFER_SIZE: usize = 0x10_0000;
 
+/// Scripts that mark prompts and commands with OSC 133 / OSC 633 sequences.
+const BASH_INTEGRATION: &str =
+    include_str!("../../defaults/shell-integration/bash.sh");
+const ZSHENV_INTEGRATION: &str =
+    include_str!("../../defaults/shell-integration/zshenv.zsh");
+const ZPROFILE_INTEGRATION: &str =
+    include_str!("../../defaults/shell-integration/zprofile.zsh");
+const ZSHRC_INTEGRATION: &str =
+    include_str!("../../defaults/shell-integration/zshrc.zsh");
+const FISH_INTEGRATION: &str =
+    include_str!("../../defaults/shell-integration/fish.fish");
+
 #[cfg(any(target_os = "linux", target_os = "macos"))]
 const PTY_READ_WRITE_TOKEN: usize = 0;
 #[cfg(any(target_os = "linux", target_os = "macos"))]
@@ -36,24 +50,46 @@
 
 pub struct TerminalSender {
     tx: Sender<Msg>,
-    poller: Arc<polling::Poller>,
+    /// Wakes up the event loop of a terminal run by the proxy, there's none
+    /// for the ones in the terminal server
+    poller: Option<Arc<polling::Poller>>,
 }
 
 impl TerminalSender {
     pub fn new(tx: Sender<Msg>, poller: Arc<polling::Poller>) -> Self {
-        Self { tx, poller }
+        Self {
+            tx,
+            poller: Some(poller),
+        }
+    }
+
+    /// A sender to a terminal kept in the terminal server.
+    pub fn session(tx: Sender<Msg>) -> Self {
+        Self { tx, poller: None }
+    }
+
+    pub fn is_session(&self) -> bool {
+        self.poller.is_none()
     }
 
     pub fn send(&self, msg: Msg) {
         if let Err(err) = self.tx.send(msg) {
             tracing::error!("{:?}", err);
         }
-        if let Err(err) = self.poller.notify() {
-            tracing::error!("{:?}", err);
+        if let Some(poller) = &self.poller {
+            if let Err(err) = poller.notify() {
+                tracing::error!("{:?}", err);
+            }
         }
     }
 }
 
+/// What happens in a running terminal.
+pub enum TerminalEvent {
+    Output(Vec<u8>),
+    Stopped(Option<i32>),
+}
+
 pub struct Terminal {
     term_id: TermId,
     pub(crate) poller: Arc<polling::Poller>,
@@ -65,12 +101,18 @@ pub struct Terminal {
 impl Terminal {
     pub fn new(
         term_id: TermId,
-        profile: TerminalProfile,
+        mut profile: TerminalProfile,
         width: usize,
         height: usize,
     ) -> Result<Terminal> {
         let poll = polling::Poller::new()?.into();
 
+        if profile.shell_integration {
+            if let Err(err) = Terminal::integrate_shell(&mut profile) {
+                tracing::error!("{:?}", err);
+            }
+        }
+
         let options = Options {
             shell: Terminal::program(&profile),
             working_directory: Terminal::workdir(&profile),
@@ -103,6 +145,20 @@ pub fn new(
     }
 
     pub fn run(&mut self, core_rpc: CoreRpcHandler) {
+        let term_id = self.term_id;
+        self.run_with(|event| match event {
+            TerminalEvent::Output(content) => {
+                core_rpc.update_terminal(term_id, content)
+            }
+            TerminalEvent::Stopped(exit_code) => {
+                core_rpc.terminal_process_stopped(term_id, exit_code)
+            }
+        });
+    }
+
+    /// Run the event loop of the terminal until the shell exits or it's shut
+    /// down, passing its output to `on_event`.
+    pub fn run_with(&mut self, mut on_event: impl FnMut(TerminalEvent)) {
         let mut state = State::default();
         let mut buf = [0u8; READ_BUFFER_SIZE];
 
@@ -141,7 +197,8 @@ pub fn run(&mut self, core_rpc: CoreRpcHandler) {
                         if let Some(tty::ChildEvent::Exited(exited_code)) =
                             self.pty.next_child_event()
                         {
-                            if let Err(err) = self.pty_read(&core_rpc, &mut buf) {
+                            if let Err(err) = self.pty_read(&mut on_event, &mut buf)
+                            {
                                 tracing::error!("{:?}", err);
                             }
                             exit_code = exited_code;
@@ -156,7 +213,8 @@ pub fn run(&mut self, core_rpc: CoreRpcHandler) {
                         }
 
                         if event.readable {
-                            if let Err(err) = self.pty_read(&core_rpc, &mut buf) {
+                            if let Err(err) = self.pty_read(&mut on_event, &mut buf)
+                            {
                                 // On Linux, a `read` on the master side of a PTY can fail
                                 // with `EIO` if the client side hangs up.  In that case,
                                 // just loop back round for the inevitable `Exited` event.
@@ -200,7 +258,7 @@ pub fn run(&mut self, core_rpc: CoreRpcHandler) {
                     .unwrap();
             }
         }
-        core_rpc.terminal_process_stopped(self.term_id, exit_code);
+        on_event(TerminalEvent::Stopped(exit_code));
         if let Err(err) = self.pty.deregister(&self.poller) {
             tracing::error!("{:?}", err);
         }
@@ -224,14 +282,14 @@ fn drain_recv_channel(&mut self, state: &mut State) -> bool {
     #[inline]
     fn pty_read(
         &mut self,
-        core_rpc: &CoreRpcHandler,
+        on_event: &mut impl FnMut(TerminalEvent),
         buf: &mut [u8],
     ) -> io::Result<()> {
         loop {
             match self.pty.reader().read(buf) {
                 Ok(0) => break,
                 Ok(n) => {
-                    core_rpc.update_terminal(self.term_id, buf[..n].to_vec());
+                    on_event(TerminalEvent::Output(buf[..n].to_vec()));
                 }
                 Err(err) => match err.kind() {
                     ErrorKind::Interrupted | ErrorKind::WouldBlock => {
@@ -295,6 +353,78 @@ fn workdir(profile: &TerminalProfile) -> Option<PathBuf> {
         BaseDirs::new().map(|d| PathBuf::from(d.home_dir()))
     }
 
+    /// Load the shell integration script into the shell, when it's bash, zsh
+    /// or fish started without arguments.
+    fn integrate_shell(profile: &mut TerminalProfile) -> Result<()> {
+        if profile.arguments.as_ref().is_some_and(|a| !a.is_empty()) {
+            return Ok(());
+        }
+        let Some(shell) = profile
+            .command
+            .clone()
+            .or_else(|| std::env::var("SHELL").ok())
+        else {
+            return Ok(());
+        };
+        let name = Path::new(&shell)
+            .file_stem()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let dir = Directory::shell_integration_directory()
+            .ok_or_else(|| anyhow!("can't get shell integration directory"))?;
+
+        let arguments = match name.as_str() {
+            "bash" => {
+                let path = dir.join("lapce.bash");
+                fs::write(&path, BASH_INTEGRATION)?;
+                vec![
+                    "--init-file".to_string(),
+                    path.to_string_lossy().to_string(),
+                    "-i".to_string(),
+                ]
+            }
+            "zsh" => {
+                // zsh has no option for an extra startup file, so it reads the
+                // ones here, which read the user's own
+                let zdotdir = dir.join("zsh");
+                fs::create_dir_all(&zdotdir)?;
+                fs::write(zdotdir.join(".zshenv"), ZSHENV_INTEGRATION)?;
+                fs::write(zdotdir.join(".zprofile"), ZPROFILE_INTEGRATION)?;
+                fs::write(zdotdir.join(".zshrc"), ZSHRC_INTEGRATION)?;
+                let env = profile.environment.get_or_insert_with(HashMap::new);
+                if let Some(user_zdotdir) = env
+                    .get("ZDOTDIR")
+                    .cloned()
+                    .or_else(|| std::env::var("ZDOTDIR").ok())
+                {
+                    env.insert("LAPCE_USER_ZDOTDIR".to_string(), user_zdotdir);
+                }
+                env.insert(
+                    "ZDOTDIR".to_string(),
+                    zdotdir.to_string_lossy().to_string(),
+                );
+                if cfg!(target_os = "macos") {
+                    vec!["-l".to_string()]
+                } else {
+                    Vec::new()
+                }
+            }
+            "fish" => {
+                let path = dir.join("lapce.fish");
+                fs::write(&path, FISH_INTEGRATION)?;
+                let path = path
+                    .to_string_lossy()
+                    .replace('\\', "\\\\")
+                    .replace('\'', "\\'");
+                vec!["--init-command".to_string(), format!("source '{path}'")]
+            }
+            _ => return Ok(()),
+        };
+        profile.command = Some(shell);
+        profile.arguments = Some(arguments);
+        Ok(())
+    }
+
     fn program(profile: &TerminalProfile) -> Option<Shell> {
         if let Some(command) = &profile.command {
             if let Some(arguments) = &profile.arguments {
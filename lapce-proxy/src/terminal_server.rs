@@ -0,0 +1,526 @@
+//! The terminal server keeps the shells of terminals running in a process of
+//! its own, like tmux, so that they survive the proxy that started them, and a
+//! new proxy can reattach to them and replay their output.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, BufRead, BufReader, Write},
+    net::Shutdown,
+    os::unix::{
+        net::{UnixListener, UnixStream},
+        process::CommandExt,
+    },
+    process::{Command, Stdio},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use alacritty_terminal::{event::WindowSize, event_loop::Msg};
+use anyhow::{Result, anyhow};
+use crossbeam_channel::{Receiver, Sender};
+use lapce_core::directory::Directory;
+use lapce_rpc::{
+    base64_bytes,
+    core::CoreRpcHandler,
+    terminal::{TermId, TerminalProfile},
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::terminal::{Terminal, TerminalEvent, TerminalSender};
+
+/// How much of the output of a session is kept to replay on reattaching.
+const SCROLLBACK_BYTES: usize = 4 * 1024 * 1024;
+/// The server exits when it had no sessions for this long.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+const CONNECT_ATTEMPTS: usize = 50;
+const CONNECT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// From the proxy to the terminal server
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(tag = "method", content = "params")]
+enum SessionRequest {
+    /// The first message of a connection, starting the session if it's not
+    /// running
+    Attach {
+        session: String,
+        profile: TerminalProfile,
+    },
+    Write {
+        #[serde(with = "base64_bytes")]
+        content: Vec<u8>,
+    },
+    Resize {
+        width: usize,
+        height: usize,
+    },
+    Close,
+}
+
+/// From the terminal server to the proxy
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(tag = "method", content = "params")]
+enum SessionEvent {
+    Attached {
+        process_id: Option<u32>,
+    },
+    LaunchFailed {
+        error: String,
+    },
+    Output {
+        #[serde(with = "base64_bytes")]
+        content: Vec<u8>,
+    },
+    Stopped {
+        exit_code: Option<i32>,
+    },
+}
+
+struct Session {
+    sender: TerminalSender,
+    process_id: Option<u32>,
+    scrollback: VecDeque<u8>,
+    /// The connections of the proxies attached to the session, as several
+    /// windows can show the same terminal, each written to by a thread of
+    /// its own
+    clients: HashMap<usize, Sender<SessionEvent>>,
+}
+
+impl Session {
+    fn record(&mut self, content: &[u8]) {
+        self.scrollback.extend(content);
+        if self.scrollback.len() > SCROLLBACK_BYTES {
+            let mut excess = self.scrollback.len() - SCROLLBACK_BYTES;
+            // start the replay at a line, rather than within an escape sequence
+            if let Some(newline) = self
+                .scrollback
+                .iter()
+                .skip(excess)
+                .position(|b| *b == b'\n')
+            {
+                excess += newline + 1;
+            }
+            self.scrollback.drain(..excess);
+        }
+    }
+
+    /// Attach another connection, replaying the output so far to it.
+    fn attach(&mut self, client_id: usize, client: Sender<SessionEvent>) {
+        let content: Vec<u8> = self.scrollback.iter().copied().collect();
+        let replayed = client
+            .send(SessionEvent::Attached {
+                process_id: self.process_id,
+            })
+            .and_then(|_| client.send(SessionEvent::Output { content }));
+        if replayed.is_ok() {
+            self.clients.insert(client_id, client);
+        }
+    }
+
+    fn send(&mut self, event: SessionEvent) {
+        // the clients whose connection closed are gone
+        self.clients
+            .retain(|_, client| client.send(event.clone()).is_ok());
+    }
+}
+
+type Sessions = Arc<Mutex<HashMap<String, Arc<Mutex<Session>>>>>;
+
+/// Run the terminal server, unless one is running already.
+pub fn mainloop() {
+    if let Err(err) = serve() {
+        tracing::error!("{:?}", err);
+    }
+}
+
+fn serve() -> Result<()> {
+    let path = Directory::terminal_server_socket()
+        .ok_or_else(|| anyhow!("can't get terminal server socket"))?;
+    if UnixStream::connect(&path).is_ok() {
+        return Ok(());
+    }
+    if path.exists() {
+        if let Err(err) = std::fs::remove_file(&path) {
+            tracing::error!("{:?}", err);
+        }
+    }
+    let listener = UnixListener::bind(&path)?;
+
+    let sessions = Sessions::default();
+    {
+        let sessions = sessions.clone();
+        thread::spawn(move || {
+            loop {
+                thread::sleep(IDLE_TIMEOUT);
+                if sessions.lock().is_empty() {
+                    std::process::exit(0);
+                }
+            }
+        });
+    }
+
+    let clients = AtomicUsize::new(0);
+    for stream in listener.incoming().flatten() {
+        let sessions = sessions.clone();
+        let client_id = clients.fetch_add(1, Ordering::Relaxed);
+        thread::spawn(move || {
+            if let Err(err) = handle_client(sessions, client_id, stream) {
+                tracing::error!("{:?}", err);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle_client(
+    sessions: Sessions,
+    client_id: usize,
+    stream: UnixStream,
+) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let Some(SessionRequest::Attach { session, profile }) =
+        read_message(&mut reader)?
+    else {
+        return Ok(());
+    };
+
+    // the session isn't kept locked while writing to the connection
+    let (tx, rx) = crossbeam_channel::unbounded();
+    thread::spawn(move || {
+        let mut stream = stream;
+        for event in rx {
+            if let Err(err) = write_message(&mut stream, &event) {
+                tracing::error!("{:?}", err);
+                break;
+            }
+        }
+        if let Err(err) = stream.shutdown(Shutdown::Both) {
+            tracing::error!("{:?}", err);
+        }
+    });
+    let session = attach(&sessions, session, profile, client_id, tx)?;
+
+    while let Ok(Some(request)) = read_message(&mut reader) {
+        let msg = match request {
+            SessionRequest::Attach { .. } => continue,
+            SessionRequest::Write { content } => Msg::Input(content.into()),
+            SessionRequest::Resize { width, height } => Msg::Resize(WindowSize {
+                num_lines: height as u16,
+                num_cols: width as u16,
+                cell_width: 1,
+                cell_height: 1,
+            }),
+            SessionRequest::Close => Msg::Shutdown,
+        };
+        session.lock().sender.send(msg);
+    }
+
+    // the proxy went away, but the shell carries on
+    session.lock().clients.remove(&client_id);
+    Ok(())
+}
+
+/// Attach the connection to the session, starting its shell if it's not
+/// running, and replay the output the session had so far.
+fn attach(
+    sessions: &Sessions,
+    name: String,
+    profile: TerminalProfile,
+    client_id: usize,
+    client: Sender<SessionEvent>,
+) -> Result<Arc<Mutex<Session>>> {
+    let existing = sessions.lock().get(&name).cloned();
+    if let Some(session) = existing {
+        session.lock().attach(client_id, client);
+        return Ok(session);
+    }
+
+    let mut terminal = match Terminal::new(TermId::next(), profile, 50, 10) {
+        Ok(terminal) => terminal,
+        Err(err) => {
+            client.send(SessionEvent::LaunchFailed {
+                error: err.to_string(),
+            })?;
+            return Err(err);
+        }
+    };
+    let process_id = Some(terminal.pty.child().id());
+    client.send(SessionEvent::Attached { process_id })?;
+
+    let session = Arc::new(Mutex::new(Session {
+        sender: TerminalSender::new(terminal.tx.clone(), terminal.poller.clone()),
+        process_id,
+        scrollback: VecDeque::new(),
+        clients: HashMap::from([(client_id, client)]),
+    }));
+    sessions.lock().insert(name.clone(), session.clone());
+
+    {
+        let sessions = sessions.clone();
+        let session = session.clone();
+        thread::spawn(move || {
+            terminal.run_with(|event| {
+                let mut session = session.lock();
+                match event {
+                    TerminalEvent::Output(content) => {
+                        session.record(&content);
+                        session.send(SessionEvent::Output { content });
+                    }
+                    TerminalEvent::Stopped(exit_code) => {
+                        session.send(SessionEvent::Stopped { exit_code });
+                    }
+                }
+            });
+            let mut sessions = sessions.lock();
+            if sessions
+                .get(&name)
+                .is_some_and(|running| Arc::ptr_eq(running, &session))
+            {
+                sessions.remove(&name);
+            }
+        });
+    }
+
+    Ok(session)
+}
+
+/// Start the shell of a terminal in the terminal server, or reattach to it,
+/// forwarding what's sent to the returned sender to it, and its output to
+/// the core. Starting the server can take a while, so this happens on a
+/// thread of its own, which holds on to what's sent until it's attached and
+/// tells the core if the terminal can't be launched.
+pub fn attach_terminal(
+    term_id: TermId,
+    session: String,
+    profile: TerminalProfile,
+    core_rpc: CoreRpcHandler,
+) -> TerminalSender {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    thread::spawn(move || {
+        if let Err(err) =
+            run_session(term_id, session, profile, rx, core_rpc.clone())
+        {
+            core_rpc.terminal_launch_failed(term_id, err.to_string());
+        }
+    });
+    TerminalSender::session(tx)
+}
+
+fn run_session(
+    term_id: TermId,
+    session: String,
+    profile: TerminalProfile,
+    rx: Receiver<Msg>,
+    core_rpc: CoreRpcHandler,
+) -> Result<()> {
+    let stream = connect()?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    write_message(&mut writer, &SessionRequest::Attach { session, profile })?;
+    match read_message(&mut reader)? {
+        Some(SessionEvent::Attached { process_id }) => {
+            core_rpc.terminal_process_id(term_id, process_id);
+        }
+        Some(SessionEvent::LaunchFailed { error }) => return Err(anyhow!(error)),
+        _ => return Err(anyhow!("the terminal server closed the connection")),
+    }
+
+    thread::spawn(move || {
+        while let Ok(Some(event)) = read_message(&mut reader) {
+            match event {
+                SessionEvent::Output { content } => {
+                    core_rpc.update_terminal(term_id, content);
+                }
+                SessionEvent::Stopped { exit_code } => {
+                    core_rpc.terminal_process_stopped(term_id, exit_code);
+                    return;
+                }
+                SessionEvent::Attached { .. }
+                | SessionEvent::LaunchFailed { .. } => {}
+            }
+        }
+    });
+
+    for msg in rx {
+        let request = match msg {
+            Msg::Input(content) => SessionRequest::Write {
+                content: content.into_owned(),
+            },
+            Msg::Resize(size) => SessionRequest::Resize {
+                width: size.num_cols as usize,
+                height: size.num_lines as usize,
+            },
+            Msg::Shutdown => SessionRequest::Close,
+        };
+        if let Err(err) = write_message(&mut writer, &request) {
+            tracing::error!("{:?}", err);
+            break;
+        }
+    }
+    // detach when the proxy drops the sender
+    if let Err(err) = writer.shutdown(Shutdown::Both) {
+        tracing::error!("{:?}", err);
+    }
+    Ok(())
+}
+
+/// Connect to the terminal server, starting it if it's not running.
+fn connect() -> Result<UnixStream> {
+    let path = Directory::terminal_server_socket()
+        .ok_or_else(|| anyhow!("can't get terminal server socket"))?;
+    if let Ok(stream) = UnixStream::connect(&path) {
+        return Ok(stream);
+    }
+
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .arg("--terminal-server")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // in a session of its own, it's not hung up on with the proxy's
+    // SAFETY: setsid is async-signal-safe, and the closure doesn't allocate
+    // or touch any lock of the parent process
+    unsafe {
+        command.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+    let mut child = command.spawn()?;
+    thread::spawn(move || child.wait());
+
+    for _ in 0..CONNECT_ATTEMPTS {
+        thread::sleep(CONNECT_INTERVAL);
+        if let Ok(stream) = UnixStream::connect(&path) {
+            return Ok(stream);
+        }
+    }
+    Err(anyhow!("can't connect to the terminal server"))
+}
+
+fn read_message<T: DeserializeOwned>(
+    reader: &mut impl BufRead,
+) -> Result<Option<T>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&line)?))
+}
+
+fn write_message<T: Serialize>(
+    writer: &mut impl Write,
+    message: &T,
+) -> io::Result<()> {
+    let message = format!("{}\n", serde_json::to_string(message)?);
+    writer.write_all(message.as_bytes())?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Session {
+        let (tx, _) = crossbeam_channel::unbounded();
+        Session {
+            sender: TerminalSender::session(tx),
+            process_id: Some(1),
+            scrollback: VecDeque::new(),
+            clients: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_messages() {
+        let mut buffer = Vec::new();
+        let output = SessionEvent::Output {
+            content: vec![0xff, 0, b'a', b'\n'],
+        };
+        write_message(&mut buffer, &output).unwrap();
+        write_message(
+            &mut buffer,
+            &SessionRequest::Write {
+                content: vec![0xc3, b'\r'],
+            },
+        )
+        .unwrap();
+        let text = String::from_utf8(buffer.clone()).unwrap();
+        assert!(
+            text.starts_with(
+                r#"{"method":"output","params":{"content":"/wBhCg=="}}"#
+            )
+        );
+
+        let mut reader = buffer.as_slice();
+        let event: Option<SessionEvent> = read_message(&mut reader).unwrap();
+        assert_eq!(event, Some(output));
+        let request: Option<SessionRequest> = read_message(&mut reader).unwrap();
+        assert!(matches!(
+            request,
+            Some(SessionRequest::Write { content }) if content == [0xc3, b'\r']
+        ));
+        assert!(read_message::<SessionEvent>(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_record() {
+        let mut session = session();
+        session.record(b"first line\n");
+        assert_eq!(session.scrollback.len(), 11);
+
+        let mut content = vec![b'a'; SCROLLBACK_BYTES];
+        content.extend(b"\nlast");
+        session.record(&content);
+        // the replay starts at a line
+        assert_eq!(
+            session.scrollback.iter().copied().collect::<Vec<_>>(),
+            b"last"
+        );
+    }
+
+    #[test]
+    fn test_clients() {
+        let mut session = session();
+        session.record(b"$ ");
+
+        let (first, first_rx) = crossbeam_channel::unbounded();
+        session.attach(0, first);
+        let (second, second_rx) = crossbeam_channel::unbounded();
+        session.attach(1, second);
+        for rx in [&first_rx, &second_rx] {
+            let replay: Vec<_> = rx.try_iter().collect();
+            assert_eq!(
+                replay,
+                vec![
+                    SessionEvent::Attached {
+                        process_id: Some(1)
+                    },
+                    SessionEvent::Output {
+                        content: b"$ ".to_vec()
+                    },
+                ]
+            );
+        }
+
+        // both windows get the output, rather than the last one attached
+        let output = SessionEvent::Output {
+            content: b"ls".to_vec(),
+        };
+        session.send(output.clone());
+        assert_eq!(first_rx.try_recv(), Ok(output.clone()));
+        assert_eq!(second_rx.try_recv(), Ok(output.clone()));
+
+        drop(first_rx);
+        session.send(output.clone());
+        assert_eq!(session.clients.len(), 1);
+        assert_eq!(second_rx.try_recv(), Ok(output));
+    }
+}
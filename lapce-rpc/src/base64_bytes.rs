@@ -0,0 +1,19 @@
+//! Bytes sent as base64, rather than as an array of numbers, for use with
+//! `#[serde(with = "base64_bytes")]`.
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+pub fn serialize<S: Serializer>(
+    bytes: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&STANDARD.encode(bytes))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<u8>, D::Error> {
+    let content = String::deserialize(deserializer)?;
+    STANDARD.decode(content).map_err(D::Error::custom)
+}
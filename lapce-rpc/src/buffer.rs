@@ -22,3 +22,66 @@ pub struct BufferHeadResponse {
     pub version: String,
     pub content: String,
 }
+
+/// The encodings files can be reopened or saved with, by their WHATWG names
+pub const ENCODINGS: &[&str] = &[
+    "UTF-8",
+    "UTF-16LE",
+    "UTF-16BE",
+    "windows-1252",
+    "ISO-8859-2",
+    "ISO-8859-15",
+    "windows-1250",
+    "windows-1251",
+    "KOI8-R",
+    "IBM866",
+    "windows-1253",
+    "windows-1254",
+    "windows-1255",
+    "windows-1256",
+    "windows-1257",
+    "windows-874",
+    "Shift_JIS",
+    "EUC-JP",
+    "ISO-2022-JP",
+    "GBK",
+    "gb18030",
+    "Big5",
+    "EUC-KR",
+    "macintosh",
+];
+
+/// The encoding of a file on disk
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FileEncoding {
+    /// The WHATWG name of the encoding, like `UTF-8` or `Shift_JIS`
+    pub name: String,
+    /// Whether the file starts with a byte order mark
+    pub bom: bool,
+}
+
+impl FileEncoding {
+    /// The encoding by its name, with the byte order mark UTF-16 files
+    /// usually have.
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        let bom = name.starts_with("UTF-16");
+        Self { name, bom }
+    }
+}
+
+impl Default for FileEncoding {
+    fn default() -> Self {
+        Self::new("UTF-8")
+    }
+}
+
+impl std::fmt::Display for FileEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.bom && self.name == "UTF-8" {
+            write!(f, "UTF-8 with BOM")
+        } else {
+            write!(f, "{}", self.name)
+        }
+    }
+}
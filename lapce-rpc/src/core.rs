@@ -16,12 +16,15 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    RequestId, RpcError, RpcMessage,
+    RequestId, RpcError, RpcMessage, base64_bytes,
     dap_types::{
         self, DapId, RunDebugConfig, Scope, StackFrame, Stopped, ThreadId, Variable,
     },
     file::PathObject,
-    plugin::{PluginId, VoltInfo, VoltMetadata},
+    plugin::{
+        LanguageServerInfo, PluginId, VoltCommand, VoltDecoration, VoltID, VoltInfo,
+        VoltMetadata, VoltStatusBarItem,
+    },
     proxy::ProxyStatus,
     source_control::DiffInfo,
     terminal::TermId,
@@ -36,7 +39,16 @@ pub enum CoreRpc {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum FileChanged {
-    Change(String),
+    Change {
+        content: String,
+        disk_hash: Option<String>,
+    },
+    /// A file open in the hex editor changed
+    Bytes {
+        #[serde(with = "base64_bytes")]
+        content: Vec<u8>,
+        disk_hash: String,
+    },
     Delete,
 }
 
@@ -72,6 +84,20 @@ pub enum CoreNotification {
     ServerStatus {
         params: ServerStatusParams,
     },
+    /// A language server has started, or its state has changed
+    LanguageServerStatus {
+        server: LanguageServerInfo,
+    },
+    /// The process of a language server exited
+    LanguageServerStopped {
+        plugin_id: PluginId,
+    },
+    /// A language server was shut down and won't be restarted
+    LanguageServerRemoved {
+        plugin_id: PluginId,
+    },
+    /// A language server asked for all semantic tokens to be requested again
+    SemanticTokensRefresh,
     WorkDoneProgress {
         progress: ProgressParams,
     },
@@ -105,6 +131,39 @@ pub enum CoreNotification {
         volt: VoltInfo,
         only_installing: bool,
     },
+    /// A volt trapped, ran for too long or used too much memory
+    VoltCrashed {
+        volt_id: VoltID,
+        message: String,
+    },
+    VoltDecorations {
+        volt_id: VoltID,
+        path: PathBuf,
+        key: String,
+        decorations: Vec<VoltDecoration>,
+    },
+    SetVoltStatusBarItem {
+        volt_id: VoltID,
+        item: VoltStatusBarItem,
+    },
+    RemoveVoltStatusBarItem {
+        volt_id: VoltID,
+        id: String,
+    },
+    /// Asks the user to pick one of the items, the answer is sent back with
+    /// [`ProxyNotification::AnswerVoltPrompt`](crate::proxy::ProxyNotification::AnswerVoltPrompt)
+    ShowVoltPrompt {
+        prompt_id: u64,
+        title: String,
+        placeholder: String,
+        items: Vec<String>,
+    },
+    /// A volt registered a command, possibly with a default keybinding
+    VoltCommandRegistered {
+        plugin_id: PluginId,
+        volt_id: VoltID,
+        command: VoltCommand,
+    },
     DiffInfo {
         diff: DiffInfo,
     },
@@ -309,6 +368,65 @@ pub fn volt_removed(&self, volt: VoltInfo, only_installing: bool) {
         });
     }
 
+    pub fn volt_crashed(&self, volt_id: VoltID, message: String) {
+        self.notification(CoreNotification::VoltCrashed { volt_id, message });
+    }
+
+    pub fn volt_decorations(
+        &self,
+        volt_id: VoltID,
+        path: PathBuf,
+        key: String,
+        decorations: Vec<VoltDecoration>,
+    ) {
+        self.notification(CoreNotification::VoltDecorations {
+            volt_id,
+            path,
+            key,
+            decorations,
+        });
+    }
+
+    pub fn set_volt_status_bar_item(
+        &self,
+        volt_id: VoltID,
+        item: VoltStatusBarItem,
+    ) {
+        self.notification(CoreNotification::SetVoltStatusBarItem { volt_id, item });
+    }
+
+    pub fn remove_volt_status_bar_item(&self, volt_id: VoltID, id: String) {
+        self.notification(CoreNotification::RemoveVoltStatusBarItem { volt_id, id });
+    }
+
+    pub fn show_volt_prompt(
+        &self,
+        prompt_id: u64,
+        title: String,
+        placeholder: String,
+        items: Vec<String>,
+    ) {
+        self.notification(CoreNotification::ShowVoltPrompt {
+            prompt_id,
+            title,
+            placeholder,
+            items,
+        });
+    }
+
+    pub fn volt_command_registered(
+        &self,
+        plugin_id: PluginId,
+        volt_id: VoltID,
+        command: VoltCommand,
+    ) {
+        self.notification(CoreNotification::VoltCommandRegistered {
+            plugin_id,
+            volt_id,
+            command,
+        });
+    }
+
     pub fn run_in_terminal(&self, config: RunDebugConfig) {
         self.notification(CoreNotification::RunInTerminal { config });
     }
@@ -329,6 +447,22 @@ pub fn server_status(&self, params: ServerStatusParams) {
         self.notification(CoreNotification::ServerStatus { params });
     }
 
+    pub fn language_server_status(&self, server: LanguageServerInfo) {
+        self.notification(CoreNotification::LanguageServerStatus { server });
+    }
+
+    pub fn language_server_stopped(&self, plugin_id: PluginId) {
+        self.notification(CoreNotification::LanguageServerStopped { plugin_id });
+    }
+
+    pub fn language_server_removed(&self, plugin_id: PluginId) {
+        self.notification(CoreNotification::LanguageServerRemoved { plugin_id });
+    }
+
+    pub fn semantic_tokens_refresh(&self) {
+        self.notification(CoreNotification::SemanticTokensRefresh);
+    }
+
     pub fn work_done_progress(&self, progress: ProgressParams) {
         self.notification(CoreNotification::WorkDoneProgress { progress });
     }
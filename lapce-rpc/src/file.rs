@@ -6,6 +6,15 @@
 
 use serde::{Deserialize, Serialize};
 
+/// The innermost of the workspace `roots` that contains `path`.
+pub fn workspace_root_of<'a>(roots: &'a [PathBuf], path: &Path) -> Option<&'a Path> {
+    roots
+        .iter()
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .map(PathBuf::as_path)
+}
+
 /// UTF8 line and column-offset
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
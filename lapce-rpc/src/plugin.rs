@@ -1,6 +1,7 @@
 use core::fmt;
 use std::{collections::HashMap, path::PathBuf};
 
+use lsp_types::{Range, Url};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -16,6 +17,21 @@ pub fn next() -> Self {
     }
 }
 
+/// The state of a running language server, as shown in the language server
+/// panel.
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct LanguageServerInfo {
+    pub plugin_id: PluginId,
+    pub volt_id: VoltID,
+    pub name: String,
+    pub running: bool,
+    /// The features the server has announced support for
+    pub capabilities: Vec<String>,
+    /// The file the messages exchanged with the server are written to, when
+    /// tracing is enabled
+    pub trace_log: Option<PathBuf>,
+}
+
 #[derive(Deserialize, Clone, Debug, Serialize)]
 pub struct PluginConfiguration {
     #[serde(rename(deserialize = "type"))]
@@ -55,6 +71,138 @@ pub struct VoltConfig {
     pub description: String,
 }
 
+/// What a wasm volt is allowed to access outside of its own directory.
+#[derive(Deserialize, Clone, Debug, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct VoltPermissions {
+    /// The hosts the volt can make http requests to, e.g. `https://github.com`
+    #[serde(default)]
+    pub network: Vec<String>,
+    /// The environment variables the volt can read, `*` for all of them
+    #[serde(default)]
+    pub env: Vec<String>,
+    /// The directories the volt can access, `${workspace}` is replaced with
+    /// the workspace folder
+    #[serde(default)]
+    pub filesystem: Vec<String>,
+    /// Whether the volt can run processes, which includes starting language
+    /// servers and debuggers
+    #[serde(default)]
+    pub process: bool,
+}
+
+impl VoltPermissions {
+    /// The access of language servers, which run as native processes and
+    /// aren't sandboxed, and of volts from before permissions were declared.
+    pub fn unrestricted() -> Self {
+        Self {
+            network: vec!["insecure:allow-all".to_string()],
+            env: vec!["*".to_string()],
+            filesystem: Vec::new(),
+            process: true,
+        }
+    }
+
+    pub fn env_allowed(&self, name: &str) -> bool {
+        self.env.iter().any(|env| env == "*" || env == name)
+    }
+}
+
+/// Where a volt is installed from.
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum VoltSource {
+    /// The url of a plugin registry, e.g. `https://plugins.lapce.dev`
+    Registry(String),
+    /// A packaged volt archive or a volt directory
+    Path(PathBuf),
+    /// A previous version of the volt, kept on disk when it was updated
+    Previous(String),
+}
+
+/// A command registered by a volt, listed in the command palette.
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VoltCommand {
+    /// The id the volt executes the command by, e.g. `deploy`, which keymaps
+    /// bind prefixed with the id of the volt, e.g. `author.name.deploy`
+    pub command: String,
+    /// The title shown in the command palette
+    pub title: String,
+    /// The default keybinding, in the format used by `keymaps.toml`
+    pub key: Option<String>,
+    /// The condition of the default keybinding, as in `keymaps.toml`
+    pub when: Option<String>,
+}
+
+/// The state of the active editor when a volt command is executed.
+#[derive(Deserialize, Clone, Debug, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VoltCommandContext {
+    pub uri: Option<Url>,
+    pub language_id: Option<String>,
+    pub selection: Option<Range>,
+}
+
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteVoltCommandParams {
+    pub command: String,
+    pub context: VoltCommandContext,
+}
+
+/// Something a volt draws over a range of a document. Colors are either hex
+/// colors, e.g. `#ff000040`, or the names of theme colors.
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VoltDecoration {
+    pub range: Range,
+    /// The background of the range
+    pub background: Option<String>,
+    /// An svg shown in the gutter next to the first line of the range
+    pub gutter_icon: Option<String>,
+    /// Text shown after the end of the last line of the range
+    pub after_text: Option<String>,
+    pub after_text_color: Option<String>,
+}
+
+/// Replaces the decorations a volt set on a document under the same key, an
+/// empty list removes them.
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SetDecorationsParams {
+    pub uri: Url,
+    pub key: String,
+    pub decorations: Vec<VoltDecoration>,
+}
+
+/// An item a volt shows in the status bar.
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct VoltStatusBarItem {
+    /// Setting an item with the same id replaces it
+    pub id: String,
+    pub text: String,
+    pub tooltip: Option<String>,
+    /// A command registered by the volt, run when the item is clicked
+    pub command: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveStatusBarItemParams {
+    pub id: String,
+}
+
+/// Lets the user pick one of the items in the palette, answered with the index
+/// of the picked item or null if the palette was closed.
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShowQuickPickParams {
+    pub placeholder: Option<String>,
+    pub items: Vec<String>,
+}
+
 #[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub struct VoltMetadata {
@@ -71,6 +219,7 @@ pub struct VoltMetadata {
     pub dir: Option<PathBuf>,
     pub activation: Option<VoltActivation>,
     pub config: Option<HashMap<String, VoltConfig>>,
+    pub permissions: Option<VoltPermissions>,
 }
 
 impl VoltMetadata {
@@ -78,6 +227,15 @@ pub fn id(&self) -> VoltID {
         VoltID::from(self)
     }
 
+    /// The permissions the volt runs with. Volts without a `permissions`
+    /// section predate it, so they keep the access volts had before, which
+    /// language server volts need to start their servers.
+    pub fn permissions(&self) -> VoltPermissions {
+        self.permissions
+            .clone()
+            .unwrap_or_else(VoltPermissions::unrestricted)
+    }
+
     pub fn info(&self) -> VoltInfo {
         VoltInfo {
             name: self.name.clone(),
@@ -142,7 +300,9 @@ fn from(volt: &VoltInfo) -> Self {
 
 #[cfg(test)]
 mod tests {
-    use super::{VoltID, VoltInfo, VoltMetadata};
+    use super::{
+        SetDecorationsParams, VoltID, VoltInfo, VoltMetadata, VoltStatusBarItem,
+    };
 
     #[test]
     fn test_volt_metadata_id() {
@@ -160,6 +320,7 @@ fn test_volt_metadata_id() {
             dir: std::env::current_dir().unwrap().canonicalize().ok(),
             activation: None,
             config: None,
+            permissions: None,
         };
         let volt_id = VoltID {
             author: "Author".to_string(),
@@ -198,6 +359,7 @@ fn test_volt_metadata_info() {
             dir: std::env::current_dir().unwrap().canonicalize().ok(),
             activation: None,
             config: None,
+            permissions: None,
         };
         let volt_info = VoltInfo {
             name: "plugin".to_string(),
@@ -234,4 +396,41 @@ fn test_volt_info_id() {
         assert_eq!(<&VoltInfo as Into<VoltID>>::into(&volt_info), volt_id);
         assert_eq!(<VoltInfo as Into<VoltID>>::into(volt_info), volt_id);
     }
+
+    #[test]
+    fn test_set_decorations_params() {
+        let params: SetDecorationsParams =
+            serde_json::from_value(serde_json::json!({
+                "uri": "file:///project/src/main.rs",
+                "key": "coverage",
+                "decorations": [{
+                    "range": {
+                        "start": { "line": 1, "character": 0 },
+                        "end": { "line": 2, "character": 4 },
+                    },
+                    "background": "#ff000040",
+                    "afterText": "2 hits",
+                }],
+            }))
+            .unwrap();
+        assert_eq!(params.key, "coverage");
+        let decoration = &params.decorations[0];
+        assert_eq!(decoration.range.end.line, 2);
+        assert_eq!(decoration.background.as_deref(), Some("#ff000040"));
+        assert_eq!(decoration.after_text.as_deref(), Some("2 hits"));
+        assert_eq!(decoration.gutter_icon, None);
+    }
+
+    #[test]
+    fn test_volt_status_bar_item() {
+        let item: VoltStatusBarItem = serde_json::from_value(serde_json::json!({
+            "id": "branch",
+            "text": "main",
+            "command": "checkout",
+        }))
+        .unwrap();
+        assert_eq!(item.id, "branch");
+        assert_eq!(item.tooltip, None);
+        assert_eq!(item.command.as_deref(), Some("checkout"));
+    }
 }
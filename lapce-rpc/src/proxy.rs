@@ -14,7 +14,7 @@
     CallHierarchyIncomingCall, CallHierarchyItem, CodeAction, CodeActionResponse,
     CodeLens, CompletionItem, Diagnostic, DocumentSymbolResponse, FoldingRange,
     GotoDefinitionResponse, Hover, InlayHint, InlineCompletionResponse,
-    InlineCompletionTriggerKind, Location, Position, PrepareRenameResponse,
+    InlineCompletionTriggerKind, Location, Position, PrepareRenameResponse, Range,
     SelectionRange, SymbolInformation, TextDocumentItem, TextEdit, WorkspaceEdit,
     request::{GotoImplementationResponse, GotoTypeDefinitionResponse},
 };
@@ -23,12 +23,15 @@
 
 use super::plugin::VoltID;
 use crate::{
-    RequestId, RpcError, RpcMessage,
-    buffer::BufferId,
+    RequestId, RpcError, RpcMessage, base64_bytes,
+    buffer::{BufferId, FileEncoding},
     dap_types::{self, DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
     file::{FileNodeItem, PathObject},
     file_line::FileLine,
-    plugin::{PluginId, VoltInfo, VoltMetadata},
+    plugin::{
+        PluginId, VoltCommandContext, VoltInfo, VoltMetadata, VoltPermissions,
+        VoltSource,
+    },
     source_control::FileDiff,
     style::SemanticStyles,
     terminal::{TermId, TerminalProfile},
@@ -63,10 +66,24 @@ pub enum ProxyRequest {
     NewBuffer {
         buffer_id: BufferId,
         path: PathBuf,
+        /// The size in bytes above which the file is opened in large file
+        /// mode
+        #[serde(default)]
+        large_file_size: Option<u64>,
+    },
+    /// Get the content of a buffer opened in large file mode from `offset`
+    LoadBufferChunk {
+        path: PathBuf,
+        offset: usize,
     },
     BufferHead {
         path: PathBuf,
     },
+    /// Read the file of an open buffer again, decoding it with `encoding`
+    ReopenWithEncoding {
+        path: PathBuf,
+        encoding: FileEncoding,
+    },
     GlobalSearch {
         pattern: String,
         case_sensitive: bool,
@@ -81,6 +98,11 @@ pub enum ProxyRequest {
         plugin_id: PluginId,
         action_item: Box<CodeAction>,
     },
+    ExecuteVoltCommand {
+        plugin_id: PluginId,
+        command: String,
+        context: VoltCommandContext,
+    },
     GetHover {
         request_id: usize,
         path: PathBuf,
@@ -133,6 +155,8 @@ pub enum ProxyRequest {
     },
     GetSemanticTokens {
         path: PathBuf,
+        /// Only request the tokens of this range, e.g. the visible viewport
+        range: Option<Range>,
     },
     LspFoldingRange {
         path: PathBuf,
@@ -180,6 +204,7 @@ pub enum ProxyRequest {
         path: PathBuf,
         /// Whether to create the parent directories if they do not exist.
         create_parents: bool,
+        encoding: FileEncoding,
     },
     SaveBufferAs {
         buffer_id: BufferId,
@@ -188,6 +213,19 @@ pub enum ProxyRequest {
         content: String,
         /// Whether to create the parent directories if they do not exist.
         create_parents: bool,
+        encoding: FileEncoding,
+    },
+    /// Read the raw bytes of a file, for the hex editor
+    ReadFileBytes {
+        path: PathBuf,
+    },
+    SaveFileBytes {
+        path: PathBuf,
+        #[serde(with = "base64_bytes")]
+        content: Vec<u8>,
+        /// The hash of the bytes the edits were made to, without which the
+        /// file is overwritten even if it changed on disk since
+        disk_hash: Option<String>,
     },
     CreateFile {
         path: PathBuf,
@@ -206,6 +244,12 @@ pub enum ProxyRequest {
         from: PathBuf,
         to: PathBuf,
     },
+    /// Ask the language servers for the edits that should be applied before
+    /// `from` is renamed to `to`, such as updating imports of a module.
+    WillRenameFiles {
+        from: PathBuf,
+        to: PathBuf,
+    },
     TestCreateAtPath {
         path: PathBuf,
     },
@@ -228,6 +272,8 @@ pub enum ProxyRequest {
 pub enum ProxyNotification {
     Initialize {
         workspace: Option<PathBuf>,
+        /// Additional root folders of a multi-root workspace
+        workspace_folders: Vec<PathBuf>,
         disabled_volts: Vec<VoltID>,
         /// Paths to extra plugins that should be loaded
         extra_plugin_paths: Vec<PathBuf>,
@@ -241,6 +287,11 @@ pub enum ProxyNotification {
     OpenPaths {
         paths: Vec<PathObject>,
     },
+    /// Add or remove root folders of a multi-root workspace
+    UpdateWorkspaceFolders {
+        added: Vec<PathBuf>,
+        removed: Vec<PathBuf>,
+    },
     Shutdown {},
     Completion {
         request_id: usize,
@@ -267,6 +318,9 @@ pub enum ProxyNotification {
     },
     InstallVolt {
         volt: VoltInfo,
+        source: VoltSource,
+        /// The permissions the user approved for a volt with wasm
+        approved: Option<VoltPermissions>,
     },
     RemoveVolt {
         volt: VoltMetadata,
@@ -295,6 +349,21 @@ pub enum ProxyNotification {
     LspCancel {
         id: i32,
     },
+    RestartLanguageServer {
+        plugin_id: PluginId,
+    },
+    /// The index of the item the user picked for a
+    /// [`CoreNotification::ShowVoltPrompt`](crate::core::CoreNotification::ShowVoltPrompt)
+    AnswerVoltPrompt {
+        prompt_id: u64,
+        index: Option<usize>,
+    },
+    /// Start or stop writing the messages exchanged with a language server to
+    /// its trace log
+    SetLanguageServerTrace {
+        plugin_id: PluginId,
+        enabled: bool,
+    },
     TerminalWrite {
         term_id: TermId,
         content: String,
@@ -363,6 +432,23 @@ pub enum ProxyResponse {
     NewBufferResponse {
         content: String,
         read_only: bool,
+        encoding: FileEncoding,
+        /// Whether the buffer was opened in large file mode, in which case
+        /// `content` is only its first chunk
+        #[serde(default)]
+        large_file: bool,
+        /// The hash of the file on disk, or `None` if it couldn't be read
+        #[serde(default)]
+        disk_hash: Option<String>,
+        /// Whether the file isn't text, so it's opened in the hex editor
+        /// instead
+        #[serde(default)]
+        binary: bool,
+    },
+    LoadBufferChunkResponse {
+        content: String,
+        /// Whether this is the last chunk of the buffer
+        done: bool,
     },
     BufferHeadResponse {
         version: String,
@@ -463,8 +549,20 @@ pub enum ProxyResponse {
     CreatePathResponse {
         path: PathBuf,
     },
+    WillRenameFilesResponse {
+        edits: Vec<WorkspaceEdit>,
+    },
     Success {},
-    SaveResponse {},
+    SaveResponse {
+        /// The hash of the file written to disk
+        #[serde(default)]
+        disk_hash: Option<String>,
+    },
+    ReadFileBytesResponse {
+        #[serde(with = "base64_bytes")]
+        content: Vec<u8>,
+        disk_hash: String,
+    },
     ReferencesResolveResponse {
         items: Vec<FileLine>,
     },
@@ -609,8 +707,17 @@ pub fn git_checkout(&self, reference: String) {
         self.notification(ProxyNotification::GitCheckout { reference });
     }
 
-    pub fn install_volt(&self, volt: VoltInfo) {
-        self.notification(ProxyNotification::InstallVolt { volt });
+    pub fn install_volt(
+        &self,
+        volt: VoltInfo,
+        source: VoltSource,
+        approved: Option<VoltPermissions>,
+    ) {
+        self.notification(ProxyNotification::InstallVolt {
+            volt,
+            source,
+            approved,
+        });
     }
 
     pub fn reload_volt(&self, volt: VoltMetadata) {
@@ -636,9 +743,11 @@ pub fn shutdown(&self) {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize(
         &self,
         workspace: Option<PathBuf>,
+        workspace_folders: Vec<PathBuf>,
         disabled_volts: Vec<VoltID>,
         extra_plugin_paths: Vec<PathBuf>,
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
@@ -647,6 +756,7 @@ pub fn initialize(
     ) {
         self.notification(ProxyNotification::Initialize {
             workspace,
+            workspace_folders,
             disabled_volts,
             extra_plugin_paths,
             plugin_configurations,
@@ -707,15 +817,41 @@ pub fn new_buffer(
         &self,
         buffer_id: BufferId,
         path: PathBuf,
+        large_file_size: Option<u64>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::NewBuffer {
+                buffer_id,
+                path,
+                large_file_size,
+            },
+            f,
+        );
+    }
+
+    pub fn load_buffer_chunk(
+        &self,
+        path: PathBuf,
+        offset: usize,
         f: impl ProxyCallback + 'static,
     ) {
-        self.request_async(ProxyRequest::NewBuffer { buffer_id, path }, f);
+        self.request_async(ProxyRequest::LoadBufferChunk { path, offset }, f);
     }
 
     pub fn get_buffer_head(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::BufferHead { path }, f);
     }
 
+    pub fn reopen_with_encoding(
+        &self,
+        path: PathBuf,
+        encoding: FileEncoding,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::ReopenWithEncoding { path, encoding }, f);
+    }
+
     pub fn create_file(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::CreateFile { path }, f);
     }
@@ -752,6 +888,41 @@ pub fn rename_path(
         self.request_async(ProxyRequest::RenamePath { from, to }, f);
     }
 
+    pub fn update_workspace_folders(
+        &self,
+        added: Vec<PathBuf>,
+        removed: Vec<PathBuf>,
+    ) {
+        self.notification(ProxyNotification::UpdateWorkspaceFolders {
+            added,
+            removed,
+        });
+    }
+
+    pub fn answer_volt_prompt(&self, prompt_id: u64, index: Option<usize>) {
+        self.notification(ProxyNotification::AnswerVoltPrompt { prompt_id, index });
+    }
+
+    pub fn restart_language_server(&self, plugin_id: PluginId) {
+        self.notification(ProxyNotification::RestartLanguageServer { plugin_id });
+    }
+
+    pub fn set_language_server_trace(&self, plugin_id: PluginId, enabled: bool) {
+        self.notification(ProxyNotification::SetLanguageServerTrace {
+            plugin_id,
+            enabled,
+        });
+    }
+
+    pub fn will_rename_files(
+        &self,
+        from: PathBuf,
+        to: PathBuf,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::WillRenameFiles { from, to }, f);
+    }
+
     pub fn test_create_at_path(
         &self,
         path: PathBuf,
@@ -767,6 +938,7 @@ pub fn save_buffer_as(
         rev: u64,
         content: String,
         create_parents: bool,
+        encoding: FileEncoding,
         f: impl ProxyCallback + 'static,
     ) {
         self.request_async(
@@ -776,6 +948,28 @@ pub fn save_buffer_as(
                 rev,
                 content,
                 create_parents,
+                encoding,
+            },
+            f,
+        );
+    }
+
+    pub fn read_file_bytes(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::ReadFileBytes { path }, f);
+    }
+
+    pub fn save_file_bytes(
+        &self,
+        path: PathBuf,
+        content: Vec<u8>,
+        disk_hash: Option<String>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::SaveFileBytes {
+                path,
+                content,
+                disk_hash,
             },
             f,
         );
@@ -805,6 +999,7 @@ pub fn save(
         rev: u64,
         path: PathBuf,
         create_parents: bool,
+        encoding: FileEncoding,
         f: impl ProxyCallback + 'static,
     ) {
         self.request_async(
@@ -812,6 +1007,7 @@ pub fn save(
                 rev,
                 path,
                 create_parents,
+                encoding,
             },
             f,
         );
@@ -864,6 +1060,23 @@ pub fn code_action_resolve(
         );
     }
 
+    pub fn execute_volt_command(
+        &self,
+        plugin_id: PluginId,
+        command: String,
+        context: VoltCommandContext,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::ExecuteVoltCommand {
+                plugin_id,
+                command,
+                context,
+            },
+            f,
+        );
+    }
+
     pub fn get_hover(
         &self,
         request_id: usize,
@@ -1014,9 +1227,10 @@ pub fn get_document_formatting(
     pub fn get_semantic_tokens(
         &self,
         path: PathBuf,
+        range: Option<Range>,
         f: impl ProxyCallback + 'static,
     ) {
-        self.request_async(ProxyRequest::GetSemanticTokens { path }, f);
+        self.request_async(ProxyRequest::GetSemanticTokens { path, range }, f);
     }
 
     pub fn get_document_symbols(
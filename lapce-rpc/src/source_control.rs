@@ -8,6 +8,17 @@ pub struct DiffInfo {
     pub branches: Vec<String>,
     pub tags: Vec<String>,
     pub diffs: Vec<FileDiff>,
+    /// The state of the other repositories of a multi-root workspace.
+    #[serde(default)]
+    pub folders: Vec<FolderDiffInfo>,
+}
+
+/// Git status of one additional folder of a multi-root workspace.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FolderDiffInfo {
+    pub path: PathBuf,
+    pub head: String,
+    pub diffs: Vec<FileDiff>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
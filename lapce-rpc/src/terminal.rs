@@ -21,6 +21,13 @@ pub struct TerminalProfile {
     pub arguments: Option<Vec<String>>,
     pub workdir: Option<url::Url>,
     pub environment: Option<HashMap<String, String>>,
+    /// Load the bundled shell integration scripts into bash, zsh or fish
+    #[serde(default)]
+    pub shell_integration: bool,
+    /// Run the shell in the terminal server under this name, so it outlives
+    /// the proxy, reattaching to it if it's still running
+    #[serde(default)]
+    pub session: Option<String>,
 }
 
 impl TerminalProfile {}